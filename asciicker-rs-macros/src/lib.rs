@@ -0,0 +1,60 @@
+//! # Asciicker-rs macros
+//! Procedural macros for [asciicker-rs](https://github.com/Niki4tap/asciicker-rs),
+//! use them through the re-exports in `asciicker_rs` instead of depending on this crate directly.
+
+#![forbid(missing_docs)]
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, spanned::Spanned, Error, ItemFn, ReturnType};
+
+/// Transforms `async fn` to return `Pin<Box<dyn Future<Output = T> + Send>>`
+/// instead of `impl Future<Output = T>`, which is required for functions
+/// passed as callbacks to `Bot::on_talk` or similar methods.
+///
+/// Attributes, visibility and arguments are kept as they are:
+///
+/// ```text
+/// #[callback]
+/// async fn talk(brc: TalkBroadcast, ...) -> BotResult { body }
+/// ```
+///
+/// becomes
+///
+/// ```text
+/// fn talk(brc: TalkBroadcast, ...) -> Pin<Box<dyn Future<Output = BotResult> + Send>> {
+///     Box::pin(async move { body })
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn callback(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        let attr = proc_macro2::TokenStream::from(attr);
+        return Error::new(attr.span(), "#[callback] does not take arguments")
+            .to_compile_error()
+            .into();
+    }
+    let mut function = parse_macro_input!(item as ItemFn);
+    if function.sig.asyncness.take().is_none() {
+        return Error::new(
+            function.sig.fn_token.span,
+            "#[callback] expects an `async fn`",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let output = match &function.sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => quote!(#ty),
+    };
+    function.sig.output = parse_quote! {
+        -> ::std::pin::Pin<::std::boxed::Box<
+            dyn ::std::future::Future<Output = #output> + ::std::marker::Send
+        >>
+    };
+    let body = &function.block;
+    function.block = parse_quote!({
+        ::std::boxed::Box::pin(async move #body)
+    });
+    quote!(#function).into()
+}
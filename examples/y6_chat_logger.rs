@@ -1,12 +1,10 @@
-use asciicker_rs::callback;
-use asciicker_rs::macro_rules_attribute::apply;
 use asciicker_rs::y6::prelude::*;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, RwLock};
 
 #[tokio::main]
 async fn main() {
-    let mut bot = Bot::new("player", "ws://asciicker.com/ws/y6/", true);
+    let mut bot = BotBuilder::new("player").replace_invalid_utf8(true).build();
     bot.on_talk(talk_callback);
     let (threads, _data) = match bot.run().await {
         Err(e) => panic!("Failed to run the bot: {:?}", e),
@@ -15,11 +13,11 @@ async fn main() {
     println!("{:?}", threads.0.thread.await);
 }
 
-#[apply(callback!)]
+#[callback]
 pub async fn talk_callback(
     talk_brc: TalkBroadcast,
     _: Arc<Mutex<Player>>,
-    _: Arc<Mutex<World>>,
+    _: Arc<RwLock<World>>,
     _: MessageSender,
 ) -> BotResult {
     println!("{:?}", talk_brc.str);
@@ -0,0 +1,151 @@
+use super::errors::AddressParseError;
+use super::version::Version;
+
+use alloc::string::{String, ToString};
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+
+/// Websocket url of an asciicker server endpoint.
+///
+/// Hand-typed urls are easy to get subtly wrong (missing `/ws/`, missing trailing slash),
+/// this builds them from parts instead.
+///
+/// # Examples
+///
+/// ## Building and parsing addresses:
+/// ```
+/// use asciicker_rs::common::{ServerAddress, Version};
+///
+/// let address = ServerAddress::new("asciicker.com").version(Version::Y6).secure(true);
+/// assert_eq!(address.to_string(), "wss://asciicker.com/ws/y6/");
+///
+/// let parsed: ServerAddress = "ws://localhost:8080/ws/y6".parse().unwrap();
+/// assert_eq!(parsed, ServerAddress::new("localhost").port(8080).version(Version::Y6));
+/// assert_eq!(parsed.to_string(), "ws://localhost:8080/ws/y6/");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ServerAddress {
+    host: String,
+    port: Option<u16>,
+    version: Version,
+    secure: bool,
+}
+
+impl ServerAddress {
+    /// Constructs a new [`ServerAddress`] for the newest supported [`Version`] over plain `ws://`.
+    pub fn new<S: Into<String>>(host: S) -> Self {
+        Self {
+            host: host.into(),
+            port: None,
+            version: *Version::ALL.last().unwrap(),
+            secure: false,
+        }
+    }
+
+    /// Sets the protocol version.
+    pub fn version(mut self, version: Version) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Uses `wss://` if `true`, `ws://` otherwise.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Sets an explicit port.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Host of the server.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Explicit port, if any.
+    pub fn get_port(&self) -> Option<u16> {
+        self.port
+    }
+
+    /// Protocol version.
+    pub fn get_version(&self) -> Version {
+        self.version
+    }
+
+    /// `true` if the address uses `wss://`.
+    pub fn is_secure(&self) -> bool {
+        self.secure
+    }
+
+    /// Full websocket url.
+    pub fn url(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Display for ServerAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let scheme = match self.secure {
+            true => "wss",
+            false => "ws",
+        };
+        match self.port {
+            Some(port) => write!(
+                f,
+                "{}://{}:{}/ws/{}/",
+                scheme, self.host, port, self.version
+            ),
+            None => write!(f, "{}://{}/ws/{}/", scheme, self.host, self.version),
+        }
+    }
+}
+
+impl From<ServerAddress> for String {
+    fn from(value: ServerAddress) -> Self {
+        value.to_string()
+    }
+}
+
+impl FromStr for ServerAddress {
+    type Err = AddressParseError;
+
+    /// Parses urls like `ws://asciicker.com/ws/y6/`.
+    ///
+    /// Scheme defaults to `ws`, missing version defaults to the newest one.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (secure, rest) = match s.split_once("://") {
+            Some(("ws", rest)) => (false, rest),
+            Some(("wss", rest)) => (true, rest),
+            Some((scheme, _)) => return Err(AddressParseError::UnknownScheme(scheme.to_string())),
+            None => (false, s),
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(idx) => rest.split_at(idx),
+            None => (rest, ""),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => match port.parse::<u16>() {
+                Ok(port) => (host, Some(port)),
+                Err(_) => return Err(AddressParseError::InvalidPort(port.to_string())),
+            },
+            None => (authority, None),
+        };
+        if host.is_empty() {
+            return Err(AddressParseError::EmptyHost);
+        }
+        let mut address = ServerAddress::new(host).secure(secure);
+        if let Some(port) = port {
+            address = address.port(port);
+        }
+        if !path.trim_matches('/').is_empty() {
+            match Version::from_path(path) {
+                Some(version) => address = address.version(version),
+                None => return Err(AddressParseError::UnknownVersion(path.to_string())),
+            }
+        }
+        Ok(address)
+    }
+}
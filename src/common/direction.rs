@@ -0,0 +1,50 @@
+use super::Position;
+
+/// Wraps a direction in degrees into `0.0..360.0`.
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::common::wrap_degrees;
+///
+/// assert_eq!(wrap_degrees(370.0), 10.0);
+/// assert_eq!(wrap_degrees(-90.0), 270.0);
+/// ```
+pub fn wrap_degrees(degrees: f32) -> f32 {
+    let wrapped = degrees.rem_euclid(360.0);
+    // rem_euclid of tiny negative values rounds up to exactly 360.0
+    if wrapped >= 360.0 {
+        0.0
+    } else {
+        wrapped
+    }
+}
+
+/// Converts a direction in degrees, as stored in poses, into radians.
+pub fn degrees_to_radians(degrees: f32) -> f32 {
+    degrees.to_radians()
+}
+
+/// Converts radians into a direction in degrees wrapped into `0.0..360.0`.
+pub fn radians_to_degrees(radians: f32) -> f32 {
+    wrap_degrees(radians.to_degrees())
+}
+
+/// Direction in degrees of looking from `from` to `to` on the ground plane, `None` if they are
+/// directly above each other.
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::common::direction_towards;
+///
+/// assert_eq!(direction_towards([0.0, 0.0, 0.0], [0.0, -5.0, 1.0]), Some(270.0));
+/// assert_eq!(direction_towards([1.0, 1.0, 0.0], [1.0, 1.0, 9.0]), None);
+/// ```
+pub fn direction_towards<A: Into<Position>, B: Into<Position>>(from: A, to: B) -> Option<f32> {
+    let offset = to.into() - from.into();
+    if offset.x == 0.0 && offset.y == 0.0 {
+        return None;
+    }
+    Some(radians_to_degrees(offset.y.atan2(offset.x)))
+}
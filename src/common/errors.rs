@@ -0,0 +1,207 @@
+use alloc::boxed::Box;
+use alloc::ffi::NulError;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{Debug, Display, Formatter};
+
+/// Error used to implement [`TryInto`] traits for packets.
+#[derive(Debug, Clone)]
+pub enum PacketParseError {
+    /// This error variant is raised if length of the packet in bytes
+    /// doesn't match the appropriate constant length: expected length, actual length.
+    SizeMismatch(usize, usize),
+    /// This error variant is raised if C-style string doesn't have terminating null byte
+    NoNullByte(Vec<u8>),
+    /// This error variant is raised if there are no bytes to parse at all
+    Empty,
+    /// This error variant is raised if the token byte doesn't belong to any packet
+    UnknownToken(u8),
+    /// This error variant is raised if a variable-length packet is shorter than
+    /// its header or its length byte says: minimum length, actual length.
+    Truncated(usize, usize),
+    /// This error variant is raised if the token byte belongs to a different packet than the one parsed
+    InvalidToken {
+        /// Token of the packet being parsed
+        expected: u8,
+        /// Token found in the bytes
+        got: u8,
+    },
+}
+
+impl Display for PacketParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PacketParseError::SizeMismatch(expected, got) => {
+                write!(
+                    f,
+                    "Size mismatch during parsing: expected: {}, got: {}",
+                    expected, got
+                )
+            }
+            PacketParseError::NoNullByte(bytes) => {
+                write!(
+                    f,
+                    "No null byte found while parsing following bytes: {:?}",
+                    bytes
+                )
+            }
+            PacketParseError::Empty => write!(f, "No bytes to parse"),
+            PacketParseError::UnknownToken(token) => {
+                write!(f, "Unknown token: {:?}", *token as char)
+            }
+            PacketParseError::Truncated(expected, got) => {
+                write!(
+                    f,
+                    "Truncated packet: expected at least: {}, got: {}",
+                    expected, got
+                )
+            }
+            PacketParseError::InvalidToken { expected, got } => {
+                write!(
+                    f,
+                    "Invalid token: expected: {:?}, got: {:?}",
+                    *expected as char, *got as char
+                )
+            }
+        }
+    }
+}
+
+impl Error for PacketParseError {}
+
+/// Error of everything above packet parsing: connecting, running bots and their callbacks.
+///
+/// Variants keep the error they were caused by, available through [`Error::source`],
+/// so failures can be matched on and reacted to, like reconnecting after [`BotError::Transport`].
+///
+/// # Examples
+///
+/// ## Failing a callback:
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+///
+/// fn check(said: &str) -> BotResult {
+///     match said.parse::<u32>() {
+///         Ok(_) => Ok(()),
+///         Err(e) => Err(BotError::callback(e)),
+///     }
+/// }
+///
+/// let error = check("one").unwrap_err();
+/// assert!(matches!(error, BotError::CallbackError(_)));
+/// assert!(std::error::Error::source(&error).is_some());
+/// ```
+#[derive(Debug, Clone)]
+pub enum BotError {
+    /// Connecting to the server failed
+    ConnectionFailed(Arc<dyn Error + Send + Sync>),
+    /// Server refused the join or didn't finish it properly
+    HandshakeFailed(String),
+    /// Sending to or receiving from an established connection failed
+    Transport(Arc<dyn Error + Send + Sync>),
+    /// Bytes received from the server aren't a valid packet
+    PacketParse(PacketParseError),
+    /// String to be sent contains a null byte
+    InvalidString(NulError),
+    /// Message to be sent is longer than a talk request allows, contains its length in bytes
+    MessageTooLong(usize),
+    /// Callback or other user code failed
+    CallbackError(Arc<dyn Error + Send + Sync>),
+    /// Part of the bot on the other side of a channel has stopped, contains its name
+    ChannelClosed(&'static str),
+    /// Server speaks a protocol version that isn't allowed or supported
+    UnsupportedVersion(String),
+    /// Anything else, described by the string
+    Other(String),
+}
+
+impl BotError {
+    /// Wraps any error returned by a callback.
+    pub fn callback<E: Into<Box<dyn Error + Send + Sync>>>(error: E) -> Self {
+        Self::CallbackError(Arc::from(error.into()))
+    }
+
+    /// Create error from [`String`]
+    #[deprecated(note = "Use one of the BotError variants instead")]
+    pub fn from_string(s: String) -> Self {
+        Self::Other(s)
+    }
+}
+
+impl Display for BotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BotError::ConnectionFailed(e) => write!(f, "Connection failed: {}", e),
+            BotError::HandshakeFailed(what) => write!(f, "Handshake failed: {}", what),
+            BotError::Transport(e) => write!(f, "Transport error: {}", e),
+            BotError::PacketParse(e) => write!(f, "Invalid packet: {}", e),
+            BotError::InvalidString(e) => write!(f, "Invalid string: {}", e),
+            BotError::MessageTooLong(len) => write!(f, "Message too long: {} bytes", len),
+            BotError::CallbackError(e) => write!(f, "Callback failed: {}", e),
+            BotError::ChannelClosed(what) => write!(f, "Channel closed, {} has stopped", what),
+            BotError::UnsupportedVersion(what) => write!(f, "Unsupported version: {}", what),
+            BotError::Other(what) => write!(f, "Runtime error: {}", what),
+        }
+    }
+}
+
+impl Error for BotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BotError::ConnectionFailed(e) | BotError::Transport(e) | BotError::CallbackError(e) => {
+                Some(e.as_ref())
+            }
+            BotError::PacketParse(e) => Some(e),
+            BotError::InvalidString(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<PacketParseError> for BotError {
+    fn from(e: PacketParseError) -> Self {
+        Self::PacketParse(e)
+    }
+}
+
+impl From<NulError> for BotError {
+    fn from(e: NulError) -> Self {
+        Self::InvalidString(e)
+    }
+}
+
+/// Former name of [`BotError`].
+#[deprecated(note = "Use BotError instead")]
+pub type RuntimeError = BotError;
+
+/// Error returned when parsing a [`ServerAddress`](super::ServerAddress) from a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressParseError {
+    /// Scheme is neither `ws` nor `wss`
+    UnknownScheme(String),
+    /// Host is empty
+    EmptyHost,
+    /// Port is not a valid number
+    InvalidPort(String),
+    /// Path points to a version this library doesn't support
+    UnknownVersion(String),
+}
+
+impl Display for AddressParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            AddressParseError::UnknownScheme(scheme) => {
+                write!(f, "Unknown scheme: {}, expected ws or wss", scheme)
+            }
+            AddressParseError::EmptyHost => write!(f, "Host is empty"),
+            AddressParseError::InvalidPort(port) => write!(f, "Invalid port: {}", port),
+            AddressParseError::UnknownVersion(path) => {
+                write!(f, "No supported version in path: {}", path)
+            }
+        }
+    }
+}
+
+impl Error for AddressParseError {}
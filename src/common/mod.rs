@@ -0,0 +1,34 @@
+//! Types shared by every protocol version.
+//!
+//! Version modules re-export these, so code that only needs math helpers or errors
+//! doesn't have to depend on a particular version.
+
+/// Server addresses
+pub mod address;
+/// Direction helpers
+#[cfg(feature = "std")]
+pub mod direction;
+/// Error types
+pub mod errors;
+/// Executors
+#[cfg(feature = "std")]
+pub mod runtime;
+/// Transports
+pub mod transport;
+/// Basic type aliases
+pub mod types;
+/// Vector math
+pub mod vec3;
+/// Protocol versions
+pub mod version;
+
+pub use address::*;
+#[cfg(feature = "std")]
+pub use direction::*;
+pub use errors::*;
+#[cfg(feature = "std")]
+pub use runtime::*;
+pub use transport::*;
+pub use types::*;
+pub use vec3::*;
+pub use version::*;
@@ -0,0 +1,41 @@
+use super::Runtime;
+
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+
+/// [`Runtime`] spawning on the global async-std executor.
+///
+/// # Examples
+///
+/// ## Running a bot on async-std:
+/// ```
+/// use asciicker_rs::common::{AsyncStdRuntime, AsyncStdWebSocketTransport};
+/// use asciicker_rs::y6::prelude::*;
+/// use std::sync::Arc;
+///
+/// # let tokio = tokio::runtime::Runtime::new().unwrap();
+/// # let server = tokio.block_on(Server::new(8).bind("127.0.0.1:0")).unwrap();
+/// # let address = format!("ws://{}", server.address);
+/// async_std::task::block_on(async {
+///     let bot = BotBuilder::new("bot")
+///         .address(&address)
+///         .runtime(Arc::new(AsyncStdRuntime))
+///         .transport(Arc::new(AsyncStdWebSocketTransport))
+///         .build();
+///     let (_threads, (player, _, _, _)) = bot.run().await.unwrap();
+///     assert_eq!(player.lock().await.nickname, "bot");
+/// });
+/// ```
+#[derive(Debug, Default, Copy, Clone)]
+pub struct AsyncStdRuntime;
+
+impl Runtime for AsyncStdRuntime {
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        async_std::task::spawn(task);
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(async_std::task::sleep(duration))
+    }
+}
@@ -0,0 +1,147 @@
+#[cfg(feature = "bot")]
+use super::Instant;
+
+use std::future::Future;
+use std::pin::Pin;
+#[cfg(feature = "bot")]
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_channel::oneshot;
+use futures_util::future::{abortable, AbortHandle, Aborted, BoxFuture, FutureExt};
+
+/// Runtime on async-std
+#[cfg(feature = "async-std")]
+mod async_std;
+/// Runtime on tokio
+#[cfg(feature = "tokio")]
+mod tokio;
+/// Runtime on the event loop of the browser
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+mod wasm;
+#[cfg(feature = "async-std")]
+pub use self::async_std::AsyncStdRuntime;
+#[cfg(feature = "tokio")]
+pub use self::tokio::TokioRuntime;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub use self::wasm::WasmRuntime;
+
+/// Executor the bot runs its tasks and timers on.
+///
+/// The bot itself only needs `futures`, spawning and sleeping go through this trait.
+/// Adapters for tokio ([`TokioRuntime`]) and async-std ([`AsyncStdRuntime`]) come with
+/// the features of the same name, the browser one (`WasmRuntime`) comes with the `wasm` feature
+/// on `wasm32` targets. Other executors like smol only need these two methods.
+pub trait Runtime: Send + Sync {
+    /// Runs `task` in the background.
+    fn spawn(&self, task: BoxFuture<'static, ()>);
+    /// Completes after `duration`.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// Runtime used when none is set: the browser in `wasm` builds, otherwise tokio if enabled,
+/// otherwise async-std.
+#[cfg(feature = "bot")]
+pub(crate) fn default_runtime() -> Option<Arc<dyn Runtime>> {
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    return Some(Arc::new(WasmRuntime));
+    #[cfg(all(feature = "tokio", not(all(feature = "wasm", target_arch = "wasm32"))))]
+    return Some(Arc::new(TokioRuntime));
+    #[cfg(all(
+        feature = "async-std",
+        not(any(feature = "tokio", all(feature = "wasm", target_arch = "wasm32")))
+    ))]
+    return Some(Arc::new(AsyncStdRuntime));
+    #[cfg(not(any(
+        feature = "tokio",
+        feature = "async-std",
+        all(feature = "wasm", target_arch = "wasm32")
+    )))]
+    return None;
+}
+
+/// Handle of a task spawned on a [`Runtime`], resolves to its output.
+///
+/// Dropping the handle detaches the task, [`Task::abort`] stops it.
+/// Resolves to [`Aborted`] if the task was aborted or its runtime dropped it.
+#[derive(Debug)]
+pub struct Task<T> {
+    output: oneshot::Receiver<T>,
+    abort: AbortHandle,
+}
+
+impl<T: Send + 'static> Task<T> {
+    /// Spawns `future` on `runtime`.
+    pub fn spawn<F: Future<Output = T> + Send + 'static>(runtime: &dyn Runtime, future: F) -> Self {
+        let (tx, output) = oneshot::channel();
+        let (future, abort) = abortable(future);
+        runtime.spawn(Box::pin(async move {
+            if let Ok(result) = future.await {
+                // Nobody waits for the output, the task was detached
+                let _ = tx.send(result);
+            }
+        }));
+        Self { output, abort }
+    }
+}
+
+impl<T> Task<T> {
+    /// Stops the task at its next await point.
+    pub fn abort(&self) {
+        self.abort.abort();
+    }
+}
+
+impl<T> Future for Task<T> {
+    type Output = Result<T, Aborted>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.output
+            .poll_unpin(cx)
+            .map(|output| output.map_err(|_| Aborted))
+    }
+}
+
+/// Ticks every `period` using a [`Runtime`], the first tick completes immediately.
+///
+/// A late tick doesn't cause a burst to catch up, the next one is scheduled `period` after it.
+/// Cancelling [`Ticker::tick`] doesn't lose the tick.
+#[cfg(feature = "bot")]
+pub(crate) struct Ticker {
+    runtime: Arc<dyn Runtime>,
+    period: Duration,
+    next: Instant,
+}
+
+#[cfg(feature = "bot")]
+impl Ticker {
+    /// Creates a new [`Ticker`] ticking every `period`.
+    pub(crate) fn new(runtime: Arc<dyn Runtime>, period: Duration) -> Self {
+        Self {
+            runtime,
+            period,
+            next: Instant::now(),
+        }
+    }
+
+    /// Creates a new [`Ticker`] ticking every `period`, the first tick completes after `period`.
+    pub(crate) fn delayed(runtime: Arc<dyn Runtime>, period: Duration) -> Self {
+        Self {
+            runtime,
+            period,
+            next: Instant::now() + period,
+        }
+    }
+
+    /// Waits for the next tick.
+    pub(crate) async fn tick(&mut self) {
+        let now = Instant::now();
+        if now < self.next {
+            self.runtime.sleep(self.next - now).await;
+            self.next += self.period;
+        } else {
+            self.next = now + self.period;
+        }
+    }
+}
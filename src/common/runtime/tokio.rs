@@ -0,0 +1,19 @@
+use super::Runtime;
+
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+
+/// [`Runtime`] spawning on the tokio runtime the bot was started from.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TokioRuntime;
+
+impl Runtime for TokioRuntime {
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        tokio::spawn(task);
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
@@ -0,0 +1,47 @@
+use super::Runtime;
+
+use std::time::Duration;
+
+use futures_channel::oneshot;
+use futures_util::future::BoxFuture;
+use js_sys::{Function, Reflect};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+/// [`Runtime`] spawning on the event loop of the browser, tasks run on the page's only thread.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct WasmRuntime;
+
+impl Runtime for WasmRuntime {
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        wasm_bindgen_futures::spawn_local(task);
+    }
+
+    /// Schedules on `setTimeout` of the global scope, so it works in windows and web workers alike.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the global scope has no `setTimeout`, a sleep that never waits would make
+    /// every loop of the bot spin.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let (tx, rx) = oneshot::channel();
+        let wake = Closure::once_into_js(move || {
+            let _ = tx.send(());
+        });
+        let timeout = duration.as_millis().min(i32::MAX as u128) as i32;
+        let global = js_sys::global();
+        let scheduled = Reflect::get(&global, &JsValue::from_str("setTimeout"))
+            .ok()
+            .and_then(|set_timeout| set_timeout.dyn_into::<Function>().ok())
+            .and_then(|set_timeout| {
+                set_timeout
+                    .call2(&global, &wake, &JsValue::from(timeout))
+                    .ok()
+            });
+        if scheduled.is_none() {
+            panic!("No setTimeout in the global scope, WasmRuntime can't sleep");
+        }
+        Box::pin(async move {
+            let _ = rx.await;
+        })
+    }
+}
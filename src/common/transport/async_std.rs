@@ -0,0 +1,116 @@
+use super::{CloseFrame, FrameHalves, FrameSink, FrameStream, Transport};
+use crate::common::errors::BotError;
+use crate::common::types::Bytes;
+
+use std::sync::Arc;
+
+use async_tungstenite::{
+    async_std::{connect_async, ConnectStream},
+    tungstenite::{
+        client::IntoClientRequest,
+        http::header::{HeaderName, HeaderValue},
+        Message as ws_Message,
+    },
+    WebSocketStream,
+};
+use futures_util::{
+    future::BoxFuture,
+    stream::{SplitSink, SplitStream},
+    SinkExt, StreamExt,
+};
+
+/// Websocket transport on async-std, binary websocket messages are packets.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct AsyncStdWebSocketTransport;
+
+impl Transport for AsyncStdWebSocketTransport {
+    fn connect<'a>(&'a self, address: &'a str) -> BoxFuture<'a, Result<FrameHalves, BotError>> {
+        self.connect_with_headers(address, &[])
+    }
+
+    fn connect_with_headers<'a>(
+        &'a self,
+        address: &'a str,
+        headers: &'a [(String, String)],
+    ) -> BoxFuture<'a, Result<FrameHalves, BotError>> {
+        Box::pin(async move {
+            let mut request = address
+                .into_client_request()
+                .map_err(|e| BotError::ConnectionFailed(Arc::new(e)))?;
+            for (name, value) in headers {
+                let name = HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|e| BotError::ConnectionFailed(Arc::new(e)))?;
+                let value = HeaderValue::from_str(value)
+                    .map_err(|e| BotError::ConnectionFailed(Arc::new(e)))?;
+                request.headers_mut().append(name, value);
+            }
+            match connect_async(request).await {
+                Ok((ws, _)) => {
+                    let (sink, stream) = ws.split();
+                    Ok((
+                        Box::new(WsFrameSink(sink)) as Box<dyn FrameSink>,
+                        Box::new(WsFrameStream {
+                            stream,
+                            close: None,
+                        }) as Box<dyn FrameStream>,
+                    ))
+                }
+                Err(e) => Err(BotError::ConnectionFailed(Arc::new(e))),
+            }
+        })
+    }
+}
+
+struct WsFrameSink(SplitSink<WebSocketStream<ConnectStream>, ws_Message>);
+
+impl FrameSink for WsFrameSink {
+    fn send_frame(&mut self, frame: Bytes) -> BoxFuture<'_, Result<(), BotError>> {
+        Box::pin(async move {
+            self.0
+                .send(ws_Message::Binary(frame))
+                .await
+                .map_err(|e| BotError::Transport(Arc::new(e)))
+        })
+    }
+
+    fn close(&mut self) -> BoxFuture<'_, Result<(), BotError>> {
+        Box::pin(async move {
+            self.0
+                .close()
+                .await
+                .map_err(|e| BotError::Transport(Arc::new(e)))
+        })
+    }
+}
+
+struct WsFrameStream {
+    stream: SplitStream<WebSocketStream<ConnectStream>>,
+    close: Option<CloseFrame>,
+}
+
+impl FrameStream for WsFrameStream {
+    fn next_frame(&mut self) -> BoxFuture<'_, Option<Result<Bytes, BotError>>> {
+        Box::pin(async move {
+            loop {
+                match self.stream.next().await? {
+                    Ok(ws_Message::Binary(data)) => return Some(Ok(data)),
+                    Ok(ws_Message::Close(frame)) => {
+                        self.close = frame.map(|frame| CloseFrame {
+                            code: frame.code.into(),
+                            reason: frame.reason.into_owned(),
+                        });
+                        return None;
+                    }
+                    // Tungstenite queues the pong and sends it on the next read, which is right away
+                    Ok(ws_Message::Ping(_)) => {}
+                    Ok(_) => {} // Text, pong
+                    Err(e) => return Some(Err(BotError::Transport(Arc::new(e)))),
+                }
+            }
+        })
+    }
+
+    fn close_frame(&self) -> Option<CloseFrame> {
+        self.close.clone()
+    }
+}
@@ -0,0 +1,224 @@
+use super::errors::BotError;
+use super::types::Bytes;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+#[cfg(all(feature = "packets", feature = "std"))]
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use futures_util::future::BoxFuture;
+
+/// Transports running on async-std
+#[cfg(feature = "async-std")]
+mod async_std;
+/// Transports through SOCKS5 and HTTP proxies
+#[cfg(feature = "tokio")]
+mod proxy;
+/// Transports with a custom TLS setup
+#[cfg(all(feature = "tokio", any(feature = "rustls", feature = "native-tls")))]
+mod tls;
+/// Transports running on tokio
+#[cfg(feature = "tokio")]
+mod tokio;
+/// Transports running in the browser
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+mod wasm;
+#[cfg(feature = "async-std")]
+pub use self::async_std::AsyncStdWebSocketTransport;
+#[cfg(feature = "tokio")]
+pub use self::proxy::{OutboundProxy, ProxyKind, ProxyTransport};
+#[cfg(all(feature = "tokio", any(feature = "rustls", feature = "native-tls")))]
+pub use self::tls::{TlsConfig, TlsWebSocketTransport};
+#[cfg(feature = "server")]
+pub(crate) use self::tokio::ws_halves;
+#[cfg(feature = "tokio")]
+pub use self::tokio::{TcpTransport, WebSocketTransport, WsStream};
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub use self::wasm::WasmWebSocketTransport;
+
+/// Transport used when none is set: websockets of the browser in `wasm` builds,
+/// otherwise on tokio if enabled, otherwise on async-std.
+#[cfg(all(feature = "packets", feature = "std"))]
+pub(crate) fn default_transport() -> Arc<dyn Transport> {
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    return Arc::new(WasmWebSocketTransport);
+    #[cfg(all(feature = "tokio", not(all(feature = "wasm", target_arch = "wasm32"))))]
+    return Arc::new(WebSocketTransport);
+    #[cfg(all(
+        feature = "async-std",
+        not(any(feature = "tokio", all(feature = "wasm", target_arch = "wasm32")))
+    ))]
+    return Arc::new(AsyncStdWebSocketTransport);
+    #[cfg(not(any(
+        feature = "tokio",
+        feature = "async-std",
+        all(feature = "wasm", target_arch = "wasm32")
+    )))]
+    return Arc::new(NoTransport);
+}
+
+/// Transport of builds without a runtime feature, fails to connect anywhere.
+#[cfg(all(
+    feature = "packets",
+    feature = "std",
+    not(any(
+        feature = "tokio",
+        feature = "async-std",
+        all(feature = "wasm", target_arch = "wasm32")
+    ))
+))]
+struct NoTransport;
+
+#[cfg(all(
+    feature = "packets",
+    feature = "std",
+    not(any(
+        feature = "tokio",
+        feature = "async-std",
+        all(feature = "wasm", target_arch = "wasm32")
+    ))
+))]
+impl Transport for NoTransport {
+    fn connect<'a>(&'a self, _: &'a str) -> BoxFuture<'a, Result<FrameHalves, BotError>> {
+        Box::pin(async {
+            Err(BotError::ConnectionFailed(Arc::from(Box::<
+                dyn core::error::Error + Send + Sync,
+            >::from(
+                "No transport, enable the tokio, async-std or wasm feature or set one",
+            ))))
+        })
+    }
+}
+
+/// Type alias for boxed halves of a connection returned by [`Transport::connect`].
+pub type FrameHalves = (Box<dyn FrameSink>, Box<dyn FrameStream>);
+
+/// Sending half of a connection.
+pub trait FrameSink: Send {
+    /// Sends one complete packet.
+    fn send_frame(&mut self, frame: Bytes) -> BoxFuture<'_, Result<(), BotError>>;
+    /// Sends one complete packet from a borrowed buffer, so the caller can reuse it.
+    ///
+    /// Defaults to copying it into [`FrameSink::send_frame`].
+    fn send_slice<'a>(&'a mut self, frame: &'a [u8]) -> BoxFuture<'a, Result<(), BotError>> {
+        self.send_frame(frame.to_vec())
+    }
+    /// Closes the connection gracefully.
+    fn close(&mut self) -> BoxFuture<'_, Result<(), BotError>>;
+}
+
+/// Code and reason the peer closed a websocket connection with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloseFrame {
+    /// Close code, see [RFC 6455](https://www.rfc-editor.org/rfc/rfc6455#section-7.4)
+    pub code: u16,
+    /// Reason given by the peer, may be empty
+    pub reason: String,
+}
+
+/// Receiving half of a connection.
+pub trait FrameStream: Send {
+    /// Receives one complete packet, [`None`] means the connection was closed.
+    ///
+    /// Control frames are handled by the transport itself: pings are answered with pongs
+    /// and a close frame ends the stream, see [`FrameStream::close_frame`].
+    fn next_frame(&mut self) -> BoxFuture<'_, Option<Result<Bytes, BotError>>>;
+    /// Close frame the peer ended the connection with, once [`FrameStream::next_frame`]
+    /// returned [`None`].
+    ///
+    /// [`None`] if the peer didn't send one or the transport has no close frames, the default.
+    fn close_frame(&self) -> Option<CloseFrame> {
+        None
+    }
+    /// Hands a frame returned by [`FrameStream::next_frame`] back once it was processed,
+    /// so its allocation can be reused. Does nothing by default.
+    fn recycle(&mut self, _frame: Bytes) {}
+}
+
+/// Pool of reusable byte buffers.
+///
+/// Buffers taken from the pool keep their capacity when given back,
+/// so a steady stream of similarly sized packets stops allocating after warming up.
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::common::BufferPool;
+///
+/// let mut pool = BufferPool::new(4);
+/// let mut buffer = pool.take();
+/// buffer.extend_from_slice(b"hello");
+/// let capacity = buffer.capacity();
+/// pool.give(buffer);
+///
+/// let buffer = pool.take();
+/// assert!(buffer.is_empty());
+/// assert_eq!(buffer.capacity(), capacity);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct BufferPool {
+    buffers: Vec<Bytes>,
+    limit: usize,
+}
+
+impl BufferPool {
+    /// Constructs a new [`BufferPool`] keeping at most `limit` idle buffers.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            buffers: Vec::with_capacity(limit),
+            limit,
+        }
+    }
+
+    /// Takes an empty buffer, allocating only if the pool is empty.
+    pub fn take(&mut self) -> Bytes {
+        self.buffers.pop().unwrap_or_default()
+    }
+
+    /// Gives a buffer back, it is dropped if the pool is full.
+    pub fn give(&mut self, mut buffer: Bytes) {
+        if self.buffers.len() < self.limit {
+            buffer.clear();
+            self.buffers.push(buffer);
+        }
+    }
+
+    /// Number of idle buffers in the pool.
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Returns `true` if there are no idle buffers.
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+}
+
+/// Way of reaching a server: opens a connection and splits it into [`FrameSink`] and [`FrameStream`].
+pub trait Transport: Send + Sync {
+    /// Connects to `address`.
+    fn connect<'a>(&'a self, address: &'a str) -> BoxFuture<'a, Result<FrameHalves, BotError>>;
+
+    /// Connects to `address` like [`Transport::connect`], sending extra `headers` with the handshake.
+    ///
+    /// Transports without an HTTP handshake, like raw TCP, ignore the headers.
+    fn connect_with_headers<'a>(
+        &'a self,
+        address: &'a str,
+        headers: &'a [(String, String)],
+    ) -> BoxFuture<'a, Result<FrameHalves, BotError>> {
+        let _ = headers;
+        self.connect(address)
+    }
+}
+
+/// Splits a byte stream into packets, used by transports without message boundaries.
+///
+/// Every connection gets a fresh instance, so implementations may keep state
+/// (for example to tell apart packets sharing a token).
+pub trait Framing: Send {
+    /// Returns length of the first packet in `buffer`,
+    /// [`None`] if more bytes are needed to tell it.
+    fn frame_len(&mut self, buffer: &[u8]) -> Result<Option<usize>, BotError>;
+}
@@ -0,0 +1,310 @@
+use super::tokio::{ws_halves, ws_request};
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+use super::TlsConfig;
+use super::{FrameHalves, Transport};
+use crate::common::errors::BotError;
+
+use std::io;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+#[cfg(not(any(feature = "rustls", feature = "native-tls")))]
+use tokio_tungstenite::MaybeTlsStream;
+
+/// Protocol spoken with an [`OutboundProxy`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProxyKind {
+    /// SOCKS5, the proxy resolves host names itself
+    Socks5,
+    /// HTTP `CONNECT` tunnel
+    Http,
+}
+
+/// Proxy that outbound connections are tunneled through, see [`ProxyTransport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboundProxy {
+    /// Protocol of the proxy
+    pub kind: ProxyKind,
+    /// `host:port` of the proxy
+    pub address: String,
+    /// Username and password, if the proxy requires them
+    pub auth: Option<(String, String)>,
+}
+
+impl OutboundProxy {
+    /// Constructs a new SOCKS5 [`OutboundProxy`] at `address` (`host:port`).
+    pub fn socks5<S: Into<String>>(address: S) -> Self {
+        Self {
+            kind: ProxyKind::Socks5,
+            address: address.into(),
+            auth: None,
+        }
+    }
+
+    /// Constructs a new HTTP `CONNECT` [`OutboundProxy`] at `address` (`host:port`).
+    pub fn http<S: Into<String>>(address: S) -> Self {
+        Self {
+            kind: ProxyKind::Http,
+            address: address.into(),
+            auth: None,
+        }
+    }
+
+    /// Sets the username and password to authenticate with.
+    pub fn auth<S: Into<String>>(mut self, username: S, password: S) -> Self {
+        self.auth = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Connects to the proxy and asks it for a tunnel to `host:port`.
+    pub async fn tunnel(&self, host: &str, port: u16) -> Result<TcpStream, BotError> {
+        let mut stream = TcpStream::connect(&self.address)
+            .await
+            .map_err(|e| BotError::ConnectionFailed(Arc::new(e)))?;
+        let handshake = match self.kind {
+            ProxyKind::Socks5 => self.socks5_handshake(&mut stream, host, port).await,
+            ProxyKind::Http => self.http_handshake(&mut stream, host, port).await,
+        };
+        handshake.map_err(|e| BotError::ConnectionFailed(Arc::new(e)))?;
+        Ok(stream)
+    }
+
+    /// Negotiates a SOCKS5 `CONNECT` as described in RFC 1928 and RFC 1929.
+    async fn socks5_handshake(
+        &self,
+        stream: &mut TcpStream,
+        host: &str,
+        port: u16,
+    ) -> io::Result<()> {
+        let too_long = |field: &str| field.len() > u8::MAX as usize;
+        if too_long(host)
+            || matches!(&self.auth, Some((user, pass)) if too_long(user) || too_long(pass))
+        {
+            return Err(proxy_error("SOCKS5 fields are limited to 255 bytes"));
+        }
+        let method = if self.auth.is_some() { 2 } else { 0 };
+        stream.write_all(&[5, 1, method]).await?;
+        let mut reply = [0u8; 2];
+        stream.read_exact(&mut reply).await?;
+        if reply != [5, method] {
+            return Err(proxy_error(
+                "SOCKS5 proxy refused the authentication method",
+            ));
+        }
+        if let Some((username, password)) = &self.auth {
+            let mut request = vec![1, username.len() as u8];
+            request.extend_from_slice(username.as_bytes());
+            request.push(password.len() as u8);
+            request.extend_from_slice(password.as_bytes());
+            stream.write_all(&request).await?;
+            stream.read_exact(&mut reply).await?;
+            if reply[1] != 0 {
+                return Err(proxy_error("SOCKS5 proxy rejected the credentials"));
+            }
+        }
+
+        let mut request = vec![5, 1, 0];
+        match host
+            .trim_matches(|c| c == '[' || c == ']')
+            .parse::<IpAddr>()
+        {
+            Ok(IpAddr::V4(ip)) => {
+                request.push(1);
+                request.extend_from_slice(&ip.octets());
+            }
+            Ok(IpAddr::V6(ip)) => {
+                request.push(4);
+                request.extend_from_slice(&ip.octets());
+            }
+            Err(_) => {
+                request.extend_from_slice(&[3, host.len() as u8]);
+                request.extend_from_slice(host.as_bytes());
+            }
+        }
+        request.extend_from_slice(&port.to_be_bytes());
+        stream.write_all(&request).await?;
+
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).await?;
+        if reply[1] != 0 {
+            return Err(proxy_error(&format!(
+                "SOCKS5 proxy failed to connect, reply code {}",
+                reply[1]
+            )));
+        }
+        // Skip the address the proxy bound, nobody needs it
+        let bound = match reply[3] {
+            1 => 4,
+            4 => 16,
+            3 => stream.read_u8().await? as usize,
+            _ => return Err(proxy_error("SOCKS5 proxy sent an unknown address type")),
+        };
+        let mut skipped = vec![0u8; bound + 2];
+        stream.read_exact(&mut skipped).await?;
+        Ok(())
+    }
+
+    /// Opens an HTTP `CONNECT` tunnel.
+    async fn http_handshake(
+        &self,
+        stream: &mut TcpStream,
+        host: &str,
+        port: u16,
+    ) -> io::Result<()> {
+        let mut request = format!(
+            "CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n",
+            host = host,
+            port = port
+        );
+        if let Some((username, password)) = &self.auth {
+            request += &format!(
+                "Proxy-Authorization: Basic {}\r\n",
+                base64::encode(format!("{}:{}", username, password))
+            );
+        }
+        request += "\r\n";
+        stream.write_all(request.as_bytes()).await?;
+
+        // Read byte by byte, whatever follows the response belongs to the tunnel
+        let mut response = vec![];
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() > MAX_PROXY_RESPONSE {
+                return Err(proxy_error("HTTP proxy sent an oversized response"));
+            }
+            response.push(stream.read_u8().await?);
+        }
+        let status = String::from_utf8_lossy(&response);
+        let status = status.lines().next().unwrap_or_default();
+        match status.split_whitespace().nth(1) {
+            Some("200") => Ok(()),
+            _ => Err(proxy_error(&format!(
+                "HTTP proxy refused the tunnel: {}",
+                status
+            ))),
+        }
+    }
+}
+
+/// Longest HTTP proxy response accepted before the tunnel starts.
+const MAX_PROXY_RESPONSE: usize = 8192;
+
+fn proxy_error(message: &str) -> io::Error {
+    io::Error::other(message)
+}
+
+/// Websocket transport tunneling through an [`OutboundProxy`], for bots on restricted networks.
+///
+/// `wss://` addresses are encrypted end to end, the proxy only sees the host and port.
+///
+/// # Examples
+///
+/// ## Connecting through an HTTP proxy:
+/// ```
+/// use asciicker_rs::common::OutboundProxy;
+/// use asciicker_rs::y6::prelude::*;
+/// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+/// use tokio::net::{TcpListener, TcpStream};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let server = Server::new(8).bind("127.0.0.1:0").await.unwrap();
+/// # let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+/// # let proxy_address = listener.local_addr().unwrap();
+/// # // Bare HTTP CONNECT proxy
+/// # tokio::spawn(async move {
+/// #     let (mut client, _) = listener.accept().await.unwrap();
+/// #     let mut request = vec![];
+/// #     while !request.ends_with(b"\r\n\r\n") {
+/// #         request.push(client.read_u8().await.unwrap());
+/// #     }
+/// #     let target = String::from_utf8(request).unwrap();
+/// #     let target = target.split_whitespace().nth(1).unwrap().to_string();
+/// #     let mut upstream = TcpStream::connect(target).await.unwrap();
+/// #     client.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").await.unwrap();
+/// #     let _ = tokio::io::copy_bidirectional(&mut client, &mut upstream).await;
+/// # });
+/// // Same as `.transport(Arc::new(ProxyTransport::new(proxy)))`
+/// let bot = BotBuilder::new("tunneled")
+///     .address(format!("ws://{}", server.address))
+///     .proxy(OutboundProxy::http(proxy_address.to_string()))
+///     .build();
+/// let (_threads, (player, _, _, _)) = bot.run().await.unwrap();
+/// assert_eq!(player.lock().await.nickname, "tunneled");
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ProxyTransport {
+    proxy: OutboundProxy,
+    #[cfg(any(feature = "rustls", feature = "native-tls"))]
+    tls: Option<TlsConfig>,
+}
+
+impl ProxyTransport {
+    /// Constructs a new [`ProxyTransport`] tunneling through `proxy`.
+    pub fn new(proxy: OutboundProxy) -> Self {
+        Self {
+            proxy,
+            #[cfg(any(feature = "rustls", feature = "native-tls"))]
+            tls: None,
+        }
+    }
+
+    /// Sets the [`TlsConfig`] of `wss://` connections, default roots are used otherwise.
+    #[cfg(any(feature = "rustls", feature = "native-tls"))]
+    pub fn tls(mut self, config: TlsConfig) -> Self {
+        self.tls = Some(config);
+        self
+    }
+}
+
+impl Transport for ProxyTransport {
+    fn connect<'a>(&'a self, address: &'a str) -> BoxFuture<'a, Result<FrameHalves, BotError>> {
+        self.connect_with_headers(address, &[])
+    }
+
+    fn connect_with_headers<'a>(
+        &'a self,
+        address: &'a str,
+        headers: &'a [(String, String)],
+    ) -> BoxFuture<'a, Result<FrameHalves, BotError>> {
+        Box::pin(async move {
+            let request = ws_request(address, headers)?;
+            let uri = request.uri();
+            let host = uri.host().unwrap_or_default().to_string();
+            let port = uri
+                .port_u16()
+                .unwrap_or(if uri.scheme_str() == Some("wss") {
+                    443
+                } else {
+                    80
+                });
+            #[cfg(not(any(feature = "rustls", feature = "native-tls")))]
+            if uri.scheme_str() == Some("wss") {
+                return Err(BotError::ConnectionFailed(Arc::new(proxy_error(
+                    "wss:// needs the rustls or native-tls feature",
+                ))));
+            }
+            let stream = self.proxy.tunnel(&host, port).await?;
+            #[cfg(any(feature = "rustls", feature = "native-tls"))]
+            let connected = tokio_tungstenite::client_async_tls_with_config(
+                request,
+                stream,
+                None,
+                self.tls.clone().map(Into::into),
+            )
+            .await;
+            #[cfg(not(any(feature = "rustls", feature = "native-tls")))]
+            let connected =
+                tokio_tungstenite::client_async(request, MaybeTlsStream::Plain(stream)).await;
+            match connected {
+                Ok((ws, _)) => Ok(ws_halves(ws)),
+                Err(e) => Err(BotError::ConnectionFailed(Arc::new(e))),
+            }
+        })
+    }
+}
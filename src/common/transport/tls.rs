@@ -0,0 +1,159 @@
+use super::tokio::{ws_halves, ws_request};
+use super::{FrameHalves, Transport};
+use crate::common::errors::BotError;
+
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+use tokio_tungstenite::Connector;
+
+/// TLS setup of a [`TlsWebSocketTransport`], one variant per enabled TLS feature.
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum TlsConfig {
+    /// `rustls` client configuration
+    #[cfg(feature = "rustls")]
+    Rustls(Arc<rustls::ClientConfig>),
+    /// `native-tls` connector
+    #[cfg(feature = "native-tls")]
+    NativeTls(native_tls::TlsConnector),
+}
+
+impl TlsConfig {
+    /// Trusts `certificate` (DER encoded) on top of the usual roots,
+    /// for servers with a self-signed certificate.
+    ///
+    /// Uses `rustls` with the webpki roots if the `rustls` feature is enabled,
+    /// otherwise `native-tls` with the roots of the system.
+    pub fn with_root_certificate(certificate: &[u8]) -> Result<Self, BotError> {
+        #[cfg(feature = "rustls")]
+        {
+            let mut roots = rustls::RootCertStore::empty();
+            roots.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|root| {
+                rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
+                    root.subject,
+                    root.spki,
+                    root.name_constraints,
+                )
+            }));
+            roots
+                .add(&rustls::Certificate(certificate.to_vec()))
+                .map_err(|e| BotError::Other(format!("Invalid certificate: {}", e)))?;
+            let config = rustls::ClientConfig::builder()
+                .with_safe_defaults()
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+            Ok(Self::Rustls(Arc::new(config)))
+        }
+        #[cfg(not(feature = "rustls"))]
+        {
+            let certificate = native_tls::Certificate::from_der(certificate)
+                .map_err(|e| BotError::Other(format!("Invalid certificate: {}", e)))?;
+            let connector = native_tls::TlsConnector::builder()
+                .add_root_certificate(certificate)
+                .build()
+                .map_err(|e| BotError::Other(format!("Invalid TLS setup: {}", e)))?;
+            Ok(Self::NativeTls(connector))
+        }
+    }
+}
+
+impl From<TlsConfig> for Connector {
+    fn from(config: TlsConfig) -> Self {
+        match config {
+            #[cfg(feature = "rustls")]
+            TlsConfig::Rustls(config) => Connector::Rustls(config),
+            #[cfg(feature = "native-tls")]
+            TlsConfig::NativeTls(connector) => Connector::NativeTls(connector),
+        }
+    }
+}
+
+/// Websocket transport with its own [`TlsConfig`] for `wss://` addresses.
+///
+/// [`WebSocketTransport`](super::WebSocketTransport) already connects to `wss://` addresses
+/// with either TLS feature, this one is for custom roots and client settings.
+/// Plain `ws://` addresses connect without TLS like before.
+///
+/// # Examples
+///
+/// ## Connecting to a server with a self-signed certificate:
+/// ```
+/// use asciicker_rs::common::{TlsConfig, TlsWebSocketTransport};
+/// use asciicker_rs::y6::prelude::*;
+/// use std::sync::Arc;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let certificate = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+/// # let der = certificate.serialize_der().unwrap();
+/// # let server = Server::new(8).bind("127.0.0.1:0").await.unwrap();
+/// # let upstream = server.address;
+/// # use tokio_rustls::rustls;
+/// # let tls = rustls::ServerConfig::builder()
+/// #     .with_safe_defaults()
+/// #     .with_no_client_auth()
+/// #     .with_single_cert(
+/// #         vec![rustls::Certificate(der.clone())],
+/// #         rustls::PrivateKey(certificate.serialize_private_key_der()),
+/// #     )
+/// #     .unwrap();
+/// # let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(tls));
+/// # let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+/// # let port = listener.local_addr().unwrap().port();
+/// # // Terminates TLS in front of the plain server
+/// # tokio::spawn(async move {
+/// #     loop {
+/// #         let (stream, _) = listener.accept().await.unwrap();
+/// #         let acceptor = acceptor.clone();
+/// #         tokio::spawn(async move {
+/// #             let mut tls = acceptor.accept(stream).await.unwrap();
+/// #             let mut plain = tokio::net::TcpStream::connect(upstream).await.unwrap();
+/// #             let _ = tokio::io::copy_bidirectional(&mut tls, &mut plain).await;
+/// #         });
+/// #     }
+/// # });
+/// // `der` is the certificate of the server
+/// let transport = TlsWebSocketTransport::new(TlsConfig::with_root_certificate(&der).unwrap());
+/// let bot = BotBuilder::new("secure")
+///     .address(format!("wss://localhost:{}", port))
+///     .transport(Arc::new(transport))
+///     .build();
+/// let (_threads, (player, _, _, _)) = bot.run().await.unwrap();
+/// assert_eq!(player.lock().await.nickname, "secure");
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct TlsWebSocketTransport {
+    config: TlsConfig,
+}
+
+impl TlsWebSocketTransport {
+    /// Constructs a new [`TlsWebSocketTransport`] connecting with `config`.
+    pub fn new(config: TlsConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Transport for TlsWebSocketTransport {
+    fn connect<'a>(&'a self, address: &'a str) -> BoxFuture<'a, Result<FrameHalves, BotError>> {
+        self.connect_with_headers(address, &[])
+    }
+
+    fn connect_with_headers<'a>(
+        &'a self,
+        address: &'a str,
+        headers: &'a [(String, String)],
+    ) -> BoxFuture<'a, Result<FrameHalves, BotError>> {
+        Box::pin(async move {
+            let request = ws_request(address, headers)?;
+            let connector = Connector::from(self.config.clone());
+            match tokio_tungstenite::connect_async_tls_with_config(request, None, Some(connector))
+                .await
+            {
+                Ok((ws, _)) => Ok(ws_halves(ws)),
+                Err(e) => Err(BotError::ConnectionFailed(Arc::new(e))),
+            }
+        })
+    }
+}
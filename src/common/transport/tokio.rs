@@ -0,0 +1,249 @@
+use super::{BufferPool, CloseFrame, FrameHalves, FrameSink, FrameStream, Framing, Transport};
+use crate::common::errors::BotError;
+use crate::common::types::Bytes;
+
+use std::sync::Arc;
+
+use futures_util::{
+    future::BoxFuture,
+    stream::{SplitSink, SplitStream},
+    SinkExt, StreamExt,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpStream,
+    },
+};
+use tokio_tungstenite::{
+    tungstenite::{
+        client::IntoClientRequest,
+        handshake::client::Request,
+        http::header::{HeaderName, HeaderValue},
+        Message as ws_Message,
+    },
+    MaybeTlsStream, WebSocketStream,
+};
+
+/// Type alias for the websocket stream used by [`WebSocketTransport`].
+pub type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Default transport, binary websocket messages are packets.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct WebSocketTransport;
+
+impl Transport for WebSocketTransport {
+    fn connect<'a>(&'a self, address: &'a str) -> BoxFuture<'a, Result<FrameHalves, BotError>> {
+        self.connect_with_headers(address, &[])
+    }
+
+    fn connect_with_headers<'a>(
+        &'a self,
+        address: &'a str,
+        headers: &'a [(String, String)],
+    ) -> BoxFuture<'a, Result<FrameHalves, BotError>> {
+        Box::pin(async move {
+            match tokio_tungstenite::connect_async(ws_request(address, headers)?).await {
+                Ok((ws, _)) => Ok(ws_halves(ws)),
+                Err(e) => Err(BotError::ConnectionFailed(Arc::new(e))),
+            }
+        })
+    }
+}
+
+/// Builds the handshake request of `address` with extra `headers`.
+pub(crate) fn ws_request(address: &str, headers: &[(String, String)]) -> Result<Request, BotError> {
+    let mut request = address
+        .into_client_request()
+        .map_err(|e| BotError::ConnectionFailed(Arc::new(e)))?;
+    for (name, value) in headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| BotError::ConnectionFailed(Arc::new(e)))?;
+        let value =
+            HeaderValue::from_str(value).map_err(|e| BotError::ConnectionFailed(Arc::new(e)))?;
+        request.headers_mut().append(name, value);
+    }
+    Ok(request)
+}
+
+/// Splits an established websocket connection, used by clients and servers alike.
+pub(crate) fn ws_halves(ws: WsStream) -> FrameHalves {
+    let (sink, stream) = ws.split();
+    (
+        Box::new(WsFrameSink(sink)) as Box<dyn FrameSink>,
+        Box::new(WsFrameStream {
+            stream,
+            close: None,
+        }) as Box<dyn FrameStream>,
+    )
+}
+
+struct WsFrameSink(SplitSink<WsStream, ws_Message>);
+
+impl FrameSink for WsFrameSink {
+    fn send_frame(&mut self, frame: Bytes) -> BoxFuture<'_, Result<(), BotError>> {
+        Box::pin(async move {
+            self.0
+                .send(ws_Message::Binary(frame))
+                .await
+                .map_err(|e| BotError::Transport(Arc::new(e)))
+        })
+    }
+
+    fn close(&mut self) -> BoxFuture<'_, Result<(), BotError>> {
+        Box::pin(async move {
+            self.0
+                .close()
+                .await
+                .map_err(|e| BotError::Transport(Arc::new(e)))
+        })
+    }
+}
+
+struct WsFrameStream {
+    stream: SplitStream<WsStream>,
+    close: Option<CloseFrame>,
+}
+
+impl FrameStream for WsFrameStream {
+    fn next_frame(&mut self) -> BoxFuture<'_, Option<Result<Bytes, BotError>>> {
+        Box::pin(async move {
+            loop {
+                match self.stream.next().await? {
+                    Ok(ws_Message::Binary(data)) => return Some(Ok(data)),
+                    Ok(ws_Message::Close(frame)) => {
+                        self.close = frame.map(|frame| CloseFrame {
+                            code: frame.code.into(),
+                            reason: frame.reason.into_owned(),
+                        });
+                        return None;
+                    }
+                    // Tungstenite queues the pong and sends it on the next read, which is right away
+                    Ok(ws_Message::Ping(_)) => {}
+                    Ok(_) => {} // Text, pong
+                    Err(e) => return Some(Err(BotError::Transport(Arc::new(e)))),
+                }
+            }
+        })
+    }
+
+    fn close_frame(&self) -> Option<CloseFrame> {
+        self.close.clone()
+    }
+}
+
+/// Raw TCP transport for servers without a websocket stack.
+///
+/// Packets are written back to back, so the stream is split using a [`Framing`] provided by the
+/// version module, for example [`ClientFraming`](crate::y6::packets::ClientFraming) for Y6.
+///
+/// Address is `host:port`, optionally prefixed with `tcp://`.
+pub struct TcpTransport {
+    framing: Box<dyn Fn() -> Box<dyn Framing> + Send + Sync>,
+}
+
+impl TcpTransport {
+    /// Constructs a new [`TcpTransport`], `framing` is called once per connection.
+    pub fn new<F: Fn() -> Box<dyn Framing> + Send + Sync + 'static>(framing: F) -> Self {
+        Self {
+            framing: Box::new(framing),
+        }
+    }
+}
+
+impl Transport for TcpTransport {
+    fn connect<'a>(&'a self, address: &'a str) -> BoxFuture<'a, Result<FrameHalves, BotError>> {
+        Box::pin(async move {
+            let address = address.trim_start_matches("tcp://").trim_end_matches('/');
+            match TcpStream::connect(address).await {
+                Ok(stream) => {
+                    let (read, write) = stream.into_split();
+                    Ok((
+                        Box::new(TcpFrameSink(write)) as Box<dyn FrameSink>,
+                        Box::new(TcpFrameStream {
+                            read,
+                            framing: (self.framing)(),
+                            buffer: Vec::with_capacity(READ_CHUNK_SIZE * 2),
+                            pool: BufferPool::new(TCP_POOL_SIZE),
+                        }) as Box<dyn FrameStream>,
+                    ))
+                }
+                Err(e) => Err(BotError::ConnectionFailed(Arc::new(e))),
+            }
+        })
+    }
+}
+
+struct TcpFrameSink(OwnedWriteHalf);
+
+impl FrameSink for TcpFrameSink {
+    fn send_frame(&mut self, frame: Bytes) -> BoxFuture<'_, Result<(), BotError>> {
+        Box::pin(async move {
+            self.0
+                .write_all(&frame)
+                .await
+                .map_err(|e| BotError::Transport(Arc::new(e)))
+        })
+    }
+
+    fn send_slice<'a>(&'a mut self, frame: &'a [u8]) -> BoxFuture<'a, Result<(), BotError>> {
+        Box::pin(async move {
+            self.0
+                .write_all(frame)
+                .await
+                .map_err(|e| BotError::Transport(Arc::new(e)))
+        })
+    }
+
+    fn close(&mut self) -> BoxFuture<'_, Result<(), BotError>> {
+        Box::pin(async move {
+            self.0
+                .shutdown()
+                .await
+                .map_err(|e| BotError::Transport(Arc::new(e)))
+        })
+    }
+}
+
+/// Bytes requested from the socket per read.
+const READ_CHUNK_SIZE: usize = 4096;
+/// Idle frame buffers kept per TCP connection.
+const TCP_POOL_SIZE: usize = 16;
+
+struct TcpFrameStream {
+    read: OwnedReadHalf,
+    framing: Box<dyn Framing>,
+    buffer: Bytes,
+    pool: BufferPool,
+}
+
+impl FrameStream for TcpFrameStream {
+    fn next_frame(&mut self) -> BoxFuture<'_, Option<Result<Bytes, BotError>>> {
+        Box::pin(async move {
+            let mut chunk = [0u8; READ_CHUNK_SIZE];
+            loop {
+                if !self.buffer.is_empty() {
+                    match self.framing.frame_len(&self.buffer) {
+                        Ok(Some(len)) if len <= self.buffer.len() => {
+                            let mut frame = self.pool.take();
+                            frame.extend(self.buffer.drain(..len));
+                            return Some(Ok(frame));
+                        }
+                        Ok(_) => {}
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                match self.read.read(&mut chunk).await {
+                    Ok(0) => return None,
+                    Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                    Err(e) => return Some(Err(BotError::Transport(Arc::new(e)))),
+                }
+            }
+        })
+    }
+
+    fn recycle(&mut self, frame: Bytes) {
+        self.pool.give(frame);
+    }
+}
@@ -0,0 +1,161 @@
+use super::{FrameHalves, FrameSink, FrameStream, Transport};
+use crate::common::errors::BotError;
+use crate::common::types::Bytes;
+
+use std::cell::RefCell;
+use std::error::Error;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use futures_channel::{mpsc, oneshot};
+use futures_util::{future::BoxFuture, StreamExt};
+use js_sys::{ArrayBuffer, Uint8Array};
+use send_wrapper::SendWrapper;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{BinaryType, MessageEvent, WebSocket};
+
+/// Websocket transport on the `WebSocket` of the browser, binary websocket messages are packets.
+///
+/// Works on the page's only thread, so it is meant to be used with [`WasmRuntime`](crate::common::WasmRuntime).
+/// Browsers don't allow extra handshake headers, so they are ignored.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct WasmWebSocketTransport;
+
+impl Transport for WasmWebSocketTransport {
+    fn connect<'a>(&'a self, address: &'a str) -> BoxFuture<'a, Result<FrameHalves, BotError>> {
+        Box::pin(async move {
+            let (socket, frames, opened) = match Socket::open(address) {
+                Ok((socket, frames, opened)) => (SendWrapper::new(Rc::new(socket)), frames, opened),
+                Err(e) => return Err(BotError::ConnectionFailed(js_error(e))),
+            };
+            // The socket reports a failed connection with a close before opening
+            match opened.await {
+                Ok(true) => Ok((
+                    Box::new(WsFrameSink(socket.clone())) as Box<dyn FrameSink>,
+                    Box::new(WsFrameStream {
+                        frames,
+                        _socket: socket,
+                    }) as Box<dyn FrameStream>,
+                )),
+                _ => Err(BotError::ConnectionFailed(js_error(JsValue::from_str(
+                    "Websocket closed before opening",
+                )))),
+            }
+        })
+    }
+}
+
+/// Browser websocket and the callbacks it calls, which have to live as long as it does.
+struct Socket {
+    ws: WebSocket,
+    _callbacks: [Closure<dyn FnMut(JsValue)>; 3],
+}
+
+/// Shared between both halves, only ever touched on the thread that created it.
+type SharedSocket = SendWrapper<Rc<Socket>>;
+
+impl Socket {
+    /// Starts connecting to `address`, returns the socket, the channel of received frames
+    /// and a receiver that resolves to whether the socket opened.
+    fn open(
+        address: &str,
+    ) -> Result<
+        (
+            Self,
+            mpsc::UnboundedReceiver<Bytes>,
+            oneshot::Receiver<bool>,
+        ),
+        JsValue,
+    > {
+        let ws = WebSocket::new(address)?;
+        ws.set_binary_type(BinaryType::Arraybuffer);
+        let (frames_tx, frames) = mpsc::unbounded();
+        let (opened_tx, opened) = oneshot::channel();
+        let opened_tx = Rc::new(RefCell::new(Some(opened_tx)));
+
+        let on_message = Closure::<dyn FnMut(JsValue)>::new({
+            let frames_tx = frames_tx.clone();
+            move |event: JsValue| {
+                let data = event.unchecked_into::<MessageEvent>().data();
+                // Text messages aren't part of the protocol
+                if let Ok(buffer) = data.dyn_into::<ArrayBuffer>() {
+                    let _ = frames_tx.unbounded_send(Uint8Array::new(&buffer).to_vec());
+                }
+            }
+        });
+        let on_open = Closure::<dyn FnMut(JsValue)>::new({
+            let opened_tx = Rc::clone(&opened_tx);
+            move |_: JsValue| {
+                if let Some(tx) = opened_tx.borrow_mut().take() {
+                    let _ = tx.send(true);
+                }
+            }
+        });
+        let on_close = Closure::<dyn FnMut(JsValue)>::new(move |_: JsValue| {
+            if let Some(tx) = opened_tx.borrow_mut().take() {
+                let _ = tx.send(false);
+            }
+            frames_tx.close_channel();
+        });
+        ws.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        ws.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        // Errors are always followed by a close, which ends the connection
+        ws.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+        let socket = Self {
+            ws,
+            _callbacks: [on_message, on_open, on_close],
+        };
+        Ok((socket, frames, opened))
+    }
+}
+
+impl Drop for Socket {
+    /// Detaches the callbacks before they are freed, then closes the connection.
+    fn drop(&mut self) {
+        self.ws.set_onmessage(None);
+        self.ws.set_onopen(None);
+        self.ws.set_onclose(None);
+        let _ = self.ws.close();
+    }
+}
+
+struct WsFrameSink(SharedSocket);
+
+impl FrameSink for WsFrameSink {
+    fn send_frame(&mut self, frame: Bytes) -> BoxFuture<'_, Result<(), BotError>> {
+        // Browser queues the message itself, there is nothing to wait for
+        let result = self
+            .0
+            .ws
+            .send_with_u8_array(&frame)
+            .map_err(|e| BotError::Transport(js_error(e)));
+        Box::pin(async move { result })
+    }
+
+    fn close(&mut self) -> BoxFuture<'_, Result<(), BotError>> {
+        let result = self
+            .0
+            .ws
+            .close()
+            .map_err(|e| BotError::Transport(js_error(e)));
+        Box::pin(async move { result })
+    }
+}
+
+struct WsFrameStream {
+    frames: mpsc::UnboundedReceiver<Bytes>,
+    // Keeps the socket open while only the stream is left
+    _socket: SharedSocket,
+}
+
+impl FrameStream for WsFrameStream {
+    fn next_frame(&mut self) -> BoxFuture<'_, Option<Result<Bytes, BotError>>> {
+        Box::pin(async move { self.frames.next().await.map(Ok) })
+    }
+}
+
+/// Turns an exception thrown by the browser into an error.
+fn js_error(value: JsValue) -> Arc<dyn Error + Send + Sync> {
+    let message = value.as_string().unwrap_or_else(|| format!("{:?}", value));
+    Arc::from(Box::<dyn Error + Send + Sync>::from(message))
+}
@@ -0,0 +1,20 @@
+use super::Vec3;
+
+use alloc::vec::Vec;
+
+/// Type alias for position
+pub type Position = Vec3;
+/// Type alias for lag stamp
+pub type LagStamp = [u8; 3];
+/// Type alias for bytes
+pub type Bytes = Vec<u8>;
+/// Wall clock time used by the bot, [`std::time::SystemTime`] everywhere but in browsers,
+/// where `web_time::SystemTime` replaces it
+#[cfg(all(feature = "bot", not(target_arch = "wasm32")))]
+pub use std::time::SystemTime;
+/// Point in time used by the bot, [`tokio::time::Instant`] everywhere but in browsers,
+/// where the standard clock panics and `web_time::Instant` replaces it
+#[cfg(all(feature = "bot", not(target_arch = "wasm32")))]
+pub use tokio::time::Instant;
+#[cfg(all(feature = "bot", target_arch = "wasm32"))]
+pub use web_time::{Instant, SystemTime};
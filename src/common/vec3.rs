@@ -0,0 +1,138 @@
+use core::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+/// Three-dimensional vector, used for positions.
+///
+/// Converts from and into `[f32; 3]`, so array literals work wherever a position is expected.
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::common::Vec3;
+///
+/// let a = Vec3::new(0.0, 0.0, 0.0);
+/// let b: Vec3 = [3.0, 4.0, 0.0].into();
+/// assert_eq!(a.distance(b), 5.0);
+/// assert_eq!(a.lerp(b, 0.5), Vec3::new(1.5, 2.0, 0.0));
+/// assert_eq!((b - a).normalize(), Vec3::new(0.6, 0.8, 0.0));
+/// assert_eq!(b * 2.0 + a, Vec3::new(6.0, 8.0, 0.0));
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialOrd, PartialEq)]
+pub struct Vec3 {
+    /// X coordinate
+    pub x: f32,
+    /// Y coordinate
+    pub y: f32,
+    /// Z coordinate, height in asciicker
+    pub z: f32,
+}
+
+impl Vec3 {
+    /// Vector with every coordinate set to zero.
+    pub const ZERO: Vec3 = Vec3::new(0.0, 0.0, 0.0);
+
+    /// Constructs a new [`Vec3`].
+    pub const fn new(x: f32, y: f32, z: f32) -> Self {
+        Self { x, y, z }
+    }
+
+    /// Length of the vector.
+    #[cfg(feature = "std")]
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    /// Dot product of two vectors.
+    pub fn dot(self, other: Vec3) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// Euclidean distance between two points.
+    #[cfg(feature = "std")]
+    pub fn distance(self, other: Vec3) -> f32 {
+        (other - self).length()
+    }
+
+    /// Point `t` of the way from `self` to `other`, `0.0` is `self` and `1.0` is `other`.
+    pub fn lerp(self, other: Vec3, t: f32) -> Vec3 {
+        self + (other - self) * t
+    }
+
+    /// Vector of length `1.0` pointing the same way, [`Vec3::ZERO`] stays as it is.
+    #[cfg(feature = "std")]
+    pub fn normalize(self) -> Vec3 {
+        let length = self.length();
+        if length == 0.0 {
+            return self;
+        }
+        self / length
+    }
+
+    /// Coordinates as an array.
+    pub fn to_array(self) -> [f32; 3] {
+        [self.x, self.y, self.z]
+    }
+}
+
+impl From<[f32; 3]> for Vec3 {
+    fn from([x, y, z]: [f32; 3]) -> Self {
+        Self { x, y, z }
+    }
+}
+
+impl From<Vec3> for [f32; 3] {
+    fn from(value: Vec3) -> Self {
+        value.to_array()
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Vec3;
+
+    fn add(self, rhs: Vec3) -> Vec3 {
+        Vec3::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl AddAssign for Vec3 {
+    fn add_assign(&mut self, rhs: Vec3) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Vec3;
+
+    fn sub(self, rhs: Vec3) -> Vec3 {
+        Vec3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl SubAssign for Vec3 {
+    fn sub_assign(&mut self, rhs: Vec3) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<f32> for Vec3 {
+    type Output = Vec3;
+
+    fn mul(self, rhs: f32) -> Vec3 {
+        Vec3::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl Div<f32> for Vec3 {
+    type Output = Vec3;
+
+    fn div(self, rhs: f32) -> Vec3 {
+        Vec3::new(self.x / rhs, self.y / rhs, self.z / rhs)
+    }
+}
+
+impl Neg for Vec3 {
+    type Output = Vec3;
+
+    fn neg(self) -> Vec3 {
+        Vec3::new(-self.x, -self.y, -self.z)
+    }
+}
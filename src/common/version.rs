@@ -0,0 +1,34 @@
+use core::fmt::{Display, Formatter};
+
+/// Protocol versions supported by the library, newest last.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Version {
+    /// [`y6`](crate::y6) module
+    Y6,
+}
+
+impl Version {
+    /// Every supported version, newest last.
+    pub const ALL: &'static [Version] = &[Version::Y6];
+
+    /// Name of the version as used in server urls: `y6`...
+    pub fn name(&self) -> &'static str {
+        match self {
+            Version::Y6 => "y6",
+        }
+    }
+
+    /// Tries to find the version in a path like `/ws/y6/`.
+    pub fn from_path(path: &str) -> Option<Version> {
+        path.split('/')
+            .filter(|s| !s.is_empty())
+            .find_map(|segment| Version::ALL.iter().find(|v| v.name() == segment))
+            .copied()
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -0,0 +1,290 @@
+//! Protocol version detection and version-agnostic connections.
+
+use crate::common::transport::default_transport;
+pub use crate::common::Version;
+use crate::common::{BotError, Position, Transport};
+use crate::traits::{ChatEvent, GameConnection, PlayerStateEvent};
+use crate::y6;
+
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+
+/// Connection established by [`Connector::connect`], protocol version is erased.
+pub enum AnyConnection {
+    /// Y6 connection
+    Y6 {
+        /// Two main connection threads
+        threads: (y6::bot::Receiver, y6::bot::Sender),
+        /// Main bot data
+        data: y6::bot::BotData,
+    },
+}
+
+impl AnyConnection {
+    /// Protocol version of the connection.
+    pub fn version(&self) -> Version {
+        match self {
+            AnyConnection::Y6 { .. } => Version::Y6,
+        }
+    }
+}
+
+/// Version-erased [`ChatEvent`].
+pub type AnyChat = Box<dyn ChatEvent + Send>;
+/// Version-erased [`PlayerStateEvent`].
+pub type AnyPlayerState = Box<dyn PlayerStateEvent + Send>;
+
+impl<T: ChatEvent + ?Sized> ChatEvent for Box<T> {
+    fn author_id(&self) -> u16 {
+        (**self).author_id()
+    }
+
+    fn text(&self) -> String {
+        (**self).text()
+    }
+}
+
+impl<T: PlayerStateEvent + ?Sized> PlayerStateEvent for Box<T> {
+    fn player_id(&self) -> u16 {
+        (**self).player_id()
+    }
+
+    fn position(&self) -> Position {
+        (**self).position()
+    }
+
+    fn direction(&self) -> f32 {
+        (**self).direction()
+    }
+}
+
+impl GameConnection for AnyConnection {
+    type Error = BotError;
+    type Chat = AnyChat;
+    type PlayerState = AnyPlayerState;
+
+    fn id(&self) -> BoxFuture<'_, u16> {
+        match self {
+            AnyConnection::Y6 { data, .. } => data.id(),
+        }
+    }
+
+    fn say(&self, text: String) -> BoxFuture<'_, Result<(), Self::Error>> {
+        Box::pin(async move {
+            match self {
+                AnyConnection::Y6 { data, .. } => data
+                    .say(text)
+                    .await
+                    .map_err(|_| BotError::ChannelClosed("sender thread")),
+            }
+        })
+    }
+
+    fn set_position(&self, position: Position) -> BoxFuture<'_, ()> {
+        match self {
+            AnyConnection::Y6 { data, .. } => data.set_position(position),
+        }
+    }
+
+    fn set_direction(&self, direction: f32) -> BoxFuture<'_, ()> {
+        match self {
+            AnyConnection::Y6 { data, .. } => data.set_direction(direction),
+        }
+    }
+
+    fn take_chat(&self) -> BoxFuture<'_, Vec<Self::Chat>> {
+        match self {
+            AnyConnection::Y6 { data, .. } => Box::pin(async move {
+                data.take_chat()
+                    .await
+                    .into_iter()
+                    .map(|m| Box::new(m) as AnyChat)
+                    .collect()
+            }),
+        }
+    }
+
+    fn players(&self) -> BoxFuture<'_, Vec<Self::PlayerState>> {
+        match self {
+            AnyConnection::Y6 { data, .. } => Box::pin(async move {
+                data.players()
+                    .await
+                    .into_iter()
+                    .map(|p| Box::new(p) as AnyPlayerState)
+                    .collect()
+            }),
+        }
+    }
+}
+
+/// Picks the protocol version of a server and connects with the matching module.
+///
+/// If the url already points to a version endpoint (`ws://asciicker.com/ws/y6/`), the version is taken from it.
+/// Otherwise every candidate endpoint (`<base>/ws/<version>/`) is probed with a websocket handshake,
+/// newest version first. Probes go through the same [`Transport`] as the bot, see [`Connector::transport`].
+///
+/// # Examples
+///
+/// ## Connecting to whatever the server speaks:
+/// ```no_run
+/// use asciicker_rs::connect::Connector;
+/// use asciicker_rs::traits::GameConnection;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let connection = Connector::new("ws://asciicker.com")
+///     .connect("bot", true)
+///     .await
+///     .unwrap();
+/// println!("Connected using {}", connection.version());
+/// connection.say("Hello!".to_string()).await.unwrap();
+/// # }
+/// ```
+pub struct Connector {
+    url: String,
+    versions: Vec<Version>,
+    transport: Arc<dyn Transport>,
+}
+
+impl Connector {
+    /// Constructs a new [`Connector`] trying every supported [`Version`].
+    pub fn new<S: Into<String>>(url: S) -> Self {
+        Self {
+            url: url.into(),
+            versions: Version::ALL.to_vec(),
+            transport: default_transport(),
+        }
+    }
+
+    /// Limits the versions to try.
+    pub fn versions(mut self, versions: &[Version]) -> Self {
+        self.versions = versions.to_vec();
+        self
+    }
+
+    /// Sets the [`Transport`] used to probe the server and to connect the bot, for example a
+    /// [`ProxyTransport`](crate::common::ProxyTransport) on networks that only allow a proxy.
+    ///
+    /// Default is [`WebSocketTransport`](crate::common::WebSocketTransport).
+    ///
+    /// # Examples
+    ///
+    /// ## Counting the probes:
+    /// ```
+    /// use asciicker_rs::common::{BotError, FrameHalves, Transport, WebSocketTransport};
+    /// use asciicker_rs::connect::{Connector, Version};
+    /// use asciicker_rs::y6::server::Server;
+    /// use futures_util::future::BoxFuture;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// #[derive(Default)]
+    /// struct Counting(AtomicUsize);
+    ///
+    /// impl Transport for Counting {
+    ///     fn connect<'a>(&'a self, address: &'a str) -> BoxFuture<'a, Result<FrameHalves, BotError>> {
+    ///         self.0.fetch_add(1, Ordering::Relaxed);
+    ///         WebSocketTransport.connect(address)
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let server = Server::new(8).bind("127.0.0.1:0").await.unwrap();
+    /// let transport = Arc::new(Counting::default());
+    /// let connector = Connector::new(format!("ws://{}", server.address)).transport(transport.clone());
+    /// assert_eq!(connector.detect().await.unwrap(), Version::Y6);
+    /// assert_eq!(transport.0.load(Ordering::Relaxed), 1);
+    ///
+    /// connector.connect("bot", true).await.unwrap();
+    /// assert_eq!(transport.0.load(Ordering::Relaxed), 3);
+    /// # }
+    /// ```
+    pub fn transport(mut self, transport: Arc<dyn Transport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Returns the endpoint url of `version` on this server.
+    pub fn endpoint(&self, version: Version) -> String {
+        let base = match self.url.find("/ws/") {
+            Some(idx) => &self.url[..idx],
+            None => self.url.trim_end_matches('/'),
+        };
+        format!("{}/ws/{}/", base, version)
+    }
+
+    /// Finds out the protocol version of the server.
+    pub async fn detect(&self) -> Result<Version, BotError> {
+        if let Some(version) = Version::from_path(&self.url) {
+            return match self.versions.contains(&version) {
+                true => Ok(version),
+                false => Err(BotError::UnsupportedVersion(format!(
+                    "Version {} from the url is not allowed",
+                    version
+                ))),
+            };
+        }
+        for version in self.versions.iter().rev() {
+            if let Ok((mut sink, _stream)) = self.transport.connect(&self.endpoint(*version)).await
+            {
+                // Only the handshake was needed, the server shouldn't wait for a join
+                let _ = sink.close().await;
+                return Ok(*version);
+            }
+        }
+        Err(BotError::UnsupportedVersion(format!(
+            "No supported version found on {}",
+            self.url
+        )))
+    }
+
+    /// Detects the version and connects a bot with the matching module.
+    pub async fn connect<S: Into<String>>(
+        &self,
+        nickname: S,
+        replace_invalid_utf8: bool,
+    ) -> Result<AnyConnection, BotError> {
+        match self.detect().await? {
+            Version::Y6 => {
+                let bot = y6::bot::BotBuilder::new(nickname)
+                    .address(self.endpoint(Version::Y6))
+                    .replace_invalid_utf8(replace_invalid_utf8)
+                    .transport(Arc::clone(&self.transport))
+                    .build();
+                let (threads, data) = bot.run().await?;
+                Ok(AnyConnection::Y6 { threads, data })
+            }
+        }
+    }
+}
+
+/// Connects a bot as `nickname` to the server at `address`, whatever protocol version it speaks.
+///
+/// Shortcut for [`Connector::connect`] trying every supported version and replacing invalid
+/// UTF-8 in messages, so a hosted bot keeps working when the server moves to a newer version
+/// this library supports. The version is taken from the path of `address` (`/ws/y6/`)
+/// if it has one, otherwise the server is probed.
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::connect::{connect_auto, Version};
+/// use asciicker_rs::traits::GameConnection;
+/// use asciicker_rs::y6::server::Server;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let server = Server::new(8);
+/// let running = server.bind("127.0.0.1:0").await.unwrap();
+/// let connection = connect_auto(&format!("ws://{}", running.address), "bot")
+///     .await
+///     .unwrap();
+/// assert_eq!(connection.version(), Version::Y6);
+/// assert_eq!(server.clients().await[0].id, connection.id().await);
+/// # }
+/// ```
+pub async fn connect_auto(address: &str, nickname: &str) -> Result<AnyConnection, BotError> {
+    Connector::new(address).connect(nickname, true).await
+}
@@ -0,0 +1,128 @@
+//! Discovery of available asciicker servers.
+
+use crate::common::Version;
+use crate::connect::Connector;
+
+use std::time::Duration;
+
+use futures_util::future::join_all;
+use tokio::time::{timeout, Instant};
+
+/// Endpoint found by [`Discovery::discover`].
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub struct ServerInfo {
+    /// Host the endpoint belongs to, as it was passed to [`Discovery::host`]
+    pub host: String,
+    /// Protocol version of the endpoint
+    pub version: Version,
+    /// Full websocket url of the endpoint
+    pub url: String,
+    /// How long the websocket handshake took
+    pub handshake_time: Duration,
+}
+
+impl ServerInfo {
+    /// Creates a [`Connector`] for this endpoint.
+    pub fn connector(&self) -> Connector {
+        Connector::new(self.url.clone()).versions(&[self.version])
+    }
+}
+
+/// Finds which protocol versions are served by a list of hosts.
+///
+/// Every `<host>/ws/<version>/` endpoint is probed with a websocket handshake, all at once.
+/// Hosts without a scheme get `ws://` prepended.
+///
+/// # Examples
+///
+/// ## Listing endpoints of the public server and a local one:
+/// ```no_run
+/// use asciicker_rs::discovery::Discovery;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let servers = Discovery::new().host("localhost:8080").discover().await;
+/// for server in servers {
+///     println!("{} speaks {} ({:?})", server.host, server.version, server.handshake_time);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Discovery {
+    hosts: Vec<String>,
+    versions: Vec<Version>,
+    timeout: Duration,
+}
+
+impl Default for Discovery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Discovery {
+    /// Public asciicker server.
+    pub const DEFAULT_HOST: &'static str = "asciicker.com";
+
+    /// Constructs a new [`Discovery`] with [`Discovery::DEFAULT_HOST`], every supported [`Version`] and 5 second timeout.
+    pub fn new() -> Self {
+        Self {
+            hosts: vec![Self::DEFAULT_HOST.to_string()],
+            versions: Version::ALL.to_vec(),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Adds an alternative host.
+    pub fn host<S: Into<String>>(mut self, host: S) -> Self {
+        self.hosts.push(host.into());
+        self
+    }
+
+    /// Removes all hosts, including the default one.
+    pub fn clear_hosts(mut self) -> Self {
+        self.hosts.clear();
+        self
+    }
+
+    /// Limits the versions to probe.
+    pub fn versions(mut self, versions: &[Version]) -> Self {
+        self.versions = versions.to_vec();
+        self
+    }
+
+    /// Sets how long to wait for a single handshake.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Probes every endpoint and returns the ones that accepted the handshake,
+    /// in the order of hosts and versions.
+    pub async fn discover(&self) -> Vec<ServerInfo> {
+        let mut probes = vec![];
+        for host in &self.hosts {
+            let base = match host.contains("://") {
+                true => host.clone(),
+                false => format!("ws://{}", host),
+            };
+            let connector = Connector::new(base);
+            for version in &self.versions {
+                let url = connector.endpoint(*version);
+                probes.push(async move {
+                    let start = Instant::now();
+                    match timeout(self.timeout, tokio_tungstenite::connect_async(&url)).await {
+                        Ok(Ok(_)) => Some(ServerInfo {
+                            host: host.clone(),
+                            version: *version,
+                            url,
+                            handshake_time: start.elapsed(),
+                        }),
+                        _ => None,
+                    }
+                });
+            }
+        }
+        join_all(probes).await.into_iter().flatten().collect()
+    }
+}
@@ -4,7 +4,52 @@
 //! - Parse packets
 //! - And (hopefully) more...
 
+//!
+//! # Adding a protocol version
+//! Every version lives in its own module (like [`y6`]) with the same layout:
+//! - `packets`: raw packets mirroring the C structs and clean packets,
+//!   byte conversions for clean packets are generated by shared macros
+//! - `bot`: callbacks, world patching and the connection threads
+//! - `utils`: errors and helpers
+//! - `protocol`: marker type implementing [`Protocol`](traits::Protocol), so bot logic
+//!   generic over it runs on the version
+//!
+//! So adding a new version means defining its packet structs and the conversions
+//! that differ from the previous version, then adapting the bot to new packets.
+//!
+//! # Tracing
+//! With the `tracing` feature the bot reports what it does through [`tracing`](https://docs.rs/tracing):
+//! - spans `connect` and `join` around the handshake, `send` and `receive` around the connection threads
+//! - `TRACE` events for every sent and parsed packet and every callback invocation
+//! - `DEBUG` and above for connection attempts, joins, disconnects and failures
+//!
+//! Any subscriber, like the one of `tracing-subscriber`, can collect them.
+//!
+//! # `no_std`
+//! Without the `std` feature (on by default, every feature but `packets` enables it) the crate is
+//! `no_std` and only needs `alloc`. [`y6::packets`], [`y6::protocol`], the errors and the
+//! traits keep working, so the wire format can be reused on embedded targets:
+//! ```toml
+//! asciicker-rs = { version = "0.0.4", default-features = false, features = ["packets"] }
+//! ```
+//! Connections, the bot and the math helpers that need floating point functions of `std`,
+//! like [`Vec3::length`](common::Vec3::length), are left out.
+
 #![forbid(missing_docs)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
+pub mod common;
+#[cfg(all(feature = "bot", feature = "tokio"))]
+pub mod connect;
+#[cfg(all(feature = "bot", feature = "tokio"))]
+pub mod discovery;
+#[cfg(feature = "packets")]
+mod macros;
+#[cfg(all(feature = "packets", feature = "std"))]
+mod trace;
+pub mod traits;
 pub mod y6;
+#[cfg(feature = "std")]
 pub use macro_rules_attribute;
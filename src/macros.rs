@@ -0,0 +1,162 @@
+//! Macros shared by all protocol version modules.
+//!
+//! Every version module is expected to follow the same layout:
+//! raw packets (`RawJoinRequest`...) mirroring C structs byte-for-byte and clean packets
+//! (`JoinRequest`...) with only meaningful fields. Each version then only has to implement
+//! `ParsePacket`/`EncodePacket` for its raw packets, `EncodePacket` for its clean packets and
+//! conversions between raw and clean ones, the byte conversions for clean packets are
+//! generated by the macros below.
+//!
+//! Names `Bytes`, `EncodePacket`, `ParseMode`, `PacketParseError`, `Display` and `fmt` are resolved at the call site, so each version module
+//! can use its own definitions.
+
+/// Implements `TryFrom<Bytes>` by delegating to `TryFrom<&[u8]>`,
+/// so parsing only has to be written once, against borrowed bytes.
+macro_rules! impl_owned_parsing {
+    ($($name:ident)+) => {
+        $(
+            impl TryFrom<Bytes> for $name {
+                type Error = PacketParseError;
+
+                fn try_from(value: Bytes) -> Result<Self, Self::Error> {
+                    Self::try_from(value.as_slice())
+                }
+            }
+        )+
+    };
+}
+
+/// Implements `TryFrom<&[u8]>` and `TryFrom<Bytes>` for raw packets by parsing with `ParseMode::Strict`.
+macro_rules! impl_strict_parsing {
+    ($($name:ident)+) => {
+        $(
+            impl TryFrom<&[u8]> for $name {
+                type Error = PacketParseError;
+
+                fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+                    Self::parse(value, ParseMode::Strict)
+                }
+            }
+        )+
+        $crate::macros::impl_owned_parsing!($($name)+);
+    };
+}
+
+/// Implements `From<T> for Bytes` by encoding with `EncodePacket`,
+/// so encoding only has to be written once, into a caller-provided buffer.
+/// The buffer is allocated once with the exact `encoded_len`.
+macro_rules! impl_owned_encoding {
+    ($($name:ident)+) => {
+        $(
+            impl From<$name> for Bytes {
+                fn from(value: $name) -> Self {
+                    let mut b = Bytes::with_capacity(value.encoded_len());
+                    value.encode_into(&mut b);
+                    b
+                }
+            }
+        )+
+    };
+}
+
+/// Implements `Display` for raw packets by writing the hexdump of `PacketFields`.
+macro_rules! impl_hex_display {
+    ($($name:ident)+) => {
+        $(
+            impl Display for $name {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    self.write_hex(f)
+                }
+            }
+        )+
+    };
+}
+
+/// Implements `TryFrom<&[u8]>` and `TryFrom<Bytes>` for clean packets by parsing the `Raw`-prefixed packet first.
+macro_rules! impl_from_bytes_for_clean {
+    ($($name:ident)+) => {
+        $(
+            impl TryFrom<&[u8]> for $name {
+                type Error = PacketParseError;
+
+                fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+                    match <::concat_idents::concat_idents!(id = Raw, $name { id })>::try_from(value) {
+                        Err(e) => Err(e),
+                        Ok(d) => Ok($name::from(d)),
+                    }
+                }
+            }
+        )+
+        $crate::macros::impl_owned_parsing!($($name)+);
+    };
+}
+
+/// Implements `to_array` for clean packets of a constant size by converting a copy into the
+/// `Raw`-prefixed packet, only meant for packets whose clone doesn't allocate.
+macro_rules! impl_copy_to_array {
+    ($($name:ident $size:ident)+) => {
+        $(
+            impl $name {
+                /// Encodes the packet onto the stack, without allocating.
+                pub fn to_array(&self) -> [u8; $size] {
+                    <::concat_idents::concat_idents!(id = Raw, $name { id })>::from(self.clone()).to_array()
+                }
+            }
+        )+
+    };
+}
+
+/// Implements `EncodePacket` for packets of a constant size by writing their `to_array`.
+macro_rules! impl_array_encoding {
+    ($($name:ident $size:ident)+) => {
+        $(
+            impl EncodePacket for $name {
+                fn encode_into(&self, buf: &mut Bytes) {
+                    buf.extend_from_slice(&self.to_array());
+                }
+
+                fn encoded_len(&self) -> usize {
+                    $size
+                }
+            }
+        )+
+    };
+}
+
+/// Implements both byte conversions for clean packets, which have to implement `EncodePacket`,
+/// see [`impl_from_bytes_for_clean`] and [`impl_owned_encoding`].
+macro_rules! impl_clean_packets {
+    ($($name:ident)+) => {
+        $crate::macros::impl_from_bytes_for_clean!($($name)+);
+        $crate::macros::impl_owned_encoding!($($name)+);
+    };
+}
+
+/// Implements proptest's `Arbitrary` for packets with the given strategies, boxed since
+/// the types of strategies mapping several fields can't be written out.
+#[cfg(feature = "testing")]
+macro_rules! impl_proptest_arbitrary {
+    ($($name:ident => $strategy:expr;)+) => {
+        $(
+            impl proptest::arbitrary::Arbitrary for $name {
+                type Parameters = ();
+                type Strategy = BoxedStrategy<Self>;
+
+                fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+                    $strategy.boxed()
+                }
+            }
+        )+
+    };
+}
+
+pub(crate) use impl_array_encoding;
+pub(crate) use impl_clean_packets;
+pub(crate) use impl_copy_to_array;
+pub(crate) use impl_from_bytes_for_clean;
+pub(crate) use impl_hex_display;
+pub(crate) use impl_owned_encoding;
+pub(crate) use impl_owned_parsing;
+#[cfg(feature = "testing")]
+pub(crate) use impl_proptest_arbitrary;
+pub(crate) use impl_strict_parsing;
@@ -0,0 +1,30 @@
+//! Helpers for the optional `tracing` instrumentation.
+//!
+//! Without the `tracing` feature the macros expand to nothing, so arguments are neither
+//! evaluated nor formatted.
+
+/// Emits a `tracing` event at `$level` (`trace`, `debug`, `info`, `warn` or `error`).
+///
+/// Takes the same arguments as the `tracing` macro of that level, usable as a statement only.
+macro_rules! event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)+);
+    };
+}
+
+/// Runs `$future` inside a new `INFO` span, the rest of the arguments are passed to `info_span!`.
+macro_rules! instrument {
+    ($future:expr, $($span:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        let future = tracing::Instrument::instrument($future, tracing::info_span!($($span)+));
+        #[cfg(not(feature = "tracing"))]
+        let future = $future;
+        future
+    }};
+}
+
+#[allow(unused_imports)]
+pub(crate) use event;
+#[allow(unused_imports)]
+pub(crate) use instrument;
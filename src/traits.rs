@@ -0,0 +1,141 @@
+//! Version-agnostic traits implemented by every protocol version module.
+//!
+//! Code written against these traits instead of concrete packet types
+//! runs on any protocol version by swapping a type parameter.
+
+use crate::common::{BotError, Bytes, Position, Version};
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::Debug;
+
+use futures_util::future::BoxFuture;
+
+/// Chat message, either a broadcast from the server or one stored in the world.
+pub trait ChatEvent {
+    /// ID of the player who sent the message
+    fn author_id(&self) -> u16;
+    /// Contents of the message, with invalid UTF-8 replaced
+    fn text(&self) -> String;
+}
+
+/// Anything that describes the state of a player: broadcasts about them or the player itself.
+pub trait PlayerStateEvent {
+    /// ID of the player
+    fn player_id(&self) -> u16;
+    /// Position of the player
+    fn position(&self) -> Position;
+    /// Direction the player is facing
+    fn direction(&self) -> f32;
+}
+
+/// Established connection of a bot to an asciicker server.
+///
+/// # Examples
+///
+/// ## Version-agnostic greeting:
+/// ```
+/// use asciicker_rs::traits::*;
+///
+/// async fn greet_everyone<C: GameConnection>(connection: &C) -> Result<(), C::Error> {
+///     for player in connection.players().await {
+///         connection
+///             .say(format!("Hi, player #{}!", player.player_id()))
+///             .await?;
+///     }
+///     Ok(())
+/// }
+/// ```
+pub trait GameConnection {
+    /// Error returned when sending fails
+    type Error: Error;
+    /// Chat message type of the version
+    type Chat: ChatEvent;
+    /// Player state type of the version
+    type PlayerState: PlayerStateEvent;
+
+    /// ID assigned to the bot by the server
+    fn id(&self) -> BoxFuture<'_, u16>;
+    /// Queues a chat message to be sent
+    fn say(&self, text: String) -> BoxFuture<'_, Result<(), Self::Error>>;
+    /// Moves the bot
+    fn set_position(&self, position: Position) -> BoxFuture<'_, ()>;
+    /// Turns the bot
+    fn set_direction(&self, direction: f32) -> BoxFuture<'_, ()>;
+    /// Removes and returns chat messages received so far
+    fn take_chat(&self) -> BoxFuture<'_, Vec<Self::Chat>>;
+    /// Players currently on the server, excluding the bot
+    fn players(&self) -> BoxFuture<'_, Vec<Self::PlayerState>>;
+}
+
+/// Game event decoded by a [`Protocol`] from a frame sent by the server.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProtocolEvent<P: Protocol + ?Sized> {
+    /// Server accepted the join of the bot
+    Joined {
+        /// ID assigned to the bot
+        id: u16,
+        /// Max amount of clients of the server
+        max_clients: u8,
+    },
+    /// Another player joined
+    PlayerJoined {
+        /// Nickname of the player
+        nickname: String,
+        /// State of the player when joining
+        state: P::PlayerState,
+    },
+    /// Player with the contained ID left
+    PlayerLeft(u16),
+    /// Player moved or turned
+    PlayerMoved(P::PlayerState),
+    /// Chat message
+    Chat(P::Chat),
+    /// Any other packet of the version, like lag responses
+    Other(P::Packet),
+}
+
+/// Wire format of a protocol version: the packets a bot sends and the events it receives.
+///
+/// Every version module implements it on a marker type, like [`y6::protocol::Y6`](crate::y6::protocol::Y6).
+/// Bot logic written against a `P: Protocol` type parameter only deals with frames and
+/// [`ProtocolEvent`]s, so it runs on any version, whatever transport carries the frames.
+///
+/// # Examples
+///
+/// ## Version-agnostic welcome:
+/// ```
+/// use asciicker_rs::common::{BotError, Bytes};
+/// use asciicker_rs::traits::*;
+///
+/// /// Frames to send in answer to `frame`.
+/// fn welcome<P: Protocol>(frame: &[u8]) -> Result<Vec<Bytes>, BotError> {
+///     match P::decode(frame)? {
+///         ProtocolEvent::PlayerJoined { nickname, .. } => {
+///             P::talk(&format!("Welcome, {}!", nickname))
+///         }
+///         _ => Ok(vec![]),
+///     }
+/// }
+/// ```
+pub trait Protocol {
+    /// Version implemented
+    const VERSION: Version;
+    /// Any packet of the version
+    type Packet: Debug + Clone + Send;
+    /// Chat message type of the version
+    type Chat: ChatEvent + Debug + Clone + Send;
+    /// Player state type of the version
+    type PlayerState: PlayerStateEvent + Debug + Clone + Send;
+
+    /// Encodes the request joining the server as `nickname`, the first frame a client sends.
+    fn join(nickname: &str) -> Result<Bytes, BotError>;
+    /// Encodes the pose of the bot at `position`, facing `direction`,
+    /// everything else the version has in a pose is left at its default.
+    fn pose(position: Position, direction: f32) -> Bytes;
+    /// Encodes `text` as chat messages, split into several if it is too long for one.
+    fn talk(text: &str) -> Result<Vec<Bytes>, BotError>;
+    /// Decodes a frame sent by the server.
+    fn decode(frame: &[u8]) -> Result<ProtocolEvent<Self>, BotError>;
+}
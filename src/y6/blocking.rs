@@ -0,0 +1,176 @@
+use super::packets::{
+    parse_packet, JoinRequest, JoinResponse, LagRequest, LagStamp, Packet, PlayerPose, PoseRequest,
+    RawJoinResponse, TalkRequest,
+};
+use super::utils::BotError;
+use crate::common::types::Bytes;
+
+use std::ffi::CString;
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tungstenite::{stream::MaybeTlsStream, Message, WebSocket};
+
+/// Synchronous connection to a server, for scripts that don't want an async runtime.
+///
+/// Works like the async [`Client`](super::client::Client): it joins, then hands every packet
+/// from the server to the caller through [`Client::next_event`] and sends what it is told to.
+/// Every call blocks the thread until it is done, nothing happens between the calls.
+///
+/// `wss://` addresses need the `rustls` or `native-tls` feature, they are checked against
+/// the webpki roots or the roots of the system respectively.
+///
+/// # Examples
+///
+/// ## Waiting for someone to say something:
+/// ```
+/// use asciicker_rs::y6::blocking::Client;
+/// use asciicker_rs::y6::packets::Packet;
+///
+/// # let runtime = tokio::runtime::Runtime::new().unwrap();
+/// # let server = runtime
+/// #     .block_on(asciicker_rs::y6::server::Server::new(8).bind("127.0.0.1:0"))
+/// #     .unwrap();
+/// # let address = format!("ws://{}", server.address);
+/// let mut client = Client::connect("script", &address).unwrap();
+/// # let mut other = Client::connect("other", &address).unwrap();
+/// # other.send_talk("hello").unwrap();
+/// let said = loop {
+///     if let Packet::TalkBroadcast(talk_brc) = client.next_event().unwrap().unwrap() {
+///         break talk_brc.str;
+///     }
+/// };
+/// assert_eq!(said.to_str().unwrap(), "hello");
+/// client.close().unwrap();
+/// ```
+pub struct Client {
+    ws: WebSocket<MaybeTlsStream<TcpStream>>,
+    join_rsp: JoinResponse,
+}
+
+impl Client {
+    /// Connects to the server at `address` and joins as `nickname`.
+    ///
+    /// Returns [`BotError::HandshakeFailed`] if the server refuses the join.
+    pub fn connect(nickname: &str, address: &str) -> Result<Self, BotError> {
+        let join_req: Bytes = JoinRequest {
+            name: CString::new(nickname)?,
+        }
+        .into();
+        let (ws, _) =
+            tungstenite::connect(address).map_err(|e| BotError::ConnectionFailed(Arc::new(e)))?;
+        let mut client = Self {
+            ws,
+            join_rsp: JoinResponse {
+                max_clients: 0,
+                id: 0,
+            },
+        };
+        client.send_frame(join_req)?;
+        // Server closes the connection instead of responding to refuse the join
+        let frame = client.next_frame().ok_or_else(|| {
+            BotError::HandshakeFailed("Server refused the join, it is probably full".to_string())
+        })??;
+        client.join_rsp = match RawJoinResponse::try_from(frame) {
+            Ok(raw) => JoinResponse::from(raw),
+            Err(e) => {
+                return Err(BotError::HandshakeFailed(format!(
+                    "Invalid join response: {}",
+                    e
+                )))
+            }
+        };
+        Ok(client)
+    }
+
+    /// Id assigned by the server.
+    pub fn id(&self) -> u16 {
+        self.join_rsp.id
+    }
+
+    /// Max amount of clients of the server.
+    pub fn max_clients(&self) -> u8 {
+        self.join_rsp.max_clients
+    }
+
+    /// Sets how long [`Client::next_event`] waits for a packet, [`None`] waits forever.
+    ///
+    /// Running out of time returns [`BotError::Transport`], the connection is still usable after it.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) -> Result<(), BotError> {
+        match self.ws.get_ref() {
+            MaybeTlsStream::Plain(stream) => stream.set_read_timeout(timeout),
+            #[cfg(feature = "rustls")]
+            MaybeTlsStream::Rustls(stream) => stream.get_ref().set_read_timeout(timeout),
+            #[cfg(feature = "native-tls")]
+            MaybeTlsStream::NativeTls(stream) => stream.get_ref().set_read_timeout(timeout),
+            _ => Ok(()),
+        }
+        .map_err(|e| BotError::Transport(Arc::new(e)))
+    }
+
+    /// Waits for the next packet from the server, [`None`] means the connection was closed.
+    ///
+    /// Frames that don't parse, like packets of a newer version, return
+    /// [`BotError::PacketParse`], the connection is still usable after it.
+    pub fn next_event(&mut self) -> Option<Result<Packet, BotError>> {
+        let frame = match self.next_frame()? {
+            Ok(frame) => frame,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(parse_packet(&frame).map_err(BotError::PacketParse))
+    }
+
+    /// Sends the pose of the client, the server broadcasts it to the others.
+    pub fn send_pose(&mut self, player_pose: PlayerPose) -> Result<(), BotError> {
+        self.send_frame(PoseRequest { player_pose }.into())
+    }
+
+    /// Says `message`, split into several talk requests if it is too long for one.
+    pub fn send_talk(&mut self, message: &str) -> Result<(), BotError> {
+        for talk_req in TalkRequest::split(message)? {
+            self.send_frame(talk_req.into())?;
+        }
+        Ok(())
+    }
+
+    /// Sends a lag request, the server answers with a [`LagResponse`](super::packets::LagResponse).
+    pub fn send_lag(&mut self, stamp: LagStamp) -> Result<(), BotError> {
+        self.send_frame(LagRequest { stamp }.into())
+    }
+
+    /// Sends any packet.
+    pub fn send(&mut self, packet: Packet) -> Result<(), BotError> {
+        self.send_frame(packet.into())
+    }
+
+    /// Closes the connection gracefully.
+    pub fn close(mut self) -> Result<(), BotError> {
+        self.ws
+            .close(None)
+            .map_err(|e| BotError::Transport(Arc::new(e)))?;
+        // Wait for the server to confirm, so the close isn't cut short
+        while self.ws.read_message().is_ok() {}
+        Ok(())
+    }
+
+    fn send_frame(&mut self, frame: Bytes) -> Result<(), BotError> {
+        self.ws
+            .write_message(Message::Binary(frame))
+            .map_err(|e| BotError::Transport(Arc::new(e)))
+    }
+
+    fn next_frame(&mut self) -> Option<Result<Bytes, BotError>> {
+        loop {
+            match self.ws.read_message() {
+                Ok(Message::Binary(data)) => return Some(Ok(data)),
+                Ok(Message::Close(_)) => return None,
+                Ok(_) => {} // Text, ping, pong
+                Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                    return None
+                }
+                Err(e) => return Some(Err(BotError::Transport(Arc::new(e)))),
+            }
+        }
+    }
+}
@@ -1,41 +1,363 @@
+use super::chatlog::ChatLogger;
+use super::client::join_response;
+use super::commands::Commands;
+use super::delivery::{with_dispatch, DeliveryPolicy, DeliveryStats, PoseQueue};
+use super::handle::{self, BotHandle, Event, EventSender};
+use super::ignore::{FloodPolicy, IgnoreList, SharedIgnoreList};
+use super::latency::{LatencyTracker, SharedLatency};
 use super::packets::{
-    Bytes, ExitBroadcast, JoinBroadcast, JoinRequest, JoinResponse, LagStamp, PlayerPose,
-    PoseBroadcast, PoseRequest, RawJoinResponse, TalkBroadcast, TalkRequest,
+    parse_packet, Bytes, ExitBroadcast, JoinBroadcast, JoinRequest, JoinResponse, LagRequest,
+    LagResponse, LagStamp, Packet, PlayerPose, PoseBroadcast, PoseRequest, Position, TalkBroadcast,
+    TalkRequest, MAX_TALK_LEN,
 };
-use super::utils::RuntimeError;
+use super::recording::{Recording, SharedRecording};
+use super::replay::{RecordingTransport, SharedRecorder};
+use super::sprites::SpriteCatalog;
+use super::stats::{PacketStats, SharedPacketStats};
+use super::utils::{BotError, PacketParseError};
+use crate::common::transport::{default_transport, CloseFrame, FrameSink, FrameStream, Transport};
+use crate::common::{
+    default_runtime, Instant, Runtime, ServerAddress, SystemTime, Task, Ticker, Version,
+};
+#[cfg(feature = "tokio")]
+use crate::common::{OutboundProxy, ProxyTransport};
+use crate::trace::{event, instrument};
+use crate::traits::{ChatEvent, GameConnection, PlayerStateEvent};
 
 use std::{
-    ffi::CString, future::Future, mem::swap, pin::Pin, sync::Arc, thread::sleep, time::Duration,
+    collections::{HashMap, VecDeque},
+    error::Error,
+    ffi::CString,
+    fmt::{Display, Formatter},
+    future::Future,
+    mem::swap,
+    ops::ControlFlow,
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
+
+use futures_util::future::{select, BoxFuture, Either};
+use tokio::sync::{
+    mpsc::{self, error::SendError},
+    watch, Mutex, Notify, RwLock,
 };
 
-use crossbeam::channel::{unbounded, Sender as channel_Sender};
-use futures_util::{SinkExt, StreamExt};
-use macro_rules_attribute::apply;
-use tokio::{sync::Mutex, task::JoinHandle, time::Instant};
-use tokio_tungstenite::tungstenite::Message as ws_Message;
+pub use asciicker_rs_macros::callback;
+
+/// # Movement module
+/// Movement module interpolates the bot's pose towards a target, used by [`BotHandle::walk_to`].
+pub mod movement;
 
 /// Result type for callbacks ([`JoinCallback`], [`ExitCallback`], [`PoseCallback`], [`TalkCallback`]), internal functions ([`patch_world`]...).
-pub type BotResult = Result<(), RuntimeError>;
+pub type BotResult = Result<(), BotError>;
 /// Type alias for two main connection threads.
-pub type ConnectionThread = JoinHandle<Result<(), RuntimeError>>;
+pub type ConnectionThread = Task<Result<(), BotError>>;
 /// Type alias for sender handle of the message channel.
-pub type MessageSender = Arc<channel_Sender<String>>;
+///
+/// The channel holds up to [`MESSAGE_CAPACITY`] messages, [`send`](mpsc::Sender::send) waits
+/// for the sender thread to make room once it is full.
+pub type MessageSender = Arc<mpsc::Sender<String>>;
+/// Amount of chat messages that can be queued before [`MessageSender`] starts waiting.
+pub const MESSAGE_CAPACITY: usize = 256;
+/// Box-pinned result of a callback, `R` is either `()` or [`ControlFlow`], see [`IntoControlFlow`].
+pub type FutureCallbackResult<R> = Pin<Box<dyn Future<Output = Result<R, BotError>> + Send>>;
 /// Box-pinned [`BotResult`].
-pub type FutureBotResult = Pin<Box<dyn Future<Output = BotResult> + Send>>;
+pub type FutureBotResult = FutureCallbackResult<()>;
+/// Box-pinned result of a callback in a chain, [`ControlFlow::Break`] skips the rest of the chain.
+pub type FutureFlowResult = FutureCallbackResult<ControlFlow<()>>;
+/// Type alias for a callback of event `T`, every event can have a chain of them,
+/// called in registration order.
+///
+/// Like every other callback, it can be any closure capturing state (configuration, counters...),
+/// [`Bot::on_join`] and similar methods box it.
+pub type Callback<T> = Arc<
+    dyn Fn(T, Arc<Mutex<Player>>, Arc<RwLock<World>>, MessageSender) -> FutureFlowResult
+        + Send
+        + Sync,
+>;
 /// Type alias for join callback.
-pub type JoinCallback =
-    fn(JoinBroadcast, Arc<Mutex<Player>>, Arc<Mutex<World>>, MessageSender) -> FutureBotResult;
+pub type JoinCallback = Callback<JoinBroadcast>;
 /// Type alias for exit callback.
-pub type ExitCallback =
-    fn(ExitBroadcast, Arc<Mutex<Player>>, Arc<Mutex<World>>, MessageSender) -> FutureBotResult;
+pub type ExitCallback = Callback<ExitBroadcast>;
 /// Type alias for pose callback.
-pub type PoseCallback =
-    fn(PoseBroadcast, Arc<Mutex<Player>>, Arc<Mutex<World>>, MessageSender) -> FutureBotResult;
+pub type PoseCallback = Callback<PoseBroadcast>;
 /// Type alias for talk callback.
-pub type TalkCallback =
-    fn(TalkBroadcast, Arc<Mutex<Player>>, Arc<Mutex<World>>, MessageSender) -> FutureBotResult;
+pub type TalkCallback = Callback<TalkBroadcast>;
+/// Type alias for lag callback, called after [`World::lag`] is updated.
+pub type LagCallback = Callback<LagResponse>;
+/// Type alias for unknown packet callback, called with the whole packet
+/// when its token doesn't belong to any packet this version knows.
+pub type UnknownPacketCallback = Callback<Bytes>;
+/// Why the connection of a bot ended, passed to the [`DisconnectCallback`].
+///
+/// # Examples
+///
+/// ## Server pinging the bot, then closing with a code:
+/// ```
+/// use asciicker_rs::common::CloseFrame;
+/// use asciicker_rs::y6::prelude::*;
+/// use futures_util::{SinkExt, StreamExt};
+/// use tokio::net::TcpListener;
+/// use tokio_tungstenite::tungstenite::protocol::{frame::coding::CloseCode, CloseFrame as WsCloseFrame};
+/// use tokio_tungstenite::tungstenite::Message as WsMessage;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+/// let address = format!("ws://{}", listener.local_addr().unwrap());
+/// let server = tokio::spawn(async move {
+///     let (stream, _) = listener.accept().await.unwrap();
+///     let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+///     ws.next().await; // Join request
+///     let join_rsp = JoinResponse { max_clients: 8, id: 1 }.to_array();
+///     ws.send(WsMessage::Binary(join_rsp.to_vec())).await.unwrap();
+///     ws.send(WsMessage::Ping(b"alive?".to_vec())).await.unwrap();
+///     while let Some(Ok(message)) = ws.next().await {
+///         if message == WsMessage::Pong(b"alive?".to_vec()) {
+///             break;
+///         }
+///     }
+///     ws.close(Some(WsCloseFrame {
+///         code: CloseCode::Library(4000),
+///         reason: "maintenance".into(),
+///     }))
+///     .await
+///     .unwrap();
+///     while ws.next().await.is_some() {}
+/// });
+///
+/// let (tx, rx) = tokio::sync::oneshot::channel();
+/// let tx = std::sync::Mutex::new(Some(tx));
+/// let mut bot = BotBuilder::new("bot").address(&address).lag_interval(None).build();
+/// bot.on_disconnect(move |reason, _, _, _| {
+///     let _ = tx.lock().unwrap().take().unwrap().send(reason);
+///     Box::pin(async { Ok(()) })
+/// });
+/// let ((receiver, _), _) = bot.run().await.unwrap();
+/// let reason = rx.await.unwrap();
+/// let close = CloseFrame { code: 4000, reason: "maintenance".to_string() };
+/// assert!(matches!(reason, DisconnectReason::ServerClosed(Some(frame)) if frame == close));
+/// receiver.thread.await.unwrap().unwrap();
+/// server.await.unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub enum DisconnectReason {
+    /// Server closed the connection, with the code and reason of its close frame if it sent one
+    ServerClosed(Option<CloseFrame>),
+    /// Connection failed while sending or receiving
+    ProtocolError(BotError),
+    /// Server sent bytes that aren't a valid packet
+    ParseFailure(PacketParseError),
+    /// A callback failed, which stops the bot as well
+    CallbackFailed(BotError),
+    /// Bot closed the connection itself, see [`BotHandle::leave`]
+    Left,
+}
+
+impl DisconnectReason {
+    /// Tells the reason from the result the receiver loop ended with,
+    /// `left` tells if the bot closed the connection itself and `close` is the close frame
+    /// of the server, see [`FrameStream::close_frame`].
+    pub(crate) fn from_result(result: &BotResult, left: bool, close: Option<CloseFrame>) -> Self {
+        match result {
+            Ok(()) if left => Self::Left,
+            Ok(()) => Self::ServerClosed(close),
+            Err(BotError::PacketParse(e)) => Self::ParseFailure(e.clone()),
+            Err(e @ (BotError::Transport(_) | BotError::ConnectionFailed(_))) => {
+                Self::ProtocolError(e.clone())
+            }
+            Err(e) => Self::CallbackFailed(e.clone()),
+        }
+    }
+}
+
+/// What an [`Interceptor`] decided to do with a received packet.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InterceptAction {
+    /// Pass the (possibly modified) packet on to the next interceptor and [`patch_world`]
+    Continue,
+    /// Forget the packet, neither the world nor any callback sees it
+    Drop,
+}
+/// Type alias for an interceptor, it sees every received packet as raw bytes before [`patch_world`]
+/// and may modify or drop it, see [`Bot::add_interceptor`].
+pub type Interceptor = Arc<dyn Fn(&mut Bytes) -> InterceptAction + Send + Sync>;
+/// Chains of callbacks for every event, in the form [`patch_world`] expects them.
+pub type Callbacks = (
+    Vec<JoinCallback>,
+    Vec<ExitCallback>,
+    Vec<PoseCallback>,
+    Vec<TalkCallback>,
+    Vec<LagCallback>,
+    Vec<UnknownPacketCallback>,
+);
+/// Type alias for dequeue callback.
+///
+/// Called once, with the amount of refused join attempts, when a bot with
+/// [join queue](Bot::join_queue) enabled finally gets in.
+pub type DequeueCallback = Arc<
+    dyn Fn(u32, Arc<Mutex<Player>>, Arc<RwLock<World>>, MessageSender) -> FutureBotResult
+        + Send
+        + Sync,
+>;
+/// Type alias for ready callback.
+///
+/// Called once with the processed [`JoinResponse`] (id of the bot and `max_clients`) before
+/// [`Bot::run`] and similar methods return, so initial announcements or positioning happen exactly once.
+pub type ReadyCallback = Arc<
+    dyn Fn(JoinResponse, Arc<Mutex<Player>>, Arc<RwLock<World>>, MessageSender) -> FutureBotResult
+        + Send
+        + Sync,
+>;
+/// Type alias for disconnect callback.
+///
+/// Called once with the [`DisconnectReason`] when the receiver loop ends, instead of polling
+/// [`Receiver::is_finished`].
+pub type DisconnectCallback = Arc<
+    dyn Fn(
+            DisconnectReason,
+            Arc<Mutex<Player>>,
+            Arc<RwLock<World>>,
+            MessageSender,
+        ) -> FutureBotResult
+        + Send
+        + Sync,
+>;
+/// Type alias for id of the server, used to tell apart connections of a [`ConnectionManager`].
+///
+/// [`ConnectionManager`]: super::manager::ConnectionManager
+pub type ServerId = String;
+/// Default amount of chat messages kept in [`World::messages`].
+const DEFAULT_CHAT_HISTORY: usize = 1024;
+/// Default shortest time between two pose requests.
+const DEFAULT_POSE_INTERVAL: Duration = Duration::from_millis(10);
+/// Default time between two lag requests, see [`Bot::lag_interval`].
+const DEFAULT_LAG_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Future returned by [`Bot::run_single_task`], drives the whole connection when polled.
+pub type SingleTask = BoxFuture<'static, BotResult>;
+/// Type alias for sender handle of the bot's pose.
+///
+/// The sender thread sends whatever pose is in the channel, so this is the way to move the bot,
+/// [`Player::pose`] of the bot is only kept in sync by [`GameConnection`] methods and [`BotHandle`].
+pub type PoseSender = Arc<watch::Sender<PlayerPose>>;
 /// Type alias for main bot data
-pub type BotData = (Arc<Mutex<Player>>, Arc<Mutex<World>>, MessageSender);
+pub type BotData = (
+    Arc<Mutex<Player>>,
+    Arc<RwLock<World>>,
+    MessageSender,
+    PoseSender,
+);
+
+/// Error of [`ChatSender::send`], returned before the message is queued where possible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChatError {
+    /// Message contains a null byte at this position
+    InteriorNul(usize),
+    /// Message is longer than [`MAX_TALK_LEN`] bytes and the sender is strict, contains the length
+    TooLong(usize),
+    /// Sender thread has stopped, contains the message that wasn't sent
+    Closed(String),
+}
+
+impl Display for ChatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChatError::InteriorNul(at) => write!(f, "Message contains a null byte at {}", at),
+            ChatError::TooLong(len) => write!(f, "Message too long: {} bytes", len),
+            ChatError::Closed(_) => write!(f, "Sender has stopped"),
+        }
+    }
+}
+
+impl Error for ChatError {}
+
+impl From<ChatError> for BotError {
+    fn from(e: ChatError) -> Self {
+        match e {
+            ChatError::TooLong(len) => BotError::MessageTooLong(len),
+            ChatError::Closed(_) => BotError::ChannelClosed("sender"),
+            e => BotError::callback(e),
+        }
+    }
+}
+
+/// [`MessageSender`] that checks messages before queueing them.
+///
+/// A message the sender thread can't send fails there and stops it, [`ChatSender::send`]
+/// reports the same problems to the caller instead.
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+/// use std::sync::Arc;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (tx, mut rx) = tokio::sync::mpsc::channel(MESSAGE_CAPACITY);
+/// let chat = ChatSender::new(Arc::new(tx)).strict(true);
+///
+/// assert_eq!(chat.send("a\0b").await, Err(ChatError::InteriorNul(1)));
+/// assert_eq!(chat.send(&"a".repeat(300)).await, Err(ChatError::TooLong(300)));
+/// chat.send("hello").await.unwrap();
+/// assert_eq!(rx.recv().await.unwrap(), "hello");
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChatSender {
+    sender: MessageSender,
+    strict: bool,
+}
+
+impl ChatSender {
+    /// Wraps `sender`, long messages are allowed as they are split, see [`Bot::strict_talk`].
+    pub fn new(sender: MessageSender) -> Self {
+        Self {
+            sender,
+            strict: false,
+        }
+    }
+
+    /// Rejects messages longer than [`MAX_TALK_LEN`] if `true`, use with [`Bot::strict_talk`].
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Checks `message` without sending it.
+    pub fn validate(&self, message: &str) -> Result<(), ChatError> {
+        if let Some(at) = message.find('\0') {
+            return Err(ChatError::InteriorNul(at));
+        }
+        if self.strict && message.len() > MAX_TALK_LEN {
+            return Err(ChatError::TooLong(message.len()));
+        }
+        Ok(())
+    }
+
+    /// Checks `message` and queues it, waits if the queue is full.
+    pub async fn send(&self, message: &str) -> Result<(), ChatError> {
+        self.validate(message)?;
+        self.sender
+            .send(message.to_string())
+            .await
+            .map_err(|SendError(message)| ChatError::Closed(message))
+    }
+
+    /// Returns the wrapped [`MessageSender`].
+    pub fn inner(&self) -> &MessageSender {
+        &self.sender
+    }
+}
+
+impl From<MessageSender> for ChatSender {
+    fn from(sender: MessageSender) -> Self {
+        Self::new(sender)
+    }
+}
 
 /// Middle level abstraction.
 ///
@@ -44,7 +366,7 @@ pub type BotData = (Arc<Mutex<Player>>, Arc<Mutex<World>>, MessageSender);
 /// Not used internally, but created by [`Bot::run`] and passed into callbacks + main bot function
 /// as representation of the bot in the asciicker world.
 ///
-/// There is also [`Vec<Player>`] in [`World`] that represents all current players
+/// There is also [`World::clients`] that represents all current players
 /// (excluding the bot) and managed by [`Receiver`] thread.
 #[derive(Debug, Clone, PartialOrd, PartialEq)]
 pub struct Player {
@@ -54,6 +376,109 @@ pub struct Player {
     pub pose: PlayerPose,
     /// ID
     pub id: u16,
+    /// When the current pose was received, `None` if it never came from a broadcast
+    pub updated: Option<Instant>,
+    /// Position and receive time of the previous pose, see [`Player::velocity`]
+    pub previous: Option<(Position, Instant)>,
+    history: VecDeque<(PlayerPose, Instant)>,
+    history_capacity: usize,
+}
+
+impl Player {
+    /// Creates a new instance of [`Player`] without pose history.
+    pub fn new<S: Into<String>>(nickname: S, pose: PlayerPose, id: u16) -> Self {
+        Self {
+            nickname: nickname.into(),
+            pose,
+            id,
+            updated: None,
+            previous: None,
+            history: VecDeque::new(),
+            history_capacity: 0,
+        }
+    }
+
+    /// Moves to `pose` received at `when`, remembering the current one for [`Player::velocity`].
+    ///
+    /// The pose is also recorded in [`Player::history`] if it is enabled.
+    pub fn update_pose(&mut self, pose: PlayerPose, when: Instant) {
+        self.previous = self.updated.map(|at| (self.pose.position, at));
+        if self.history_capacity > 0 {
+            if self.history.len() == self.history_capacity {
+                self.history.pop_front();
+            }
+            self.history.push_back((pose.clone(), when));
+        }
+        self.pose = pose;
+        self.updated = Some(when);
+    }
+
+    /// Keeps the last `capacity` poses in [`Player::history`], `0` disables it.
+    ///
+    /// Poses over the new capacity are dropped, oldest first.
+    pub fn keep_history(&mut self, capacity: usize) {
+        while self.history.len() > capacity {
+            self.history.pop_front();
+        }
+        self.history_capacity = capacity;
+    }
+
+    /// Iterates over the recorded poses and their receive times, oldest first.
+    ///
+    /// Empty unless enabled with [`Player::keep_history`] or [`BotBuilder::pose_history`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use tokio::time::Instant;
+    ///
+    /// let mut player = Player::new("walker", PlayerPose::default(), 2);
+    /// player.keep_history(2);
+    /// for x in 0..3 {
+    ///     let mut pose = PlayerPose::default();
+    ///     pose.position = [x as f32, 0.0, 0.0].into();
+    ///     player.update_pose(pose, Instant::now());
+    /// }
+    ///
+    /// let trail: Vec<f32> = player.history().map(|(pose, _)| pose.position.x).collect();
+    /// assert_eq!(trail, [1.0, 2.0]);
+    /// ```
+    pub fn history(&self) -> impl DoubleEndedIterator<Item = &(PlayerPose, Instant)> {
+        self.history.iter()
+    }
+
+    /// Velocity in units per second between the last two poses, zero until there are two of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use tokio::time::{Duration, Instant};
+    ///
+    /// let start = Instant::now();
+    /// let mut player = Player::new("runner", PlayerPose::default(), 2);
+    /// player.update_pose(PlayerPose::default(), start);
+    /// let mut pose = PlayerPose::default();
+    /// pose.position = [6.0, 8.0, 0.0].into();
+    /// player.update_pose(pose, start + Duration::from_secs(2));
+    ///
+    /// assert_eq!(player.velocity(), Vec3::new(3.0, 4.0, 0.0));
+    /// assert_eq!(player.speed(), 5.0);
+    /// ```
+    pub fn velocity(&self) -> Position {
+        match (self.previous, self.updated) {
+            (Some((position, at)), Some(updated)) if updated > at => {
+                (self.pose.position - position) / (updated - at).as_secs_f32()
+            }
+            _ => Position::ZERO,
+        }
+    }
+
+    /// Speed in units per second between the last two poses, see [`Player::velocity`].
+    pub fn speed(&self) -> f32 {
+        self.velocity().length()
+    }
 }
 
 /// Middle level abstraction.
@@ -61,22 +486,140 @@ pub struct Player {
 /// Represents a message sent by someone in asciicker
 #[derive(Debug, Clone, PartialOrd, PartialEq)]
 pub struct Message {
+    /// Id of the message, increasing in the order messages were received, see [`ChatHistory::push`]
+    pub id: u64,
     /// Contents of the message
     pub content: String,
     /// Author id
     pub author: u16,
+    /// Nickname of the author when the message was received, `None` if the author was unknown
+    pub author_name: Option<String>,
     /// When the message was sent
     pub when: Instant,
+    /// Wall-clock time of when the message was sent, unlike [`Message::when`] it can be stored or displayed
+    pub sent_at: SystemTime,
 }
 
 impl Message {
-    /// Creates a new instance of [`Message`].
+    /// Creates a new instance of [`Message`] without an author name, sent at [`SystemTime::now`].
     pub fn new<S: Into<String>>(content: S, author: u16, when: Instant) -> Self {
         Self {
+            id: 0,
             content: content.into(),
             author,
+            author_name: None,
             when,
+            sent_at: SystemTime::now(),
+        }
+    }
+}
+
+/// Ring buffer of the latest chat [`Message`]s, oldest ones are dropped once it is full.
+///
+/// Every pushed message gets the next [`Message::id`], ids keep increasing after messages are
+/// dropped or drained.
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+/// use tokio::time::{Duration, Instant};
+///
+/// let start = Instant::now();
+/// let mut history = ChatHistory::new(2);
+/// history.push(Message::new("first", 2, start));
+/// history.push(Message::new("second", 2, start + Duration::from_secs(1)));
+/// history.push(Message::new("third", 3, start + Duration::from_secs(2)));
+///
+/// let since: Vec<_> = history.iter_since(start).map(|m| m.content.as_str()).collect();
+/// assert_eq!(since, vec!["second", "third"]);
+/// assert_eq!(history.iter().map(|m| m.id).collect::<Vec<_>>(), vec![1, 2]);
+/// assert_eq!(history.drain().count(), 2);
+/// assert!(history.is_empty());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatHistory {
+    messages: VecDeque<Message>,
+    capacity: usize,
+    next_id: u64,
+}
+
+impl ChatHistory {
+    /// Creates an empty [`ChatHistory`] keeping at most `capacity` messages.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            messages: VecDeque::with_capacity(capacity),
+            capacity,
+            next_id: 0,
+        }
+    }
+
+    /// Adds a message with the next id, dropping the oldest one if the history is full.
+    pub fn push(&mut self, mut message: Message) {
+        message.id = self.next_id;
+        self.next_id += 1;
+        if self.capacity == 0 {
+            return;
+        }
+        if self.messages.len() == self.capacity {
+            self.messages.pop_front();
+        }
+        self.messages.push_back(message);
+    }
+
+    /// Removes and returns all messages, oldest first.
+    pub fn drain(&mut self) -> impl Iterator<Item = Message> + '_ {
+        self.messages.drain(..)
+    }
+
+    /// Returns messages sent after `when`, oldest first.
+    pub fn iter_since(&self, when: Instant) -> impl Iterator<Item = &Message> {
+        self.messages.iter().filter(move |m| m.when > when)
+    }
+
+    /// Returns all messages, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &Message> {
+        self.messages.iter()
+    }
+
+    /// Amount of messages in the history.
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Returns `true` if there are no messages.
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Maximum amount of messages kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Id the next pushed message gets.
+    pub(crate) fn next_id(&self) -> u64 {
+        self.next_id
+    }
+
+    /// Replaces the messages, keeping the newest ones that fit, and the next id.
+    pub(crate) fn restore<I: IntoIterator<Item = Message>>(&mut self, messages: I, next_id: u64) {
+        self.messages.clear();
+        for message in messages {
+            if self.messages.len() == self.capacity {
+                self.messages.pop_front();
+            }
+            if self.capacity > 0 {
+                self.messages.push_back(message);
+            }
         }
+        self.next_id = next_id;
+    }
+}
+
+impl Default for ChatHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_CHAT_HISTORY)
     }
 }
 
@@ -86,16 +629,357 @@ impl Message {
 ///
 /// Not used internally, but created by [`Bot::run`] and updated by the [`Receiver`] thread.
 /// Main purpose is to give the user of the library an accurate representation of what is happening.
-#[derive(Default, Debug, Clone, PartialOrd, PartialEq)]
+///
+/// It is shared behind a [`RwLock`], only the [`Receiver`] thread writes to it, so callbacks
+/// should take [`RwLock::read`] to inspect it without waiting for each other.
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct World {
     /// Max amount of client the server supports.
     pub max_clients: u8,
-    /// Current clients
-    pub clients: Vec<Player>,
-    /// Stack of messages, need to be popped manually
-    pub messages: Vec<Message>,
-    /// [`LagStamp`]
+    /// Current clients by their id
+    pub clients: HashMap<u16, Player>,
+    /// Latest chat messages, see [`BotBuilder::chat_history`]
+    pub messages: ChatHistory,
+    /// How many poses each client keeps, see [`BotBuilder::pose_history`]
+    pub pose_history: usize,
+    /// [`LagStamp`] of the latest lag response
+    #[deprecated(note = "Use BotHandle::latency or Bot::latency instead")]
     pub lag: LagStamp,
+    /// Id of the server this world belongs to, if it was set with [`Bot::server_id`]
+    pub server_id: Option<ServerId>,
+}
+
+impl World {
+    /// Returns the client with `id`, if they are on the server.
+    pub fn client(&self, id: u16) -> Option<&Player> {
+        self.clients.get(&id)
+    }
+
+    /// Returns the client with `id` for modification, if they are on the server.
+    pub fn client_mut(&mut self, id: u16) -> Option<&mut Player> {
+        self.clients.get_mut(&id)
+    }
+
+    /// Returns the player with `id`, same as [`World::client`].
+    pub fn player_by_id(&self, id: u16) -> Option<&Player> {
+        self.client(id)
+    }
+
+    /// Returns the player whose nickname equals `name` ignoring case.
+    ///
+    /// If several players share the name, the one with the lowest id is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    ///
+    /// let mut world = World::default();
+    /// for (id, nickname) in [(2, "Alice"), (3, "bob"), (4, "robert")] {
+    ///     let nickname = nickname.to_string();
+    ///     let pose = Default::default();
+    ///     world.clients.insert(id, Player::new(nickname, pose, id));
+    /// }
+    ///
+    /// assert_eq!(world.player_by_name("alice").unwrap().id, 2);
+    /// assert!(world.player_by_name("alcie").is_none());
+    /// assert_eq!(world.player_by_name_fuzzy("alcie", 2).unwrap().id, 2);
+    /// assert_eq!(world.players_matching(|p| p.nickname.contains('b')).count(), 2);
+    /// ```
+    pub fn player_by_name(&self, name: &str) -> Option<&Player> {
+        let name = name.to_lowercase();
+        self.players_matching(|p| p.nickname.to_lowercase() == name)
+            .min_by_key(|p| p.id)
+    }
+
+    /// Returns the player whose nickname is closest to `name` ignoring case,
+    /// if it takes at most `max_distance` single character edits to get there.
+    ///
+    /// Ties are broken by the lowest id.
+    pub fn player_by_name_fuzzy(&self, name: &str, max_distance: usize) -> Option<&Player> {
+        let name = name.to_lowercase();
+        self.clients
+            .values()
+            .map(|p| (edit_distance(&p.nickname.to_lowercase(), &name), p))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min_by_key(|(distance, p)| (*distance, p.id))
+            .map(|(_, p)| p)
+    }
+
+    /// Returns players matching `predicate` in no particular order.
+    pub fn players_matching<F: Fn(&Player) -> bool>(
+        &self,
+        predicate: F,
+    ) -> impl Iterator<Item = &Player> {
+        self.clients.values().filter(move |p| predicate(p))
+    }
+
+    /// Returns players at most `radius` away from `center` in no particular order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    ///
+    /// let mut world = World::default();
+    /// for (id, x) in [(2, 1.0), (3, 5.0), (4, 20.0)] {
+    ///     let mut pose = PlayerPose::default();
+    ///     pose.position = [x, 0.0, 0.0].into();
+    ///     world.clients.insert(id, Player::new(id.to_string(), pose, id));
+    /// }
+    ///
+    /// assert_eq!(world.players_within([0.0, 0.0, 0.0], 10.0).count(), 2);
+    /// assert_eq!(world.nearest_player([18.0, 0.0, 0.0]).unwrap().id, 4);
+    /// ```
+    pub fn players_within<P: Into<Position>>(
+        &self,
+        center: P,
+        radius: f32,
+    ) -> impl Iterator<Item = &Player> {
+        let center = center.into();
+        self.players_matching(move |p| p.pose.position.distance(center) <= radius)
+    }
+
+    /// Returns the player closest to `to`, `None` if there are no players.
+    pub fn nearest_player<P: Into<Position>>(&self, to: P) -> Option<&Player> {
+        let to = to.into();
+        self.clients.values().min_by(|a, b| {
+            a.pose
+                .position
+                .distance(to)
+                .total_cmp(&b.pose.position.distance(to))
+        })
+    }
+}
+
+/// Levenshtein distance between `a` and `b` in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+impl ChatEvent for Message {
+    fn author_id(&self) -> u16 {
+        self.author
+    }
+
+    fn text(&self) -> String {
+        self.content.clone()
+    }
+}
+
+impl PlayerStateEvent for Player {
+    fn player_id(&self) -> u16 {
+        self.id
+    }
+
+    fn position(&self) -> Position {
+        self.pose.position
+    }
+
+    fn direction(&self) -> f32 {
+        self.pose.direction
+    }
+}
+
+impl GameConnection for BotData {
+    type Error = SendError<String>;
+    type Chat = Message;
+    type PlayerState = Player;
+
+    fn id(&self) -> BoxFuture<'_, u16> {
+        Box::pin(async move { self.0.lock().await.id })
+    }
+
+    fn say(&self, text: String) -> BoxFuture<'_, Result<(), Self::Error>> {
+        Box::pin(async move { self.2.send(text).await })
+    }
+
+    fn set_position(&self, position: Position) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            modify_pose(&self.3, |pose| pose.position = position);
+            self.0.lock().await.pose.position = position
+        })
+    }
+
+    fn set_direction(&self, direction: f32) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            modify_pose(&self.3, |pose| pose.direction = direction);
+            self.0.lock().await.pose.direction = direction
+        })
+    }
+
+    fn take_chat(&self) -> BoxFuture<'_, Vec<Self::Chat>> {
+        Box::pin(async move { self.1.write().await.messages.drain().collect() })
+    }
+
+    fn players(&self) -> BoxFuture<'_, Vec<Self::PlayerState>> {
+        Box::pin(async move { self.1.read().await.clients.values().cloned().collect() })
+    }
+}
+
+/// Changes the pose published to the sender thread.
+pub fn modify_pose<F: FnOnce(&mut PlayerPose)>(pose: &PoseSender, f: F) {
+    let mut new = pose.borrow().clone();
+    f(&mut new);
+    pose.send_replace(new);
+}
+
+/// Value a callback resolves to, decides whether the rest of the chain runs.
+///
+/// `()` always continues, so plain callbacks returning [`BotResult`] can be chained as they are.
+///
+/// # Examples
+///
+/// ## Commands that are not logged:
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+/// use std::ops::ControlFlow;
+/// use std::sync::{Arc, Mutex as StdMutex};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let log = Arc::new(StdMutex::new(vec![]));
+/// let mut bot = BotBuilder::new("moderator").replace_invalid_utf8(true).build();
+/// bot.on_talk(|brc, _, _, sender| {
+///     Box::pin(async move {
+///         if brc.str.as_bytes() == b"!ping" {
+///             sender.send("pong".to_string()).await.unwrap();
+///             return Ok(ControlFlow::Break(()));
+///         }
+///         Ok(ControlFlow::Continue(()))
+///     })
+/// });
+/// let logger = Arc::clone(&log);
+/// bot.on_talk(move |brc, _, _, _| {
+///     logger.lock().unwrap().push(brc.str);
+///     Box::pin(async { Ok(()) })
+/// });
+///
+/// Scenario::new(&bot)
+///     .talk(2, "!ping")
+///     .expect_talk(|m| m == "pong", std::time::Duration::from_secs(1))
+///     .talk(2, "hello")
+///     .run()
+///     .await
+///     .unwrap();
+/// assert_eq!(log.lock().unwrap().len(), 1);
+/// # }
+/// ```
+pub trait IntoControlFlow: Send {
+    /// Converts into [`ControlFlow`].
+    fn into_control_flow(self) -> ControlFlow<()>;
+}
+
+impl IntoControlFlow for () {
+    fn into_control_flow(self) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+impl IntoControlFlow for ControlFlow<()> {
+    fn into_control_flow(self) -> ControlFlow<()> {
+        self
+    }
+}
+
+/// Boxes `callback` into a [`Callback`] of the chain.
+pub(crate) fn into_callback<T, F, R>(callback: F) -> Callback<T>
+where
+    F: Fn(T, Arc<Mutex<Player>>, Arc<RwLock<World>>, MessageSender) -> FutureCallbackResult<R>
+        + Send
+        + Sync
+        + 'static,
+    R: IntoControlFlow + 'static,
+{
+    Arc::new(move |event, bot, world, sender| {
+        let future = callback(event, bot, world, sender);
+        Box::pin(async move { future.await.map(IntoControlFlow::into_control_flow) })
+    })
+}
+
+/// Runs `frame` through `interceptors` in order until one of them drops it.
+pub(crate) fn intercept(interceptors: &[Interceptor], frame: &mut Bytes) -> InterceptAction {
+    for interceptor in interceptors {
+        if interceptor(frame) == InterceptAction::Drop {
+            return InterceptAction::Drop;
+        }
+    }
+    InterceptAction::Continue
+}
+
+/// Time limit of every callback in a chain, see [`Bot::callback_timeout`].
+#[derive(Clone, Copy)]
+pub(crate) struct CallbackLimit<'a> {
+    /// Runtime measuring the time
+    pub(crate) runtime: &'a dyn Runtime,
+    /// Longest time one callback may run
+    pub(crate) timeout: Duration,
+    /// [`Event::CallbackTimeout`] is published here
+    pub(crate) events: Option<&'a EventSender>,
+}
+
+/// Calls `callbacks` in order until one of them breaks or fails.
+///
+/// With a `limit`, a callback running longer is dropped and the next one is called.
+pub(crate) async fn call_chain<T: Clone>(
+    callbacks: &[Callback<T>],
+    event: T,
+    bot: &Arc<Mutex<Player>>,
+    world: &Arc<RwLock<World>>,
+    sender: &MessageSender,
+    limit: Option<CallbackLimit<'_>>,
+) -> BotResult {
+    for callback in callbacks {
+        event!(
+            trace,
+            event = std::any::type_name::<T>(),
+            "Calling callback"
+        );
+        let call = callback(
+            event.clone(),
+            Arc::clone(bot),
+            Arc::clone(world),
+            Arc::clone(sender),
+        );
+        let flow = match limit {
+            None => call.await,
+            Some(limit) => match select(call, limit.runtime.sleep(limit.timeout)).await {
+                Either::Left((flow, _)) => flow,
+                Either::Right(_) => {
+                    event!(warn, timeout = ?limit.timeout, "Callback timed out");
+                    if let Some(events) = limit.events {
+                        let _ = events.send(Event::CallbackTimeout {
+                            event: std::any::type_name::<T>(),
+                            timeout: limit.timeout,
+                        });
+                    }
+                    continue;
+                }
+            },
+        };
+        let flow = match flow {
+            Ok(flow) => flow,
+            Err(e) => {
+                event!(warn, error = %e, "Callback failed");
+                return Err(e);
+            }
+        };
+        if flow.is_break() {
+            event!(trace, "Callback stopped the chain");
+            break;
+        }
+    }
+    Ok(())
 }
 
 /// A high-level abstraction function that is used
@@ -104,127 +988,209 @@ pub struct World {
 ///
 /// For example, if this function receives a [`JoinBroadcast`],
 /// it will add a new [`Player`] to the [`World`] and call
-/// [`JoinCallback`] that was passed in.
+/// the chain of [`JoinCallback`]s that was passed in.
 ///
 /// [`World`]: ./struct.World.html
 /// [`Player`]: ./struct.Player.html
 /// [`JoinBroadcast`]: ../packets/struct.JoinBroadcast.html
 /// [`JoinCallback`]: ./type.JoinCallback.html
-// FIXME: This function should not call callbacks if there is none.
-// Right now we just supply `default_join` and similar, but
-// really we shouldn't create and call them at all
-// if we don't need to.
 pub async fn patch_world(
-    callbacks: Arc<(JoinCallback, ExitCallback, PoseCallback, TalkCallback)>,
-    data: Bytes,
-    world: Arc<Mutex<World>>,
+    callbacks: Arc<Callbacks>,
+    data: &[u8],
+    world: Arc<RwLock<World>>,
     bot: Arc<Mutex<Player>>,
     replace_invalid_utf8: bool,
     sender: MessageSender,
 ) -> BotResult {
-    match data[0] {
-        /* Accept only stuff we care about, aka broadcasts */
-        b'j' => {
-            // Someone has joined
-            let join_brc: JoinBroadcast = match data.try_into() {
-                Err(e) => return Err(RuntimeError::from_string(format!("{:?}", e))),
-                Ok(brc) => brc,
-            };
-            match (&callbacks.0)(
-                join_brc.clone(),
-                Arc::clone(&bot),
-                Arc::clone(&world),
-                sender,
-            )
-            .await
-            {
-                Err(e) => return Err(e),
-                _ => {}
-            }
-            let nickname = match replace_invalid_utf8 {
-                true => join_brc
+    patch_world_with(
+        callbacks,
+        data,
+        world,
+        bot,
+        replace_invalid_utf8,
+        sender,
+        Taps::default(),
+    )
+    .await
+}
+
+/// Consumers of received packets besides the callbacks, see [`patch_world_with`].
+#[derive(Default, Clone, Copy)]
+pub(crate) struct Taps<'a> {
+    /// Pose broadcasts go here instead of the [`PoseCallback`]s
+    pub(crate) poses: Option<&'a PoseQueue>,
+    /// Every [`Event`] is published here
+    pub(crate) events: Option<&'a EventSender>,
+    /// Poses with sprites missing from the catalog are warned about
+    pub(crate) sprites: Option<&'a SpriteCatalog>,
+    /// Talk broadcasts of ignored players are dropped before anything else sees them
+    pub(crate) ignore: Option<&'a std::sync::Mutex<IgnoreList>>,
+    /// Lag responses are matched to the lag requests of the sender
+    pub(crate) latency: Option<&'a std::sync::Mutex<LatencyTracker>>,
+    /// Every received frame is counted here before it is handled
+    pub(crate) stats: Option<&'a std::sync::Mutex<PacketStats>>,
+    /// Callbacks running longer are skipped
+    pub(crate) callback_limit: Option<CallbackLimit<'a>>,
+}
+
+impl Taps<'_> {
+    /// Publishes the event made by `event` if anyone listens.
+    fn emit<F: FnOnce() -> Event>(&self, event: F) {
+        if let Some(events) = self.events {
+            // Nobody subscribed at the moment, the event is not needed
+            let _ = events.send(event());
+        }
+    }
+
+    /// Warns and publishes [`Event::UnknownSprite`] if validation is enabled and `sprite` is unknown.
+    fn check_sprite(&self, player: u16, sprite: u16) {
+        if self
+            .sprites
+            .is_some_and(|sprites| !sprites.contains(sprite))
+        {
+            event!(warn, player, sprite, "Unknown sprite");
+            self.emit(|| Event::UnknownSprite { player, sprite });
+        }
+    }
+}
+
+/// Same as [`patch_world`], but packets also go to `taps`.
+pub(crate) async fn patch_world_with(
+    callbacks: Arc<Callbacks>,
+    data: &[u8],
+    world: Arc<RwLock<World>>,
+    bot: Arc<Mutex<Player>>,
+    replace_invalid_utf8: bool,
+    sender: MessageSender,
+    taps: Taps<'_>,
+) -> BotResult {
+    let parsed = parse_packet(data);
+    if let Some(stats) = taps.stats {
+        stats
+            .lock()
+            .expect("Packet stats are poisoned")
+            .record(data, &parsed, Instant::now());
+    }
+    let packet = match parsed {
+        // Newer servers may send packets this version doesn't know about
+        Err(PacketParseError::UnknownToken(_token)) => {
+            event!(debug, token = ?_token, len = data.len(), "Received unknown packet");
+            return call_chain(
+                &callbacks.5,
+                data.to_vec(),
+                &bot,
+                &world,
+                &sender,
+                taps.callback_limit,
+            )
+            .await;
+        }
+        Err(e) => {
+            event!(warn, error = %e, len = data.len(), "Received invalid packet");
+            return Err(BotError::PacketParse(e));
+        }
+        Ok(packet) => packet,
+    };
+    event!(trace, ?packet, "Received packet");
+    match packet {
+        /* Accept only stuff we care about, aka broadcasts */
+        Packet::JoinBroadcast(join_brc) => {
+            // Someone has joined
+            taps.emit(|| Event::Join(join_brc.clone()));
+            taps.check_sprite(join_brc.id, join_brc.player_pose.sprite);
+            call_chain(
+                &callbacks.0,
+                join_brc.clone(),
+                &bot,
+                &world,
+                &sender,
+                taps.callback_limit,
+            )
+            .await?;
+            let nickname = match replace_invalid_utf8 {
+                true => join_brc
                     .name
                     .to_string_lossy()
                     .into_owned()
                     .replace('\u{0}', ""),
                 false => join_brc.name.to_string_lossy().into_owned(),
             };
-            let mut world = world.lock().await;
-            world.clients.push(Player {
-                nickname,
-                pose: join_brc.player_pose,
-                id: join_brc.id,
-            });
+            let mut world = world.write().await;
+            let mut player = Player::new(nickname, PlayerPose::default(), join_brc.id);
+            player.keep_history(world.pose_history);
+            player.update_pose(join_brc.player_pose, Instant::now());
+            world.clients.insert(join_brc.id, player);
         }
 
-        b'e' => {
+        Packet::ExitBroadcast(exit_brc) => {
             // Someone has left
-            let exit_brc: ExitBroadcast = match data.try_into() {
-                Err(e) => return Err(RuntimeError::from_string(format!("{:?}", e))),
-                Ok(brc) => brc,
-            };
-            match (&callbacks.1)(
+            taps.emit(|| Event::Exit(exit_brc.clone()));
+            call_chain(
+                &callbacks.1,
                 exit_brc.clone(),
-                Arc::clone(&bot),
-                Arc::clone(&world),
-                sender,
+                &bot,
+                &world,
+                &sender,
+                taps.callback_limit,
             )
-            .await
-            {
-                Err(e) => return Err(e),
-                _ => {}
+            .await?;
+            // Exit of a player the bot never saw joining is nothing to clean up
+            world.write().await.clients.remove(&exit_brc.id);
+            if let Some(ignore) = taps.ignore {
+                ignore.lock().unwrap().forget(exit_brc.id);
             }
-            let mut world = world.lock().await;
-            let idx = world
-                .clients
-                .iter()
-                .position(|c| c.id == exit_brc.id)
-                .unwrap();
-            world.clients.remove(idx);
         }
 
-        b'p' => {
+        Packet::PoseBroadcast(pose_brc) => {
             // Someone has moved or their pose changed for any reason
-            let pose_brc: PoseBroadcast = match data.try_into() {
-                Err(e) => return Err(RuntimeError::from_string(format!("{:?}", e))),
-                Ok(brc) => brc,
-            };
-            match (&callbacks.2)(
-                pose_brc.clone(),
-                Arc::clone(&bot),
-                Arc::clone(&world),
-                sender,
-            )
-            .await
-            {
-                Err(e) => return Err(e),
-                _ => {}
+            taps.emit(|| Event::Pose(pose_brc.clone()));
+            taps.check_sprite(pose_brc.id, pose_brc.player_pose.sprite);
+            match taps.poses {
+                Some(queue) => queue.push(pose_brc.clone()),
+                None => {
+                    call_chain(
+                        &callbacks.2,
+                        pose_brc.clone(),
+                        &bot,
+                        &world,
+                        &sender,
+                        taps.callback_limit,
+                    )
+                    .await?
+                }
+            }
+            if let Some(client) = world.write().await.client_mut(pose_brc.id) {
+                client.update_pose(pose_brc.player_pose, Instant::now());
             }
-            let mut world = world.lock().await;
-            let mut client = match world.clients.iter_mut().find(|c| c.id == pose_brc.id) {
-                Some(v) => v,
-                None => return Ok(()),
-            };
-            client.pose = pose_brc.player_pose;
         }
 
-        b't' => {
+        Packet::TalkBroadcast(talk_brc) => {
             // Someone has said something
-            let talk_brc: TalkBroadcast = match data.try_into() {
-                Err(e) => return Err(RuntimeError::from_string(format!("{:?}", e))),
-                Ok(brc) => brc,
-            };
-            match (&callbacks.3)(
+            if let Some(ignore) = taps.ignore {
+                let nickname = world
+                    .read()
+                    .await
+                    .client(talk_brc.id)
+                    .map(|c| c.nickname.clone());
+                let allowed =
+                    ignore
+                        .lock()
+                        .unwrap()
+                        .allow(talk_brc.id, nickname.as_deref(), Instant::now());
+                if !allowed {
+                    return Ok(());
+                }
+            }
+            taps.emit(|| Event::Talk(talk_brc.clone()));
+            call_chain(
+                &callbacks.3,
                 talk_brc.clone(),
-                Arc::clone(&bot),
-                Arc::clone(&world),
-                sender,
+                &bot,
+                &world,
+                &sender,
+                taps.callback_limit,
             )
-            .await
-            {
-                Err(e) => return Err(e),
-                _ => {}
-            }
+            .await?;
             let content = match replace_invalid_utf8 {
                 true => talk_brc
                     .str
@@ -233,11 +1199,40 @@ pub async fn patch_world(
                     .replace('\u{0}', ""),
                 false => talk_brc.str.to_string_lossy().into_owned(),
             };
-            world
-                .lock()
-                .await
-                .messages
-                .push(Message::new(content, talk_brc.id, Instant::now()));
+            let bot_name = {
+                let bot = bot.lock().await;
+                (bot.id == talk_brc.id).then(|| bot.nickname.clone())
+            };
+            let mut world = world.write().await;
+            let mut message = Message::new(content, talk_brc.id, Instant::now());
+            // Resolved now, the author may leave before anyone reads the message
+            message.author_name =
+                bot_name.or_else(|| world.client(talk_brc.id).map(|c| c.nickname.clone()));
+            world.messages.push(message);
+        }
+
+        Packet::LagResponse(lag_rsp) => {
+            // Server answered a lag request
+            taps.emit(|| Event::Lag(lag_rsp.clone()));
+            if let Some(latency) = taps.latency {
+                latency
+                    .lock()
+                    .expect("Latency tracker is poisoned")
+                    .pong(lag_rsp.stamp, Instant::now());
+            }
+            #[allow(deprecated)]
+            {
+                world.write().await.lag = lag_rsp.stamp;
+            }
+            call_chain(
+                &callbacks.4,
+                lag_rsp,
+                &bot,
+                &world,
+                &sender,
+                taps.callback_limit,
+            )
+            .await?;
         }
 
         _ => {} // Don't care
@@ -252,8 +1247,12 @@ pub async fn patch_world(
 /// and is required for functions which are planned to be
 /// passed as an argument to [`Bot::on_talk`] or similar methods.
 ///
+/// Deprecated in favor of the [`#[callback]`](macro@callback) attribute, which does the same
+/// without going through `macro_rules_attribute`.
+///
 /// Stolen from [here](https://users.rust-lang.org/t/how-to-store-async-function-pointer/38343/4)
 /// , thanks to [Yandros](https://users.rust-lang.org/u/Yandros).
+#[deprecated(note = "use the `#[callback]` attribute from `asciicker_rs::y6::prelude` instead")]
 #[macro_export]
 macro_rules! callback {(
     $( #[$attr:meta] )* // includes doc strings
@@ -284,7 +1283,7 @@ macro_rules! callback {(
 ///
 /// [patching the world]: ./fn.patch_world.html
 pub struct Receiver {
-    /// Thread [`JoinHandle`]
+    /// Thread [`Task`]
     pub thread: ConnectionThread,
     /// `true` if thread is still alive
     pub is_finished: Arc<Mutex<bool>>,
@@ -297,252 +1296,1340 @@ pub struct Receiver {
 /// (required to get broadcasts) and sending talk requests when needed.
 ///
 /// Sender has a receiver handle of message channel, so anyone with a [sender
-/// handle] (callbacks, main function) can push those messages in and next time sender thread wants to
-/// send a pose request, it will also send the requested messages from the channel.
+/// handle] (callbacks, main function) can push those messages in and sender thread
+/// sends them as soon as they arrive, between pose requests.
 ///
 /// [sender handle]: ./type.MessageSender.html
 pub struct Sender {
-    /// Thread [`JoinHandle`]
+    /// Thread [`Task`]
     pub thread: ConnectionThread,
     /// `true` if thread is still alive
     pub is_finished: Arc<Mutex<bool>>,
 }
 
+/// Decides whether and when [`Bot`] tries to connect again after the [`Transport`] failed to connect.
+///
+/// Only connection attempts are retried, a refused join is handled by [`Bot::join_queue`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ReconnectPolicy {
+    /// Return the error right away
+    #[default]
+    Never,
+    /// Wait the same `interval` before every attempt, give up after `attempts` failures if set
+    Fixed {
+        /// Delay between attempts
+        interval: Duration,
+        /// Maximum amount of failed attempts, `None` retries forever
+        attempts: Option<u32>,
+    },
+    /// Double the delay after every failure, starting at `initial` and never exceeding `max`
+    Backoff {
+        /// Delay before the first retry
+        initial: Duration,
+        /// Longest delay between attempts
+        max: Duration,
+        /// Maximum amount of failed attempts, `None` retries forever
+        attempts: Option<u32>,
+    },
+}
+
+impl ReconnectPolicy {
+    /// Returns how long to wait after `failed` failed attempts, `None` if the bot should give up.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let policy = ReconnectPolicy::Backoff {
+    ///     initial: Duration::from_secs(1),
+    ///     max: Duration::from_secs(5),
+    ///     attempts: Some(4),
+    /// };
+    /// assert_eq!(policy.delay(1), Some(Duration::from_secs(1)));
+    /// assert_eq!(policy.delay(3), Some(Duration::from_secs(4)));
+    /// assert_eq!(policy.delay(4), Some(Duration::from_secs(5)));
+    /// assert_eq!(policy.delay(5), None);
+    /// assert_eq!(ReconnectPolicy::Never.delay(1), None);
+    /// ```
+    pub fn delay(&self, failed: u32) -> Option<Duration> {
+        match *self {
+            Self::Never => None,
+            Self::Fixed { attempts, .. } | Self::Backoff { attempts, .. }
+                if attempts.is_some_and(|attempts| failed > attempts) =>
+            {
+                None
+            }
+            Self::Fixed { interval, .. } => Some(interval),
+            Self::Backoff { initial, max, .. } => {
+                let factor = 1u32
+                    .checked_shl(failed.saturating_sub(1))
+                    .unwrap_or(u32::MAX);
+                Some(initial.saturating_mul(factor).min(max))
+            }
+        }
+    }
+}
+
+/// Builder of [`Bot`], every option except the nickname has a default.
+///
+/// Defaults are `ws://asciicker.com/ws/y6/` address, no invalid UTF-8 replacement,
+/// a pose request every 10 milliseconds, [`ReconnectPolicy::Never`]
+/// and 1024 messages of chat history.
+///
+/// # Examples
+///
+/// ## Building a bot that keeps trying to connect:
+/// ```no_run
+/// use asciicker_rs::y6::prelude::*;
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let bot = BotBuilder::new("bot")
+///     .address("ws://localhost:8080/ws/y6/")
+///     .pose_interval(Some(Duration::from_millis(50)))
+///     .replace_invalid_utf8(true)
+///     .reconnect(ReconnectPolicy::Fixed {
+///         interval: Duration::from_secs(5),
+///         attempts: None,
+///     })
+///     .build();
+/// let (threads, data) = bot.run().await.unwrap();
+/// # }
+/// ```
+pub struct BotBuilder {
+    nickname: String,
+    address: String,
+    replace_invalid_utf8: bool,
+    pose_interval: Option<Duration>,
+    skip_unchanged_poses: bool,
+    lag_interval: Option<Duration>,
+    strict_talk: bool,
+    reconnect: ReconnectPolicy,
+    chat_history: usize,
+    pose_history: usize,
+    sprites: Option<Arc<SpriteCatalog>>,
+    callback_timeout: Option<Duration>,
+    flood: Option<FloodPolicy>,
+    join_queue: Option<Duration>,
+    server_id: Option<ServerId>,
+    headers: Vec<(String, String)>,
+    query: Vec<(String, String)>,
+    transport: Arc<dyn Transport>,
+    runtime: Option<Arc<dyn Runtime>>,
+    pose_delivery: DeliveryPolicy,
+}
+
+impl BotBuilder {
+    /// Constructs a new [`BotBuilder`] with default options.
+    pub fn new<S: Into<String>>(nickname: S) -> Self {
+        Self {
+            nickname: nickname.into(),
+            address: ServerAddress::new("asciicker.com")
+                .version(Version::Y6)
+                .into(),
+            replace_invalid_utf8: false,
+            pose_interval: Some(DEFAULT_POSE_INTERVAL),
+            skip_unchanged_poses: false,
+            lag_interval: Some(DEFAULT_LAG_INTERVAL),
+            strict_talk: false,
+            reconnect: ReconnectPolicy::Never,
+            chat_history: DEFAULT_CHAT_HISTORY,
+            pose_history: 0,
+            sprites: None,
+            callback_timeout: None,
+            flood: None,
+            join_queue: None,
+            server_id: None,
+            headers: vec![],
+            query: vec![],
+            transport: default_transport(),
+            runtime: default_runtime(),
+            pose_delivery: DeliveryPolicy::Inline,
+        }
+    }
+
+    /// Sets the address of the server, a string or a [`ServerAddress`].
+    pub fn address<S: Into<String>>(mut self, address: S) -> Self {
+        self.address = address.into();
+        self
+    }
+
+    /// Replaces invalid UTF-8 in player names and messages if `true`, see [`patch_world`].
+    pub fn replace_invalid_utf8(mut self, replace_invalid_utf8: bool) -> Self {
+        self.replace_invalid_utf8 = replace_invalid_utf8;
+        self
+    }
+
+    /// Sets the time between two lag requests, see [`Bot::lag_interval`].
+    pub fn lag_interval(mut self, lag_interval: Option<Duration>) -> Self {
+        self.lag_interval = lag_interval;
+        self
+    }
+
+    /// Sets the shortest time between two pose requests, see [`Bot::pose_interval`].
+    pub fn pose_interval(mut self, pose_interval: Option<Duration>) -> Self {
+        self.pose_interval = pose_interval;
+        self
+    }
+
+    /// Skips pose requests repeating the last sent pose if `true`, which can stop broadcasts
+    /// from the server, see [`Bot::skip_unchanged_poses`].
+    pub fn skip_unchanged_poses(mut self, skip_unchanged_poses: bool) -> Self {
+        self.skip_unchanged_poses = skip_unchanged_poses;
+        self
+    }
+
+    /// Fails instead of splitting long messages if `true`, see [`Bot::strict_talk`].
+    pub fn strict_talk(mut self, strict_talk: bool) -> Self {
+        self.strict_talk = strict_talk;
+        self
+    }
+
+    /// Sets the [`ReconnectPolicy`] used when connecting fails.
+    pub fn reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = policy;
+        self
+    }
+
+    /// Sets how many of the latest chat messages [`World::messages`] keeps.
+    pub fn chat_history(mut self, capacity: usize) -> Self {
+        self.chat_history = capacity;
+        self
+    }
+
+    /// Sets how many of the latest poses every client keeps in [`Player::history`],
+    /// `0` (the default) disables the history.
+    pub fn pose_history(mut self, capacity: usize) -> Self {
+        self.pose_history = capacity;
+        self
+    }
+
+    /// Warns whenever a pose references a sprite missing from `sprites`, with a `tracing` event
+    /// and [`Event::UnknownSprite`] on [`BotHandle::events`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let server = Server::new(8).bind("127.0.0.1:0").await.unwrap();
+    /// let address = format!("ws://{}", server.address);
+    /// let (_, watcher) = BotBuilder::new("watcher")
+    ///     .address(&address)
+    ///     .validate_sprites((0..16).collect())
+    ///     .build()
+    ///     .spawn()
+    ///     .await
+    ///     .unwrap();
+    /// let (_, modded) = BotBuilder::new("modded").address(&address).build().spawn().await.unwrap();
+    ///
+    /// let unknown = watcher.wait_for(
+    ///     |event| match event {
+    ///         Event::UnknownSprite { sprite, .. } => Some(*sprite),
+    ///         _ => None,
+    ///     },
+    ///     Duration::from_secs(5),
+    /// );
+    /// let mut pose = modded.snapshot().player.pose.clone();
+    /// pose.sprite = 0x7fff;
+    /// modded.set_pose(pose).unwrap();
+    /// assert_eq!(unknown.await.unwrap(), 0x7fff);
+    /// # }
+    /// ```
+    pub fn validate_sprites(mut self, sprites: SpriteCatalog) -> Self {
+        self.sprites = Some(Arc::new(sprites));
+        self
+    }
+
+    /// Sets the longest time a callback may run, see [`Bot::callback_timeout`].
+    pub fn callback_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.callback_timeout = timeout;
+        self
+    }
+
+    /// Enables flood suppression of the [`IgnoreList`], see [`Bot::ignore_list`].
+    pub fn flood(mut self, policy: FloodPolicy) -> Self {
+        self.flood = Some(policy);
+        self
+    }
+
+    /// Enables the join queue, see [`Bot::join_queue`].
+    pub fn join_queue(mut self, retry_interval: Duration) -> Self {
+        self.join_queue = Some(retry_interval);
+        self
+    }
+
+    /// Sets the [`ServerId`], see [`Bot::server_id`].
+    pub fn server_id<S: Into<ServerId>>(mut self, server_id: S) -> Self {
+        self.server_id = Some(server_id.into());
+        self
+    }
+
+    /// Adds a header to the websocket handshake, see [`Bot::headers`].
+    pub fn header<S: Into<String>>(mut self, name: S, value: S) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Adds a query parameter to the address, see [`Bot::query`].
+    pub fn query<S: Into<String>>(mut self, name: S, value: S) -> Self {
+        self.query.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the [`Transport`], see [`Bot::transport`].
+    pub fn transport(mut self, transport: Arc<dyn Transport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Tunnels the connection through `proxy`, replacing the [`Transport`] with a
+    /// [`ProxyTransport`].
+    #[cfg(feature = "tokio")]
+    pub fn proxy(mut self, proxy: OutboundProxy) -> Self {
+        self.transport = Arc::new(ProxyTransport::new(proxy));
+        self
+    }
+
+    /// Sets the [`Runtime`], see [`Bot::runtime`].
+    pub fn runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Sets the [`DeliveryPolicy`] of pose broadcasts, see [`Bot::pose_delivery`].
+    pub fn pose_delivery(mut self, policy: DeliveryPolicy) -> Self {
+        self.pose_delivery = policy;
+        self
+    }
+
+    /// Builds the [`Bot`].
+    pub fn build(self) -> Bot {
+        debug_assert!(
+            self.nickname.len() <= 31,
+            "Bot's name cannot be longer than 31 character"
+        );
+        Bot {
+            nickname: self.nickname,
+            callbacks: Default::default(),
+            interceptors: vec![],
+            dequeue_callback: None,
+            ready_callback: None,
+            disconnect_callback: None,
+            replace_invalid_utf8: self.replace_invalid_utf8,
+            address: self.address,
+            pose_interval: self.pose_interval,
+            skip_unchanged_poses: self.skip_unchanged_poses,
+            lag_interval: self.lag_interval,
+            strict_talk: self.strict_talk,
+            reconnect: self.reconnect,
+            chat_history: self.chat_history,
+            pose_history: self.pose_history,
+            sprites: self.sprites,
+            callback_timeout: self.callback_timeout,
+            outbound_recording: None,
+            join_queue: self.join_queue,
+            server_id: self.server_id,
+            headers: self.headers,
+            query: self.query,
+            transport: self.transport,
+            runtime: self.runtime,
+            pose_delivery: self.pose_delivery,
+            delivery_stats: Default::default(),
+            latency: Default::default(),
+            stats: Default::default(),
+            ignore_list: {
+                let mut ignore_list = IgnoreList::new();
+                ignore_list.flood(self.flood);
+                Arc::new(std::sync::Mutex::new(ignore_list))
+            },
+        }
+    }
+}
+
 /// Provides highest level of abstraction.
 ///
-/// Can be easily constructed with [`Bot::new`] and ran with [`Bot::run`].
+/// Can be easily constructed with [`BotBuilder`] and ran with [`Bot::run`].
 ///
 /// # Examples
 ///
 /// ## Creating a new bot and running it:
-/// ```
+/// ```no_run
 /// use asciicker_rs::y6::prelude::*;
 ///
-/// let bot = Bot::new("bot", "ws://asciicker.com/ws/y6/", true);
+/// let bot = BotBuilder::new("bot").replace_invalid_utf8(true).build();
 ///
 /// bot.run();
 /// loop {}
 /// ```
 pub struct Bot {
     nickname: String,
-    join_callback: Option<JoinCallback>,
-    exit_callback: Option<ExitCallback>,
-    pose_callback: Option<PoseCallback>,
-    talk_callback: Option<TalkCallback>,
+    callbacks: Callbacks,
+    interceptors: Vec<Interceptor>,
+    dequeue_callback: Option<DequeueCallback>,
+    ready_callback: Option<ReadyCallback>,
+    disconnect_callback: Option<DisconnectCallback>,
     replace_invalid_utf8: bool,
     address: String,
+    pose_interval: Option<Duration>,
+    skip_unchanged_poses: bool,
+    lag_interval: Option<Duration>,
+    strict_talk: bool,
+    reconnect: ReconnectPolicy,
+    chat_history: usize,
+    pose_history: usize,
+    sprites: Option<Arc<SpriteCatalog>>,
+    callback_timeout: Option<Duration>,
+    outbound_recording: Option<SharedRecording>,
+    join_queue: Option<Duration>,
+    server_id: Option<ServerId>,
+    headers: Vec<(String, String)>,
+    query: Vec<(String, String)>,
+    transport: Arc<dyn Transport>,
+    runtime: Option<Arc<dyn Runtime>>,
+    pose_delivery: DeliveryPolicy,
+    delivery_stats: Arc<DeliveryStats>,
+    latency: SharedLatency,
+    stats: SharedPacketStats,
+    ignore_list: SharedIgnoreList,
 }
 
 impl Bot {
     /// Constructs a new [`Bot`] instance.
+    #[deprecated(note = "Use BotBuilder instead")]
     pub fn new<S: Into<String>>(nickname: S, address: S, replace_invalid_utf8: bool) -> Self {
-        let nickname = nickname.into();
-        let address = address.into();
-        debug_assert!(
-            nickname.len() <= 31,
-            "Bot's name cannot be longer than 31 character"
-        );
-        Self {
-            nickname,
-            join_callback: None,
-            exit_callback: None,
-            pose_callback: None,
-            talk_callback: None,
-            replace_invalid_utf8,
-            address,
-        }
+        BotBuilder::new(nickname)
+            .address(address)
+            .replace_invalid_utf8(replace_invalid_utf8)
+            .build()
     }
 
-    /// Replaces [`JoinCallback`] and returns [`Some(JoinCallback)`] if any was set already.
-    /// [`Some(JoinCallback)`]: [Option::Some]
-    pub fn on_join(&mut self, callback: JoinCallback) -> Option<JoinCallback> {
-        let mut callback = Some(callback);
-        swap(&mut callback, &mut self.join_callback);
-        callback
+    /// Replaces [`Runtime`] the bot spawns its threads and timers on and returns the previous one.
+    ///
+    /// Default is [`TokioRuntime`](crate::common::TokioRuntime) with the `tokio` feature,
+    /// otherwise [`AsyncStdRuntime`](crate::common::AsyncStdRuntime) with the `async-std` feature.
+    /// `wasm` builds for the browser default to `WasmRuntime`, which runs everything on the
+    /// event loop of the page instead of spawning threads.
+    /// Without either of them the bot fails to start until a runtime is set.
+    ///
+    /// # Examples
+    ///
+    /// ## Running a bot on a different executor:
+    /// ```no_run
+    /// use asciicker_rs::common::Runtime;
+    /// use asciicker_rs::y6::prelude::*;
+    /// use futures_util::future::BoxFuture;
+    /// use std::{sync::Arc, time::Duration};
+    ///
+    /// struct Smol;
+    ///
+    /// impl Runtime for Smol {
+    ///     fn spawn(&self, task: BoxFuture<'static, ()>) {
+    ///         # let _ = task;
+    ///         # /*
+    ///         smol::spawn(task).detach();
+    ///         # */
+    ///     }
+    ///
+    ///     fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+    ///         # let _ = duration;
+    ///         # /*
+    ///         Box::pin(async move { smol::Timer::after(duration).await; })
+    ///         # */
+    ///         # Box::pin(async {})
+    ///     }
+    /// }
+    ///
+    /// let mut bot = BotBuilder::new("bot").build();
+    /// bot.runtime(Some(Arc::new(Smol)));
+    /// ```
+    pub fn runtime(&mut self, runtime: Option<Arc<dyn Runtime>>) -> Option<Arc<dyn Runtime>> {
+        let mut runtime = runtime;
+        swap(&mut runtime, &mut self.runtime);
+        runtime
+    }
+
+    /// Starts recording every frame the bot sends and returns the [`SharedRecording`] it is written to.
+    ///
+    /// Calling this again replaces the previous recording with a new empty one.
+    pub fn record_outbound(&mut self) -> SharedRecording {
+        let recording = Arc::new(Mutex::new(Recording::new()));
+        self.outbound_recording = Some(Arc::clone(&recording));
+        recording
     }
 
-    /// Replaces [`ExitCallback`] and returns [`Some(ExitCallback)`] if any was set already.
-    /// [`Some(ExitCallback)`]: [Option::Some]
-    pub fn on_exit(&mut self, callback: ExitCallback) -> Option<ExitCallback> {
-        let mut callback = Some(callback);
-        swap(&mut callback, &mut self.exit_callback);
+    /// Adds a [`JoinCallback`] to the end of the chain, callbacks run in registration order
+    /// until one of them returns [`ControlFlow::Break`].
+    pub fn on_join<F, R>(&mut self, callback: F)
+    where
+        F: Fn(
+                JoinBroadcast,
+                Arc<Mutex<Player>>,
+                Arc<RwLock<World>>,
+                MessageSender,
+            ) -> FutureCallbackResult<R>
+            + Send
+            + Sync
+            + 'static,
+        R: IntoControlFlow + 'static,
+    {
+        self.callbacks.0.push(into_callback(callback));
+    }
+
+    /// Adds a [`ExitCallback`] to the end of the chain, callbacks run in registration order
+    /// until one of them returns [`ControlFlow::Break`].
+    pub fn on_exit<F, R>(&mut self, callback: F)
+    where
+        F: Fn(
+                ExitBroadcast,
+                Arc<Mutex<Player>>,
+                Arc<RwLock<World>>,
+                MessageSender,
+            ) -> FutureCallbackResult<R>
+            + Send
+            + Sync
+            + 'static,
+        R: IntoControlFlow + 'static,
+    {
+        self.callbacks.1.push(into_callback(callback));
+    }
+
+    /// Adds a [`PoseCallback`] to the end of the chain, callbacks run in registration order
+    /// until one of them returns [`ControlFlow::Break`].
+    pub fn on_pose<F, R>(&mut self, callback: F)
+    where
+        F: Fn(
+                PoseBroadcast,
+                Arc<Mutex<Player>>,
+                Arc<RwLock<World>>,
+                MessageSender,
+            ) -> FutureCallbackResult<R>
+            + Send
+            + Sync
+            + 'static,
+        R: IntoControlFlow + 'static,
+    {
+        self.callbacks.2.push(into_callback(callback));
+    }
+
+    /// Adds a [`TalkCallback`] to the end of the chain, callbacks run in registration order
+    /// until one of them returns [`ControlFlow::Break`].
+    ///
+    /// # Examples
+    ///
+    /// ## Counting messages with a closure:
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let said = Arc::new(AtomicUsize::new(0));
+    /// let mut bot = BotBuilder::new("counter").replace_invalid_utf8(true).build();
+    /// let counter = Arc::clone(&said);
+    /// bot.on_talk(move |_, _, _, _| {
+    ///     counter.fetch_add(1, Ordering::Relaxed);
+    ///     Box::pin(async { Ok(()) })
+    /// });
+    ///
+    /// Scenario::new(&bot)
+    ///     .talk(2, "one")
+    ///     .talk(2, "two")
+    ///     .run()
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(said.load(Ordering::Relaxed), 2);
+    /// # }
+    /// ```
+    pub fn on_talk<F, R>(&mut self, callback: F)
+    where
+        F: Fn(
+                TalkBroadcast,
+                Arc<Mutex<Player>>,
+                Arc<RwLock<World>>,
+                MessageSender,
+            ) -> FutureCallbackResult<R>
+            + Send
+            + Sync
+            + 'static,
+        R: IntoControlFlow + 'static,
+    {
+        self.callbacks.3.push(into_callback(callback));
+    }
+
+    /// Adds [`Commands`] to the end of the talk chain, see [`Commands`] for an example.
+    ///
+    /// Callbacks after it aren't called for messages it handled.
+    pub fn on_commands(&mut self, commands: Commands) {
+        self.callbacks.3.push(commands.into_callback());
+    }
+
+    /// Adds a [`ChatLogger`] to the end of the talk chain, see [`ChatLogger`] for an example.
+    ///
+    /// Messages stopped by an earlier callback, like handled [`Commands`], aren't logged.
+    pub fn on_chat_log(&mut self, logger: ChatLogger) {
+        self.callbacks.3.push(logger.into_callback());
+    }
+
+    /// Adds a [`LagCallback`] to the end of the chain, callbacks run in registration order
+    /// until one of them returns [`ControlFlow::Break`].
+    ///
+    /// # Examples
+    ///
+    /// ## Reacting to a lag response:
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut bot = BotBuilder::new("pinger").replace_invalid_utf8(true).build();
+    /// bot.on_lag(|rsp, _, _, sender| {
+    ///     Box::pin(async move {
+    ///         sender
+    ///             .send(format!("Lag stamp: {:?}", rsp.stamp))
+    ///             .await
+    ///             .map_err(BotError::callback)
+    ///     })
+    /// });
+    ///
+    /// let world = Scenario::new(&bot)
+    ///     .lag([1, 2, 3])
+    ///     .expect_talk(|m| m == "Lag stamp: [1, 2, 3]", std::time::Duration::from_secs(1))
+    ///     .run()
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(world.lag, [1, 2, 3]);
+    /// # }
+    /// ```
+    pub fn on_lag<F, R>(&mut self, callback: F)
+    where
+        F: Fn(
+                LagResponse,
+                Arc<Mutex<Player>>,
+                Arc<RwLock<World>>,
+                MessageSender,
+            ) -> FutureCallbackResult<R>
+            + Send
+            + Sync
+            + 'static,
+        R: IntoControlFlow + 'static,
+    {
+        self.callbacks.4.push(into_callback(callback));
+    }
+
+    /// Adds an [`UnknownPacketCallback`] to the end of the chain, callbacks run in registration order
+    /// until one of them returns [`ControlFlow::Break`].
+    ///
+    /// # Examples
+    ///
+    /// ## Collecting packets of a newer server:
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use std::sync::{Arc, Mutex as StdMutex};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let unknown = Arc::new(StdMutex::new(vec![]));
+    /// let mut bot = BotBuilder::new("explorer").replace_invalid_utf8(true).build();
+    /// let collected = Arc::clone(&unknown);
+    /// bot.on_unknown_packet(move |packet, _, _, _| {
+    ///     collected.lock().unwrap().push(packet);
+    ///     Box::pin(async { Ok(()) })
+    /// });
+    ///
+    /// Scenario::new(&bot)
+    ///     .receive(vec![b'x', 1, 2, 3])
+    ///     .run()
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(*unknown.lock().unwrap(), vec![vec![b'x', 1, 2, 3]]);
+    /// # }
+    /// ```
+    pub fn on_unknown_packet<F, R>(&mut self, callback: F)
+    where
+        F: Fn(
+                Bytes,
+                Arc<Mutex<Player>>,
+                Arc<RwLock<World>>,
+                MessageSender,
+            ) -> FutureCallbackResult<R>
+            + Send
+            + Sync
+            + 'static,
+        R: IntoControlFlow + 'static,
+    {
+        self.callbacks.5.push(into_callback(callback));
+    }
+
+    /// Adds an [`Interceptor`] to the end of the chain, interceptors run in registration order
+    /// on every received packet until one of them returns [`InterceptAction::Drop`].
+    ///
+    /// # Examples
+    ///
+    /// ## Ignoring one player and logging everything else:
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut bot = BotBuilder::new("bot").replace_invalid_utf8(true).build();
+    /// bot.add_interceptor(|frame: &mut Bytes| match parse_packet(frame) {
+    ///     Ok(Packet::JoinBroadcast(brc)) if brc.id == 13 => InterceptAction::Drop,
+    ///     _ => InterceptAction::Continue,
+    /// });
+    /// bot.add_interceptor(|frame: &mut Bytes| {
+    ///     println!("Received {:?}", frame);
+    ///     InterceptAction::Continue
+    /// });
+    ///
+    /// let world = Scenario::new(&bot)
+    ///     .join(13, "spammer")
+    ///     .join(2, "player")
+    ///     .run()
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(world.clients.len(), 1);
+    /// assert!(world.client(13).is_none());
+    /// assert_eq!(world.client(2).unwrap().nickname, "player");
+    /// # }
+    /// ```
+    pub fn add_interceptor<F>(&mut self, interceptor: F)
+    where
+        F: Fn(&mut Bytes) -> InterceptAction + Send + Sync + 'static,
+    {
+        self.interceptors.push(Arc::new(interceptor));
+    }
+
+    /// Replaces [`DequeueCallback`] and returns [`Some(DequeueCallback)`] if any was set already.
+    /// [`Some(DequeueCallback)`]: [Option::Some]
+    pub fn on_dequeue<F>(&mut self, callback: F) -> Option<DequeueCallback>
+    where
+        F: Fn(u32, Arc<Mutex<Player>>, Arc<RwLock<World>>, MessageSender) -> FutureBotResult
+            + Send
+            + Sync
+            + 'static,
+    {
+        let mut callback: Option<DequeueCallback> = Some(Arc::new(callback));
+        swap(&mut callback, &mut self.dequeue_callback);
         callback
     }
 
-    /// Replaces [`PoseCallback`] and returns [`Some(PoseCallback)`] if any was set already.
-    /// [`Some(PoseCallback)`]: [Option::Some]
-    pub fn on_pose(&mut self, callback: PoseCallback) -> Option<PoseCallback> {
-        let mut callback = Some(callback);
-        swap(&mut callback, &mut self.pose_callback);
+    /// Replaces [`ReadyCallback`] and returns [`Some(ReadyCallback)`] if any was set already.
+    /// [`Some(ReadyCallback)`]: [Option::Some]
+    ///
+    /// If the callback fails, [`Bot::run`] and [`Bot::spawn`] return its error and disconnect.
+    ///
+    /// # Examples
+    ///
+    /// ## Greeting once after joining:
+    /// ```no_run
+    /// use asciicker_rs::y6::prelude::*;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut bot = BotBuilder::new("greeter").replace_invalid_utf8(true).build();
+    /// bot.on_ready(|rsp, _, _, sender| {
+    ///     Box::pin(async move {
+    ///         sender
+    ///             .send(format!("Hello, I am #{} of {}", rsp.id, rsp.max_clients))
+    ///             .await
+    ///             .map_err(BotError::callback)
+    ///     })
+    /// });
+    /// let (_threads, _data) = bot.run().await.unwrap();
+    /// # }
+    /// ```
+    ///
+    /// ## Disconnecting when the callback fails:
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let server = Server::new(8);
+    /// let running = server.bind("127.0.0.1:0").await.unwrap();
+    /// let mut bot = BotBuilder::new("picky")
+    ///     .address(format!("ws://{}", running.address))
+    ///     .build();
+    /// bot.on_ready(|_, _, _, _| Box::pin(async { Err(BotError::Other("Wrong server".into())) }));
+    /// assert!(bot.run().await.is_err());
+    /// let left = async {
+    ///     while !server.clients().await.is_empty() {
+    ///         tokio::time::sleep(Duration::from_millis(5)).await;
+    ///     }
+    /// };
+    /// tokio::time::timeout(Duration::from_secs(5), left).await.unwrap();
+    /// # }
+    /// ```
+    pub fn on_ready<F>(&mut self, callback: F) -> Option<ReadyCallback>
+    where
+        F: Fn(
+                JoinResponse,
+                Arc<Mutex<Player>>,
+                Arc<RwLock<World>>,
+                MessageSender,
+            ) -> FutureBotResult
+            + Send
+            + Sync
+            + 'static,
+    {
+        let mut callback: Option<ReadyCallback> = Some(Arc::new(callback));
+        swap(&mut callback, &mut self.ready_callback);
         callback
     }
 
-    /// Replaces [`TalkCallback`] and returns [`Some(TalkCallback)`] if any was set already.
-    /// [`Some(TalkCallback)`]: [Option::Some]
-    pub fn on_talk(&mut self, callback: TalkCallback) -> Option<TalkCallback> {
-        let mut callback = Some(callback);
-        swap(&mut callback, &mut self.talk_callback);
+    /// Replaces [`DisconnectCallback`] and returns [`Some(DisconnectCallback)`] if any was set already.
+    /// [`Some(DisconnectCallback)`]: [Option::Some]
+    pub fn on_disconnect<F>(&mut self, callback: F) -> Option<DisconnectCallback>
+    where
+        F: Fn(
+                DisconnectReason,
+                Arc<Mutex<Player>>,
+                Arc<RwLock<World>>,
+                MessageSender,
+            ) -> FutureBotResult
+            + Send
+            + Sync
+            + 'static,
+    {
+        let mut callback: Option<DisconnectCallback> = Some(Arc::new(callback));
+        swap(&mut callback, &mut self.disconnect_callback);
         callback
     }
 
+    /// Enables (or with [`None`] disables) the join queue and returns the previous retry interval.
+    ///
+    /// The server refuses the join by closing the connection instead of sending a join response,
+    /// which happens when it already has `max_clients` players. Without the queue [`Bot::run`]
+    /// returns an error in that case, with the queue it reconnects every `retry_interval`
+    /// until it gets in and then calls the [`DequeueCallback`].
+    pub fn join_queue(&mut self, retry_interval: Option<Duration>) -> Option<Duration> {
+        let mut retry_interval = retry_interval;
+        swap(&mut retry_interval, &mut self.join_queue);
+        retry_interval
+    }
+
+    /// Replaces the shortest time between two pose requests and returns the previous one.
+    ///
+    /// The sender ticks on the [`Runtime`] of the bot: every tick it sends the latest pose, poses
+    /// set in between are never sent. Unless [`Bot::skip_unchanged_poses`] is enabled it is sent
+    /// even if it didn't change. A tick delayed by a slow connection pushes the following ones back
+    /// instead of causing a burst of requests. Queued chat messages are sent as soon as they arrive.
+    ///
+    /// `None` stops sending poses entirely, which suits passive observer bots.
+    pub fn pose_interval(&mut self, pose_interval: Option<Duration>) -> Option<Duration> {
+        let mut pose_interval = pose_interval;
+        swap(&mut pose_interval, &mut self.pose_interval);
+        pose_interval
+    }
+
+    /// Replaces the longest time a callback may run and returns the previous one.
+    ///
+    /// Callbacks run on the receiver, so one that never completes stops the bot from patching
+    /// the [`World`]. With a timeout, such a callback is dropped, [`Event::CallbackTimeout`] is
+    /// published and the next callback of the chain is called. [`None`] (the default) waits
+    /// forever. Only callbacks that wait asynchronously can be stopped: one blocking its thread,
+    /// say with a synchronous HTTP client, is never polled again to notice the timeout.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let server = Server::new(8).bind("127.0.0.1:0").await.unwrap();
+    /// let address = format!("ws://{}", server.address);
+    /// let mut bot = BotBuilder::new("patient")
+    ///     .address(&address)
+    ///     .callback_timeout(Some(Duration::from_millis(50)))
+    ///     .build();
+    /// bot.on_talk(|talk, _, _, _| {
+    ///     Box::pin(async move {
+    ///         if talk.str.as_bytes() == b"hang" {
+    ///             std::future::pending::<()>().await;
+    ///         }
+    ///         Ok(())
+    ///     })
+    /// });
+    /// let (_threads, handle) = bot.spawn().await.unwrap();
+    /// let (_, talker) = BotBuilder::new("talker").address(&address).build().spawn().await.unwrap();
+    ///
+    /// let timed_out = handle.wait_for(
+    ///     |event| matches!(event, Event::CallbackTimeout { .. }).then_some(()),
+    ///     Duration::from_secs(5),
+    /// );
+    /// talker.say("hang").unwrap();
+    /// timed_out.await.unwrap();
+    ///
+    /// // The receiver goes on
+    /// let heard = handle.wait_for_message(|talk| talk.str.as_bytes() == b"hi", Duration::from_secs(5));
+    /// talker.say("hi").unwrap();
+    /// heard.await.unwrap();
+    /// # }
+    /// ```
+    pub fn callback_timeout(&mut self, timeout: Option<Duration>) -> Option<Duration> {
+        let mut timeout = timeout;
+        swap(&mut timeout, &mut self.callback_timeout);
+        timeout
+    }
+
+    /// Replaces the flag skipping unchanged poses and returns the previous one.
+    ///
+    /// When enabled, a tick of [`Bot::pose_interval`] sends nothing if the pose is equal to the
+    /// last one sent, which saves bandwidth on idle bots. The first pose after joining is always
+    /// sent. Disabled by default: pose requests are also what makes the server send broadcasts,
+    /// so a bot that skips them may stop receiving poses of the others until it moves again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// let bot = BotBuilder::new("idle")
+    ///     .pose_interval(Some(Duration::from_millis(100)))
+    ///     .skip_unchanged_poses(true)
+    ///     .lag_interval(None)
+    ///     .build();
+    /// let report = Simulation::new(bot)
+    ///     .run_with(|handle| async move {
+    ///         tokio::time::sleep(Duration::from_millis(450)).await;
+    ///         handle.set_position([1.0, 2.0, 3.0]).unwrap();
+    ///         tokio::time::sleep(Duration::from_secs(1)).await;
+    ///     })
+    ///     .unwrap();
+    /// let poses = report
+    ///     .sent
+    ///     .iter()
+    ///     .filter(|(_, packet)| matches!(packet, Packet::PoseRequest(_)))
+    ///     .count();
+    /// assert_eq!(poses, 2);
+    /// ```
+    pub fn skip_unchanged_poses(&mut self, skip_unchanged_poses: bool) -> bool {
+        let mut skip_unchanged_poses = skip_unchanged_poses;
+        swap(&mut skip_unchanged_poses, &mut self.skip_unchanged_poses);
+        skip_unchanged_poses
+    }
+
+    /// Replaces the strict talk flag and returns the previous one.
+    ///
+    /// Messages longer than [`MAX_TALK_LEN`] bytes don't fit into a single talk request.
+    /// By default they are split into several requests with [`TalkRequest::split`], in strict mode
+    /// the sender fails with [`BotError::MessageTooLong`] instead.
+    pub fn strict_talk(&mut self, strict_talk: bool) -> bool {
+        let mut strict_talk = strict_talk;
+        swap(&mut strict_talk, &mut self.strict_talk);
+        strict_talk
+    }
+
+    /// Replaces [`DeliveryPolicy`] of pose broadcasts and returns the previous one.
+    ///
+    /// Default is [`DeliveryPolicy::Inline`], on crowded servers [`DeliveryPolicy::LatestPerPlayer`]
+    /// keeps a slow [`PoseCallback`] from falling behind.
+    pub fn pose_delivery(&mut self, policy: DeliveryPolicy) -> DeliveryPolicy {
+        let mut policy = policy;
+        swap(&mut policy, &mut self.pose_delivery);
+        policy
+    }
+
+    /// Returns counters of the pose [`DeliveryPolicy`], updated while the bot runs.
+    pub fn delivery_stats(&self) -> Arc<DeliveryStats> {
+        Arc::clone(&self.delivery_stats)
+    }
+
+    /// Replaces the time between two lag requests and returns the previous one.
+    ///
+    /// Lag requests are answered by the server with a lag response carrying the same stamp,
+    /// the round trip times end up in [`Bot::latency`]. The first request is sent one interval
+    /// after joining. Default is 2 seconds, `None` stops sending them.
+    pub fn lag_interval(&mut self, lag_interval: Option<Duration>) -> Option<Duration> {
+        let mut lag_interval = lag_interval;
+        swap(&mut lag_interval, &mut self.lag_interval);
+        lag_interval
+    }
+
+    /// Returns the [`LatencyTracker`] of the bot, updated while it runs.
+    ///
+    /// See [`BotHandle::latency`] for the statistics of a spawned bot.
+    pub fn latency(&self) -> SharedLatency {
+        Arc::clone(&self.latency)
+    }
+
+    /// Returns the [`PacketStats`] of the bot, updated by the receiver while it runs.
+    ///
+    /// See [`BotHandle::stats`] for the statistics of a spawned bot.
+    pub fn stats(&self) -> SharedPacketStats {
+        Arc::clone(&self.stats)
+    }
+
+    /// Returns the [`IgnoreList`] of the bot, shared with it while it runs.
+    ///
+    /// Talk broadcasts of ignored players never reach the talk callbacks.
+    pub fn ignore_list(&self) -> SharedIgnoreList {
+        Arc::clone(&self.ignore_list)
+    }
+
+    /// Replaces [`ServerId`] that is stored in [`World::server_id`] and returns the previous one.
+    pub fn server_id(&mut self, server_id: Option<ServerId>) -> Option<ServerId> {
+        let mut server_id = server_id;
+        swap(&mut server_id, &mut self.server_id);
+        server_id
+    }
+
+    /// Replaces extra headers of the websocket handshake and returns the previous ones.
+    ///
+    /// For servers behind authenticating reverse proxies: user agents, tokens, cookies and such.
+    /// Headers are sent in order, a name can repeat. Transports without an HTTP handshake
+    /// ignore them, see [`Transport::connect_with_headers`].
+    ///
+    /// # Examples
+    ///
+    /// ## Passing a token to a reverse proxy:
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use tokio::net::TcpListener;
+    /// use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    /// # let address = listener.local_addr().unwrap();
+    /// # // Reverse proxy that only looks at the handshake
+    /// # let proxy = tokio::spawn(async move {
+    /// #     let (stream, _) = listener.accept().await.unwrap();
+    /// #     let mut seen = None;
+    /// #     let _ = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response: Response| {
+    /// #         seen = Some((request.uri().to_string(), request.headers().clone()));
+    /// #         Ok(response)
+    /// #     })
+    /// #     .await;
+    /// #     seen.unwrap()
+    /// # });
+    /// let bot = BotBuilder::new("bot")
+    ///     .address(format!("ws://{}/ws/y6/", address))
+    ///     .header("Authorization", "Bearer secret")
+    ///     .header("User-Agent", "asciicker-rs")
+    ///     .query("room", "lobby 1")
+    ///     .build();
+    /// // This proxy doesn't forward anything, so the join fails
+    /// assert!(bot.run().await.is_err());
+    ///
+    /// let (uri, headers) = proxy.await.unwrap();
+    /// assert_eq!(uri, "/ws/y6/?room=lobby%201");
+    /// assert_eq!(headers["authorization"], "Bearer secret");
+    /// assert_eq!(headers["user-agent"], "asciicker-rs");
+    /// # }
+    /// ```
+    pub fn headers(&mut self, headers: Vec<(String, String)>) -> Vec<(String, String)> {
+        let mut headers = headers;
+        swap(&mut headers, &mut self.headers);
+        headers
+    }
+
+    /// Replaces query parameters appended to the address and returns the previous ones.
+    ///
+    /// Names and values are percent-encoded, parameters already in the address are kept.
+    pub fn query(&mut self, query: Vec<(String, String)>) -> Vec<(String, String)> {
+        let mut query = query;
+        swap(&mut query, &mut self.query);
+        query
+    }
+
+    /// Replaces [`Transport`] used to reach the server and returns the previous one.
+    ///
+    /// Default is [`WebSocketTransport`](crate::common::WebSocketTransport) on tokio
+    /// or [`AsyncStdWebSocketTransport`](crate::common::AsyncStdWebSocketTransport) on async-std.
+    ///
+    /// # Examples
+    ///
+    /// ## Connecting to a native server over raw TCP:
+    /// ```no_run
+    /// use asciicker_rs::common::TcpTransport;
+    /// use asciicker_rs::y6::prelude::*;
+    /// use std::sync::Arc;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let bot = BotBuilder::new("bot")
+    ///     .address("tcp://localhost:8080")
+    ///     .transport(Arc::new(TcpTransport::new(|| Box::new(ClientFraming::default()))))
+    ///     .build();
+    /// let (threads, data) = bot.run().await.unwrap();
+    /// # }
+    /// ```
+    pub fn transport(&mut self, transport: Arc<dyn Transport>) -> Arc<dyn Transport> {
+        let mut transport = transport;
+        swap(&mut transport, &mut self.transport);
+        transport
+    }
+
+    /// Starts recording every frame the bot sends and receives with timestamps into `recorder`,
+    /// see [`SessionRecorder`](super::replay::SessionRecorder) for streaming to a file or keeping the replay in memory.
+    ///
+    /// Wraps the current [`Transport`], so call it after [`Bot::transport`].
+    /// Reconnects are recorded into the same recorder.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let server = Server::new(8).bind("127.0.0.1:0").await.unwrap();
+    /// let mut bot = BotBuilder::new("camera")
+    ///     .address(format!("ws://{}", server.address))
+    ///     .build();
+    /// let recorder = SessionRecorder::in_memory();
+    /// bot.record_session(recorder.clone());
+    /// let (_, _data) = bot.run().await.unwrap();
+    ///
+    /// let replay = recorder.replay().unwrap();
+    /// assert_eq!(replay.entries[0].direction, Direction::ToServer);
+    /// assert_eq!(replay.entries[0].frame[0], b'J');
+    /// let mut file = vec![];
+    /// replay.write_to(&mut file, false).unwrap();
+    /// assert_eq!(Replay::read_from(&file[..]).unwrap(), replay);
+    /// # }
+    /// ```
+    pub fn record_session(&mut self, recorder: SharedRecorder) {
+        self.transport = Arc::new(RecordingTransport::new(
+            Arc::clone(&self.transport),
+            recorder,
+        ));
+    }
+
+    /// Returns callbacks in the form [`patch_world`] expects them.
+    pub(crate) fn callbacks(&self) -> Arc<Callbacks> {
+        Arc::new(self.callbacks.clone())
+    }
+
+    /// Returns the chain of interceptors.
+    pub(crate) fn interceptors(&self) -> Vec<Interceptor> {
+        self.interceptors.clone()
+    }
+
+    /// Replaces every chain of callbacks at once.
+    pub(crate) fn set_callbacks(&mut self, callbacks: Callbacks) {
+        self.callbacks = callbacks;
+    }
+
+    /// Returns `replace_invalid_utf8` the bot was constructed with.
+    pub(crate) fn replaces_invalid_utf8(&self) -> bool {
+        self.replace_invalid_utf8
+    }
+
+    /// Returns nickname the bot was constructed with.
+    pub(crate) fn nickname(&self) -> &str {
+        &self.nickname
+    }
+
+    /// Returns the [`Runtime`] of the bot, or an error if none is set.
+    pub(crate) fn executor(&self) -> Result<Arc<dyn Runtime>, BotError> {
+        self.runtime.clone().ok_or_else(|| {
+            BotError::Other(
+                "No runtime, enable the tokio, async-std or wasm feature or set one with Bot::runtime"
+                    .to_string(),
+            )
+        })
+    }
+
+    /// Returns the catalog sprites are validated against, if validation is enabled.
+    pub(crate) fn sprite_catalog(&self) -> Option<Arc<SpriteCatalog>> {
+        self.sprites.clone()
+    }
+
     /// Runs the bot.
     ///
     /// Spawns two threads: [`Receiver`], [`Sender`] and returns them with [`BotData`] if connecting was successful.
-    pub async fn run(self) -> Result<((Receiver, Sender), BotData), RuntimeError> {
-        let (mut ws_s, mut ws_r) = match tokio_tungstenite::connect_async(self.address).await {
-            Ok(ws) => ws.0.split(),
-            Err(e) => {
-                return Err(RuntimeError::from_string(format!(
-                    "Connection failed: {:?}",
-                    e
-                )))
-            }
-        };
-        let join_req: Bytes = JoinRequest {
-            name: match CString::new(self.nickname.clone()) {
-                Ok(s) => s,
-                Err(e) => {
-                    return Err(RuntimeError::from_string(format!(
-                        "Failed to make new CString: {:?}",
-                        e
-                    )))
-                }
-            },
-        }
-        .into();
-        ws_s.send(ws_Message::Binary(join_req)).await.unwrap();
-        let join_rsp = JoinResponse::from(
-            RawJoinResponse::try_from(match ws_r.next().await {
-                Some(message) => match message.unwrap() {
-                    ws_Message::Binary(data) => data,
-                    _ => panic!("Server returned unknown data."),
-                },
-                None => panic!("Server dropped connection"),
-            })
-            .unwrap(),
-        );
-        let (tx, rx) = unbounded();
-        let rx = Arc::new(rx);
+    /// Otherwise returns [`BotError::ConnectionFailed`], [`BotError::Transport`] or [`BotError::HandshakeFailed`]
+    /// instead of panicking, so the caller can retry.
+    pub async fn run(self) -> Result<((Receiver, Sender), BotData), BotError> {
+        self.start(None, None, None, None).await
+    }
+
+    /// Runs the bot like [`Bot::run`], but returns a [`BotHandle`] instead of [`BotData`].
+    ///
+    /// Callbacks still receive [`BotData`] parts, the handle is meant for the main function.
+    pub async fn spawn(self) -> Result<((Receiver, Sender), BotHandle), BotError> {
+        handle::spawn(self).await
+    }
+
+    /// Runs the bot, the receiver thread notifies `refresh` every time it patched the world
+    /// and publishes what it received to `events`. Once `leave` changes, the sender thread
+    /// closes the connection and stops. The receiver thread drops `alive` once disconnected,
+    /// right before publishing [`Event::Disconnect`].
+    pub(crate) async fn start(
+        self,
+        refresh: Option<Arc<Notify>>,
+        events: Option<EventSender>,
+        leave: Option<watch::Receiver<bool>>,
+        alive: Option<watch::Sender<()>>,
+    ) -> Result<((Receiver, Sender), BotData), BotError> {
+        let runtime = self.executor()?;
+        let handshake = self.handshake(&*runtime);
+        let (mut ws_s, mut ws_r, join_rsp, refused) =
+            instrument!(handshake, "connect", address = %self.address).await?;
+        let callbacks = self.callbacks();
+        let interceptors = self.interceptors();
+        let (bot, world) = self.initial_data(&join_rsp);
+        let (tx, mut rx) = mpsc::channel(MESSAGE_CAPACITY);
         let tx = Arc::new(tx);
-        let bot = Arc::new(Mutex::new(Player {
-            nickname: self.nickname,
-            pose: Default::default(),
-            id: join_rsp.id,
-        }));
-        let world = Arc::new(Mutex::new(World {
-            max_clients: join_rsp.max_clients,
-            clients: vec![],
-            messages: vec![],
-            lag: [0u8; 3],
-        }));
-        let s_bot = Arc::clone(&bot);
+        let (pose, mut s_pose) = watch::channel(PlayerPose::default());
+        let pose = Arc::new(pose);
         let sender_finished = Arc::new(Mutex::new(false));
         let _sender_finished = Arc::clone(&sender_finished);
-        let a_rx = Arc::clone(&rx);
-        let sender = tokio::spawn(async move {
-            loop {
-                match ws_s
-                    .send(ws_Message::Binary(
-                        PoseRequest {
-                            player_pose: s_bot.lock().await.pose.clone(),
+        let s_recording = self.outbound_recording.clone();
+        let pose_interval = self.pose_interval;
+        let skip_unchanged_poses = self.skip_unchanged_poses;
+        let lag_interval = self.lag_interval;
+        let strict_talk = self.strict_talk;
+        let s_runtime = Arc::clone(&runtime);
+        let s_latency = Arc::clone(&self.latency);
+        let can_leave = leave.is_some();
+        let (_, stay) = watch::channel(false);
+        let mut s_leave = leave.unwrap_or(stay);
+        let r_leave = s_leave.clone();
+        let sender = async move {
+            let mut lags = lag_ticks(Arc::clone(&s_runtime), lag_interval);
+            let mut ticks = pose_ticks(s_runtime, pose_interval);
+            let mut last_pose = None;
+            let result: BotResult = async {
+                loop {
+                    tokio::select! {
+                        _ = ticks.tick(), if pose_interval.is_some() => {
+                            send_pose(
+                                &mut ws_s,
+                                &s_recording,
+                                &mut s_pose,
+                                &mut last_pose,
+                                skip_unchanged_poses,
+                            )
+                            .await?
                         }
-                        .into(),
-                    ))
-                    .await
-                {
-                    Err(e) => {
-                        *sender_finished.lock().await = true;
-                        return Err(RuntimeError::from_string(format!("{:?}", e)));
-                    }
-                    _ => {}
-                };
-                while let Ok(m) = Arc::clone(&a_rx).try_recv() {
-                    match ws_s
-                        .send(ws_Message::Binary(
-                            TalkRequest {
-                                str: match CString::new(m) {
-                                    Ok(b) => b,
-                                    Err(e) => {
-                                        *sender_finished.lock().await = true;
-                                        return Err(RuntimeError::from_string(format!(
-                                            "CString::new failed: {:?}",
-                                            e
-                                        )));
-                                    }
-                                },
-                            }
-                            .into(),
-                        ))
-                        .await
-                    {
-                        Err(e) => {
-                            *sender_finished.lock().await = true;
-                            return Err(RuntimeError::from_string(format!("{:?}", e)));
+                        _ = lags.tick(), if lag_interval.is_some() => {
+                            send_lag(&mut ws_s, &s_recording, &s_latency).await?
+                        }
+                        Some(m) = rx.recv() => {
+                            send_talk(&mut ws_s, &s_recording, m, strict_talk).await?
+                        }
+                        Ok(()) = s_leave.changed(), if can_leave => {
+                            event!(debug, "Leaving");
+                            ws_s.close().await?;
+                            return Ok(());
                         }
-                        Ok(_) => {}
-                    };
+                        // Passive bot without anyone left to queue messages has nothing to send
+                        else => return Ok(()),
+                    }
                 }
-                sleep(Duration::from_millis(10));
             }
-        });
+            .await;
+            if result.is_err() {
+                *sender_finished.lock().await = true;
+            }
+            result
+        };
+        let sender = Task::spawn(
+            &*runtime,
+            instrument!(sender, "send", nickname = %self.nickname),
+        );
         let w = Arc::clone(&world);
         let b = Arc::clone(&bot);
-        let callbacks = Arc::new((
-            match self.join_callback {
-                Some(f) => f,
-                None => default_join,
-            },
-            match self.exit_callback {
-                Some(f) => f,
-                None => default_exit,
-            },
-            match self.pose_callback {
-                Some(f) => f,
-                None => default_pose,
-            },
-            match self.talk_callback {
-                Some(f) => f,
-                None => default_talk,
-            },
-        ));
         let receiver_finished = Arc::new(Mutex::new(false));
         let _receiver_finished = Arc::clone(&receiver_finished);
         let a_tx = Arc::clone(&tx);
-        let receiver = tokio::spawn(async move {
-            while let Some(message) = ws_r.next().await {
-                match message {
-                    Ok(m) => match m {
-                        ws_Message::Binary(data) => {
-                            match patch_world(
-                                Arc::clone(&callbacks),
-                                data,
-                                Arc::clone(&w),
-                                Arc::clone(&b),
-                                self.replace_invalid_utf8,
-                                Arc::clone(&a_tx),
-                            )
-                            .await
-                            {
-                                Err(e) => {
-                                    *receiver_finished.lock().await = true;
-                                    return Err(RuntimeError::from_string(e.to_string()));
-                                }
-                                _ => {}
-                            }
-                        }
-                        _ => {}
-                    },
-                    Err(e) => {
-                        *receiver_finished.lock().await = true;
-                        return Err(RuntimeError::from_string(e.to_string()));
+        let pose_queue = PoseQueue::new(self.pose_delivery, Arc::clone(&self.delivery_stats));
+        let pose_callbacks = callbacks.2.clone();
+        let disconnect_callback = self.disconnect_callback.clone();
+        let sprites = self.sprites.clone();
+        let ignore_list = Arc::clone(&self.ignore_list);
+        let latency = Arc::clone(&self.latency);
+        let stats = Arc::clone(&self.stats);
+        let callback_timeout = self.callback_timeout;
+        let r_runtime = Arc::clone(&runtime);
+        let receiver = async move {
+            let callback_limit = callback_timeout.map(|timeout| CallbackLimit {
+                runtime: &*r_runtime,
+                timeout,
+                events: events.as_ref(),
+            });
+            let receive = async {
+                while let Some(frame) = ws_r.next_frame().await {
+                    let mut data = frame?;
+                    if intercept(&interceptors, &mut data) == InterceptAction::Drop {
+                        ws_r.recycle(data);
+                        continue;
+                    }
+                    let patched = patch_world_with(
+                        Arc::clone(&callbacks),
+                        &data,
+                        Arc::clone(&w),
+                        Arc::clone(&b),
+                        self.replace_invalid_utf8,
+                        Arc::clone(&a_tx),
+                        Taps {
+                            poses: pose_queue.as_deref(),
+                            events: events.as_ref(),
+                            sprites: sprites.as_deref(),
+                            ignore: Some(&ignore_list),
+                            latency: Some(&latency),
+                            stats: Some(&stats),
+                            callback_limit,
+                        },
+                    )
+                    .await;
+                    ws_r.recycle(data);
+                    patched?;
+                    if let Some(refresh) = &refresh {
+                        refresh.notify_one();
                     }
                 }
+                Ok(())
+            };
+            let result = with_dispatch(
+                receive,
+                pose_queue.clone(),
+                pose_callbacks,
+                Arc::clone(&b),
+                Arc::clone(&w),
+                Arc::clone(&a_tx),
+                callback_limit,
+            )
+            .await;
+            let reason =
+                DisconnectReason::from_result(&result, *r_leave.borrow(), ws_r.close_frame());
+            let result =
+                result.and(disconnected(&disconnect_callback, reason.clone(), &b, &w, &a_tx).await);
+            if result.is_err() {
+                *receiver_finished.lock().await = true;
+            }
+            drop(alive);
+            if let Some(events) = &events {
+                let _ = events.send(Event::Disconnect(reason));
             }
-            Ok(())
-        });
-        let main_world = Arc::clone(&world);
-        let main_bot = Arc::clone(&bot);
-        let main_sender = Arc::clone(&tx);
+            result
+        };
+        let receiver = Task::spawn(
+            &*runtime,
+            instrument!(receiver, "receive", nickname = %self.nickname),
+        );
+        let data = (bot, world, tx, pose);
+        if let Err(e) = self.joined(refused, &join_rsp, &data).await {
+            // Detached tasks would keep the connection open after run returned the error
+            receiver.abort();
+            sender.abort();
+            return Err(e);
+        }
         Ok((
             (
                 Receiver {
@@ -554,51 +2641,383 @@ impl Bot {
                     is_finished: Arc::clone(&_sender_finished),
                 },
             ),
-            (main_bot, main_world, main_sender),
+            data,
         ))
     }
+
+    /// Connects the bot without spawning any threads.
+    ///
+    /// Returns [`BotData`] and a [`SingleTask`] future that multiplexes receiving, pose ticks and
+    /// queued chat messages with [`tokio::select!`]. Nothing happens until the future is polled,
+    /// and callbacks run inside it, so a bot driven this way never waits on another task.
+    /// For the same reason a callback that queues more than [`MESSAGE_CAPACITY`] messages at once
+    /// waits forever, since the messages are only sent after it returns.
+    ///
+    /// The future completes when the server closes the connection or an error occurs.
+    ///
+    /// # Examples
+    ///
+    /// ## Greeting everyone from a single task:
+    /// ```no_run
+    /// use asciicker_rs::y6::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let bot = BotBuilder::new("greeter").replace_invalid_utf8(true).build();
+    /// let (task, (_bot, _world, sender, _pose)) = bot.run_single_task().await.unwrap();
+    /// sender.send("Hello!".to_string()).await.unwrap();
+    /// task.await.unwrap();
+    /// # }
+    /// ```
+    pub async fn run_single_task(self) -> Result<(SingleTask, BotData), BotError> {
+        let runtime = self.executor()?;
+        let handshake = self.handshake(&*runtime);
+        let (mut ws_s, mut ws_r, join_rsp, refused) =
+            instrument!(handshake, "connect", address = %self.address).await?;
+        let callbacks = self.callbacks();
+        let interceptors = self.interceptors();
+        let (bot, world) = self.initial_data(&join_rsp);
+        let (tx, mut rx) = mpsc::channel(MESSAGE_CAPACITY);
+        let tx = Arc::new(tx);
+        let (pose, mut s_pose) = watch::channel(PlayerPose::default());
+        let data = (
+            Arc::clone(&bot),
+            Arc::clone(&world),
+            Arc::clone(&tx),
+            Arc::new(pose),
+        );
+        self.joined(refused, &join_rsp, &data).await?;
+        let recording = self.outbound_recording;
+        let replace_invalid_utf8 = self.replace_invalid_utf8;
+        let pose_queue = PoseQueue::new(self.pose_delivery, Arc::clone(&self.delivery_stats));
+        let pose_callbacks = callbacks.2.clone();
+        let disconnect_callback = self.disconnect_callback;
+        let pose_interval = self.pose_interval;
+        let skip_unchanged_poses = self.skip_unchanged_poses;
+        let lag_interval = self.lag_interval;
+        let strict_talk = self.strict_talk;
+        let ignore_list = self.ignore_list;
+        let latency = self.latency;
+        let stats = self.stats;
+        let callback_timeout = self.callback_timeout;
+        let sprites = self.sprites;
+        let task = async move {
+            let callback_limit = callback_timeout.map(|timeout| CallbackLimit {
+                runtime: &*runtime,
+                timeout,
+                events: None,
+            });
+            let mut lags = lag_ticks(Arc::clone(&runtime), lag_interval);
+            let mut ticks = pose_ticks(Arc::clone(&runtime), pose_interval);
+            let mut last_pose = None;
+            let main = async {
+                loop {
+                    tokio::select! {
+                        frame = ws_r.next_frame() => match frame {
+                            Some(Ok(mut data)) => {
+                                if intercept(&interceptors, &mut data) == InterceptAction::Drop {
+                                    ws_r.recycle(data);
+                                    continue;
+                                }
+                                let patched = patch_world_with(
+                                    Arc::clone(&callbacks),
+                                    &data,
+                                    Arc::clone(&world),
+                                    Arc::clone(&bot),
+                                    replace_invalid_utf8,
+                                    Arc::clone(&tx),
+                                    Taps {
+                                        poses: pose_queue.as_deref(),
+                                        events: None,
+                                        sprites: sprites.as_deref(),
+                                        ignore: Some(&ignore_list),
+                                        latency: Some(&latency),
+                                        stats: Some(&stats),
+                                        callback_limit,
+                                    },
+                                )
+                                .await;
+                                ws_r.recycle(data);
+                                patched?;
+                            }
+                            Some(Err(e)) => return Err(e),
+                            None => return Ok(()),
+                        },
+                        _ = ticks.tick(), if pose_interval.is_some() => {
+                            send_pose(
+                                &mut ws_s,
+                                &recording,
+                                &mut s_pose,
+                                &mut last_pose,
+                                skip_unchanged_poses,
+                            )
+                            .await?
+                        }
+                        _ = lags.tick(), if lag_interval.is_some() => {
+                            send_lag(&mut ws_s, &recording, &latency).await?
+                        }
+                        Some(m) = rx.recv() => {
+                            send_talk(&mut ws_s, &recording, m, strict_talk).await?
+                        }
+                    }
+                }
+            };
+            let result = with_dispatch(
+                main,
+                pose_queue.clone(),
+                pose_callbacks,
+                Arc::clone(&bot),
+                Arc::clone(&world),
+                Arc::clone(&tx),
+                callback_limit,
+            )
+            .await;
+            let reason = DisconnectReason::from_result(&result, false, ws_r.close_frame());
+            result.and(disconnected(&disconnect_callback, reason, &bot, &world, &tx).await)
+        };
+        let task = Box::pin(instrument!(task, "connection", nickname = %self.nickname));
+        Ok((task, data))
+    }
+
+    /// Connects and joins, retrying while the join queue is enabled
+    /// or the [`ReconnectPolicy`] allows it.
+    ///
+    /// Returns both halves of the connection, the join response and the amount of refused attempts.
+    async fn handshake(
+        &self,
+        runtime: &dyn Runtime,
+    ) -> Result<(Box<dyn FrameSink>, Box<dyn FrameStream>, JoinResponse, u32), BotError> {
+        let join_req: Bytes = JoinRequest {
+            name: CString::new(self.nickname.clone())?,
+        }
+        .into();
+        let address = with_query(&self.address, &self.query);
+        let mut refused = 0u32;
+        let mut failed = 0u32;
+        loop {
+            event!(debug, attempt = failed + refused + 1, "Connecting");
+            let connected = self.transport.connect_with_headers(&address, &self.headers);
+            let (mut ws_s, mut ws_r) = match connected.await {
+                Ok(halves) => halves,
+                Err(e) => {
+                    failed += 1;
+                    event!(warn, error = %e, failed, "Connecting failed");
+                    match self.reconnect.delay(failed) {
+                        Some(delay) => {
+                            runtime.sleep(delay).await;
+                            continue;
+                        }
+                        None => return Err(e),
+                    }
+                }
+            };
+            let join = async {
+                if let Some(recording) = &self.outbound_recording {
+                    recording.lock().await.push(join_req.clone());
+                }
+                ws_s.send_frame(join_req.clone()).await?;
+                join_response(&mut ws_r).await
+            };
+            let joined = instrument!(join, "join", nickname = %self.nickname).await?;
+            if let Some(join_rsp) = joined {
+                event!(
+                    info,
+                    id = join_rsp.id,
+                    max_clients = join_rsp.max_clients,
+                    "Joined"
+                );
+                return Ok((ws_s, ws_r, join_rsp, refused));
+            }
+            // Server closed the connection instead of responding, so it refused the join
+            match self.join_queue {
+                Some(retry_interval) => {
+                    refused += 1;
+                    event!(info, refused, "Join refused, waiting in the queue");
+                    runtime.sleep(retry_interval).await;
+                }
+                None => {
+                    return Err(BotError::HandshakeFailed(
+                        "Server refused the join, it is probably full".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Creates the bot and its world after a successful join.
+    pub(crate) fn initial_data(
+        &self,
+        join_rsp: &JoinResponse,
+    ) -> (Arc<Mutex<Player>>, Arc<RwLock<World>>) {
+        let bot = Arc::new(Mutex::new(Player::new(
+            self.nickname.clone(),
+            Default::default(),
+            join_rsp.id,
+        )));
+        #[allow(deprecated)]
+        let world = Arc::new(RwLock::new(World {
+            max_clients: join_rsp.max_clients,
+            clients: HashMap::new(),
+            messages: ChatHistory::new(self.chat_history),
+            pose_history: self.pose_history,
+            lag: [0u8; 3],
+            server_id: self.server_id.clone(),
+        }));
+        (bot, world)
+    }
+
+    /// Calls the dequeue callback if the join was refused at least once, then the ready callback.
+    pub(crate) async fn joined(
+        &self,
+        refused: u32,
+        join_rsp: &JoinResponse,
+        data: &BotData,
+    ) -> BotResult {
+        if refused > 0 {
+            if let Some(callback) = &self.dequeue_callback {
+                event!(trace, refused, "Calling dequeue callback");
+                callback(
+                    refused,
+                    Arc::clone(&data.0),
+                    Arc::clone(&data.1),
+                    Arc::clone(&data.2),
+                )
+                .await?;
+            }
+        }
+        if let Some(callback) = &self.ready_callback {
+            event!(trace, "Calling ready callback");
+            callback(
+                join_rsp.clone(),
+                Arc::clone(&data.0),
+                Arc::clone(&data.1),
+                Arc::clone(&data.2),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Creates the ticker the sender ticks on, first tick completes immediately.
+///
+/// Pose requests are also what makes the server send broadcasts, so the current pose is resent
+/// on every tick even if it didn't change, unless [`Bot::skip_unchanged_poses`] is enabled.
+/// Ticks missed because sending took too long are not made up for in a burst, the next one
+/// is scheduled `pose_interval` after the late one.
+fn pose_ticks(runtime: Arc<dyn Runtime>, pose_interval: Option<Duration>) -> Ticker {
+    Ticker::new(runtime, pose_interval.unwrap_or(DEFAULT_POSE_INTERVAL))
+}
+
+/// Creates the [`Ticker`] of the lag requests, the first request waits for one interval.
+fn lag_ticks(runtime: Arc<dyn Runtime>, lag_interval: Option<Duration>) -> Ticker {
+    Ticker::delayed(runtime, lag_interval.unwrap_or(DEFAULT_LAG_INTERVAL))
+}
+
+/// Appends percent-encoded `query` parameters to `address`.
+fn with_query(address: &str, query: &[(String, String)]) -> String {
+    let encode = |text: &str| {
+        text.bytes()
+            .map(|byte| match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                    (byte as char).to_string()
+                }
+                _ => format!("%{:02X}", byte),
+            })
+            .collect::<String>()
+    };
+    let mut address = address.to_string();
+    for (name, value) in query {
+        address.push(if address.contains('?') { '&' } else { '?' });
+        address += &format!("{}={}", encode(name), encode(value));
+    }
+    address
 }
 
-#[doc(hidden)]
-#[apply(callback!)]
-async fn default_join(
-    _: JoinBroadcast,
-    _: Arc<Mutex<Player>>,
-    _: Arc<Mutex<World>>,
-    _: MessageSender,
+/// Calls `callback` once the connection ended for `reason`.
+async fn disconnected(
+    callback: &Option<DisconnectCallback>,
+    reason: DisconnectReason,
+    bot: &Arc<Mutex<Player>>,
+    world: &Arc<RwLock<World>>,
+    sender: &MessageSender,
 ) -> BotResult {
-    Ok(())
+    event!(info, ?reason, "Disconnected");
+    match callback {
+        Some(callback) => {
+            event!(trace, "Calling disconnect callback");
+            callback(
+                reason,
+                Arc::clone(bot),
+                Arc::clone(world),
+                Arc::clone(sender),
+            )
+            .await
+        }
+        None => Ok(()),
+    }
 }
 
-#[doc(hidden)]
-#[apply(callback!)]
-async fn default_exit(
-    _: ExitBroadcast,
-    _: Arc<Mutex<Player>>,
-    _: Arc<Mutex<World>>,
-    _: MessageSender,
+/// Records `frame` if recording is enabled and sends it, `frame` can be reused afterwards.
+async fn send_recorded(
+    sink: &mut Box<dyn FrameSink>,
+    recording: &Option<SharedRecording>,
+    frame: &[u8],
 ) -> BotResult {
-    Ok(())
+    if let Some(recording) = recording {
+        recording.lock().await.push(frame.to_vec());
+    }
+    event!(trace, token = ?frame.first().map(|&token| token as char), len = frame.len(), "Sending packet");
+    sink.send_slice(frame).await
 }
 
-#[doc(hidden)]
-#[apply(callback!)]
-async fn default_pose(
-    _: PoseBroadcast,
-    _: Arc<Mutex<Player>>,
-    _: Arc<Mutex<World>>,
-    _: MessageSender,
+/// Sends the latest pose of `pose` unless `skip_unchanged` is set and it equals `last`,
+/// which is updated to what was sent.
+async fn send_pose(
+    sink: &mut Box<dyn FrameSink>,
+    recording: &Option<SharedRecording>,
+    pose: &mut watch::Receiver<PlayerPose>,
+    last: &mut Option<PlayerPose>,
+    skip_unchanged: bool,
 ) -> BotResult {
+    let player_pose = pose.borrow_and_update().clone();
+    if skip_unchanged && last.as_ref() == Some(&player_pose) {
+        return Ok(());
+    }
+    let pose_req = PoseRequest {
+        player_pose: player_pose.clone(),
+    };
+    send_recorded(sink, recording, &pose_req.to_array()).await?;
+    *last = Some(player_pose);
     Ok(())
 }
 
-#[doc(hidden)]
-#[apply(callback!)]
-async fn default_talk(
-    _: TalkBroadcast,
-    _: Arc<Mutex<Player>>,
-    _: Arc<Mutex<World>>,
-    _: MessageSender,
+/// Sends a lag request with a fresh stamp of `latency`.
+async fn send_lag(
+    sink: &mut Box<dyn FrameSink>,
+    recording: &Option<SharedRecording>,
+    latency: &SharedLatency,
+) -> BotResult {
+    let stamp = latency
+        .lock()
+        .expect("Latency tracker is poisoned")
+        .ping(Instant::now());
+    send_recorded(sink, recording, &LagRequest { stamp }.to_array()).await
+}
+
+/// Sends a queued chat message as talk requests, see [`Bot::strict_talk`].
+async fn send_talk(
+    sink: &mut Box<dyn FrameSink>,
+    recording: &Option<SharedRecording>,
+    message: String,
+    strict: bool,
 ) -> BotResult {
+    if strict && message.len() > MAX_TALK_LEN {
+        return Err(BotError::MessageTooLong(message.len()));
+    }
+    for talk_req in TalkRequest::split(&message)? {
+        send_recorded(sink, recording, &Bytes::from(talk_req)).await?;
+    }
     Ok(())
 }
@@ -0,0 +1,209 @@
+use crate::common::Position;
+use crate::y6::packets::{Animation, PlayerPose};
+
+use std::time::Duration;
+
+/// Amount of frames in the walk animation, [`step_towards`] cycles through them.
+pub const WALK_FRAMES: u8 = 8;
+/// Speed of [`BotHandle::follow`](crate::y6::handle::BotHandle::follow) in units per second.
+pub const WALK_SPEED: f32 = 10.0;
+/// How often [`BotHandle::walk_to`](crate::y6::handle::BotHandle::walk_to) moves the bot.
+pub const STEP_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Moves `pose` at most `max_step` towards `target`, returns `true` once it got there.
+///
+/// While walking the pose faces the direction of travel and cycles the walk animation,
+/// after arriving it switches to the idle animation and keeps the last direction.
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::bot::movement::*;
+/// use asciicker_rs::y6::prelude::*;
+///
+/// let mut pose = PlayerPose::default();
+/// assert!(!step_towards(&mut pose, [0.0, 10.0, 0.0], 4.0));
+/// assert_eq!(pose.position.to_array(), [0.0, 4.0, 0.0]);
+/// assert_eq!(pose.direction, 90.0);
+/// assert!(pose.is_walking());
+///
+/// assert!(!step_towards(&mut pose, [0.0, 10.0, 0.0], 4.0));
+/// assert!(step_towards(&mut pose, [0.0, 10.0, 0.0], 4.0));
+/// assert_eq!(pose.position.to_array(), [0.0, 10.0, 0.0]);
+/// assert_eq!(pose.animation, Animation::Idle);
+/// ```
+pub fn step_towards<P: Into<Position>>(pose: &mut PlayerPose, target: P, max_step: f32) -> bool {
+    let target = target.into();
+    let offset = target - pose.position;
+    let left = offset.length();
+    if left <= max_step {
+        pose.position = target;
+        pose.set_idle();
+        return true;
+    }
+    pose.face_towards(target);
+    pose.position += offset * (max_step / left);
+    match pose.is_walking() {
+        true => pose.frame = (pose.frame + 1) % WALK_FRAMES,
+        false => pose.set_walking(),
+    }
+    false
+}
+
+/// Moves `pose` at most `max_step` towards `target` until it is within `within` of it,
+/// returns `true` if the pose changed.
+///
+/// Once close enough the pose stops and switches to the idle animation.
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::bot::movement::*;
+/// use asciicker_rs::y6::prelude::*;
+///
+/// let mut pose = PlayerPose::default();
+/// assert!(step_within(&mut pose, [10.0, 0.0, 0.0], 3.0, 5.0));
+/// assert!(step_within(&mut pose, [10.0, 0.0, 0.0], 3.0, 5.0));
+/// assert_eq!(pose.position.to_array(), [7.0, 0.0, 0.0]);
+/// assert!(step_within(&mut pose, [10.0, 0.0, 0.0], 3.0, 5.0));
+/// assert_eq!(pose.animation, Animation::Idle);
+/// assert!(!step_within(&mut pose, [10.0, 0.0, 0.0], 3.0, 5.0));
+/// ```
+pub fn step_within<P: Into<Position>>(
+    pose: &mut PlayerPose,
+    target: P,
+    within: f32,
+    max_step: f32,
+) -> bool {
+    let target = target.into();
+    let left = pose.position.distance(target) - within;
+    if left > 0.0 {
+        step_towards(pose, target, max_step.min(left));
+        return true;
+    }
+    if pose.animation == Animation::Idle && pose.frame == 0 {
+        return false;
+    }
+    pose.set_idle();
+    true
+}
+
+/// Order in which [`BotHandle::patrol`](crate::y6::handle::BotHandle::patrol) visits waypoints.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum PathMode {
+    /// First to last, then stop
+    #[default]
+    Once,
+    /// First to last, then from the first again
+    Loop,
+    /// First to last, then back to the first and so on
+    PingPong,
+}
+
+/// Point of a [`Path`].
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Waypoint {
+    /// Where to walk
+    pub position: Position,
+    /// How long to stand still after getting there
+    pub pause: Duration,
+}
+
+impl Waypoint {
+    /// Constructs a new [`Waypoint`] without a pause.
+    pub fn new<P: Into<Position>>(position: P) -> Self {
+        Self {
+            position: position.into(),
+            pause: Duration::ZERO,
+        }
+    }
+
+    /// Sets how long to stand still after getting there.
+    pub fn pause(mut self, pause: Duration) -> Self {
+        self.pause = pause;
+        self
+    }
+}
+
+impl From<Position> for Waypoint {
+    fn from(position: Position) -> Self {
+        Self::new(position)
+    }
+}
+
+impl From<[f32; 3]> for Waypoint {
+    fn from(position: [f32; 3]) -> Self {
+        Self::new(position)
+    }
+}
+
+/// Route for [`BotHandle::patrol`](crate::y6::handle::BotHandle::patrol).
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::bot::movement::*;
+/// use std::time::Duration;
+///
+/// let path = Path::new(PathMode::PingPong)
+///     .waypoint([0.0, 0.0, 300.0])
+///     .waypoint(Waypoint::new([10.0, 0.0, 300.0]).pause(Duration::from_secs(1)))
+///     .waypoint([10.0, 10.0, 300.0])
+///     .speed(5.0);
+///
+/// let xy: Vec<_> = path.visits().take(5).map(|w| [w.position.x, w.position.y]).collect();
+/// assert_eq!(xy, vec![[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [10.0, 0.0], [0.0, 0.0]]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    waypoints: Vec<Waypoint>,
+    mode: PathMode,
+    pub(crate) speed: f32,
+}
+
+impl Path {
+    /// Constructs a new empty [`Path`] walked at [`WALK_SPEED`].
+    pub fn new(mode: PathMode) -> Self {
+        Self {
+            waypoints: vec![],
+            mode,
+            speed: WALK_SPEED,
+        }
+    }
+
+    /// Adds a waypoint, either a [`Waypoint`] or just a [`Position`].
+    pub fn waypoint<W: Into<Waypoint>>(mut self, waypoint: W) -> Self {
+        self.waypoints.push(waypoint.into());
+        self
+    }
+
+    /// Sets the speed in units per second.
+    pub fn speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Returns the waypoints in the order they were added.
+    pub fn waypoints(&self) -> &[Waypoint] {
+        &self.waypoints
+    }
+
+    /// Returns the waypoints in the order they are visited, endless unless the mode is [`PathMode::Once`].
+    pub fn visits(&self) -> Box<dyn Iterator<Item = &Waypoint> + Send + '_> {
+        let forward = self.waypoints.iter();
+        match self.mode {
+            PathMode::Once => Box::new(forward),
+            PathMode::Loop => Box::new(forward.cycle()),
+            PathMode::PingPong => {
+                let len = self.waypoints.len();
+                let back = self
+                    .waypoints
+                    .get(1..len.saturating_sub(1))
+                    .unwrap_or_default()
+                    .iter()
+                    .rev();
+                Box::new(forward.chain(back).cycle())
+            }
+        }
+    }
+}
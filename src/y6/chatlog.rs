@@ -0,0 +1,342 @@
+use super::bot::{into_callback, BotResult, Player, TalkCallback, World};
+use super::packets::TalkBroadcast;
+use super::utils::BotError;
+use crate::common::SystemTime;
+
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tokio::sync::{Mutex, RwLock};
+
+/// Format of the lines written by a [`ChatLogger`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ChatLogFormat {
+    /// One JSON object per line with the keys `time`, `id`, `nickname` and `message`,
+    /// `nickname` is `null` if the author is unknown
+    JsonLines,
+    /// Comma separated `time,id,nickname,message` rows after a header row, quoted as in RFC 4180
+    Csv,
+}
+
+/// Writes every talk broadcast the bot receives to a file.
+///
+/// Every line has the UTC time the message was received in RFC 3339 format, the id of the
+/// author and their nickname, resolved when the message was received. Lines are appended to the
+/// file and flushed right away, so the log can be followed while the bot runs.
+///
+/// With [`ChatLogger::rotate`] the file is moved aside once it grows too big: `chat.log` becomes
+/// `chat.log.1`, `chat.log.1` becomes `chat.log.2` and so on.
+///
+/// Failing to write the log is a callback failure, which stops the bot.
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let dir = std::env::temp_dir().join(format!("chatlog-{}", std::process::id()));
+/// # std::fs::create_dir_all(&dir).unwrap();
+/// let path = dir.join("chat.csv");
+/// let mut bot = BotBuilder::new("scribe").replace_invalid_utf8(true).build();
+/// bot.on_chat_log(ChatLogger::new(&path, ChatLogFormat::Csv));
+///
+/// Scenario::new(&bot)
+///     .join(2, "alice")
+///     .talk(2, "hello, \"world\"")
+///     .talk(9, "who am I")
+///     .run()
+///     .await
+///     .unwrap();
+///
+/// let log = std::fs::read_to_string(&path).unwrap();
+/// let lines: Vec<_> = log.lines().collect();
+/// assert_eq!(lines[0], "time,id,nickname,message");
+/// assert!(lines[1].ends_with(",2,alice,\"hello, \"\"world\"\"\""));
+/// assert!(lines[2].ends_with(",9,,who am I"));
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ChatLogger {
+    path: PathBuf,
+    format: ChatLogFormat,
+    max_size: Option<u64>,
+    keep: usize,
+    file: std::sync::Mutex<Option<LogFile>>,
+}
+
+/// Open log file and the amount of bytes in it.
+#[derive(Debug)]
+struct LogFile {
+    writer: BufWriter<File>,
+    size: u64,
+}
+
+impl ChatLogger {
+    /// Creates a new [`ChatLogger`] appending to the file at `path`, created when the first
+    /// message arrives.
+    pub fn new<P: AsRef<Path>>(path: P, format: ChatLogFormat) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            format,
+            max_size: None,
+            keep: 0,
+            file: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Rotates the file once writing a line would make it bigger than `max_size` bytes,
+    /// keeping `keep` rotated files, older ones are deleted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let dir = std::env::temp_dir().join(format!("chatlog-rotate-{}", std::process::id()));
+    /// # std::fs::create_dir_all(&dir).unwrap();
+    /// let path = dir.join("chat.jsonl");
+    /// let mut bot = BotBuilder::new("scribe").replace_invalid_utf8(true).build();
+    /// bot.on_chat_log(ChatLogger::new(&path, ChatLogFormat::JsonLines).rotate(1, 1));
+    ///
+    /// Scenario::new(&bot)
+    ///     .talk(2, "one")
+    ///     .talk(2, "two")
+    ///     .talk(2, "three")
+    ///     .run()
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// // Every line is bigger than a byte, so each one ends up in a new file
+    /// let current = std::fs::read_to_string(&path).unwrap();
+    /// assert!(current.ends_with("\"id\":2,\"nickname\":null,\"message\":\"three\"}\n"));
+    /// let rotated = std::fs::read_to_string(dir.join("chat.jsonl.1")).unwrap();
+    /// assert!(rotated.contains("\"two\""));
+    /// assert!(!dir.join("chat.jsonl.2").exists());
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// # }
+    /// ```
+    pub fn rotate(mut self, max_size: u64, keep: usize) -> Self {
+        self.max_size = Some(max_size);
+        self.keep = keep;
+        self
+    }
+
+    /// Writes a message of player `id` with `nickname`, received at `time`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use std::time::{Duration, SystemTime};
+    ///
+    /// # let dir = std::env::temp_dir().join(format!("chatlog-log-{}", std::process::id()));
+    /// # std::fs::create_dir_all(&dir).unwrap();
+    /// let path = dir.join("chat.jsonl");
+    /// let logger = ChatLogger::new(&path, ChatLogFormat::JsonLines);
+    /// let time = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_250);
+    /// logger.log(time, 3, Some("bob"), "line\nbreak").unwrap();
+    ///
+    /// assert_eq!(
+    ///     std::fs::read_to_string(&path).unwrap(),
+    ///     "{\"time\":\"2023-11-14T22:13:20.250Z\",\"id\":3,\"nickname\":\"bob\",\"message\":\"line\\nbreak\"}\n"
+    /// );
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    pub fn log(
+        &self,
+        time: SystemTime,
+        id: u16,
+        nickname: Option<&str>,
+        message: &str,
+    ) -> Result<(), BotError> {
+        let line = self.line(time, id, nickname, message);
+        self.write(line.as_bytes()).map_err(BotError::callback)
+    }
+
+    /// Boxes the logger into a [`TalkCallback`], which never stops the chain.
+    pub fn into_callback(self) -> TalkCallback {
+        let logger = Arc::new(self);
+        into_callback(move |talk_brc: TalkBroadcast, bot, world, _| {
+            let logger = Arc::clone(&logger);
+            Box::pin(async move { logger.record(&talk_brc, bot, world).await })
+        })
+    }
+
+    /// Resolves the nickname of the author of `talk_brc` and logs the message.
+    async fn record(
+        &self,
+        talk_brc: &TalkBroadcast,
+        bot: Arc<Mutex<Player>>,
+        world: Arc<RwLock<World>>,
+    ) -> BotResult {
+        let nickname = {
+            let bot = bot.lock().await;
+            (bot.id == talk_brc.id).then(|| bot.nickname.clone())
+        };
+        let nickname = match nickname {
+            Some(nickname) => Some(nickname),
+            None => world
+                .read()
+                .await
+                .client(talk_brc.id)
+                .map(|c| c.nickname.clone()),
+        };
+        let message = talk_brc.str.to_string_lossy();
+        self.log(
+            SystemTime::now(),
+            talk_brc.id,
+            nickname.as_deref(),
+            &message,
+        )
+    }
+
+    /// Formats one line, including the line break.
+    fn line(&self, time: SystemTime, id: u16, nickname: Option<&str>, message: &str) -> String {
+        let time = rfc3339(time);
+        match self.format {
+            ChatLogFormat::JsonLines => {
+                let nickname = match nickname {
+                    Some(nickname) => json_string(nickname),
+                    None => "null".to_string(),
+                };
+                format!(
+                    "{{\"time\":\"{}\",\"id\":{},\"nickname\":{},\"message\":{}}}\n",
+                    time,
+                    id,
+                    nickname,
+                    json_string(message)
+                )
+            }
+            ChatLogFormat::Csv => format!(
+                "{},{},{},{}\n",
+                time,
+                id,
+                csv_field(nickname.unwrap_or_default()),
+                csv_field(message)
+            ),
+        }
+    }
+
+    /// Appends `line` to the file, rotating it first if needed.
+    fn write(&self, line: &[u8]) -> io::Result<()> {
+        let mut file = self.file.lock().expect("Chat log is poisoned");
+        if let (Some(max_size), Some(open)) = (self.max_size, file.as_ref()) {
+            if open.size > 0 && open.size + line.len() as u64 > max_size {
+                *file = None;
+                self.rotate_files()?;
+            }
+        }
+        let open = match &mut *file {
+            Some(open) => open,
+            None => file.insert(self.open()?),
+        };
+        open.writer.write_all(line)?;
+        open.writer.flush()?;
+        open.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Opens the file for appending, writing the CSV header into a new file.
+    fn open(&self) -> io::Result<LogFile> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut log_file = LogFile {
+            size: file.metadata()?.len(),
+            writer: BufWriter::new(file),
+        };
+        if self.format == ChatLogFormat::Csv && log_file.size == 0 {
+            let header = b"time,id,nickname,message\n";
+            log_file.writer.write_all(header)?;
+            log_file.size += header.len() as u64;
+        }
+        Ok(log_file)
+    }
+
+    /// Shifts the rotated files by one and moves the current file aside.
+    fn rotate_files(&self) -> io::Result<()> {
+        if self.keep == 0 {
+            return fs::remove_file(&self.path);
+        }
+        let rotated = |index: usize| {
+            let mut path = self.path.clone().into_os_string();
+            path.push(format!(".{}", index));
+            PathBuf::from(path)
+        };
+        for index in (1..self.keep).rev() {
+            match fs::rename(rotated(index), rotated(index + 1)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        fs::rename(&self.path, rotated(1))
+    }
+}
+
+/// Formats `time` as an RFC 3339 UTC timestamp with milliseconds.
+fn rfc3339(time: SystemTime) -> String {
+    let since_epoch = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+    // Days to a civil date, see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Quotes and escapes `text` as a JSON string.
+fn json_string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Quotes `text` as a CSV field if it contains a separator, a quote or a line break.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
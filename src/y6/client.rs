@@ -0,0 +1,220 @@
+use super::packets::{
+    parse_packet, JoinRequest, JoinResponse, LagRequest, LagStamp, Packet, PlayerPose, PoseRequest,
+    RawJoinResponse, TalkRequest,
+};
+use super::utils::BotError;
+use crate::common::transport::{default_transport, FrameSink, FrameStream, Transport};
+#[cfg(feature = "tokio")]
+use crate::common::transport::{OutboundProxy, ProxyTransport};
+use crate::trace::{event, instrument};
+
+use std::ffi::CString;
+
+/// Connection to a server without any tasks, state or callbacks.
+///
+/// [`Client`] only joins, then hands every packet from the server to the caller through
+/// [`Client::next_event`] and sends what it is told to. Nothing happens between the calls:
+/// poses are not resent, the world is not tracked and lag requests are not answered,
+/// all of that is up to the event loop of the caller.
+///
+/// Use [`Bot`](super::bot::Bot) unless you need that much control.
+///
+/// # Examples
+///
+/// ## Own event loop:
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let server = Server::new(8).bind("127.0.0.1:0").await.unwrap();
+/// # let address = format!("ws://{}", server.address);
+/// let mut client = Client::connect("watcher", &address).await.unwrap();
+/// # let mut other = Client::connect("other", &address).await.unwrap();
+/// # other.send_talk("hello").await.unwrap();
+/// let mut names = std::collections::HashMap::new();
+/// let said = loop {
+///     match client.next_event().await.unwrap().unwrap() {
+///         Packet::JoinBroadcast(join_brc) => {
+///             names.insert(join_brc.id, join_brc.name);
+///         }
+///         Packet::TalkBroadcast(talk_brc) => break (names[&talk_brc.id].clone(), talk_brc.str),
+///         _ => {}
+///     }
+/// };
+/// assert_eq!(said.0.to_str().unwrap(), "other");
+/// assert_eq!(said.1.to_str().unwrap(), "hello");
+/// client.close().await.unwrap();
+/// # }
+/// ```
+pub struct Client {
+    sink: Box<dyn FrameSink>,
+    stream: Box<dyn FrameStream>,
+    join_rsp: JoinResponse,
+}
+
+impl Client {
+    /// Connects to the server at `address` over websockets and joins as `nickname`.
+    ///
+    /// Uses the same transport as [`Bot`](super::bot::Bot) by default, see
+    /// [`Bot::transport`](super::bot::Bot::transport).
+    pub async fn connect(nickname: &str, address: &str) -> Result<Self, BotError> {
+        Self::connect_with(&*default_transport(), nickname, address).await
+    }
+
+    /// Connects to the server at `address` through `proxy` and joins as `nickname`.
+    ///
+    /// # Examples
+    ///
+    /// ## Joining through a SOCKS5 proxy:
+    /// ```
+    /// use asciicker_rs::common::OutboundProxy;
+    /// use asciicker_rs::y6::prelude::*;
+    /// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    /// use tokio::net::{TcpListener, TcpStream};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let server = Server::new(8).bind("127.0.0.1:0").await.unwrap();
+    /// # let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    /// # let proxy_address = listener.local_addr().unwrap();
+    /// # // Bare SOCKS5 proxy, only IPv4 targets and username/password authentication
+    /// # tokio::spawn(async move {
+    /// #     let (mut client, _) = listener.accept().await.unwrap();
+    /// #     let mut greeting = [0u8; 3];
+    /// #     client.read_exact(&mut greeting).await.unwrap();
+    /// #     assert_eq!(greeting, [5, 1, 2]);
+    /// #     client.write_all(&[5, 2]).await.unwrap();
+    /// #     let mut auth = vec![0u8; 2 + 5 + 1 + 6];
+    /// #     client.read_exact(&mut auth).await.unwrap();
+    /// #     assert_eq!(&auth[2..7], b"robot");
+    /// #     client.write_all(&[1, 0]).await.unwrap();
+    /// #     let mut request = [0u8; 10];
+    /// #     client.read_exact(&mut request).await.unwrap();
+    /// #     let ip = std::net::Ipv4Addr::new(request[4], request[5], request[6], request[7]);
+    /// #     let port = u16::from_be_bytes([request[8], request[9]]);
+    /// #     let mut upstream = TcpStream::connect((ip, port)).await.unwrap();
+    /// #     client.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).await.unwrap();
+    /// #     let _ = tokio::io::copy_bidirectional(&mut client, &mut upstream).await;
+    /// # });
+    /// let proxy = OutboundProxy::socks5(proxy_address.to_string()).auth("robot", "secret");
+    /// let address = format!("ws://{}", server.address);
+    /// let client = Client::connect_via(&proxy, "hidden", &address).await.unwrap();
+    /// assert_eq!(client.max_clients(), 8);
+    /// client.close().await.unwrap();
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn connect_via(
+        proxy: &OutboundProxy,
+        nickname: &str,
+        address: &str,
+    ) -> Result<Self, BotError> {
+        Self::connect_with(&ProxyTransport::new(proxy.clone()), nickname, address).await
+    }
+
+    /// Connects to the server at `address` with `transport` and joins as `nickname`.
+    ///
+    /// Returns [`BotError::HandshakeFailed`] if the server refuses the join.
+    pub async fn connect_with(
+        transport: &dyn Transport,
+        nickname: &str,
+        address: &str,
+    ) -> Result<Self, BotError> {
+        let connect = async {
+            let (mut sink, mut stream) = transport.connect(address).await?;
+            sink.send_frame(
+                JoinRequest {
+                    name: CString::new(nickname)?,
+                }
+                .into(),
+            )
+            .await?;
+            let join_rsp = join_response(&mut stream).await?;
+            Ok::<_, BotError>((sink, stream, join_rsp))
+        };
+        let connected = instrument!(connect, "connect", address, nickname);
+        match connected.await? {
+            (sink, stream, Some(join_rsp)) => Ok(Self {
+                sink,
+                stream,
+                join_rsp,
+            }),
+            (_, _, None) => Err(BotError::HandshakeFailed(
+                "Server refused the join, it is probably full".to_string(),
+            )),
+        }
+    }
+
+    /// Id assigned by the server.
+    pub fn id(&self) -> u16 {
+        self.join_rsp.id
+    }
+
+    /// Max amount of clients of the server.
+    pub fn max_clients(&self) -> u8 {
+        self.join_rsp.max_clients
+    }
+
+    /// Waits for the next packet from the server, [`None`] means the connection was closed.
+    ///
+    /// Frames that don't parse, like packets of a newer version, return
+    /// [`BotError::PacketParse`], the connection is still usable after it.
+    pub async fn next_event(&mut self) -> Option<Result<Packet, BotError>> {
+        let frame = match self.stream.next_frame().await? {
+            Ok(frame) => frame,
+            Err(e) => return Some(Err(e)),
+        };
+        let packet = parse_packet(&frame).map_err(BotError::PacketParse);
+        event!(trace, ?packet, "Received packet");
+        self.stream.recycle(frame);
+        Some(packet)
+    }
+
+    /// Sends the pose of the client, the server broadcasts it to the others.
+    pub async fn send_pose(&mut self, player_pose: PlayerPose) -> Result<(), BotError> {
+        self.sink
+            .send_frame(PoseRequest { player_pose }.into())
+            .await
+    }
+
+    /// Says `message`, split into several talk requests if it is too long for one.
+    pub async fn send_talk(&mut self, message: &str) -> Result<(), BotError> {
+        for talk_req in TalkRequest::split(message)? {
+            self.sink.send_frame(talk_req.into()).await?;
+        }
+        Ok(())
+    }
+
+    /// Sends a lag request, the server answers with a [`LagResponse`](super::packets::LagResponse).
+    pub async fn send_lag(&mut self, stamp: LagStamp) -> Result<(), BotError> {
+        self.sink.send_frame(LagRequest { stamp }.into()).await
+    }
+
+    /// Sends any packet.
+    pub async fn send(&mut self, packet: Packet) -> Result<(), BotError> {
+        self.sink.send_frame(packet.into()).await
+    }
+
+    /// Closes the connection gracefully.
+    pub async fn close(mut self) -> Result<(), BotError> {
+        self.sink.close().await
+    }
+}
+
+/// Reads the answer to a join request, [`None`] if the server closed the connection
+/// instead, which is how it refuses joins.
+pub(crate) async fn join_response(
+    stream: &mut Box<dyn FrameStream>,
+) -> Result<Option<JoinResponse>, BotError> {
+    let Some(frame) = stream.next_frame().await else {
+        return Ok(None);
+    };
+    match RawJoinResponse::try_from(frame?) {
+        Ok(raw) => Ok(Some(JoinResponse::from(raw))),
+        Err(e) => Err(BotError::HandshakeFailed(format!(
+            "Invalid join response: {}",
+            e
+        ))),
+    }
+}
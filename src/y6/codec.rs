@@ -0,0 +1,79 @@
+use super::packets::{parse_packet, Bytes, ClientFraming, Packet};
+use crate::common::transport::Framing;
+
+use std::io::{Error, ErrorKind};
+
+use bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Codec for using packets with [`Framed`](tokio_util::codec::Framed) over any `AsyncRead + AsyncWrite`.
+///
+/// Decodes [`Packet`]s split by the [`Framing`] it was created with and encodes anything
+/// that converts into [`Bytes`] (clean, raw packets or [`Packet`] itself).
+/// Packets that fail to parse are reported as [`ErrorKind::InvalidData`].
+///
+/// # Examples
+///
+/// ## Sending a talk request over a raw TCP stream:
+/// ```no_run
+/// use asciicker_rs::y6::prelude::*;
+/// use futures_util::{SinkExt, StreamExt};
+/// use std::ffi::CString;
+/// use tokio::net::TcpStream;
+/// use tokio_util::codec::Framed;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let stream = TcpStream::connect("localhost:8080").await.unwrap();
+/// let mut framed = Framed::new(stream, Y6Codec::new(ClientFraming::default()));
+/// framed
+///     .send(JoinRequest { name: CString::new("bot").unwrap() })
+///     .await
+///     .unwrap();
+/// while let Some(packet) = framed.next().await {
+///     println!("{:?}", packet.unwrap());
+/// }
+/// # }
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct Y6Codec<F: Framing = ClientFraming> {
+    framing: F,
+}
+
+impl<F: Framing> Y6Codec<F> {
+    /// Constructs a new [`Y6Codec`], use [`ClientFraming`] on clients
+    /// and [`ServerFraming`](super::packets::ServerFraming) on servers.
+    pub fn new(framing: F) -> Self {
+        Self { framing }
+    }
+}
+
+impl<F: Framing> Decoder for Y6Codec<F> {
+    type Item = Packet;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+        let len = match self.framing.frame_len(src) {
+            Ok(Some(len)) if len <= src.len() => len,
+            Ok(_) => return Ok(None),
+            Err(e) => return Err(Error::new(ErrorKind::InvalidData, e)),
+        };
+        let frame = src.split_to(len);
+        match parse_packet(&frame) {
+            Ok(packet) => Ok(Some(packet)),
+            Err(e) => Err(Error::new(ErrorKind::InvalidData, e)),
+        }
+    }
+}
+
+impl<F: Framing, T: Into<Bytes>> Encoder<T> for Y6Codec<F> {
+    type Error = Error;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.extend_from_slice(&item.into());
+        Ok(())
+    }
+}
@@ -0,0 +1,620 @@
+use super::bot::{
+    into_callback, BotResult, ChatSender, FutureBotResult, MessageSender, Player, TalkCallback,
+    World,
+};
+use super::packets::TalkBroadcast;
+use super::utils::BotError;
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+use tokio::sync::{Mutex, RwLock};
+
+/// Type of a [`ChatCommand`] argument.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ArgKind {
+    /// Whole number, [`Args::int`]
+    Int,
+    /// Any number, [`Args::float`]
+    Float,
+    /// Nickname of a player on the server, matched case-insensitively, [`Args::player`]
+    Player,
+    /// Single word, [`Args::text`]
+    Word,
+    /// Everything until the end of the message, has to be the last argument, [`Args::text`]
+    Rest,
+}
+
+/// Declared argument of a [`ChatCommand`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arg {
+    /// Name used to get the value from [`Args`] and in the usage
+    pub name: String,
+    /// Type of the value
+    pub kind: ArgKind,
+    /// If `false`, the command fails without this argument
+    pub optional: bool,
+}
+
+impl Display for Arg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let rest = if self.kind == ArgKind::Rest {
+            "..."
+        } else {
+            ""
+        };
+        match self.optional {
+            true => write!(f, "[{}{}]", self.name, rest),
+            false => write!(f, "<{}{}>", self.name, rest),
+        }
+    }
+}
+
+/// Parsed value of an [`Arg`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgValue {
+    /// [`ArgKind::Int`]
+    Int(i64),
+    /// [`ArgKind::Float`]
+    Float(f64),
+    /// [`ArgKind::Player`], as the player was when the command was parsed
+    Player(Player),
+    /// [`ArgKind::Word`] or [`ArgKind::Rest`]
+    Text(String),
+}
+
+/// Error of parsing the arguments of a [`ChatCommand`], told to the author with the usage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgError {
+    /// Required argument is missing, contains its name
+    Missing(String),
+    /// Argument doesn't have the declared type: name, value
+    Invalid(String, String),
+    /// No player with the given nickname: name, nickname
+    UnknownPlayer(String, String),
+    /// More words than the command takes, contains the first extra one
+    TooMany(String),
+}
+
+impl Display for ArgError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArgError::Missing(name) => write!(f, "Missing {}", name),
+            ArgError::Invalid(name, value) => write!(f, "Invalid {}: {}", name, value),
+            ArgError::UnknownPlayer(name, nickname) => {
+                write!(f, "Unknown player for {}: {}", name, nickname)
+            }
+            ArgError::TooMany(extra) => write!(f, "Unexpected argument: {}", extra),
+        }
+    }
+}
+
+impl Error for ArgError {}
+
+/// Values of the arguments a [`ChatCommand`] was called with, missing optional ones are absent.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Args {
+    values: Vec<(String, ArgValue)>,
+}
+
+impl Args {
+    /// Returns the value of the argument `name`.
+    pub fn get(&self, name: &str) -> Option<&ArgValue> {
+        self.values
+            .iter()
+            .find(|(arg, _)| arg == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the [`ArgKind::Int`] argument `name`.
+    pub fn int(&self, name: &str) -> Option<i64> {
+        match self.get(name)? {
+            ArgValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the [`ArgKind::Float`] or [`ArgKind::Int`] argument `name`.
+    pub fn float(&self, name: &str) -> Option<f64> {
+        match self.get(name)? {
+            ArgValue::Float(value) => Some(*value),
+            ArgValue::Int(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    /// Returns the [`ArgKind::Player`] argument `name`.
+    pub fn player(&self, name: &str) -> Option<&Player> {
+        match self.get(name)? {
+            ArgValue::Player(player) => Some(player),
+            _ => None,
+        }
+    }
+
+    /// Returns the [`ArgKind::Word`] or [`ArgKind::Rest`] argument `name`.
+    pub fn text(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            ArgValue::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// Everything a [`CommandHandler`] gets about one call of its command.
+pub struct CommandContext {
+    /// Parsed arguments
+    pub args: Args,
+    /// Id of the player who called the command
+    pub author: u16,
+    /// Nickname of the author, if they are in [`World::clients`]
+    pub author_name: Option<String>,
+    /// The bot
+    pub bot: Arc<Mutex<Player>>,
+    /// The world
+    pub world: Arc<RwLock<World>>,
+    /// Sender of the bot
+    pub sender: MessageSender,
+}
+
+impl CommandContext {
+    /// Says `text` in the chat, checked by [`ChatSender::send`].
+    pub async fn reply<S: AsRef<str>>(&self, text: S) -> BotResult {
+        Ok(ChatSender::new(Arc::clone(&self.sender))
+            .send(text.as_ref())
+            .await?)
+    }
+}
+
+/// Decides which roles a player has, see [`ChatCommand::role`].
+///
+/// Nicknames are neither unique nor reserved, anyone can join with the nickname
+/// of a moderator, so prefer ids for anything sensitive.
+///
+/// Any `Fn(u16, Option<&str>, &str) -> bool` closure is a provider too.
+pub trait PermissionProvider: Send + Sync {
+    /// Returns `true` if the player with `id` and `nickname` has `role`.
+    ///
+    /// `nickname` is [`None`] if the player isn't in [`World::clients`].
+    fn has_role(&self, id: u16, nickname: Option<&str>, role: &str) -> bool;
+}
+
+impl<F: Fn(u16, Option<&str>, &str) -> bool + Send + Sync> PermissionProvider for F {
+    fn has_role(&self, id: u16, nickname: Option<&str>, role: &str) -> bool {
+        self(id, nickname, role)
+    }
+}
+
+/// [`PermissionProvider`] with a fixed set of roles by id and by nickname (case-insensitive).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Roles {
+    by_id: HashMap<u16, HashSet<String>>,
+    by_name: HashMap<String, HashSet<String>>,
+}
+
+impl Roles {
+    /// Constructs a new [`Roles`] without any grants.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Grants `role` to the player with `id`.
+    pub fn grant_id<S: Into<String>>(mut self, id: u16, role: S) -> Self {
+        self.by_id.entry(id).or_default().insert(role.into());
+        self
+    }
+
+    /// Grants `role` to every player called `nickname`.
+    pub fn grant_name<N: AsRef<str>, S: Into<String>>(mut self, nickname: N, role: S) -> Self {
+        self.by_name
+            .entry(nickname.as_ref().to_lowercase())
+            .or_default()
+            .insert(role.into());
+        self
+    }
+}
+
+impl PermissionProvider for Roles {
+    fn has_role(&self, id: u16, nickname: Option<&str>, role: &str) -> bool {
+        self.by_id
+            .get(&id)
+            .is_some_and(|roles| roles.contains(role))
+            || nickname
+                .and_then(|nickname| self.by_name.get(&nickname.to_lowercase()))
+                .is_some_and(|roles| roles.contains(role))
+    }
+}
+
+/// Type alias for command handler.
+pub type CommandHandler = Arc<dyn Fn(CommandContext) -> FutureBotResult + Send + Sync>;
+
+/// Single chat command of [`Commands`].
+///
+/// Words after the name are matched to the declared arguments in order.
+#[derive(Clone)]
+pub struct ChatCommand {
+    name: String,
+    about: String,
+    args: Vec<Arg>,
+    roles: Vec<String>,
+    handler: CommandHandler,
+}
+
+impl ChatCommand {
+    /// Constructs a new [`ChatCommand`] without arguments, called as `<prefix><name>`.
+    pub fn new<S, F>(name: S, handler: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(CommandContext) -> FutureBotResult + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            about: String::new(),
+            args: vec![],
+            roles: vec![],
+            handler: Arc::new(handler),
+        }
+    }
+
+    /// Sets the description shown by the help command.
+    pub fn about<S: Into<String>>(mut self, about: S) -> Self {
+        self.about = about.into();
+        self
+    }
+
+    /// Adds a required argument.
+    pub fn arg<S: Into<String>>(mut self, name: S, kind: ArgKind) -> Self {
+        self.args.push(Arg {
+            name: name.into(),
+            kind,
+            optional: false,
+        });
+        self
+    }
+
+    /// Adds an optional argument, every argument after it should be optional too.
+    pub fn optional_arg<S: Into<String>>(mut self, name: S, kind: ArgKind) -> Self {
+        self.args.push(Arg {
+            name: name.into(),
+            kind,
+            optional: true,
+        });
+        self
+    }
+
+    /// Allows the command only to players with `role`, if called several times,
+    /// any of the roles is enough, see [`Commands::permissions`].
+    pub fn role<S: Into<String>>(mut self, role: S) -> Self {
+        self.roles.push(role.into());
+        self
+    }
+
+    /// Roles allowed to use the command, everyone can use it if there are none.
+    pub fn roles(&self) -> &[String] {
+        &self.roles
+    }
+
+    /// Name of the command.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Declared arguments.
+    pub fn args(&self) -> &[Arg] {
+        &self.args
+    }
+
+    /// Usage line like `!tp <player> [distance]`.
+    pub fn usage(&self, prefix: &str) -> String {
+        let mut usage = format!("{}{}", prefix, self.name);
+        for arg in &self.args {
+            usage.push_str(&format!(" {}", arg));
+        }
+        usage
+    }
+
+    /// Parses the text after the command name.
+    pub fn parse(&self, input: &str, world: &World) -> Result<Args, ArgError> {
+        let mut args = Args::default();
+        let mut rest = input.trim_start();
+        for arg in &self.args {
+            if rest.is_empty() {
+                if arg.optional {
+                    break;
+                }
+                return Err(ArgError::Missing(arg.name.clone()));
+            }
+            let word = match arg.kind {
+                ArgKind::Rest => rest.trim_end(),
+                _ => rest.split_whitespace().next().unwrap_or_default(),
+            };
+            rest = rest[word.len()..].trim_start();
+            let invalid = || ArgError::Invalid(arg.name.clone(), word.to_string());
+            let value = match arg.kind {
+                ArgKind::Int => ArgValue::Int(word.parse().map_err(|_| invalid())?),
+                ArgKind::Float => ArgValue::Float(word.parse().map_err(|_| invalid())?),
+                ArgKind::Player => match world.player_by_name(word) {
+                    Some(player) => ArgValue::Player(player.clone()),
+                    None => {
+                        return Err(ArgError::UnknownPlayer(arg.name.clone(), word.to_string()))
+                    }
+                },
+                ArgKind::Word | ArgKind::Rest => ArgValue::Text(word.to_string()),
+            };
+            args.values.push((arg.name.clone(), value));
+        }
+        match rest.split_whitespace().next() {
+            Some(extra) => Err(ArgError::TooMany(extra.to_string())),
+            None => Ok(args),
+        }
+    }
+}
+
+/// Set of chat commands sharing a prefix, dispatched from talk broadcasts.
+///
+/// Add it to a bot with [`Bot::on_commands`](super::bot::Bot::on_commands). Messages starting
+/// with the prefix and a known command name (case-insensitive) are parsed and handled,
+/// the rest of the talk chain is skipped for them. On invalid arguments the bot replies
+/// with the error and the usage instead of calling the handler.
+///
+/// A `help` command listing every command, or describing one with `help <command>`,
+/// is added unless disabled with [`Commands::help`].
+///
+/// Commands with [roles](ChatCommand::role) are only handled for players who have one of them
+/// according to the [`PermissionProvider`], others are told they aren't allowed and don't
+/// see the command in the help.
+///
+/// # Examples
+///
+/// ## Dice bot:
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let commands = Commands::new("!").command(
+///     ChatCommand::new("roll", |ctx| {
+///         Box::pin(async move {
+///             let sides = ctx.args.int("sides").unwrap_or(6);
+///             ctx.reply(format!("Rolled {} of {}", sides, sides)).await
+///         })
+///     })
+///     .about("Rolls a die")
+///     .optional_arg("sides", ArgKind::Int),
+/// );
+/// let mut bot = BotBuilder::new("dice").build();
+/// bot.on_commands(commands);
+///
+/// let second = Duration::from_secs(1);
+/// Scenario::new(&bot)
+///     .join(2, "player")
+///     .talk(2, "!roll 20")
+///     .expect_talk(|m| m == "Rolled 20 of 20", second)
+///     .talk(2, "!roll twenty")
+///     .expect_talk(|m| m == "Invalid sides: twenty, usage: !roll [sides]", second)
+///     .talk(2, "!help")
+///     .expect_talk(|m| m == "Commands: !roll, !help", second)
+///     .talk(2, "!help roll")
+///     .expect_talk(|m| m == "!roll [sides] - Rolls a die", second)
+///     .talk(2, "roll")
+///     .expect_silence(Duration::from_millis(10))
+///     .run()
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+///
+/// ## Moderation command:
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let commands = Commands::new("!")
+///     .permissions(Roles::new().grant_id(2, "mod"))
+///     .command(
+///         ChatCommand::new("kick", |ctx| {
+///             Box::pin(async move {
+///                 let target = ctx.args.player("target").unwrap();
+///                 ctx.reply(format!("Bye, {}", target.nickname)).await
+///             })
+///         })
+///         .arg("target", ArgKind::Player)
+///         .role("mod"),
+///     );
+/// let mut bot = BotBuilder::new("moderator").build();
+/// bot.on_commands(commands);
+///
+/// let second = Duration::from_secs(1);
+/// Scenario::new(&bot)
+///     .join(2, "moderator")
+///     .join(3, "griefer")
+///     .talk(3, "!kick moderator")
+///     .expect_talk(|m| m == "Not allowed to use !kick", second)
+///     .talk(3, "!help")
+///     .expect_talk(|m| m == "Commands: !help", second)
+///     .talk(2, "!kick griefer")
+///     .expect_talk(|m| m == "Bye, griefer", second)
+///     .run()
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Commands {
+    prefix: String,
+    commands: Vec<ChatCommand>,
+    help: bool,
+    permissions: Option<Arc<dyn PermissionProvider>>,
+}
+
+impl Commands {
+    /// Constructs a new [`Commands`] with the built-in help command.
+    pub fn new<S: Into<String>>(prefix: S) -> Self {
+        Self {
+            prefix: prefix.into(),
+            commands: vec![],
+            help: true,
+            permissions: None,
+        }
+    }
+
+    /// Adds a [`ChatCommand`], a command with the same name added earlier takes precedence.
+    pub fn command(mut self, command: ChatCommand) -> Self {
+        self.commands.push(command);
+        self
+    }
+
+    /// Enables or disables the built-in help command.
+    pub fn help(mut self, help: bool) -> Self {
+        self.help = help;
+        self
+    }
+
+    /// Sets the [`PermissionProvider`] checked for commands with roles,
+    /// without one nobody can use them.
+    pub fn permissions<P: PermissionProvider + 'static>(mut self, permissions: P) -> Self {
+        self.permissions = Some(Arc::new(permissions));
+        self
+    }
+
+    /// Returns `true` if the player with `id` and `nickname` can use `command`.
+    pub fn allowed(&self, command: &ChatCommand, id: u16, nickname: Option<&str>) -> bool {
+        command.roles.is_empty()
+            || self.permissions.as_ref().is_some_and(|permissions| {
+                command
+                    .roles
+                    .iter()
+                    .any(|role| permissions.has_role(id, nickname, role))
+            })
+    }
+
+    /// Prefix of every command.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Returns the command called `name`, case-insensitive.
+    pub fn get(&self, name: &str) -> Option<&ChatCommand> {
+        self.commands
+            .iter()
+            .find(|command| command.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Text of the help command for `topic`, or the list of commands without it.
+    pub fn help_text(&self, topic: Option<&str>) -> String {
+        self.help_text_filtered(topic, |_| true)
+    }
+
+    /// Same as [`Commands::help_text`], but only with the commands the player can use.
+    pub fn help_text_for(&self, topic: Option<&str>, id: u16, nickname: Option<&str>) -> String {
+        self.help_text_filtered(topic, |command| self.allowed(command, id, nickname))
+    }
+
+    fn help_text_filtered<F: Fn(&ChatCommand) -> bool>(
+        &self,
+        topic: Option<&str>,
+        visible: F,
+    ) -> String {
+        match topic.map(|name| name.trim_start_matches(self.prefix.as_str())) {
+            Some(name) => match self.get(name).filter(|command| visible(command)) {
+                Some(command) if command.about.is_empty() => command.usage(&self.prefix),
+                Some(command) => format!("{} - {}", command.usage(&self.prefix), command.about),
+                None => format!("Unknown command: {}", name),
+            },
+            None => {
+                let mut names: Vec<String> = self
+                    .commands
+                    .iter()
+                    .filter(|command| visible(command))
+                    .map(|command| format!("{}{}", self.prefix, command.name))
+                    .collect();
+                if self.help && self.get("help").is_none() {
+                    names.push(format!("{}help", self.prefix));
+                }
+                format!("Commands: {}", names.join(", "))
+            }
+        }
+    }
+
+    /// Handles `talk_brc` if it is a command, returns [`ControlFlow::Break`] if it was.
+    pub async fn dispatch(
+        &self,
+        talk_brc: &TalkBroadcast,
+        bot: Arc<Mutex<Player>>,
+        world: Arc<RwLock<World>>,
+        sender: MessageSender,
+    ) -> Result<ControlFlow<()>, BotError> {
+        if bot.lock().await.id == talk_brc.id {
+            return Ok(ControlFlow::Continue(()));
+        }
+        let text = talk_brc.str.to_string_lossy();
+        let Some(line) = text.strip_prefix(self.prefix.as_str()) else {
+            return Ok(ControlFlow::Continue(()));
+        };
+        let line = line.trim();
+        let (name, input) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let command = self.get(name);
+        if command.is_none() && !(self.help && name.eq_ignore_ascii_case("help")) {
+            return Ok(ControlFlow::Continue(()));
+        }
+        let (parsed, author_name) = {
+            let world = world.read().await;
+            let author_name = world
+                .client(talk_brc.id)
+                .map(|author| author.nickname.clone());
+            let parsed = command.map(|command| command.parse(input, &world));
+            (parsed, author_name)
+        };
+        let reply = |text: String| async {
+            sender
+                .send(text)
+                .await
+                .map_err(|_| BotError::ChannelClosed("sender"))
+        };
+        let (command, parsed) = match (command, parsed) {
+            (Some(command), Some(parsed)) => (command, parsed),
+            _ => {
+                let topic = input.split_whitespace().next();
+                reply(self.help_text_for(topic, talk_brc.id, author_name.as_deref())).await?;
+                return Ok(ControlFlow::Break(()));
+            }
+        };
+        if !self.allowed(command, talk_brc.id, author_name.as_deref()) {
+            reply(format!(
+                "Not allowed to use {}{}",
+                self.prefix, command.name
+            ))
+            .await?;
+            return Ok(ControlFlow::Break(()));
+        }
+        match parsed {
+            Ok(args) => {
+                (command.handler)(CommandContext {
+                    args,
+                    author: talk_brc.id,
+                    author_name,
+                    bot,
+                    world,
+                    sender,
+                })
+                .await?
+            }
+            Err(e) => reply(format!("{}, usage: {}", e, command.usage(&self.prefix))).await?,
+        }
+        Ok(ControlFlow::Break(()))
+    }
+
+    /// Boxes the commands into a [`TalkCallback`].
+    pub fn into_callback(self) -> TalkCallback {
+        let commands = Arc::new(self);
+        into_callback(move |talk_brc: TalkBroadcast, bot, world, sender| {
+            let commands = Arc::clone(&commands);
+            Box::pin(async move { commands.dispatch(&talk_brc, bot, world, sender).await })
+        })
+    }
+}
@@ -0,0 +1,188 @@
+use super::bot::{
+    call_chain, BotResult, CallbackLimit, MessageSender, Player, PoseCallback, World,
+};
+use super::packets::PoseBroadcast;
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+};
+
+use futures_util::future::try_join;
+use tokio::sync::{Mutex, Notify, RwLock};
+
+/// How pose broadcasts are handed to the [`PoseCallback`]s, set with [`Bot::pose_delivery`].
+///
+/// With any policy other than [`DeliveryPolicy::Inline`] the world is still patched as soon as a
+/// broadcast arrives, only the callbacks run later, next to the receiver, so a slow callback
+/// never delays other packets.
+///
+/// [`Bot::pose_delivery`]: super::bot::Bot::pose_delivery
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum DeliveryPolicy {
+    /// Callback is awaited by the receiver before the next packet is read, default
+    #[default]
+    Inline,
+    /// At most this many broadcasts wait for the callback, newer ones are dropped when it is full
+    Bounded(usize),
+    /// Only the latest broadcast of every player waits for the callback,
+    /// older ones are replaced in place
+    LatestPerPlayer,
+}
+
+/// Counters of a [`DeliveryPolicy`], shared with the running bot.
+///
+/// Get them with [`Bot::delivery_stats`](super::bot::Bot::delivery_stats).
+#[derive(Debug, Default)]
+pub struct DeliveryStats {
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    coalesced: AtomicU64,
+}
+
+impl DeliveryStats {
+    /// Broadcasts passed to the callback.
+    pub fn delivered(&self) -> u64 {
+        self.delivered.load(Ordering::Relaxed)
+    }
+
+    /// Broadcasts dropped because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Broadcasts replaced by a newer one of the same player before reaching the callback.
+    pub fn coalesced(&self) -> u64 {
+        self.coalesced.load(Ordering::Relaxed)
+    }
+}
+
+/// Broadcasts waiting for the callback.
+#[derive(Debug, Default)]
+struct Pending {
+    fifo: VecDeque<PoseBroadcast>,
+    latest: HashMap<u16, PoseBroadcast>,
+    order: VecDeque<u16>,
+}
+
+/// Queue between the receiver and the [`PoseCallback`]s, see [`DeliveryPolicy`].
+#[derive(Debug)]
+pub(crate) struct PoseQueue {
+    policy: DeliveryPolicy,
+    pending: std::sync::Mutex<Pending>,
+    notify: Notify,
+    closed: AtomicBool,
+    stats: Arc<DeliveryStats>,
+}
+
+impl PoseQueue {
+    /// Returns [`None`] for [`DeliveryPolicy::Inline`], which needs no queue.
+    pub(crate) fn new(policy: DeliveryPolicy, stats: Arc<DeliveryStats>) -> Option<Arc<Self>> {
+        match policy {
+            DeliveryPolicy::Inline => None,
+            _ => Some(Arc::new(Self {
+                policy,
+                pending: Default::default(),
+                notify: Notify::new(),
+                closed: AtomicBool::new(false),
+                stats,
+            })),
+        }
+    }
+
+    /// Queues a broadcast according to the policy.
+    pub(crate) fn push(&self, brc: PoseBroadcast) {
+        let mut pending = self.pending.lock().unwrap();
+        match self.policy {
+            DeliveryPolicy::Inline => unreachable!("inline delivery has no queue"),
+            DeliveryPolicy::Bounded(capacity) => {
+                if pending.fifo.len() >= capacity {
+                    self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+                pending.fifo.push_back(brc);
+            }
+            DeliveryPolicy::LatestPerPlayer => {
+                let id = brc.id;
+                match pending.latest.insert(id, brc) {
+                    Some(_) => {
+                        self.stats.coalesced.fetch_add(1, Ordering::Relaxed);
+                        return;
+                    }
+                    None => pending.order.push_back(id),
+                }
+            }
+        }
+        drop(pending);
+        self.notify.notify_one();
+    }
+
+    /// Stops [`dispatch`] once the queue is drained.
+    pub(crate) fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.notify.notify_one();
+    }
+
+    fn pop(&self) -> Option<PoseBroadcast> {
+        let mut pending = self.pending.lock().unwrap();
+        match pending.fifo.pop_front() {
+            Some(brc) => Some(brc),
+            None => {
+                let id = pending.order.pop_front()?;
+                pending.latest.remove(&id)
+            }
+        }
+    }
+}
+
+/// Passes queued broadcasts to `callbacks` until the queue is closed and drained.
+pub(crate) async fn dispatch(
+    queue: Arc<PoseQueue>,
+    callbacks: Vec<PoseCallback>,
+    bot: Arc<Mutex<Player>>,
+    world: Arc<RwLock<World>>,
+    sender: MessageSender,
+    limit: Option<CallbackLimit<'_>>,
+) -> BotResult {
+    loop {
+        while let Some(brc) = queue.pop() {
+            call_chain(&callbacks, brc, &bot, &world, &sender, limit).await?;
+            queue.stats.delivered.fetch_add(1, Ordering::Relaxed);
+        }
+        if queue.closed.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        queue.notify.notified().await;
+    }
+}
+
+/// Runs `main` (the receive loop) next to [`dispatch`] if there is a queue,
+/// the queue is closed once `main` finishes.
+pub(crate) async fn with_dispatch<F: Future<Output = BotResult>>(
+    main: F,
+    queue: Option<Arc<PoseQueue>>,
+    callbacks: Vec<PoseCallback>,
+    bot: Arc<Mutex<Player>>,
+    world: Arc<RwLock<World>>,
+    sender: MessageSender,
+    limit: Option<CallbackLimit<'_>>,
+) -> BotResult {
+    match queue {
+        None => main.await,
+        Some(queue) => {
+            let main = async {
+                let result = main.await;
+                queue.close();
+                result
+            };
+            try_join(
+                main,
+                dispatch(Arc::clone(&queue), callbacks, bot, world, sender, limit),
+            )
+            .await
+            .map(|_| ())
+        }
+    }
+}
@@ -0,0 +1,410 @@
+//! C interface to the packets of this version.
+//!
+//! Packets are exchanged as [`FfiPacket`], a flat `#[repr(C)]` struct with a field for
+//! everything any packet has. Parsing and encoding never allocate memory the caller has to free:
+//! results are written into structs and buffers the caller provides.
+//!
+//! Build a library to link against with the `ffi` feature, for example
+//! `cargo rustc --release --no-default-features --features ffi,std --crate-type staticlib`.
+//! Declarations for C:
+//!
+//! ```c
+//! #include <stddef.h>
+//! #include <stdint.h>
+//! #include <sys/types.h>
+//!
+//! typedef struct {
+//!     uint8_t animation, frame, action_or_mount;
+//!     float position[3], direction;
+//!     uint16_t sprite;
+//! } asciicker_y6_pose;
+//!
+//! typedef struct {
+//!     uint8_t kind, max_clients;
+//!     uint16_t id;
+//!     asciicker_y6_pose pose;
+//!     uint8_t stamp[3];
+//!     char text[256];
+//! } asciicker_y6_packet;
+//!
+//! ssize_t asciicker_y6_parse(const uint8_t *bytes, size_t len, asciicker_y6_packet *out);
+//! ssize_t asciicker_y6_encode(const asciicker_y6_packet *packet, uint8_t *buf, size_t capacity);
+//! ```
+
+use super::packets::{
+    EncodePacket, ExitBroadcast, JoinBroadcast, JoinRequest, JoinResponse, LagRequest, LagResponse,
+    Packet, PlayerPose, PoseBroadcast, PoseRequest, TalkBroadcast, TalkRequest, MAX_TALK_LEN,
+};
+use super::utils::first_nul;
+
+use alloc::ffi::CString;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::ffi::CStr;
+use core::slice;
+
+/// Size of [`FfiPacket::text`], long enough for the longest message and its nul byte.
+pub const FFI_TEXT_SIZE: usize = MAX_TALK_LEN + 1;
+/// Longest name a join request can carry, in bytes.
+pub const FFI_MAX_NAME_LEN: usize = 31;
+
+/// Error code: a pointer is null.
+pub const FFI_ERROR_NULL: isize = -1;
+/// Error code: bytes aren't a valid packet.
+pub const FFI_ERROR_PARSE: isize = -2;
+/// Error code: [`FfiPacket::kind`] isn't one of [`FfiPacketKind`].
+pub const FFI_ERROR_KIND: isize = -3;
+/// Error code: [`FfiPacket::text`] has no nul byte or is too long for the packet.
+pub const FFI_ERROR_TEXT: isize = -4;
+
+/// Values of [`FfiPacket::kind`], one per [`Packet`] variant.
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FfiPacketKind {
+    /// [`JoinRequest`], uses `text`
+    JoinRequest = 0,
+    /// [`JoinResponse`], uses `max_clients` and `id`
+    JoinResponse = 1,
+    /// [`JoinBroadcast`], uses `pose`, `id` and `text`
+    JoinBroadcast = 2,
+    /// [`ExitBroadcast`], uses `id`
+    ExitBroadcast = 3,
+    /// [`PoseRequest`], uses `pose`
+    PoseRequest = 4,
+    /// [`PoseBroadcast`], uses `pose` and `id`
+    PoseBroadcast = 5,
+    /// [`TalkRequest`], uses `text`
+    TalkRequest = 6,
+    /// [`TalkBroadcast`], uses `id` and `text`
+    TalkBroadcast = 7,
+    /// [`LagRequest`], uses `stamp`
+    LagRequest = 8,
+    /// [`LagResponse`], uses `stamp`
+    LagResponse = 9,
+}
+
+impl TryFrom<u8> for FfiPacketKind {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Self::JoinRequest,
+            1 => Self::JoinResponse,
+            2 => Self::JoinBroadcast,
+            3 => Self::ExitBroadcast,
+            4 => Self::PoseRequest,
+            5 => Self::PoseBroadcast,
+            6 => Self::TalkRequest,
+            7 => Self::TalkBroadcast,
+            8 => Self::LagRequest,
+            9 => Self::LagResponse,
+            other => return Err(other),
+        })
+    }
+}
+
+/// [`PlayerPose`] laid out for C.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct FfiPose {
+    /// Animation, see [`Animation`](super::packets::Animation)
+    pub animation: u8,
+    /// Frame of the animation
+    pub frame: u8,
+    /// Action or mount, see [`Action`](super::packets::Action)
+    pub action_or_mount: u8,
+    /// Position
+    pub position: [f32; 3],
+    /// Direction
+    pub direction: f32,
+    /// Current sprite
+    pub sprite: u16,
+}
+
+impl From<&PlayerPose> for FfiPose {
+    fn from(value: &PlayerPose) -> Self {
+        Self {
+            animation: value.animation.into(),
+            frame: value.frame,
+            action_or_mount: value.action_or_mount.into(),
+            position: value.position.into(),
+            direction: value.direction,
+            sprite: value.sprite,
+        }
+    }
+}
+
+impl From<&FfiPose> for PlayerPose {
+    fn from(value: &FfiPose) -> Self {
+        Self {
+            animation: value.animation.into(),
+            frame: value.frame,
+            action_or_mount: value.action_or_mount.into(),
+            position: value.position.into(),
+            direction: value.direction,
+            sprite: value.sprite,
+        }
+    }
+}
+
+/// Any [`Packet`] laid out for C, [`FfiPacket::kind`] tells which one and which fields are used.
+///
+/// Unused fields are zeroed by [`asciicker_y6_parse`] and ignored by [`asciicker_y6_encode`].
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::ffi::*;
+/// use asciicker_rs::y6::packets::*;
+/// use std::ffi::CString;
+///
+/// let talk = Packet::TalkBroadcast(TalkBroadcast { id: 3, str: CString::new("hi").unwrap() });
+/// let ffi = FfiPacket::from(&talk);
+/// assert_eq!(ffi.kind, FfiPacketKind::TalkBroadcast as u8);
+/// assert_eq!(&ffi.text[..3], b"hi\0");
+/// assert_eq!(Packet::try_from(&ffi).unwrap(), talk);
+/// ```
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FfiPacket {
+    /// Kind of the packet, one of [`FfiPacketKind`]
+    pub kind: u8,
+    /// Max amount of clients
+    pub max_clients: u8,
+    /// Id of the player
+    pub id: u16,
+    /// Pose of the player
+    pub pose: FfiPose,
+    /// Lag stamp
+    pub stamp: [u8; 3],
+    /// Name or message, nul-terminated
+    pub text: [u8; FFI_TEXT_SIZE],
+}
+
+impl Default for FfiPacket {
+    fn default() -> Self {
+        Self {
+            kind: 0,
+            max_clients: 0,
+            id: 0,
+            pose: FfiPose::default(),
+            stamp: [0; 3],
+            text: [0; FFI_TEXT_SIZE],
+        }
+    }
+}
+
+impl FfiPacket {
+    /// Creates a packet of `kind` with every field zeroed.
+    fn of_kind(kind: FfiPacketKind) -> Self {
+        Self {
+            kind: kind as u8,
+            ..Self::default()
+        }
+    }
+
+    /// Copies `text` into [`FfiPacket::text`], cut to fit along with the nul byte.
+    fn with_text(mut self, text: &CStr) -> Self {
+        let bytes = text.to_bytes();
+        let len = bytes.len().min(FFI_TEXT_SIZE - 1);
+        self.text[..len].copy_from_slice(&bytes[..len]);
+        self
+    }
+
+    /// [`FfiPacket::text`] up to its nul byte, [`FFI_ERROR_TEXT`] if it is longer than `max_len`.
+    fn text(&self, max_len: usize) -> Result<CString, isize> {
+        match first_nul(&self.text) {
+            Some(len) if len <= max_len => {
+                CString::new(&self.text[..len]).map_err(|_| FFI_ERROR_TEXT)
+            }
+            _ => Err(FFI_ERROR_TEXT),
+        }
+    }
+}
+
+impl From<&Packet> for FfiPacket {
+    fn from(value: &Packet) -> Self {
+        match value {
+            Packet::JoinRequest(join_req) => {
+                Self::of_kind(FfiPacketKind::JoinRequest).with_text(&join_req.name)
+            }
+            Packet::JoinResponse(join_rsp) => Self {
+                max_clients: join_rsp.max_clients,
+                id: join_rsp.id,
+                ..Self::of_kind(FfiPacketKind::JoinResponse)
+            },
+            Packet::JoinBroadcast(join_brc) => Self {
+                id: join_brc.id,
+                pose: (&join_brc.player_pose).into(),
+                ..Self::of_kind(FfiPacketKind::JoinBroadcast)
+            }
+            .with_text(&join_brc.name),
+            Packet::ExitBroadcast(exit_brc) => Self {
+                id: exit_brc.id,
+                ..Self::of_kind(FfiPacketKind::ExitBroadcast)
+            },
+            Packet::PoseRequest(pose_req) => Self {
+                pose: (&pose_req.player_pose).into(),
+                ..Self::of_kind(FfiPacketKind::PoseRequest)
+            },
+            Packet::PoseBroadcast(pose_brc) => Self {
+                id: pose_brc.id,
+                pose: (&pose_brc.player_pose).into(),
+                ..Self::of_kind(FfiPacketKind::PoseBroadcast)
+            },
+            Packet::TalkRequest(talk_req) => {
+                Self::of_kind(FfiPacketKind::TalkRequest).with_text(&talk_req.str)
+            }
+            Packet::TalkBroadcast(talk_brc) => Self {
+                id: talk_brc.id,
+                ..Self::of_kind(FfiPacketKind::TalkBroadcast)
+            }
+            .with_text(&talk_brc.str),
+            Packet::LagRequest(lag_req) => Self {
+                stamp: lag_req.stamp,
+                ..Self::of_kind(FfiPacketKind::LagRequest)
+            },
+            Packet::LagResponse(lag_rsp) => Self {
+                stamp: lag_rsp.stamp,
+                ..Self::of_kind(FfiPacketKind::LagResponse)
+            },
+        }
+    }
+}
+
+impl TryFrom<&FfiPacket> for Packet {
+    /// One of the `FFI_ERROR_*` codes
+    type Error = isize;
+
+    fn try_from(value: &FfiPacket) -> Result<Self, Self::Error> {
+        let kind = FfiPacketKind::try_from(value.kind).map_err(|_| FFI_ERROR_KIND)?;
+        Ok(match kind {
+            FfiPacketKind::JoinRequest => Packet::JoinRequest(JoinRequest {
+                name: value.text(FFI_MAX_NAME_LEN)?,
+            }),
+            FfiPacketKind::JoinResponse => Packet::JoinResponse(JoinResponse {
+                max_clients: value.max_clients,
+                id: value.id,
+            }),
+            FfiPacketKind::JoinBroadcast => Packet::JoinBroadcast(JoinBroadcast {
+                player_pose: (&value.pose).into(),
+                id: value.id,
+                // Broadcasts have room for one more byte, they don't need the nul
+                name: value.text(FFI_MAX_NAME_LEN + 1)?,
+            }),
+            FfiPacketKind::ExitBroadcast => Packet::ExitBroadcast(ExitBroadcast { id: value.id }),
+            FfiPacketKind::PoseRequest => Packet::PoseRequest(PoseRequest {
+                player_pose: (&value.pose).into(),
+            }),
+            FfiPacketKind::PoseBroadcast => Packet::PoseBroadcast(PoseBroadcast {
+                player_pose: (&value.pose).into(),
+                id: value.id,
+            }),
+            FfiPacketKind::TalkRequest => Packet::TalkRequest(TalkRequest {
+                str: value.text(MAX_TALK_LEN)?,
+            }),
+            FfiPacketKind::TalkBroadcast => Packet::TalkBroadcast(TalkBroadcast {
+                id: value.id,
+                str: value.text(MAX_TALK_LEN)?,
+            }),
+            FfiPacketKind::LagRequest => Packet::LagRequest(LagRequest { stamp: value.stamp }),
+            FfiPacketKind::LagResponse => Packet::LagResponse(LagResponse { stamp: value.stamp }),
+        })
+    }
+}
+
+/// Parses the packet in the `len` bytes at `bytes` into `out`.
+///
+/// Returns `0` on success, [`FFI_ERROR_NULL`] or [`FFI_ERROR_PARSE`] otherwise,
+/// `out` is left untouched on errors.
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes and `out` to a writable [`FfiPacket`],
+/// both may be null only to get [`FFI_ERROR_NULL`].
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::ffi::*;
+/// use asciicker_rs::y6::packets::*;
+///
+/// let bytes: Bytes = ExitBroadcast { id: 7 }.into();
+/// let mut packet = FfiPacket::default();
+/// let result = unsafe { asciicker_y6_parse(bytes.as_ptr(), bytes.len(), &mut packet) };
+/// assert_eq!(result, 0);
+/// assert_eq!(packet.kind, FfiPacketKind::ExitBroadcast as u8);
+/// assert_eq!(packet.id, 7);
+///
+/// let result = unsafe { asciicker_y6_parse(b"x".as_ptr(), 1, &mut packet) };
+/// assert_eq!(result, FFI_ERROR_PARSE);
+/// ```
+#[no_mangle]
+pub unsafe extern "C" fn asciicker_y6_parse(
+    bytes: *const u8,
+    len: usize,
+    out: *mut FfiPacket,
+) -> isize {
+    if bytes.is_null() || out.is_null() {
+        return FFI_ERROR_NULL;
+    }
+    let bytes = slice::from_raw_parts(bytes, len);
+    match Packet::try_from(bytes) {
+        Ok(packet) => {
+            out.write(FfiPacket::from(&packet));
+            0
+        }
+        Err(_) => FFI_ERROR_PARSE,
+    }
+}
+
+/// Encodes `packet` into the `capacity` bytes at `buf`.
+///
+/// Returns the length of the encoded packet, which is only written if it fits,
+/// so a result bigger than `capacity` tells how big the buffer has to be. `buf` may be null
+/// if `capacity` is `0`. Returns [`FFI_ERROR_NULL`], [`FFI_ERROR_KIND`] or [`FFI_ERROR_TEXT`]
+/// if the packet can't be encoded.
+///
+/// # Safety
+///
+/// `packet` must point to a readable [`FfiPacket`] and `buf` to `capacity` writable bytes.
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::ffi::*;
+/// use asciicker_rs::y6::packets::*;
+///
+/// let mut packet = FfiPacket::default();
+/// packet.kind = FfiPacketKind::TalkRequest as u8;
+/// packet.text[..6].copy_from_slice(b"hello\0");
+///
+/// let needed = unsafe { asciicker_y6_encode(&packet, std::ptr::null_mut(), 0) };
+/// let mut buf = vec![0u8; needed as usize];
+/// let written = unsafe { asciicker_y6_encode(&packet, buf.as_mut_ptr(), buf.len()) };
+/// assert_eq!(written, needed);
+/// assert!(matches!(parse_packet(&buf).unwrap(), Packet::TalkRequest(talk) if talk.str.to_str() == Ok("hello")));
+///
+/// packet.kind = 42;
+/// assert_eq!(unsafe { asciicker_y6_encode(&packet, buf.as_mut_ptr(), buf.len()) }, FFI_ERROR_KIND);
+/// ```
+#[no_mangle]
+pub unsafe extern "C" fn asciicker_y6_encode(
+    packet: *const FfiPacket,
+    buf: *mut u8,
+    capacity: usize,
+) -> isize {
+    if packet.is_null() || (buf.is_null() && capacity > 0) {
+        return FFI_ERROR_NULL;
+    }
+    let packet = match Packet::try_from(&*packet) {
+        Ok(packet) => packet,
+        Err(code) => return code,
+    };
+    let mut encoded = Vec::with_capacity(packet.encoded_len());
+    packet.encode_into(&mut encoded);
+    if encoded.len() <= capacity {
+        slice::from_raw_parts_mut(buf, encoded.len()).copy_from_slice(&encoded);
+    }
+    encoded.len() as isize
+}
@@ -0,0 +1,817 @@
+use super::bot::movement::{step_towards, step_within, Path, STEP_INTERVAL, WALK_SPEED};
+use super::bot::{
+    modify_pose, Bot, BotData, BotResult, DisconnectReason, Message, MessageSender, Player,
+    PoseSender, Receiver, Sender, World,
+};
+use super::latency::{Latency, SharedLatency};
+use super::packets::{
+    ExitBroadcast, JoinBroadcast, LagResponse, PlayerPose, PoseBroadcast, Position, TalkBroadcast,
+};
+use super::stats::{PacketStats, SharedPacketStats};
+use super::utils::BotError;
+use crate::common::{Instant, Runtime, Ticker};
+use crate::traits::GameConnection;
+
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{
+    future::{select, BoxFuture, Either},
+    stream, Stream, StreamExt,
+};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc::{self, error::TrySendError},
+    oneshot, watch, Mutex, Notify, RwLock,
+};
+
+/// Amount of events kept for subscribers of [`BotHandle::events`] that fall behind.
+pub(crate) const EVENT_CAPACITY: usize = 256;
+
+/// Amount of commands queued for the actor behind [`BotHandle`] before setters fail.
+pub const COMMAND_CAPACITY: usize = 64;
+
+/// Consistent copy of the bot and its world at one point in time, published by the actor behind [`BotHandle`].
+///
+/// Parts that didn't change are shared with the previous snapshot, so cloning one is cheap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    /// The bot itself
+    pub player: Arc<Player>,
+    /// World the bot is in
+    pub world: Arc<World>,
+}
+
+/// Everything that happened to a bot behind a [`BotHandle`], see [`BotHandle::events`].
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// Someone has joined
+    Join(JoinBroadcast),
+    /// Someone has left
+    Exit(ExitBroadcast),
+    /// Someone has moved or their pose changed for any reason
+    Pose(PoseBroadcast),
+    /// Someone has said something
+    Talk(TalkBroadcast),
+    /// Server answered a lag request
+    Lag(LagResponse),
+    /// Someone's pose references a sprite this version doesn't know,
+    /// only published if enabled with [`BotBuilder::validate_sprites`](super::bot::BotBuilder::validate_sprites)
+    UnknownSprite {
+        /// Id of the player
+        player: u16,
+        /// Unknown sprite id
+        sprite: u16,
+    },
+    /// A callback ran longer than [`Bot::callback_timeout`] allows and was stopped
+    CallbackTimeout {
+        /// Type name of the event the callback was called with
+        event: &'static str,
+        /// Timeout it ran into
+        timeout: Duration,
+    },
+    /// Connection is over
+    Disconnect(DisconnectReason),
+}
+
+/// Sender half of the event channel, written to by the receiver thread.
+pub(crate) type EventSender = broadcast::Sender<Event>;
+
+/// Requests handled by the actor task, sent by [`BotHandle`].
+#[derive(Debug)]
+pub(crate) enum Command {
+    /// Replace the whole pose of the bot
+    SetPose(PlayerPose),
+    /// Move the bot
+    SetPosition(Position),
+    /// Turn the bot
+    SetDirection(f32),
+    /// Remove received chat messages and send them back
+    TakeChat(oneshot::Sender<Vec<Message>>),
+}
+
+/// Sender half of the actor's command channel.
+pub(crate) type CommandSender = mpsc::Sender<Command>;
+
+/// Message-passing handle to a running bot, created by [`Bot::spawn`].
+///
+/// A single actor task owns access to [`BotData`] on behalf of every handle: writes are sent
+/// to it as commands, and reads go to the latest [`Snapshot`] it published, so user code never
+/// holds a lock the connection threads wait on. Handles are cheap to clone.
+///
+/// Up to [`COMMAND_CAPACITY`] commands are queued, setters fail with [`BotError::Other`]
+/// rather than wait once the actor falls that far behind.
+///
+/// # Examples
+///
+/// ## Walking in a straight line and reporting who is around:
+/// ```no_run
+/// use asciicker_rs::y6::prelude::*;
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let bot = BotBuilder::new("walker").replace_invalid_utf8(true).build();
+/// let (_threads, handle) = bot.spawn().await.unwrap();
+/// loop {
+///     let snapshot = handle.snapshot();
+///     let mut position = snapshot.player.pose.position;
+///     position.x += 1.0;
+///     handle.set_position(position).unwrap();
+///     println!("{} players around", snapshot.world.clients.len());
+///     tokio::time::sleep(Duration::from_millis(100)).await;
+/// }
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct BotHandle {
+    commands: CommandSender,
+    messages: MessageSender,
+    snapshots: watch::Receiver<Arc<Snapshot>>,
+    events: EventSender,
+    runtime: Arc<dyn Runtime>,
+    latency: SharedLatency,
+    stats: SharedPacketStats,
+    leave: Arc<watch::Sender<bool>>,
+    alive: watch::Receiver<()>,
+}
+
+impl Debug for BotHandle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BotHandle")
+            .field("commands", &self.commands)
+            .field("snapshots", &self.snapshots)
+            .field("events", &self.events)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BotHandle {
+    /// Returns the latest published [`Snapshot`], never waits for the connection threads.
+    pub fn snapshot(&self) -> Arc<Snapshot> {
+        Arc::clone(&self.snapshots.borrow())
+    }
+
+    /// Returns the round trip statistics of the lag requests answered so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let server = Server::new(8).bind("127.0.0.1:0").await.unwrap();
+    /// let bot = BotBuilder::new("pinger")
+    ///     .address(format!("ws://{}", server.address))
+    ///     .lag_interval(Some(Duration::from_millis(10)))
+    ///     .build();
+    /// let (_threads, handle) = bot.spawn().await.unwrap();
+    /// while handle.latency().samples == 0 {
+    ///     tokio::time::sleep(Duration::from_millis(10)).await;
+    /// }
+    /// let latency = handle.latency();
+    /// assert!(latency.p95 >= latency.average);
+    /// assert!(latency.average < Duration::from_secs(1));
+    /// # }
+    /// ```
+    pub fn latency(&self) -> Latency {
+        self.latency
+            .lock()
+            .expect("Latency tracker is poisoned")
+            .latency()
+    }
+
+    /// Returns the counters of the packets received so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let server = Server::new(8).bind("127.0.0.1:0").await.unwrap();
+    /// let bot = BotBuilder::new("counter")
+    ///     .address(format!("ws://{}", server.address))
+    ///     .lag_interval(Some(Duration::from_millis(10)))
+    ///     .build();
+    /// let (_threads, handle) = bot.spawn().await.unwrap();
+    /// while handle.stats().token(b'l').packets == 0 {
+    ///     tokio::time::sleep(Duration::from_millis(10)).await;
+    /// }
+    /// let stats = handle.stats();
+    /// assert_eq!(stats.token(b'l').bytes, stats.token(b'l').packets * LAG_RSP_SIZE as u64);
+    /// assert_eq!(stats.parse_errors, 0);
+    /// println!("{}", stats);
+    /// # }
+    /// ```
+    pub fn stats(&self) -> PacketStats {
+        self.stats
+            .lock()
+            .expect("Packet stats are poisoned")
+            .clone()
+    }
+
+    /// Waits until a new [`Snapshot`] is published and returns it.
+    pub async fn changed(&mut self) -> Result<Arc<Snapshot>, BotError> {
+        match self.snapshots.changed().await {
+            Ok(()) => Ok(self.snapshot()),
+            Err(_) => Err(BotError::ChannelClosed("bot actor")),
+        }
+    }
+
+    /// Returns a stream of every [`Event`] from now on, an alternative to callbacks.
+    ///
+    /// The stream ends after [`Event::Disconnect`]. A subscriber that falls more than a few hundred
+    /// events behind skips the oldest ones instead of slowing the bot down.
+    ///
+    /// # Examples
+    ///
+    /// ## Printing the chat until the bot disconnects:
+    /// ```no_run
+    /// use asciicker_rs::y6::prelude::*;
+    /// use futures_util::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let bot = BotBuilder::new("listener").replace_invalid_utf8(true).build();
+    /// let (_threads, handle) = bot.spawn().await.unwrap();
+    /// let mut events = handle.events();
+    /// while let Some(event) = events.next().await {
+    ///     match event {
+    ///         Event::Talk(talk) => println!("{}: {:?}", talk.id, talk.str),
+    ///         Event::Disconnect(reason) => println!("Disconnected: {:?}", reason),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn events(&self) -> impl Stream<Item = Event> + Send + Unpin {
+        subscribe(&self.events)
+    }
+
+    /// Waits for the first [`Event`] `matches` returns [`Some`] for and returns that value.
+    ///
+    /// Subscribes to [`BotHandle::events`] and starts the `timeout` right away, not once the
+    /// future is polled, so a question can be asked after creating the future without missing
+    /// a quick answer. Fails after `timeout` or once the bot disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ## Asking a question and waiting for the answer:
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let server = Server::new(8).bind("127.0.0.1:0").await.unwrap();
+    /// let address = format!("ws://{}", server.address);
+    /// let (_, asker) = BotBuilder::new("asker").address(&address).build().spawn().await.unwrap();
+    ///
+    /// let joined = asker.wait_for_join("oracle", Duration::from_secs(5));
+    /// let (_, oracle) = BotBuilder::new("oracle").address(&address).build().spawn().await.unwrap();
+    /// let oracle_id = joined.await.unwrap().id;
+    ///
+    /// let answer = asker.wait_for_message(move |talk| talk.id == oracle_id, Duration::from_secs(5));
+    /// asker.say("What is the answer?").unwrap();
+    /// oracle.say("42").unwrap();
+    /// assert_eq!(answer.await.unwrap().str.as_bytes(), b"42");
+    ///
+    /// // Nobody else is going to talk
+    /// let silence = asker.wait_for(
+    ///     |event| matches!(event, Event::Talk(_)).then_some(()),
+    ///     Duration::from_millis(50),
+    /// );
+    /// assert!(silence.await.is_err());
+    /// # }
+    /// ```
+    pub fn wait_for<T, F>(
+        &self,
+        mut matches: F,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<T, BotError>> + Send + 'static
+    where
+        T: Send + 'static,
+        F: FnMut(&Event) -> Option<T> + Send + 'static,
+    {
+        let mut events = self.events();
+        // Runtimes may start a sleep only once it is polled, so only the deadline is fixed now
+        let deadline = Instant::now() + timeout;
+        let runtime = Arc::clone(&self.runtime);
+        async move {
+            let deadline = runtime.sleep(deadline.saturating_duration_since(Instant::now()));
+            let found = Box::pin(async move {
+                while let Some(event) = events.next().await {
+                    if let Some(found) = matches(&event) {
+                        return Ok(found);
+                    }
+                }
+                Err(BotError::ChannelClosed("receiver"))
+            });
+            match select(found, deadline).await {
+                Either::Left((found, _)) => found,
+                Either::Right(_) => Err(BotError::Other(format!(
+                    "No matching event within {:?}",
+                    timeout
+                ))),
+            }
+        }
+    }
+
+    /// Waits for a chat message `predicate` returns `true` for, see [`BotHandle::wait_for`].
+    pub fn wait_for_message<F>(
+        &self,
+        mut predicate: F,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<TalkBroadcast, BotError>> + Send + 'static
+    where
+        F: FnMut(&TalkBroadcast) -> bool + Send + 'static,
+    {
+        self.wait_for(
+            move |event| match event {
+                Event::Talk(talk) if predicate(talk) => Some(talk.clone()),
+                _ => None,
+            },
+            timeout,
+        )
+    }
+
+    /// Waits for a player called `name` to join, see [`BotHandle::wait_for`].
+    ///
+    /// Players already on the server don't count, look for them in [`BotHandle::snapshot`].
+    pub fn wait_for_join(
+        &self,
+        name: &str,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<JoinBroadcast, BotError>> + Send + 'static {
+        let name = name.as_bytes().to_vec();
+        self.wait_for(
+            move |event| match event {
+                Event::Join(join) if join.name.as_bytes() == name => Some(join.clone()),
+                _ => None,
+            },
+            timeout,
+        )
+    }
+
+    /// Returns `false` once the bot disconnected, right before [`Event::Disconnect`] is published.
+    pub fn is_connected(&self) -> bool {
+        self.alive.has_changed().is_ok()
+    }
+
+    /// Replaces the whole pose of the bot.
+    pub fn set_pose(&self, pose: PlayerPose) -> Result<(), BotError> {
+        self.send(Command::SetPose(pose))
+    }
+
+    /// Moves the bot.
+    pub fn set_position<P: Into<Position>>(&self, position: P) -> Result<(), BotError> {
+        self.send(Command::SetPosition(position.into()))
+    }
+
+    /// Turns the bot.
+    pub fn set_direction(&self, direction: f32) -> Result<(), BotError> {
+        self.send(Command::SetDirection(direction))
+    }
+
+    /// Walks the bot to `target` at `speed` units per second, completes once it got there.
+    ///
+    /// The pose is updated every [`STEP_INTERVAL`], facing the direction of travel and cycling
+    /// the walk animation, see [`step_towards`]. Moving the bot any other way meanwhile is
+    /// overwritten on the next step. Returns [`BotError::ChannelClosed`] if the bot disconnects
+    /// before it got there.
+    ///
+    /// # Examples
+    ///
+    /// ## Walking back and forth:
+    /// ```no_run
+    /// use asciicker_rs::y6::prelude::*;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let bot = BotBuilder::new("walker").replace_invalid_utf8(true).build();
+    /// let (_threads, handle) = bot.spawn().await.unwrap();
+    /// loop {
+    ///     handle.walk_to([10.0, 0.0, 300.0], 5.0).await.unwrap();
+    ///     handle.walk_to([-10.0, 0.0, 300.0], 5.0).await.unwrap();
+    /// }
+    /// # }
+    /// ```
+    pub async fn walk_to<P: Into<Position>>(&self, target: P, speed: f32) -> Result<(), BotError> {
+        let mut pose = self.snapshot().player.pose.clone();
+        self.walk(&mut pose, target.into(), speed).await
+    }
+
+    /// Walks the bot along `path`, completes at the end of a [`PathMode::Once`] path.
+    ///
+    /// Works like [`BotHandle::walk_to`] for every visited [`Waypoint`], standing still for its pause.
+    /// Returns [`BotError::ChannelClosed`] once the bot disconnects, so a looping path doesn't
+    /// outlive the connection.
+    ///
+    /// [`PathMode::Once`]: super::bot::movement::PathMode::Once
+    /// [`Waypoint`]: super::bot::movement::Waypoint
+    ///
+    /// # Examples
+    ///
+    /// ## Circling around the spawn:
+    /// ```no_run
+    /// use asciicker_rs::y6::bot::movement::*;
+    /// use asciicker_rs::y6::prelude::*;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let bot = BotBuilder::new("guard").replace_invalid_utf8(true).build();
+    /// let (_threads, handle) = bot.spawn().await.unwrap();
+    /// let path = (0..16)
+    ///     .map(|i| i as f32 * std::f32::consts::PI / 8.0)
+    ///     .fold(Path::new(PathMode::Loop), |path, angle| {
+    ///         path.waypoint([angle.cos() * 4.0, angle.sin() * 4.0, 300.0])
+    ///     });
+    /// handle.patrol(&path).await.unwrap();
+    /// # }
+    /// ```
+    pub async fn patrol(&self, path: &Path) -> Result<(), BotError> {
+        let mut pose = self.snapshot().player.pose.clone();
+        for waypoint in path.visits() {
+            self.walk(&mut pose, waypoint.position, path.speed).await?;
+            if !waypoint.pause.is_zero() {
+                self.runtime.sleep(waypoint.pause).await;
+                self.check_connected()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Keeps the bot within `distance` of the player with `player_id`, walking at [`WALK_SPEED`].
+    ///
+    /// Completes once the player leaves the server or isn't there to begin with, returns
+    /// [`BotError::ChannelClosed`] once the bot itself disconnects.
+    /// Like [`BotHandle::walk_to`], it overwrites the pose every [`STEP_INTERVAL`] while it runs.
+    ///
+    /// # Examples
+    ///
+    /// ## Following whoever says "follow me":
+    /// ```no_run
+    /// use asciicker_rs::y6::prelude::*;
+    /// use futures_util::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let bot = BotBuilder::new("pet").replace_invalid_utf8(true).build();
+    /// let (_threads, handle) = bot.spawn().await.unwrap();
+    /// let mut events = handle.events();
+    /// while let Some(event) = events.next().await {
+    ///     if let Event::Talk(talk) = event {
+    ///         if talk.str.as_bytes() == b"follow me" {
+    ///             handle.follow(talk.id, 2.0).await.unwrap();
+    ///         }
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// ## Stopping when the bot leaves:
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let server = Server::new(8).bind("127.0.0.1:0").await.unwrap();
+    /// let address = format!("ws://{}", server.address);
+    /// let (_, leader) = BotBuilder::new("leader").address(&address).build().spawn().await.unwrap();
+    /// let (_, pet) = BotBuilder::new("pet").address(&address).build().spawn().await.unwrap();
+    /// let id = leader.snapshot().player.id;
+    /// while pet.snapshot().world.client(id).is_none() {
+    ///     tokio::time::sleep(Duration::from_millis(10)).await;
+    /// }
+    ///
+    /// let following = tokio::spawn({
+    ///     let pet = pet.clone();
+    ///     async move { pet.follow(id, 2.0).await }
+    /// });
+    /// pet.leave().await;
+    /// assert!(!pet.is_connected());
+    /// let result = following.await.unwrap();
+    /// assert!(matches!(result, Err(BotError::ChannelClosed(_))));
+    /// # }
+    /// ```
+    pub async fn follow(&self, player_id: u16, distance: f32) -> Result<(), BotError> {
+        let mut pose = self.snapshot().player.pose.clone();
+        let mut ticks = Ticker::new(Arc::clone(&self.runtime), STEP_INTERVAL);
+        let mut last = Instant::now();
+        loop {
+            ticks.tick().await;
+            self.check_connected()?;
+            let max_step = WALK_SPEED * last.elapsed().as_secs_f32();
+            last = Instant::now();
+            let target = match self.snapshot().world.client(player_id) {
+                Some(target) => target.pose.position,
+                None => return Ok(()),
+            };
+            if step_within(&mut pose, target, distance, max_step) {
+                self.send_waiting(Command::SetPose(pose.clone())).await?;
+            }
+        }
+    }
+
+    /// Queues a chat message to be sent, fails if [`MESSAGE_CAPACITY`] messages are queued already.
+    ///
+    /// [`MESSAGE_CAPACITY`]: super::bot::MESSAGE_CAPACITY
+    pub fn say<S: Into<String>>(&self, text: S) -> Result<(), BotError> {
+        self.messages.try_send(text.into()).map_err(|e| match e {
+            TrySendError::Full(_) => BotError::Other("Message queue is full".to_string()),
+            TrySendError::Closed(_) => BotError::ChannelClosed("sender"),
+        })
+    }
+
+    /// Removes and returns chat messages received so far.
+    pub async fn take_chat(&self) -> Result<Vec<Message>, BotError> {
+        let (tx, rx) = oneshot::channel();
+        self.send_waiting(Command::TakeChat(tx)).await?;
+        rx.await.map_err(|_| BotError::ChannelClosed("bot actor"))
+    }
+
+    /// Leaves the server cleanly, completes once the connection threads stopped.
+    ///
+    /// Stops sending poses, sends a websocket close frame and waits for the server to close
+    /// the connection, by then it told everyone else the bot left. Without it players see
+    /// the bot time out once its process ends. The disconnect reason is
+    /// [`DisconnectReason::Left`], returns immediately if the bot is already disconnected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use futures_util::StreamExt;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let server = Server::new(8).bind("127.0.0.1:0").await.unwrap();
+    /// let address = format!("ws://{}", server.address);
+    /// let (_, watcher) = BotBuilder::new("watcher").address(&address).build().spawn().await.unwrap();
+    /// let (_, leaver) = BotBuilder::new("leaver").address(&address).build().spawn().await.unwrap();
+    /// let id = leaver.snapshot().player.id;
+    ///
+    /// let mut events = watcher.events();
+    /// let mut own_events = leaver.events();
+    /// leaver.leave().await;
+    /// let last = own_events.collect::<Vec<_>>().await.pop();
+    /// assert!(matches!(last, Some(Event::Disconnect(DisconnectReason::Left))));
+    /// while let Some(event) = events.next().await {
+    ///     if let Event::Exit(exit) = event {
+    ///         assert_eq!(exit.id, id);
+    ///         break;
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub async fn leave(&self) {
+        self.leave.send_replace(true);
+        // Both connection threads hold a receiver until they stop
+        self.leave.closed().await
+    }
+
+    /// Walks from `pose` to `target`, leaving `pose` where the bot ended up.
+    async fn walk(&self, pose: &mut PlayerPose, target: Position, speed: f32) -> BotResult {
+        let mut ticks = Ticker::new(Arc::clone(&self.runtime), STEP_INTERVAL);
+        let mut last = Instant::now();
+        loop {
+            ticks.tick().await;
+            self.check_connected()?;
+            let max_step = speed * last.elapsed().as_secs_f32();
+            last = Instant::now();
+            let arrived = step_towards(pose, target, max_step);
+            self.send_waiting(Command::SetPose(pose.clone())).await?;
+            if arrived {
+                return Ok(());
+            }
+        }
+    }
+
+    fn check_connected(&self) -> Result<(), BotError> {
+        match self.is_connected() {
+            true => Ok(()),
+            false => Err(BotError::ChannelClosed("receiver")),
+        }
+    }
+
+    fn send(&self, command: Command) -> Result<(), BotError> {
+        self.commands.try_send(command).map_err(|e| match e {
+            TrySendError::Full(_) => BotError::Other("Command queue is full".to_string()),
+            TrySendError::Closed(_) => BotError::ChannelClosed("bot actor"),
+        })
+    }
+
+    /// Like [`BotHandle::send`], but waits for room in the queue instead of failing.
+    async fn send_waiting(&self, command: Command) -> Result<(), BotError> {
+        self.commands
+            .send(command)
+            .await
+            .map_err(|_| BotError::ChannelClosed("bot actor"))
+    }
+}
+
+impl GameConnection for BotHandle {
+    type Error = BotError;
+    type Chat = Message;
+    type PlayerState = Player;
+
+    fn id(&self) -> BoxFuture<'_, u16> {
+        Box::pin(async move { self.snapshot().player.id })
+    }
+
+    fn say(&self, text: String) -> BoxFuture<'_, Result<(), Self::Error>> {
+        Box::pin(async move {
+            self.messages
+                .send(text)
+                .await
+                .map_err(|_| BotError::ChannelClosed("sender"))
+        })
+    }
+
+    fn set_position(&self, position: Position) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            let _ = BotHandle::set_position(self, position);
+        })
+    }
+
+    fn set_direction(&self, direction: f32) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            let _ = BotHandle::set_direction(self, direction);
+        })
+    }
+
+    fn take_chat(&self) -> BoxFuture<'_, Vec<Self::Chat>> {
+        Box::pin(async move { BotHandle::take_chat(self).await.unwrap_or_default() })
+    }
+
+    fn players(&self) -> BoxFuture<'_, Vec<Self::PlayerState>> {
+        Box::pin(async move { self.snapshot().world.clients.values().cloned().collect() })
+    }
+}
+
+/// Subscribes to `events`, the stream ends after [`Event::Disconnect`] or once `events` is dropped.
+///
+/// Subscribers falling behind by more than [`EVENT_CAPACITY`] events skip the oldest ones.
+pub(crate) fn subscribe(events: &EventSender) -> impl Stream<Item = Event> + Send + Unpin {
+    let events = events.subscribe();
+    Box::pin(stream::unfold(Some(events), |events| async move {
+        let mut events = events?;
+        loop {
+            match events.recv().await {
+                Ok(event @ Event::Disconnect(_)) => return Some((event, None)),
+                Ok(event) => return Some((event, Some(events))),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }))
+}
+
+/// Runs `bot` and spawns the actor behind the returned [`BotHandle`], see [`Bot::spawn`].
+pub(crate) async fn spawn(bot: Bot) -> Result<((Receiver, Sender), BotHandle), BotError> {
+    let runtime = bot.executor()?;
+    let latency = bot.latency();
+    let stats = bot.stats();
+    let (commands, command_rx) = mpsc::channel(COMMAND_CAPACITY);
+    let refresh = Arc::new(Notify::new());
+    let (events, _) = broadcast::channel(EVENT_CAPACITY);
+    let (leave, leave_rx) = watch::channel(false);
+    let (alive_tx, alive) = watch::channel(());
+    let (threads, data) = bot
+        .start(
+            Some(Arc::clone(&refresh)),
+            Some(events.clone()),
+            Some(leave_rx),
+            Some(alive_tx),
+        )
+        .await?;
+    let snapshot = Snapshot {
+        player: Arc::new(data.0.lock().await.clone()),
+        world: Arc::new(data.1.read().await.clone()),
+    };
+    let messages = Arc::clone(&data.2);
+    let (snapshot_tx, snapshots) = watch::channel(Arc::new(snapshot));
+    runtime.spawn(Box::pin(actor(data, command_rx, refresh, snapshot_tx)));
+    Ok((
+        threads,
+        BotHandle {
+            commands,
+            messages,
+            snapshots,
+            events,
+            runtime,
+            latency,
+            stats,
+            leave: Arc::new(leave),
+            alive,
+        },
+    ))
+}
+
+/// Owns access to [`BotData`] for every [`BotHandle`], stops once all handles are gone.
+///
+/// Handles every queued command and every world patch the receiver signalled to `refresh`
+/// before publishing, so a burst costs a single snapshot. Only the parts that changed are cloned.
+async fn actor(
+    data: BotData,
+    mut commands: mpsc::Receiver<Command>,
+    refresh: Arc<Notify>,
+    snapshots: watch::Sender<Arc<Snapshot>>,
+) {
+    let (bot, world, _, pose) = data;
+    loop {
+        let mut changes = Changes::default();
+        tokio::select! {
+            command = commands.recv() => match command {
+                Some(command) => changes |= apply(command, &bot, &world, &pose).await,
+                None => break,
+            },
+            _ = refresh.notified() => changes = Changes::ALL,
+        }
+        while let Ok(command) = commands.try_recv() {
+            changes |= apply(command, &bot, &world, &pose).await;
+        }
+        let previous = Arc::clone(&snapshots.borrow());
+        let snapshot = Snapshot {
+            player: match changes.player {
+                true => Arc::new(bot.lock().await.clone()),
+                false => Arc::clone(&previous.player),
+            },
+            world: match changes.world {
+                true => Arc::new(world.read().await.clone()),
+                false => Arc::clone(&previous.world),
+            },
+        };
+        if snapshots.send(Arc::new(snapshot)).is_err() {
+            // Every handle was dropped
+            break;
+        }
+    }
+}
+
+/// Parts of the [`Snapshot`] a command changed.
+#[derive(Debug, Default, Copy, Clone)]
+struct Changes {
+    player: bool,
+    world: bool,
+}
+
+impl Changes {
+    /// Receiver patches may change anything
+    const ALL: Self = Self {
+        player: true,
+        world: true,
+    };
+}
+
+impl std::ops::BitOrAssign for Changes {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.player |= rhs.player;
+        self.world |= rhs.world;
+    }
+}
+
+/// Applies `command` to [`BotData`], returns what it changed.
+async fn apply(
+    command: Command,
+    bot: &Mutex<Player>,
+    world: &RwLock<World>,
+    pose: &PoseSender,
+) -> Changes {
+    let player = Changes {
+        player: true,
+        world: false,
+    };
+    match command {
+        Command::SetPose(new) => {
+            modify_pose(pose, |pose| *pose = new.clone());
+            bot.lock().await.pose = new;
+            player
+        }
+        Command::SetPosition(position) => {
+            modify_pose(pose, |pose| pose.position = position);
+            bot.lock().await.pose.position = position;
+            player
+        }
+        Command::SetDirection(direction) => {
+            modify_pose(pose, |pose| pose.direction = direction);
+            bot.lock().await.pose.direction = direction;
+            player
+        }
+        Command::TakeChat(reply) => {
+            let _ = reply.send(world.write().await.messages.drain().collect());
+            Changes {
+                player: false,
+                world: true,
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::common::Instant;
+
+/// Flood heuristic of an [`IgnoreList`], set with [`BotBuilder::flood`].
+///
+/// A player who says more than `messages` messages within `window` is ignored
+/// for `ignore_for`, or until [`IgnoreList::unignore_id`] if it is [`None`].
+///
+/// [`BotBuilder::flood`]: super::bot::BotBuilder::flood
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FloodPolicy {
+    /// Messages allowed within the window
+    pub messages: usize,
+    /// Length of the window
+    pub window: Duration,
+    /// How long an offender is ignored, [`None`] is until unignored
+    pub ignore_for: Option<Duration>,
+}
+
+impl FloodPolicy {
+    /// Constructs a new [`FloodPolicy`] ignoring offenders until they are unignored.
+    pub fn new(messages: usize, window: Duration) -> Self {
+        Self {
+            messages,
+            window,
+            ignore_for: None,
+        }
+    }
+
+    /// Sets how long an offender is ignored.
+    pub fn ignore_for(mut self, ignore_for: Duration) -> Self {
+        self.ignore_for = Some(ignore_for);
+        self
+    }
+}
+
+/// Players whose talk broadcasts are dropped before the talk callbacks run.
+///
+/// Ignored messages don't reach the callbacks, [`World::messages`] or
+/// [`BotHandle::events`], as if they were never said.
+///
+/// Ids are reused by the server once a player leaves, so everything known about an id,
+/// including [`IgnoreList::ignore_id`], is forgotten when its player exits.
+/// Nickname patterns are kept, they are matched case-insensitively and `*` matches
+/// any number of characters.
+///
+/// Shared with the running bot, get it with [`Bot::ignore_list`].
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let bot = BotBuilder::new("listener")
+///     .flood(FloodPolicy::new(2, Duration::from_secs(10)))
+///     .build();
+/// bot.ignore_list().lock().unwrap().ignore_name("spam*");
+///
+/// let world = Scenario::new(&bot)
+///     .join(2, "spammer")
+///     .join(3, "chatty")
+///     .talk(2, "buy gold")
+///     .talk(3, "one")
+///     .talk(3, "two")
+///     .talk(3, "three")
+///     .run()
+///     .await
+///     .unwrap();
+/// let said: Vec<&str> = world.messages.iter().map(|m| m.content.as_str()).collect();
+/// assert_eq!(said, ["one", "two"]);
+/// assert!(bot.ignore_list().lock().unwrap().is_ignored(3, Some("chatty")));
+/// # }
+/// ```
+///
+/// [`World::messages`]: super::bot::World::messages
+/// [`BotHandle::events`]: super::handle::BotHandle::events
+/// [`Bot::ignore_list`]: super::bot::Bot::ignore_list
+#[derive(Debug, Default, Clone)]
+pub struct IgnoreList {
+    ids: HashSet<u16>,
+    patterns: Vec<String>,
+    flood: Option<FloodPolicy>,
+    recent: HashMap<u16, VecDeque<Instant>>,
+    flooded: HashMap<u16, Option<Instant>>,
+}
+
+/// [`IgnoreList`] shared between the bot and its owner.
+pub type SharedIgnoreList = Arc<Mutex<IgnoreList>>;
+
+impl IgnoreList {
+    /// Constructs a new empty [`IgnoreList`] without flood suppression.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the [`FloodPolicy`], [`None`] disables flood suppression.
+    pub fn flood(&mut self, flood: Option<FloodPolicy>) {
+        self.flood = flood;
+    }
+
+    /// Ignores the player with `id` until they leave.
+    pub fn ignore_id(&mut self, id: u16) {
+        self.ids.insert(id);
+    }
+
+    /// Stops ignoring the player with `id`, also if they were ignored for flooding.
+    ///
+    /// Returns `true` if they were ignored by id or for flooding.
+    pub fn unignore_id(&mut self, id: u16) -> bool {
+        self.recent.remove(&id);
+        let flooded = self.flooded.remove(&id).is_some();
+        self.ids.remove(&id) || flooded
+    }
+
+    /// Ignores every player whose nickname matches `pattern`.
+    pub fn ignore_name<S: AsRef<str>>(&mut self, pattern: S) {
+        let pattern = pattern.as_ref().to_lowercase();
+        if !self.patterns.contains(&pattern) {
+            self.patterns.push(pattern);
+        }
+    }
+
+    /// Removes the nickname `pattern`, returns `true` if it was there.
+    pub fn unignore_name<S: AsRef<str>>(&mut self, pattern: S) -> bool {
+        let pattern = pattern.as_ref().to_lowercase();
+        let len = self.patterns.len();
+        self.patterns.retain(|p| *p != pattern);
+        self.patterns.len() != len
+    }
+
+    /// Returns `true` if the player is ignored by id, nickname or for flooding.
+    pub fn is_ignored(&self, id: u16, nickname: Option<&str>) -> bool {
+        self.ids.contains(&id)
+            || nickname.is_some_and(|nickname| {
+                let nickname = nickname.to_lowercase();
+                self.patterns
+                    .iter()
+                    .any(|pattern| wildcard_match(pattern, &nickname))
+            })
+            || self
+                .flooded
+                .get(&id)
+                .is_some_and(|until| until.is_none_or(|until| Instant::now() < until))
+    }
+
+    /// Records a message of the player said at `now`, returns `false` if it should be dropped.
+    pub fn allow(&mut self, id: u16, nickname: Option<&str>, now: Instant) -> bool {
+        if let Some(Some(until)) = self.flooded.get(&id) {
+            if now >= *until {
+                self.flooded.remove(&id);
+            }
+        }
+        if self.is_ignored(id, nickname) {
+            return false;
+        }
+        let Some(flood) = self.flood else {
+            return true;
+        };
+        let recent = self.recent.entry(id).or_default();
+        while recent
+            .front()
+            .is_some_and(|said| now.duration_since(*said) >= flood.window)
+        {
+            recent.pop_front();
+        }
+        recent.push_back(now);
+        if recent.len() > flood.messages {
+            self.recent.remove(&id);
+            self.flooded
+                .insert(id, flood.ignore_for.map(|ignore_for| now + ignore_for));
+            return false;
+        }
+        true
+    }
+
+    /// Forgets everything about `id`, called when its player leaves.
+    pub fn forget(&mut self, id: u16) {
+        self.ids.remove(&id);
+        self.recent.remove(&id);
+        self.flooded.remove(&id);
+    }
+}
+
+/// Matches `text` against `pattern` where `*` is any number of characters.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
@@ -0,0 +1,110 @@
+use super::packets::LagStamp;
+use crate::common::Instant;
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Amount of round trips the [`Latency`] statistics are computed from.
+pub const LATENCY_WINDOW: usize = 32;
+/// Lag requests waiting for a response, the oldest one counts as lost once there are more.
+const MAX_PENDING: usize = 16;
+
+/// Round trip statistics of the lag requests the bot sends, see [`Bot::lag_interval`].
+///
+/// [`Bot::lag_interval`]: super::bot::Bot::lag_interval
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Latency {
+    /// Mean round trip time
+    pub average: Duration,
+    /// 95th percentile of the round trip time
+    pub p95: Duration,
+    /// Mean difference between consecutive round trip times
+    pub jitter: Duration,
+    /// Round trips the statistics are based on, at most [`LATENCY_WINDOW`], `0` before the first response
+    pub samples: usize,
+    /// Lag requests that never got a response
+    pub lost: u64,
+}
+
+/// Shared handle to a [`LatencyTracker`], updated by the running bot.
+pub type SharedLatency = Arc<Mutex<LatencyTracker>>;
+
+/// Matches lag responses to the lag requests they answer and keeps the round trip times.
+///
+/// Get the one of a bot with [`Bot::latency`](super::bot::Bot::latency).
+#[derive(Debug, Default)]
+pub struct LatencyTracker {
+    next: u32,
+    pending: VecDeque<(LagStamp, Instant)>,
+    samples: VecDeque<Duration>,
+    lost: u64,
+}
+
+impl LatencyTracker {
+    /// Constructs a new empty [`LatencyTracker`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the stamp for the next lag request and remembers it was sent at `now`.
+    pub fn ping(&mut self, now: Instant) -> LagStamp {
+        let [_, high, middle, low] = self.next.to_be_bytes();
+        // Stamps are 24 bits, they wrap around long after any response could arrive
+        self.next = (self.next + 1) & 0xFF_FFFF;
+        let stamp = [high, middle, low];
+        if self.pending.len() == MAX_PENDING {
+            self.pending.pop_front();
+            self.lost += 1;
+        }
+        self.pending.push_back((stamp, now));
+        stamp
+    }
+
+    /// Records the response with `stamp` that arrived at `now`, returns its round trip time.
+    ///
+    /// Returns [`None`] for stamps the tracker didn't hand out, like responses to requests
+    /// sent by callbacks. Requests older than the answered one are counted as lost,
+    /// since the server answers in order.
+    pub fn pong(&mut self, stamp: LagStamp, now: Instant) -> Option<Duration> {
+        let index = self.pending.iter().position(|(sent, _)| *sent == stamp)?;
+        self.lost += index as u64;
+        self.pending.drain(..index);
+        let (_, sent) = self.pending.pop_front()?;
+        let rtt = now.saturating_duration_since(sent);
+        if self.samples.len() == LATENCY_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(rtt);
+        Some(rtt)
+    }
+
+    /// Computes the [`Latency`] statistics of the current window.
+    pub fn latency(&self) -> Latency {
+        let samples = self.samples.len();
+        if samples == 0 {
+            return Latency {
+                lost: self.lost,
+                ..Default::default()
+            };
+        }
+        let average = self.samples.iter().sum::<Duration>() / samples as u32;
+        let mut sorted: Vec<_> = self.samples.iter().copied().collect();
+        sorted.sort();
+        let p95 = sorted[(samples * 95).div_ceil(100) - 1];
+        let jitter = match samples {
+            1 => Duration::ZERO,
+            _ => {
+                let differences = self.samples.iter().zip(self.samples.iter().skip(1));
+                differences.map(|(a, b)| a.abs_diff(*b)).sum::<Duration>() / (samples - 1) as u32
+            }
+        };
+        Latency {
+            average,
+            p95,
+            jitter,
+            samples,
+            lost: self.lost,
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use super::bot::{
+    into_callback, BotBuilder, BotData, Callbacks, FutureCallbackResult, IntoControlFlow,
+    MessageSender, Player, Receiver, Sender, ServerId, World,
+};
+use super::packets::{
+    Bytes, ExitBroadcast, JoinBroadcast, LagResponse, PoseBroadcast, TalkBroadcast,
+};
+use super::utils::BotError;
+
+use std::sync::Arc;
+
+use futures_util::future::join_all;
+use tokio::sync::{Mutex, RwLock};
+
+/// Single connection established by [`ConnectionManager::run`].
+pub struct Connection {
+    /// Id of the server, same as in [`World::server_id`](super::bot::World::server_id)
+    pub server_id: ServerId,
+    /// Two main connection threads
+    pub threads: (Receiver, Sender),
+    /// Main bot data
+    pub data: BotData,
+}
+
+/// Maintains simultaneous connections to several servers with one set of callbacks.
+///
+/// Every connection is a normal [`Bot`](super::bot::Bot) whose [`World::server_id`] is set
+/// to the id passed into [`ConnectionManager::add_server`], so callbacks can tell which server
+/// an event came from.
+///
+/// # Examples
+///
+/// ## Connecting to two servers:
+/// ```no_run
+/// use asciicker_rs::y6::prelude::*;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut manager = ConnectionManager::new("bridge", true);
+/// manager.add_server("main", "ws://asciicker.com/ws/y6/");
+/// manager.add_server("private", "ws://localhost:8080/ws/y6/");
+///
+/// let connections = manager.run().await.unwrap();
+/// for connection in connections {
+///     println!("Connected to {}", connection.server_id);
+/// }
+/// # }
+/// ```
+///
+/// [`World::server_id`]: super::bot::World::server_id
+pub struct ConnectionManager {
+    nickname: String,
+    replace_invalid_utf8: bool,
+    servers: Vec<(ServerId, String)>,
+    callbacks: Callbacks,
+}
+
+impl ConnectionManager {
+    /// Constructs a new [`ConnectionManager`] instance without any servers.
+    pub fn new<S: Into<String>>(nickname: S, replace_invalid_utf8: bool) -> Self {
+        Self {
+            nickname: nickname.into(),
+            replace_invalid_utf8,
+            servers: vec![],
+            callbacks: Default::default(),
+        }
+    }
+
+    /// Adds a server to connect to on [`ConnectionManager::run`].
+    pub fn add_server<S: Into<String>>(&mut self, server_id: S, address: S) {
+        self.servers.push((server_id.into(), address.into()));
+    }
+
+    /// Adds a [`JoinCallback`](super::bot::JoinCallback) to the end of the chain,
+    /// see [`Bot::on_join`](super::bot::Bot::on_join).
+    pub fn on_join<F, R>(&mut self, callback: F)
+    where
+        F: Fn(
+                JoinBroadcast,
+                Arc<Mutex<Player>>,
+                Arc<RwLock<World>>,
+                MessageSender,
+            ) -> FutureCallbackResult<R>
+            + Send
+            + Sync
+            + 'static,
+        R: IntoControlFlow + 'static,
+    {
+        self.callbacks.0.push(into_callback(callback));
+    }
+
+    /// Adds a [`ExitCallback`](super::bot::ExitCallback) to the end of the chain,
+    /// see [`Bot::on_exit`](super::bot::Bot::on_exit).
+    pub fn on_exit<F, R>(&mut self, callback: F)
+    where
+        F: Fn(
+                ExitBroadcast,
+                Arc<Mutex<Player>>,
+                Arc<RwLock<World>>,
+                MessageSender,
+            ) -> FutureCallbackResult<R>
+            + Send
+            + Sync
+            + 'static,
+        R: IntoControlFlow + 'static,
+    {
+        self.callbacks.1.push(into_callback(callback));
+    }
+
+    /// Adds a [`PoseCallback`](super::bot::PoseCallback) to the end of the chain,
+    /// see [`Bot::on_pose`](super::bot::Bot::on_pose).
+    pub fn on_pose<F, R>(&mut self, callback: F)
+    where
+        F: Fn(
+                PoseBroadcast,
+                Arc<Mutex<Player>>,
+                Arc<RwLock<World>>,
+                MessageSender,
+            ) -> FutureCallbackResult<R>
+            + Send
+            + Sync
+            + 'static,
+        R: IntoControlFlow + 'static,
+    {
+        self.callbacks.2.push(into_callback(callback));
+    }
+
+    /// Adds a [`TalkCallback`](super::bot::TalkCallback) to the end of the chain,
+    /// see [`Bot::on_talk`](super::bot::Bot::on_talk).
+    pub fn on_talk<F, R>(&mut self, callback: F)
+    where
+        F: Fn(
+                TalkBroadcast,
+                Arc<Mutex<Player>>,
+                Arc<RwLock<World>>,
+                MessageSender,
+            ) -> FutureCallbackResult<R>
+            + Send
+            + Sync
+            + 'static,
+        R: IntoControlFlow + 'static,
+    {
+        self.callbacks.3.push(into_callback(callback));
+    }
+
+    /// Adds a [`LagCallback`](super::bot::LagCallback) to the end of the chain,
+    /// see [`Bot::on_lag`](super::bot::Bot::on_lag).
+    pub fn on_lag<F, R>(&mut self, callback: F)
+    where
+        F: Fn(
+                LagResponse,
+                Arc<Mutex<Player>>,
+                Arc<RwLock<World>>,
+                MessageSender,
+            ) -> FutureCallbackResult<R>
+            + Send
+            + Sync
+            + 'static,
+        R: IntoControlFlow + 'static,
+    {
+        self.callbacks.4.push(into_callback(callback));
+    }
+
+    /// Adds an [`UnknownPacketCallback`](super::bot::UnknownPacketCallback) to the end of the chain,
+    /// see [`Bot::on_unknown_packet`](super::bot::Bot::on_unknown_packet).
+    pub fn on_unknown_packet<F, R>(&mut self, callback: F)
+    where
+        F: Fn(
+                Bytes,
+                Arc<Mutex<Player>>,
+                Arc<RwLock<World>>,
+                MessageSender,
+            ) -> FutureCallbackResult<R>
+            + Send
+            + Sync
+            + 'static,
+        R: IntoControlFlow + 'static,
+    {
+        self.callbacks.5.push(into_callback(callback));
+    }
+
+    /// Connects to all of the servers at once.
+    ///
+    /// Returns a [`Connection`] for every server in the order they were added.
+    /// If any of the connections fails, threads of the successful ones are aborted and the error is returned.
+    pub async fn run(self) -> Result<Vec<Connection>, BotError> {
+        let mut server_ids = vec![];
+        let mut bots = vec![];
+        for (server_id, address) in self.servers {
+            let mut bot = BotBuilder::new(self.nickname.clone())
+                .address(address)
+                .replace_invalid_utf8(self.replace_invalid_utf8)
+                .server_id(server_id.clone())
+                .build();
+            bot.set_callbacks(self.callbacks.clone());
+            server_ids.push(server_id);
+            bots.push(bot.run());
+        }
+
+        let mut connections = vec![];
+        let mut error = None;
+        for (server_id, result) in server_ids.into_iter().zip(join_all(bots).await) {
+            match result {
+                Ok((threads, data)) => connections.push(Connection {
+                    server_id,
+                    threads,
+                    data,
+                }),
+                Err(e) => error = Some(e),
+            }
+        }
+        match error {
+            Some(e) => {
+                for connection in connections {
+                    connection.threads.0.thread.abort();
+                    connection.threads.1.thread.abort();
+                }
+                Err(e)
+            }
+            None => Ok(connections),
+        }
+    }
+}
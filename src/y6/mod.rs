@@ -2,6 +2,11 @@
 //!
 //! Y6 version commit hash: 80708c9ca5f0ea8539653bb632082ce38b103903
 
+/// # Blocking module
+/// Blocking module provides a synchronous [`Client`](blocking::Client) built directly on `tungstenite`,
+/// for small scripts that don't want an async runtime.
+#[cfg(feature = "blocking")]
+pub mod blocking;
 /// # Bot module
 /// Bot module is supposed to provide highest level of abstraction and allow easy creation
 /// of bots.
@@ -10,16 +15,14 @@
 ///
 /// ## Simple chat logger:
 ///
-/// ```rust
-/// use asciicker_rs::callback;
-/// use asciicker_rs::macro_rules_attribute::apply;
+/// ```rust,no_run
 /// use asciicker_rs::y6::prelude::*;
 /// use std::sync::Arc;
-/// use tokio::sync::Mutex;
+/// use tokio::sync::{Mutex, RwLock};
 ///
 /// #[tokio::main]
 /// async fn main() {
-///     let mut bot = Bot::new("player", "ws://asciicker.com/ws/y6/", true);
+///     let mut bot = BotBuilder::new("player").replace_invalid_utf8(true).build();
 ///     bot.on_talk(talk_callback);
 ///     let (threads, _data) = match bot.run().await {
 ///         Err(e) => panic!("Failed to run the bot: {:?}", e),
@@ -28,11 +31,11 @@
 ///     println!("{:?}", threads.0.thread.await);
 /// }
 ///
-/// #[apply(callback!)]
+/// #[callback]
 /// pub async fn talk_callback(
 ///     talk_brc: TalkBroadcast,
 ///     _: Arc<Mutex<Player>>,
-///     _: Arc<Mutex<World>>,
+///     _: Arc<RwLock<World>>,
 ///     _: MessageSender,
 /// ) -> BotResult {
 ///     println!("{:?}", talk_brc.str);
@@ -43,18 +46,146 @@
 /// Look in `examples/` directory more for examples.
 #[cfg(feature = "bot")]
 pub mod bot;
+/// # Chat log module
+/// Chat log module provides [`ChatLogger`](chatlog::ChatLogger), which writes the chat to a file
+/// in JSON Lines or CSV format.
+#[cfg(feature = "bot")]
+pub mod chatlog;
+/// # Client module
+/// Client module provides [`Client`](client::Client), a bare connection below the bot
+/// for users who run their own event loop and keep their own state.
+#[cfg(all(feature = "packets", feature = "std"))]
+pub mod client;
+/// # Codec module
+/// Codec module provides [`Y6Codec`](codec::Y6Codec), a `tokio-util` codec for using packets
+/// with `Framed` over any byte stream, not just websockets.
+#[cfg(feature = "codec")]
+pub mod codec;
+/// # Commands module
+/// Commands module provides [`Commands`](commands::Commands), chat commands with typed arguments
+/// and a generated help, dispatched from talk broadcasts.
+#[cfg(feature = "bot")]
+pub mod commands;
+/// # Delivery module
+/// Delivery module decides how pose broadcasts reach the pose callback on crowded servers:
+/// awaited inline, through a bounded queue or coalesced per player.
+#[cfg(feature = "bot")]
+pub mod delivery;
+/// # FFI module
+/// FFI module exposes `extern "C"` functions parsing and encoding packets into buffers of the caller,
+/// so tools written in other languages can link against the parsers.
+#[cfg(feature = "ffi")]
+pub mod ffi;
+/// # Handle module
+/// Handle module provides [`BotHandle`](handle::BotHandle), a message-passing handle to a running bot
+/// that reads lock-free snapshots instead of locking the bot data.
+#[cfg(feature = "bot")]
+pub mod handle;
+/// # Ignore module
+/// Ignore module provides [`IgnoreList`](ignore::IgnoreList), which mutes players by id or nickname
+/// and suppresses chat floods before the talk callbacks run.
+#[cfg(feature = "bot")]
+pub mod ignore;
+/// # Latency module
+/// Latency module provides [`LatencyTracker`](latency::LatencyTracker), which matches lag responses
+/// to the lag requests the bot sends and keeps round trip statistics.
+#[cfg(feature = "bot")]
+pub mod latency;
+/// # Manager module
+/// Manager module allows running one bot on several servers at once with a shared set of callbacks.
+#[cfg(feature = "bot")]
+pub mod manager;
 /// # Packets module
 /// Packets module is supposed to provide the most basic abstractions around asciicker packets
 /// and conversion from and into bytes for them.
 ///
 /// In theory this module can be used to create not only bots, but also full clients and servers.
+///
+/// With the `testing` feature, every packet implements `arbitrary::Arbitrary` and
+/// `proptest::arbitrary::Arbitrary`, generating only packets that encode and parse back to themselves.
+///
+/// ```
+/// # #[cfg(feature = "testing")]
+/// # {
+/// use asciicker_rs::y6::packets::*;
+/// use proptest::prelude::*;
+/// use proptest::test_runner::TestRunner;
+///
+/// TestRunner::default()
+///     .run(&any::<Packet>(), |packet| {
+///         prop_assert_eq!(parse_packet(&Bytes::from(packet.clone())).unwrap(), packet);
+///         Ok(())
+///     })
+///     .unwrap();
+/// # }
+/// ```
 #[cfg(feature = "packets")]
 pub mod packets;
+/// # Playback module
+/// Playback module provides [`ReplayPlayer`](playback::ReplayPlayer), which plays a recorded session
+/// into the callbacks of a bot for testing bot logic offline, or re-enacts it against a live server.
+#[cfg(feature = "bot")]
+pub mod playback;
 #[cfg(any(feature = "bot", feature = "packets"))]
 /// # Prelude module
 /// Prelude module includes basically every other module of the library in it.
 pub mod prelude;
+/// # Protocol module
+/// Protocol module provides [`Y6`](protocol::Y6), the [`Protocol`](crate::traits::Protocol)
+/// of this version for bot logic generic over versions.
+#[cfg(feature = "packets")]
+pub mod protocol;
+/// # Proxy module
+/// Proxy module provides [`Proxy`](proxy::Proxy), which sits between real clients and a server
+/// and lets hooks observe and modify the packets passing through.
+#[cfg(feature = "server")]
+pub mod proxy;
+/// # Python module
+/// Python module provides the `asciicker_rs` Python extension, a bot calling Python functions
+/// for its events and the packet types, for bot authors scripting in Python.
+#[cfg(feature = "python")]
+pub mod python;
+/// # Recording module
+/// Recording module allows capturing the exact bytes a bot sends and comparing two captures,
+/// which is useful to check that refactors of the sender or serializers are byte-for-byte compatible.
+#[cfg(feature = "bot")]
+pub mod recording;
+/// # Replay module
+/// Replay module provides [`SessionRecorder`](replay::SessionRecorder), which captures every packet
+/// of a bot, proxy or server with timestamps into a versioned replay file.
+#[cfg(all(feature = "packets", feature = "std"))]
+pub mod replay;
+/// # Server module
+/// Server module provides [`Server`](server::Server), a Y6 server for hosting private games
+/// and testing bots end to end.
+#[cfg(feature = "server")]
+pub mod server;
+/// # Snapshot module
+/// Snapshot module provides [`WorldSnapshot`](snapshot::WorldSnapshot), a copy of a world
+/// that can be saved to a file and restored later.
+#[cfg(feature = "bot")]
+pub mod snapshot;
+/// # Sprites module
+/// Sprites module provides [`SpriteCatalog`](sprites::SpriteCatalog), the sprite ids a bot
+/// expects in poses, used to warn about unknown ones.
+#[cfg(feature = "packets")]
+pub mod sprites;
+/// # Stats module
+/// Stats module provides [`PacketStats`](stats::PacketStats), counters of the packets, bytes and
+/// parse errors the receiver of a bot has seen, by token.
+#[cfg(feature = "bot")]
+pub mod stats;
+/// # Test utilities module
+/// Test utilities module provides tools for testing bot logic without connecting to a real server:
+/// scenarios running the callbacks in-process and, with the `server` feature, a scripted mock server.
+#[cfg(feature = "bot")]
+pub mod test_util;
 #[cfg(any(feature = "bot", feature = "packets"))]
 /// # Utilities module
 /// Shouldn't be used directly, only used internally for error types and similar.
 pub mod utils;
+/// # World module
+/// World module reads the static data of asciicker worlds, like the terrain,
+/// see [`Terrain`](world::terrain::Terrain).
+#[cfg(all(feature = "packets", feature = "std"))]
+pub mod world;
@@ -1,83 +1,468 @@
 #![allow(non_camel_case_types)]
 
-use super::utils::{first_nul, PacketParseError};
+use super::utils::{first_nul, BotError, PacketParseError};
+#[cfg(feature = "std")]
+use crate::common::direction::{direction_towards, wrap_degrees};
+use crate::common::transport::Framing;
+#[cfg(feature = "testing")]
+use crate::macros::impl_proptest_arbitrary;
+use crate::macros::{
+    impl_array_encoding, impl_clean_packets, impl_copy_to_array, impl_hex_display,
+    impl_owned_encoding, impl_owned_parsing, impl_strict_parsing,
+};
+use crate::traits::{ChatEvent, PlayerStateEvent};
+
+use alloc::ffi::CString;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::{TryFrom, TryInto};
+use core::ffi::CStr;
+use core::fmt::{self, Display, Write};
+use core::mem::size_of;
+
+#[cfg(feature = "testing")]
+use arbitrary::{Arbitrary, Unstructured};
+#[cfg(feature = "testing")]
+use proptest::prelude::{any, prop_oneof, BoxedStrategy, Strategy};
+
+pub use crate::common::types::{Bytes, LagStamp, Position};
+pub use crate::common::vec3::Vec3;
+
+/// How strictly [`ParsePacket::parse`] treats the token byte.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Token byte must match [`ParsePacket::TOKEN`], used by [`TryFrom`] implementations, default
+    #[default]
+    Strict,
+    /// Token byte is kept as it is, for inspecting packets of unknown origin
+    Lenient,
+}
+
+/// Parsing of raw packets with a [`ParseMode`].
+///
+/// [`TryFrom`] implementations of raw and clean packets parse in [`ParseMode::Strict`],
+/// so bytes of one packet are never mistaken for another one of the same size.
+///
+/// # Examples
+///
+/// ## Rejecting a packet with a wrong token:
+/// ```
+/// use asciicker_rs::y6::packets::*;
+/// use asciicker_rs::y6::utils::PacketParseError;
+///
+/// let bytes = [b'l', 1, 2, 3];
+/// assert!(matches!(
+///     RawLagRequest::try_from(&bytes[..]),
+///     Err(PacketParseError::InvalidToken { expected: b'L', got: b'l' })
+/// ));
+/// let lenient = RawLagRequest::parse(&bytes, ParseMode::Lenient).unwrap();
+/// assert_eq!(lenient.token, b'l');
+/// ```
+pub trait ParsePacket: Sized {
+    /// Token byte the packet starts with.
+    const TOKEN: u8;
+    /// Parses the packet from `value`.
+    fn parse(value: &[u8], mode: ParseMode) -> Result<Self, PacketParseError>;
+}
+
+/// Encoding of packets into a caller-provided buffer.
+///
+/// Every packet also converts [`into`](std::convert::Into) [`Bytes`], which allocates a new buffer
+/// each time; this trait lets hot paths (like the pose loop of the bot) reuse one instead.
+///
+/// # Examples
+///
+/// ## Reusing one buffer for several packets:
+/// ```
+/// use asciicker_rs::y6::packets::*;
+///
+/// let mut buffer = Bytes::with_capacity(POSE_REQ_SIZE);
+/// for x in 0..3 {
+///     let mut pose = PlayerPose::default();
+///     pose.position.x = x as f32;
+///     let req = PoseRequest { player_pose: pose };
+///     buffer.clear();
+///     req.encode_into(&mut buffer);
+///     assert_eq!(buffer.len(), req.encoded_len());
+///     assert_eq!(buffer, Bytes::from(req));
+/// }
+/// ```
+pub trait EncodePacket {
+    /// Appends the encoded packet to `buf`.
+    fn encode_into(&self, buf: &mut Bytes);
+    /// Length of the encoded packet in bytes.
+    ///
+    /// Must be exact, it is used to allocate buffers that are never grown.
+    fn encoded_len(&self) -> usize;
+}
 
-use std::convert::{TryFrom, TryInto};
-use std::ffi::CString;
-use std::mem::size_of;
+/// Field of a raw packet as laid out on the wire, see [`PacketFields`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PacketField {
+    /// Name of the field, fields of the pose are prefixed with `player_pose.`
+    pub name: &'static str,
+    /// Length of the field in bytes
+    pub len: usize,
+    /// Value of the field, formatted for reading
+    pub value: String,
+}
 
-use concat_idents::concat_idents;
+impl PacketField {
+    /// Constructs a new [`PacketField`].
+    pub fn new<V: Display>(name: &'static str, len: usize, value: V) -> Self {
+        Self {
+            name,
+            len,
+            value: value.to_string(),
+        }
+    }
+}
 
-// TODO: Add tests
+/// Wire layout of raw packets, for debugging byte offsets.
+///
+/// Raw packets also implement [`Display`] with [`PacketFields::write_hex`].
+///
+/// # Examples
+///
+/// ## Finding the id in the middle of a join broadcast:
+/// ```
+/// use asciicker_rs::y6::packets::*;
+/// use std::ffi::CString;
+///
+/// let join_brc = RawJoinBroadcast::from(JoinBroadcast {
+///     player_pose: PlayerPose::default(),
+///     id: 0x0102,
+///     name: CString::new("alice").unwrap(),
+/// });
+/// let dump = join_brc.fmt_hex();
+/// let lines: Vec<_> = dump.lines().collect();
+/// assert_eq!(lines[0], "0000  6a                       token                        'j'");
+/// assert_eq!(lines[8], "0014  02 01                    id                           258");
+/// assert_eq!(lines[9], "0016  00 00                    player_pose.sprite           0");
+/// assert_eq!(lines[10], "0018  61 6c 69 63 65 00 00 00  name                         \"alice\"");
+/// assert_eq!(lines[11], "0020  00 00 00 00 00 00 00 00");
+/// assert_eq!(join_brc.to_string(), dump);
+/// ```
+pub trait PacketFields: EncodePacket {
+    /// Fields in the order they are encoded, their lengths add up to [`EncodePacket::encoded_len`].
+    fn fields(&self) -> Vec<PacketField>;
+
+    /// Writes a hexdump of the encoded packet, one field per line with its offset, bytes,
+    /// name and value. Fields longer than 8 bytes continue on the next lines.
+    fn write_hex(&self, out: &mut dyn Write) -> fmt::Result {
+        let mut bytes = Bytes::with_capacity(self.encoded_len());
+        self.encode_into(&mut bytes);
+        let fields = self.fields();
+        let width = fields
+            .iter()
+            .map(|field| field.name.len())
+            .max()
+            .unwrap_or(0);
+        let mut offset = 0;
+        for field in fields {
+            let end = (offset + field.len).min(bytes.len());
+            for (line, chunk) in bytes[offset..end].chunks(8).enumerate() {
+                let hex: Vec<String> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+                let hex = hex.join(" ");
+                write!(out, "{:04x}  ", offset + line * 8)?;
+                match line {
+                    0 => writeln!(out, "{:<23}  {:<width$}  {}", hex, field.name, field.value)?,
+                    _ => writeln!(out, "{}", hex)?,
+                }
+            }
+            offset = end;
+        }
+        Ok(())
+    }
 
-/// Type alias for position
-pub type Position = [f32; 3];
-/// Type alias for lag stamp
-pub type LagStamp = [u8; 3];
-/// Type alias for bytes
-pub type Bytes = Vec<u8>;
+    /// Returns the hexdump written by [`PacketFields::write_hex`].
+    fn fmt_hex(&self) -> String {
+        let mut dump = String::new();
+        let _ = self.write_hex(&mut dump);
+        dump
+    }
+}
 
 /// Helper, that contains data, about player's pose: position + frame + animation + sprite...
+///
+/// Like every multi-byte field in this module, it is encoded little-endian (the asciicker wire format)
+/// regardless of the host.
+///
+/// # Examples
+///
+/// ## Byte-exact encoding:
+/// ```
+/// use asciicker_rs::y6::packets::*;
+///
+/// let pose = PlayerPose {
+///     animation: Animation::Walk,
+///     frame: 2,
+///     action_or_mount: Action::Stand,
+///     position: [1.0, -2.0, 0.5].into(),
+///     direction: 90.0,
+///     sprite: 0x0102,
+/// };
+/// let bytes: Bytes = pose.clone().into();
+/// assert_eq!(
+///     bytes,
+///     [
+///         1, 2, 3,
+///         0x00, 0x00, 0x80, 0x3f, // 1.0
+///         0x00, 0x00, 0x00, 0xc0, // -2.0
+///         0x00, 0x00, 0x00, 0x3f, // 0.5
+///         0x00, 0x00, 0xb4, 0x42, // 90.0
+///         0x02, 0x01,
+///     ]
+/// );
+/// assert_eq!(PlayerPose::try_from(bytes).unwrap(), pose);
+/// ```
 #[derive(Debug, Default, Clone, PartialOrd, PartialEq)]
 pub struct PlayerPose {
     /// Animation
-    pub animation: u8,
-    /// Frame
+    pub animation: Animation,
+    /// Frame of the animation
     pub frame: u8,
     /// Action or mount (wolf)
-    pub action_or_mount: u8,
+    pub action_or_mount: Action,
     /// Position
     pub position: Position,
     /// Direction
     pub direction: f32,
-    /// Current sprite
+    /// Current sprite, see [`Sprite`](super::sprites::Sprite)
     pub sprite: u16,
 }
 
+impl PlayerPose {
+    /// Switches to the walk animation, starting from the first frame if it wasn't walking already.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::packets::*;
+    ///
+    /// let mut pose = PlayerPose::default();
+    /// pose.set_walking();
+    /// assert!(pose.is_walking());
+    /// pose.set_idle();
+    /// assert_eq!(pose.animation, Animation::Idle);
+    /// ```
+    pub fn set_walking(&mut self) {
+        if self.animation != Animation::Walk {
+            self.animation = Animation::Walk;
+            self.frame = 0;
+        }
+        self.action_or_mount = Action::None;
+    }
+
+    /// Switches to the idle animation.
+    pub fn set_idle(&mut self) {
+        self.animation = Animation::Idle;
+        self.frame = 0;
+    }
+
+    /// Returns `true` if the walk animation is playing.
+    pub fn is_walking(&self) -> bool {
+        self.animation == Animation::Walk
+    }
+
+    /// Turns to face `target`, keeps the direction if `target` is right above or below.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::packets::*;
+    ///
+    /// let mut pose = PlayerPose::default();
+    /// pose.face_towards([-3.0, 0.0, 0.0]);
+    /// assert_eq!(pose.direction, 180.0);
+    /// pose.turn(270.0);
+    /// assert_eq!(pose.direction, 90.0);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn face_towards<P: Into<Position>>(&mut self, target: P) {
+        if let Some(direction) = direction_towards(self.position, target) {
+            self.direction = direction;
+        }
+    }
+
+    /// Turns by `degrees`, wrapping the direction into `0.0..360.0`.
+    #[cfg(feature = "std")]
+    pub fn turn(&mut self, degrees: f32) {
+        self.direction = wrap_degrees(self.direction + degrees);
+    }
+}
+
+/// Animation of a [`PlayerPose`], index of the animation in Y6 player sprites.
+///
+/// Values this version doesn't know are kept as [`Animation::Unknown`], so they survive a round trip.
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::packets::*;
+///
+/// assert_eq!(Animation::from(1), Animation::Walk);
+/// assert_eq!(Animation::from(42), Animation::Unknown(42));
+/// assert_eq!(u8::from(Animation::Unknown(42)), 42);
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialOrd, PartialEq, Eq, Hash)]
+pub enum Animation {
+    /// Standing still
+    #[default]
+    Idle,
+    /// Walking
+    Walk,
+    /// Swinging a weapon
+    Attack,
+    /// Falling down
+    Fall,
+    /// Getting up
+    Stand,
+    /// Lying dead
+    Dead,
+    /// Animation this version doesn't know
+    Unknown(u8),
+}
+
+impl From<u8> for Animation {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Idle,
+            1 => Self::Walk,
+            2 => Self::Attack,
+            3 => Self::Fall,
+            4 => Self::Stand,
+            5 => Self::Dead,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+impl From<Animation> for u8 {
+    fn from(value: Animation) -> Self {
+        match value {
+            Animation::Idle => 0,
+            Animation::Walk => 1,
+            Animation::Attack => 2,
+            Animation::Fall => 3,
+            Animation::Stand => 4,
+            Animation::Dead => 5,
+            Animation::Unknown(other) => other,
+        }
+    }
+}
+
+/// Action of a [`PlayerPose`], mirrors `ACTION` of the Y6 game code.
+///
+/// Values this version doesn't know, like the wolf mount, are kept as [`Action::Unknown`].
+#[derive(Debug, Default, Copy, Clone, PartialOrd, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Doing nothing special
+    #[default]
+    None,
+    /// Attacking
+    Attack,
+    /// Falling down
+    Fall,
+    /// Getting up
+    Stand,
+    /// Dead
+    Dead,
+    /// Action this version doesn't know
+    Unknown(u8),
+}
+
+impl From<u8> for Action {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::None,
+            1 => Self::Attack,
+            2 => Self::Fall,
+            3 => Self::Stand,
+            4 => Self::Dead,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+impl From<Action> for u8 {
+    fn from(value: Action) -> Self {
+        match value {
+            Action::None => 0,
+            Action::Attack => 1,
+            Action::Fall => 2,
+            Action::Stand => 3,
+            Action::Dead => 4,
+            Action::Unknown(other) => other,
+        }
+    }
+}
+
 /// Size of the [`PlayerPose`] struct
 pub const PLAYER_POSE_SIZE: usize = size_of::<u8>() * 3 + size_of::<f32>() * 4 + size_of::<u16>();
 
-impl Into<Bytes> for PlayerPose {
-    fn into(self) -> Bytes {
-        let mut b = Bytes::new();
-        b.push(self.animation);
-        b.push(self.frame);
-        b.push(self.action_or_mount);
-        for coord in self.position {
-            b.extend_from_slice(&coord.to_ne_bytes());
-        }
-        b.extend_from_slice(&self.direction.to_ne_bytes());
-        b.extend_from_slice(&self.sprite.to_ne_bytes());
-        b
+impl PlayerPose {
+    /// Encodes the pose onto the stack, without allocating.
+    pub fn to_array(&self) -> [u8; PLAYER_POSE_SIZE] {
+        let [x, y, z] = self.position.to_array();
+        concat_array(&[
+            &[
+                self.animation.into(),
+                self.frame,
+                self.action_or_mount.into(),
+            ],
+            &x.to_le_bytes(),
+            &y.to_le_bytes(),
+            &z.to_le_bytes(),
+            &self.direction.to_le_bytes(),
+            &self.sprite.to_le_bytes(),
+        ])
     }
 }
 
-impl TryFrom<Bytes> for PlayerPose {
+impl EncodePacket for PlayerPose {
+    fn encode_into(&self, b: &mut Bytes) {
+        b.extend_from_slice(&self.to_array());
+    }
+
+    fn encoded_len(&self) -> usize {
+        PLAYER_POSE_SIZE
+    }
+}
+
+impl_owned_encoding!(PlayerPose);
+
+impl TryFrom<&[u8]> for PlayerPose {
     type Error = PacketParseError;
 
-    fn try_from(value: Bytes) -> Result<Self, Self::Error> {
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
         if value.len() != PLAYER_POSE_SIZE {
             return Err(PacketParseError::SizeMismatch(
-                value.len(),
                 PLAYER_POSE_SIZE,
+                value.len(),
             ));
         }
 
         Ok(PlayerPose {
-            animation: value[0],
+            animation: value[0].into(),
             frame: value[1],
-            action_or_mount: value[2],
+            action_or_mount: value[2].into(),
             position: [
-                f32::from_ne_bytes([value[3], value[4], value[5], value[6]]),
-                f32::from_ne_bytes([value[7], value[8], value[9], value[10]]),
-                f32::from_ne_bytes([value[11], value[12], value[13], value[14]]),
-            ],
-            direction: f32::from_ne_bytes([value[15], value[16], value[17], value[18]]),
-            sprite: u16::from_ne_bytes([value[19], value[20]]),
+                f32::from_le_bytes([value[3], value[4], value[5], value[6]]),
+                f32::from_le_bytes([value[7], value[8], value[9], value[10]]),
+                f32::from_le_bytes([value[11], value[12], value[13], value[14]]),
+            ]
+            .into(),
+            direction: f32::from_le_bytes([value[15], value[16], value[17], value[18]]),
+            sprite: u16::from_le_bytes([value[19], value[20]]),
         })
     }
 }
 
+impl_owned_parsing!(PlayerPose);
+
 // Raw packets:
 
 /// Provides lowest level of abstraction.
@@ -86,7 +471,7 @@ impl TryFrom<Bytes> for PlayerPose {
 ///
 /// Definition basically copied from here: <https://github.com/msokalski/asciicker/blob/80708c9ca5f0ea8539653bb632082ce38b103903/network.h#L69>
 ///
-/// Can be transformed [`from`](std::convert::From) [`Bytes`] and [`into`](std::convert::Into) [`JoinRequest`]
+/// Can be transformed [`from`](std::convert::From) [`Bytes`] or a borrowed `&[u8]` and [`into`](std::convert::Into) [`JoinRequest`]
 #[derive(Default, Debug, Clone, PartialOrd, PartialEq)]
 pub struct RawJoinRequest {
     /// Token: 'J'
@@ -101,7 +486,18 @@ pub struct RawJoinRequest {
 ///
 /// Definition basically copied from here: <https://github.com/msokalski/asciicker/blob/80708c9ca5f0ea8539653bb632082ce38b103903/network.h#L75>
 ///
-/// Can be transformed [`from`](std::convert::From) [`Bytes`] and [`into`](std::convert::Into) [`JoinResponse`]
+/// Can be transformed [`from`](std::convert::From) [`Bytes`] or a borrowed `&[u8]` and [`into`](std::convert::Into) [`JoinResponse`]
+///
+/// # Examples
+///
+/// ## Parsing straight from a receive buffer, without copying:
+/// ```
+/// use asciicker_rs::y6::packets::*;
+///
+/// let buffer = [b'j', 8, 3, 0, b'e', 0, 3, 0];
+/// let rsp = RawJoinResponse::try_from(&buffer[..JOIN_RSP_SIZE]).unwrap();
+/// assert_eq!((rsp.max_clients, rsp.id), (8, 3));
+/// ```
 #[derive(Default, Debug, Clone, PartialOrd, PartialEq)]
 pub struct RawJoinResponse {
     /// Token: 'j'
@@ -118,7 +514,7 @@ pub struct RawJoinResponse {
 ///
 /// Definition basically copied from here: <https://github.com/msokalski/asciicker/blob/80708c9ca5f0ea8539653bb632082ce38b103903/network.h#L82>
 ///
-/// Can be transformed [`from`](std::convert::From) [`Bytes`] and [`into`](std::convert::Into) [`JoinBroadcast`]
+/// Can be transformed [`from`](std::convert::From) [`Bytes`] or a borrowed `&[u8]` and [`into`](std::convert::Into) [`JoinBroadcast`]
 #[derive(Default, Debug, Clone, PartialOrd, PartialEq)]
 pub struct RawJoinBroadcast {
     /// Token: 'j'
@@ -139,7 +535,7 @@ pub struct RawJoinBroadcast {
 ///
 /// Definition basically copied from here: <https://github.com/msokalski/asciicker/blob/80708c9ca5f0ea8539653bb632082ce38b103903/network.h#L95>
 ///
-/// Can be transformed [`from`](std::convert::From) [`Bytes`] and [`into`](std::convert::Into) [`ExitBroadcast`]
+/// Can be transformed [`from`](std::convert::From) [`Bytes`] or a borrowed `&[u8]` and [`into`](std::convert::Into) [`ExitBroadcast`]
 #[derive(Default, Debug, Clone, PartialOrd, PartialEq)]
 pub struct RawExitBroadcast {
     /// Token: 'e'
@@ -156,7 +552,7 @@ pub struct RawExitBroadcast {
 ///
 /// Definition basically copied from here: <https://github.com/msokalski/asciicker/blob/80708c9ca5f0ea8539653bb632082ce38b103903/network.h#L102>
 ///
-/// Can be transformed [`from`](std::convert::From) [`Bytes`] and [`into`](std::convert::Into) [`PoseRequest`]
+/// Can be transformed [`from`](std::convert::From) [`Bytes`] or a borrowed `&[u8]` and [`into`](std::convert::Into) [`PoseRequest`]
 #[derive(Default, Debug, Clone, PartialOrd, PartialEq)]
 pub struct RawPoseRequest {
     /// Token: 'P'
@@ -171,7 +567,7 @@ pub struct RawPoseRequest {
 ///
 /// Definition basically copied from here: <https://github.com/msokalski/asciicker/blob/80708c9ca5f0ea8539653bb632082ce38b103903/network.h#L113>
 ///
-/// Can be transformed [`from`](std::convert::From) [`Bytes`] and [`into`](std::convert::Into) [`PoseBroadcast`]
+/// Can be transformed [`from`](std::convert::From) [`Bytes`] or a borrowed `&[u8]` and [`into`](std::convert::Into) [`PoseBroadcast`]
 #[derive(Default, Debug, Clone, PartialOrd, PartialEq)]
 pub struct RawPoseBroadcast {
     /// Token: 'p'
@@ -188,7 +584,7 @@ pub struct RawPoseBroadcast {
 ///
 /// Definition basically copied from here: <https://github.com/msokalski/asciicker/blob/80708c9ca5f0ea8539653bb632082ce38b103903/network.h#L125>
 ///
-/// Can be transformed [`from`](std::convert::From) [`Bytes`] and [`into`](std::convert::Into) [`TalkRequest`]
+/// Can be transformed [`from`](std::convert::From) [`Bytes`] or a borrowed `&[u8]` and [`into`](std::convert::Into) [`TalkRequest`]
 #[derive(Debug, Clone, PartialOrd, PartialEq)]
 pub struct RawTalkRequest {
     /// Token: 'T'
@@ -210,7 +606,7 @@ pub struct RawTalkRequest {
 ///
 /// Definition basically copied from here: <https://github.com/msokalski/asciicker/blob/80708c9ca5f0ea8539653bb632082ce38b103903/network.h#L132>
 ///
-/// Can be transformed [`from`](std::convert::From) [`Bytes`] and [`into`](std::convert::Into) [`TalkBroadcast`]
+/// Can be transformed [`from`](std::convert::From) [`Bytes`] or a borrowed `&[u8]` and [`into`](std::convert::Into) [`TalkBroadcast`]
 #[derive(Debug, Clone, PartialOrd, PartialEq)]
 pub struct RawTalkBroadcast {
     /// Token: 't'
@@ -234,7 +630,7 @@ pub struct RawTalkBroadcast {
 ///
 /// Definition basically copied from here: <https://github.com/msokalski/asciicker/blob/80708c9ca5f0ea8539653bb632082ce38b103903/network.h#L140>
 ///
-/// Can be transformed [`from`](std::convert::From) [`Bytes`] and [`into`](std::convert::Into) [`LagRequest`]
+/// Can be transformed [`from`](std::convert::From) [`Bytes`] or a borrowed `&[u8]` and [`into`](std::convert::Into) [`LagRequest`]
 #[derive(Default, Debug, Clone, PartialOrd, PartialEq)]
 pub struct RawLagRequest {
     /// Token: 'L'
@@ -249,7 +645,7 @@ pub struct RawLagRequest {
 ///
 /// Definition basically copied from here: <https://github.com/msokalski/asciicker/blob/80708c9ca5f0ea8539653bb632082ce38b103903/network.h#L146>
 ///
-/// Can be transformed [`from`](std::convert::From) [`Bytes`] and [`into`](std::convert::Into) [`LagResponse`]
+/// Can be transformed [`from`](std::convert::From) [`Bytes`] or a borrowed `&[u8]` and [`into`](std::convert::Into) [`LagResponse`]
 #[derive(Default, Debug, Clone, PartialOrd, PartialEq)]
 pub struct RawLagResponse {
     /// Token: 'l'
@@ -288,6 +684,18 @@ pub type STRUCT_RSP_LAG = RawLagResponse;
 /// Represents clean version of the join request, sent from client to server.
 ///
 /// Can be transformed [`from`](std::convert::From) [`RawJoinRequest`] and [`into`](std::convert::Into) [`Bytes`]
+///
+/// # Examples
+///
+/// ## Parsing a name that fills the whole field:
+/// ```
+/// use asciicker_rs::y6::packets::*;
+///
+/// let mut bytes = [b'x'; JOIN_REQ_SIZE];
+/// bytes[0] = b'J';
+/// let join = JoinRequest::try_from(&bytes[..]).unwrap();
+/// assert_eq!(join.name.as_bytes(), [b'x'; 31]);
+/// ```
 #[derive(Default, Debug, Clone, PartialOrd, PartialEq)]
 pub struct JoinRequest {
     /// Name of the player who requests to join the server
@@ -349,6 +757,20 @@ pub struct PoseRequest {
 /// Represents clean version of the pose broadcast, sent from server to clients.
 ///
 /// Can be transformed [`from`](std::convert::From) [`RawPoseBroadcast`] and [`into`](std::convert::Into) [`Bytes`]
+///
+/// # Examples
+///
+/// ## Roundtrip with a little-endian id:
+/// ```
+/// use asciicker_rs::y6::packets::*;
+///
+/// let brc = PoseBroadcast { player_pose: PlayerPose::default(), id: 0x1234 };
+/// let bytes: Bytes = brc.clone().into();
+/// assert_eq!(bytes.len(), POSE_BRC_SIZE);
+/// assert_eq!(bytes[0], b'p');
+/// assert_eq!(&bytes[22..24], &[0x34, 0x12]);
+/// assert_eq!(PoseBroadcast::try_from(bytes).unwrap(), brc);
+/// ```
 #[derive(Default, Debug, Clone, PartialOrd, PartialEq)]
 pub struct PoseBroadcast {
     /// Current pose of the player who sent the pose request
@@ -368,6 +790,53 @@ pub struct TalkRequest {
     pub str: CString,
 }
 
+impl TalkRequest {
+    /// Splits `message` into talk requests of at most [`MAX_TALK_LEN`] bytes each.
+    ///
+    /// Parts end at the last whitespace that fits, or at the last whole character if there is none,
+    /// so multi-byte characters are never cut in half. Whitespace at the cuts is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::packets::*;
+    ///
+    /// let message = format!("{} {}", "a".repeat(200), "ä".repeat(100));
+    /// let parts = TalkRequest::split(&message).unwrap();
+    /// assert_eq!(parts.len(), 2);
+    /// assert_eq!(parts[0].str.as_bytes(), "a".repeat(200).as_bytes());
+    /// assert_eq!(parts[1].str.to_str().unwrap(), "ä".repeat(100));
+    /// assert!(TalkRequest::split("short").unwrap().len() == 1);
+    /// ```
+    pub fn split(message: &str) -> Result<Vec<TalkRequest>, BotError> {
+        let mut parts = vec![];
+        let mut rest = message;
+        while rest.len() > MAX_TALK_LEN {
+            let mut end = MAX_TALK_LEN;
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+            let cut = rest[..end]
+                .rfind(char::is_whitespace)
+                .filter(|cut| *cut > 0)
+                .unwrap_or(end);
+            parts.push(&rest[..cut]);
+            rest = rest[cut..].trim_start();
+        }
+        if !rest.is_empty() || parts.is_empty() {
+            parts.push(rest);
+        }
+        parts
+            .into_iter()
+            .map(|part| {
+                Ok(TalkRequest {
+                    str: CString::new(part)?,
+                })
+            })
+            .collect()
+    }
+}
+
 /// Low level abstraction.
 ///
 /// Represents clean version of the talk broadcast, sent from server to clients.
@@ -417,6 +886,10 @@ pub const EXIT_BRC_SIZE: usize = size_of::<u8>() * 2 + size_of::<u16>();
 pub const POSE_REQ_SIZE: usize = size_of::<u8>() * 4 + size_of::<f32>() * 4 + size_of::<u16>();
 /// Size of the [`RawPoseBroadcast`] struct in C
 pub const POSE_BRC_SIZE: usize = size_of::<u8>() * 4 + size_of::<f32>() * 4 + size_of::<u16>() * 2;
+/// Max length of a message in bytes, [`RawTalkRequest::len`] is a single byte
+pub const MAX_TALK_LEN: usize = u8::MAX as usize;
+/// Max length of a name in bytes, [`RawJoinRequest::name`] has no room for more
+pub const MAX_NAME_LEN: usize = 31;
 /// Max possible size of the [`RawTalkRequest`] struct in C
 pub const TOTAL_TALK_REQ_SIZE: usize = size_of::<u8>() * 258;
 /// Max possible size of the [`RawTalkBroadcast`] struct in C
@@ -426,12 +899,585 @@ pub const LAG_REQ_SIZE: usize = size_of::<u8>() * 4;
 /// Size of the [`RawLagResponse`] struct in C
 pub const LAG_RSP_SIZE: usize = size_of::<u8>() * 4;
 
-// Bytes to raw packet structs:
+// Packet enum:
 
-impl TryFrom<Bytes> for RawJoinRequest {
+/// Any clean packet, returned by [`parse_packet`].
+///
+/// # Examples
+///
+/// ## Dispatching on a received packet:
+/// ```
+/// use asciicker_rs::y6::packets::*;
+///
+/// let bytes: Bytes = ExitBroadcast { id: 7 }.into();
+/// match parse_packet(&bytes).unwrap() {
+///     Packet::ExitBroadcast(exit_brc) => assert_eq!(exit_brc.id, 7),
+///     other => panic!("Unexpected packet: {:?}", other),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub enum Packet {
+    /// [`JoinRequest`], token `'J'`
+    JoinRequest(JoinRequest),
+    /// [`JoinResponse`], token `'j'`, told apart from [`JoinBroadcast`] by size
+    JoinResponse(JoinResponse),
+    /// [`JoinBroadcast`], token `'j'`
+    JoinBroadcast(JoinBroadcast),
+    /// [`ExitBroadcast`], token `'e'`
+    ExitBroadcast(ExitBroadcast),
+    /// [`PoseRequest`], token `'P'`
+    PoseRequest(PoseRequest),
+    /// [`PoseBroadcast`], token `'p'`
+    PoseBroadcast(PoseBroadcast),
+    /// [`TalkRequest`], token `'T'`
+    TalkRequest(TalkRequest),
+    /// [`TalkBroadcast`], token `'t'`
+    TalkBroadcast(TalkBroadcast),
+    /// [`LagRequest`], token `'L'`
+    LagRequest(LagRequest),
+    /// [`LagResponse`], token `'l'`
+    LagResponse(LagResponse),
+}
+
+impl Packet {
+    /// Token byte the packet starts with.
+    pub fn token(&self) -> u8 {
+        match self {
+            Packet::JoinRequest(_) => b'J',
+            Packet::JoinResponse(_) | Packet::JoinBroadcast(_) => b'j',
+            Packet::ExitBroadcast(_) => b'e',
+            Packet::PoseRequest(_) => b'P',
+            Packet::PoseBroadcast(_) => b'p',
+            Packet::TalkRequest(_) => b'T',
+            Packet::TalkBroadcast(_) => b't',
+            Packet::LagRequest(_) => b'L',
+            Packet::LagResponse(_) => b'l',
+        }
+    }
+}
+
+/// Parses any packet, dispatching on its token byte.
+///
+/// Malformed input, like a talk packet cut short by the network, is reported as an error, never a panic.
+///
+/// # Examples
+///
+/// ## Parsing random bytes:
+/// ```
+/// use asciicker_rs::y6::packets::*;
+///
+/// let tokens = b"JjePpTtLlx";
+/// let mut state = 0x2545_f491_u32;
+/// for _ in 0..2000 {
+///     // xorshift, good enough to shuffle bytes around
+///     let mut next = || {
+///         state ^= state << 13;
+///         state ^= state >> 17;
+///         state ^= state << 5;
+///         state
+///     };
+///     let len = next() as usize % 300;
+///     let mut bytes: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+///     if let Some(first) = bytes.first_mut() {
+///         *first = tokens[*first as usize % tokens.len()];
+///     }
+///     let _ = parse_packet(&bytes);
+/// }
+/// ```
+///
+/// ## Parsing a truncated talk broadcast:
+/// ```
+/// use asciicker_rs::y6::packets::*;
+/// use asciicker_rs::y6::utils::PacketParseError;
+/// use std::ffi::CString;
+///
+/// let talk = TalkBroadcast { id: 1, str: CString::new("hello").unwrap() };
+/// let mut bytes = Bytes::from(talk.clone());
+/// assert_eq!(parse_packet(&bytes).unwrap(), Packet::TalkBroadcast(talk));
+/// bytes.truncate(6);
+/// assert!(matches!(
+///     parse_packet(&bytes),
+///     Err(PacketParseError::Truncated(10, 6))
+/// ));
+/// ```
+///
+/// ## Parsing edge cases:
+/// ```
+/// use asciicker_rs::y6::packets::*;
+///
+/// // Name without a null byte takes the whole field
+/// let mut join = [b'x'; JOIN_REQ_SIZE];
+/// join[0] = b'J';
+/// match parse_packet(&join).unwrap() {
+///     Packet::JoinRequest(join) => assert_eq!(join.name.as_bytes().len(), 31),
+///     packet => panic!("Expected a join request, got {:?}", packet),
+/// }
+///
+/// // Message is cut at the length byte, whatever follows it
+/// let talk = [b't', 2, 1, 0, b'h', b'i', b'x', b'y', 0, 0];
+/// match parse_packet(&talk).unwrap() {
+///     Packet::TalkBroadcast(talk) => assert_eq!(talk.str.as_bytes(), b"hi"),
+///     packet => panic!("Expected a talk broadcast, got {:?}", packet),
+/// }
+///
+/// // Null byte before the length byte says ends the message early
+/// let talk = [b'T', 4, b'h', 0, b'i', b'!', 0];
+/// match parse_packet(&talk).unwrap() {
+///     Packet::TalkRequest(talk) => assert_eq!(talk.str.as_bytes(), b"h"),
+///     packet => panic!("Expected a talk request, got {:?}", packet),
+/// }
+/// ```
+pub fn parse_packet(bytes: &[u8]) -> Result<Packet, PacketParseError> {
+    let token = match bytes.first() {
+        Some(token) => *token,
+        None => return Err(PacketParseError::Empty),
+    };
+    Ok(match token {
+        b'J' => Packet::JoinRequest(bytes.try_into()?),
+        b'j' if bytes.len() == JOIN_RSP_SIZE => Packet::JoinResponse(bytes.try_into()?),
+        b'j' => Packet::JoinBroadcast(bytes.try_into()?),
+        b'e' => Packet::ExitBroadcast(bytes.try_into()?),
+        b'P' => Packet::PoseRequest(bytes.try_into()?),
+        b'p' => Packet::PoseBroadcast(bytes.try_into()?),
+        b'T' => Packet::TalkRequest(bytes.try_into()?),
+        b't' => Packet::TalkBroadcast(bytes.try_into()?),
+        b'L' => Packet::LagRequest(bytes.try_into()?),
+        b'l' => Packet::LagResponse(bytes.try_into()?),
+        token => return Err(PacketParseError::UnknownToken(token)),
+    })
+}
+
+impl TryFrom<&[u8]> for Packet {
     type Error = PacketParseError;
 
-    fn try_from(value: Bytes) -> Result<Self, Self::Error> {
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        parse_packet(value)
+    }
+}
+
+impl_owned_parsing!(Packet);
+
+impl Packet {
+    /// Returns the wrapped packet as [`EncodePacket`].
+    fn as_encode(&self) -> &dyn EncodePacket {
+        match self {
+            Packet::JoinRequest(p) => p,
+            Packet::JoinResponse(p) => p,
+            Packet::JoinBroadcast(p) => p,
+            Packet::ExitBroadcast(p) => p,
+            Packet::PoseRequest(p) => p,
+            Packet::PoseBroadcast(p) => p,
+            Packet::TalkRequest(p) => p,
+            Packet::TalkBroadcast(p) => p,
+            Packet::LagRequest(p) => p,
+            Packet::LagResponse(p) => p,
+        }
+    }
+}
+
+impl EncodePacket for Packet {
+    fn encode_into(&self, buf: &mut Bytes) {
+        self.as_encode().encode_into(buf)
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.as_encode().encoded_len()
+    }
+}
+
+impl_owned_encoding!(Packet);
+
+// Generators for property tests:
+
+/// Generates a string of at most `max_len` bytes without null bytes.
+#[cfg(feature = "testing")]
+fn arbitrary_text(u: &mut Unstructured<'_>, max_len: usize) -> arbitrary::Result<CString> {
+    let len = u.int_in_range(0..=max_len)?;
+    let bytes = (0..len)
+        .map(|_| u.int_in_range(1..=u8::MAX))
+        .collect::<arbitrary::Result<Vec<u8>>>()?;
+    Ok(CString::new(bytes).expect("Generated text has no null bytes"))
+}
+
+/// Generates a finite float, NaN would keep packets from comparing equal to themselves.
+#[cfg(feature = "testing")]
+fn arbitrary_finite(u: &mut Unstructured<'_>) -> arbitrary::Result<f32> {
+    let value = f32::arbitrary(u)?;
+    Ok(if value.is_finite() { value } else { 0.0 })
+}
+
+/// Strategy for a string of at most `max_len` bytes without null bytes.
+#[cfg(feature = "testing")]
+fn text_strategy(max_len: usize) -> impl Strategy<Value = CString> {
+    proptest::collection::vec(1..=u8::MAX, 0..=max_len)
+        .prop_map(|bytes| CString::new(bytes).expect("Generated text has no null bytes"))
+}
+
+/// Strategy for a finite float, see [`arbitrary_finite`].
+#[cfg(feature = "testing")]
+fn finite_strategy() -> impl Strategy<Value = f32> {
+    use proptest::num::f32::{NEGATIVE, NORMAL, POSITIVE, SUBNORMAL, ZERO};
+    POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO
+}
+
+#[cfg(feature = "testing")]
+impl<'a> Arbitrary<'a> for PlayerPose {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            animation: u8::arbitrary(u)?.into(),
+            frame: u8::arbitrary(u)?,
+            action_or_mount: u8::arbitrary(u)?.into(),
+            position: [
+                arbitrary_finite(u)?,
+                arbitrary_finite(u)?,
+                arbitrary_finite(u)?,
+            ]
+            .into(),
+            direction: arbitrary_finite(u)?,
+            sprite: u16::arbitrary(u)?,
+        })
+    }
+}
+
+#[cfg(feature = "testing")]
+impl<'a> Arbitrary<'a> for JoinRequest {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            name: arbitrary_text(u, MAX_NAME_LEN)?,
+        })
+    }
+}
+
+#[cfg(feature = "testing")]
+impl<'a> Arbitrary<'a> for JoinResponse {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            max_clients: u.arbitrary()?,
+            id: u.arbitrary()?,
+        })
+    }
+}
+
+#[cfg(feature = "testing")]
+impl<'a> Arbitrary<'a> for JoinBroadcast {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            player_pose: u.arbitrary()?,
+            id: u.arbitrary()?,
+            name: arbitrary_text(u, MAX_NAME_LEN)?,
+        })
+    }
+}
+
+#[cfg(feature = "testing")]
+impl<'a> Arbitrary<'a> for ExitBroadcast {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self { id: u.arbitrary()? })
+    }
+}
+
+#[cfg(feature = "testing")]
+impl<'a> Arbitrary<'a> for PoseRequest {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            player_pose: u.arbitrary()?,
+        })
+    }
+}
+
+#[cfg(feature = "testing")]
+impl<'a> Arbitrary<'a> for PoseBroadcast {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            player_pose: u.arbitrary()?,
+            id: u.arbitrary()?,
+        })
+    }
+}
+
+#[cfg(feature = "testing")]
+impl<'a> Arbitrary<'a> for TalkRequest {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            str: arbitrary_text(u, MAX_TALK_LEN)?,
+        })
+    }
+}
+
+#[cfg(feature = "testing")]
+impl<'a> Arbitrary<'a> for TalkBroadcast {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            id: u.arbitrary()?,
+            str: arbitrary_text(u, MAX_TALK_LEN)?,
+        })
+    }
+}
+
+#[cfg(feature = "testing")]
+impl<'a> Arbitrary<'a> for LagRequest {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            stamp: u.arbitrary()?,
+        })
+    }
+}
+
+#[cfg(feature = "testing")]
+impl<'a> Arbitrary<'a> for LagResponse {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            stamp: u.arbitrary()?,
+        })
+    }
+}
+
+/// Generates any packet. Names are at most [`MAX_NAME_LEN`] and messages at most
+/// [`MAX_TALK_LEN`] bytes without null bytes, positions and directions are finite,
+/// so every generated packet encodes and parses back to itself.
+///
+/// # Examples
+///
+/// ## Roundtrip of generated packets:
+/// ```
+/// use arbitrary::{Arbitrary, Unstructured};
+/// use asciicker_rs::y6::packets::*;
+///
+/// let mut state = 0x2545_f491_u32;
+/// let data: Vec<u8> = (0..1 << 16)
+///     .map(|_| {
+///         // xorshift, good enough to shuffle bytes around
+///         state ^= state << 13;
+///         state ^= state >> 17;
+///         state ^= state << 5;
+///         state as u8
+///     })
+///     .collect();
+/// let mut u = Unstructured::new(&data);
+/// while !u.is_empty() {
+///     let packet = Packet::arbitrary(&mut u).unwrap();
+///     assert_eq!(parse_packet(&Bytes::from(packet.clone())).unwrap(), packet);
+/// }
+/// ```
+#[cfg(feature = "testing")]
+impl<'a> Arbitrary<'a> for Packet {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(match u.int_in_range(0..=9u8)? {
+            0 => Packet::JoinRequest(u.arbitrary()?),
+            1 => Packet::JoinResponse(u.arbitrary()?),
+            2 => Packet::JoinBroadcast(u.arbitrary()?),
+            3 => Packet::ExitBroadcast(u.arbitrary()?),
+            4 => Packet::PoseRequest(u.arbitrary()?),
+            5 => Packet::PoseBroadcast(u.arbitrary()?),
+            6 => Packet::TalkRequest(u.arbitrary()?),
+            7 => Packet::TalkBroadcast(u.arbitrary()?),
+            8 => Packet::LagRequest(u.arbitrary()?),
+            _ => Packet::LagResponse(u.arbitrary()?),
+        })
+    }
+}
+
+#[cfg(feature = "testing")]
+impl_proptest_arbitrary! {
+    PlayerPose => (
+        any::<u8>(),
+        any::<u8>(),
+        any::<u8>(),
+        [finite_strategy(), finite_strategy(), finite_strategy()],
+        finite_strategy(),
+        any::<u16>(),
+    )
+        .prop_map(|(animation, frame, action, position, direction, sprite)| PlayerPose {
+            animation: animation.into(),
+            frame,
+            action_or_mount: action.into(),
+            position: position.into(),
+            direction,
+            sprite,
+        });
+    JoinRequest => text_strategy(MAX_NAME_LEN).prop_map(|name| JoinRequest { name });
+    JoinResponse => any::<(u8, u16)>()
+        .prop_map(|(max_clients, id)| JoinResponse { max_clients, id });
+    JoinBroadcast => (any::<PlayerPose>(), any::<u16>(), text_strategy(MAX_NAME_LEN))
+        .prop_map(|(player_pose, id, name)| JoinBroadcast { player_pose, id, name });
+    ExitBroadcast => any::<u16>().prop_map(|id| ExitBroadcast { id });
+    PoseRequest => any::<PlayerPose>().prop_map(|player_pose| PoseRequest { player_pose });
+    PoseBroadcast => (any::<PlayerPose>(), any::<u16>())
+        .prop_map(|(player_pose, id)| PoseBroadcast { player_pose, id });
+    TalkRequest => text_strategy(MAX_TALK_LEN).prop_map(|str| TalkRequest { str });
+    TalkBroadcast => (any::<u16>(), text_strategy(MAX_TALK_LEN))
+        .prop_map(|(id, str)| TalkBroadcast { id, str });
+    LagRequest => any::<LagStamp>().prop_map(|stamp| LagRequest { stamp });
+    LagResponse => any::<LagStamp>().prop_map(|stamp| LagResponse { stamp });
+    Packet => prop_oneof![
+        any::<JoinRequest>().prop_map(Packet::JoinRequest),
+        any::<JoinResponse>().prop_map(Packet::JoinResponse),
+        any::<JoinBroadcast>().prop_map(Packet::JoinBroadcast),
+        any::<ExitBroadcast>().prop_map(Packet::ExitBroadcast),
+        any::<PoseRequest>().prop_map(Packet::PoseRequest),
+        any::<PoseBroadcast>().prop_map(Packet::PoseBroadcast),
+        any::<TalkRequest>().prop_map(Packet::TalkRequest),
+        any::<TalkBroadcast>().prop_map(Packet::TalkBroadcast),
+        any::<LagRequest>().prop_map(Packet::LagRequest),
+        any::<LagResponse>().prop_map(Packet::LagResponse),
+    ];
+}
+
+// Framing for transports without message boundaries:
+
+/// [`Framing`] for streams received by clients.
+///
+/// Join response and join broadcast share the `'j'` token, so the first `'j'` packet
+/// is treated as the response (server sends it only once, before any broadcast).
+///
+/// Talk packets are framed the way this library encodes them: header, `len` bytes of text,
+/// terminating null byte and one padding null byte.
+#[derive(Default, Debug, Clone)]
+pub struct ClientFraming {
+    joined: bool,
+}
+
+impl Framing for ClientFraming {
+    fn frame_len(&mut self, buffer: &[u8]) -> Result<Option<usize>, BotError> {
+        Ok(Some(match buffer[0] {
+            // Only commit to the response once it is complete, a partial one must be framed the same way again
+            b'j' if !self.joined && buffer.len() < JOIN_RSP_SIZE => return Ok(None),
+            b'j' if !self.joined => {
+                self.joined = true;
+                JOIN_RSP_SIZE
+            }
+            b'j' => JOIN_BRC_SIZE,
+            b'e' => EXIT_BRC_SIZE,
+            b'p' => POSE_BRC_SIZE,
+            b't' => match buffer.get(1) {
+                Some(len) => 4 + *len as usize + 2,
+                None => return Ok(None),
+            },
+            b'l' => LAG_RSP_SIZE,
+            token => return Err(PacketParseError::UnknownToken(token).into()),
+        }))
+    }
+}
+
+/// [`Framing`] for streams received by servers, see [`ClientFraming`].
+#[derive(Default, Debug, Clone)]
+pub struct ServerFraming;
+
+impl Framing for ServerFraming {
+    fn frame_len(&mut self, buffer: &[u8]) -> Result<Option<usize>, BotError> {
+        Ok(Some(match buffer[0] {
+            b'J' => JOIN_REQ_SIZE,
+            b'P' => POSE_REQ_SIZE,
+            b'T' => match buffer.get(1) {
+                Some(len) => 2 + *len as usize + 2,
+                None => return Ok(None),
+            },
+            b'L' => LAG_REQ_SIZE,
+            token => return Err(PacketParseError::UnknownToken(token).into()),
+        }))
+    }
+}
+
+// Streaming decoder:
+
+/// Splits arbitrary chunks of a byte stream into packets.
+///
+/// Chunks may end in the middle of a packet or contain several packets back to back,
+/// which happens on transports that don't preserve message boundaries (see [`Framing`]).
+///
+/// # Examples
+///
+/// ## Decoding two packets split across three chunks:
+/// ```
+/// use asciicker_rs::y6::packets::*;
+///
+/// let mut stream: Bytes = JoinResponse { max_clients: 8, id: 1 }.into();
+/// stream.extend(Bytes::from(ExitBroadcast { id: 2 }));
+///
+/// let mut decoder = PacketDecoder::new(ClientFraming::default());
+/// decoder.feed(&stream[..3]);
+/// assert_eq!(decoder.next_packet().unwrap(), None);
+/// decoder.feed(&stream[3..6]);
+/// assert!(matches!(decoder.next_packet().unwrap(), Some(Packet::JoinResponse(_))));
+/// assert_eq!(decoder.next_packet().unwrap(), None);
+/// decoder.feed(&stream[6..]);
+/// assert_eq!(
+///     decoder.next_packet().unwrap(),
+///     Some(Packet::ExitBroadcast(ExitBroadcast { id: 2 }))
+/// );
+/// assert_eq!(decoder.buffered(), 0);
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct PacketDecoder<F: Framing = ClientFraming> {
+    framing: F,
+    buffer: Bytes,
+}
+
+impl<F: Framing> PacketDecoder<F> {
+    /// Constructs a new [`PacketDecoder`], use [`ClientFraming`] for data received from a server
+    /// and [`ServerFraming`] for data received from clients.
+    pub fn new(framing: F) -> Self {
+        Self {
+            framing,
+            buffer: vec![],
+        }
+    }
+
+    /// Appends a chunk of the stream.
+    pub fn feed(&mut self, chunk: &[u8]) {
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// Amount of bytes not yet returned as packets.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Appends bytes of the next complete packet to `frame` and returns `true`,
+    /// or returns `false` if more bytes are needed.
+    ///
+    /// Lets callers reuse `frame` between packets instead of allocating a new one.
+    pub fn next_frame_into(&mut self, frame: &mut Bytes) -> Result<bool, BotError> {
+        if self.buffer.is_empty() {
+            return Ok(false);
+        }
+        match self.framing.frame_len(&self.buffer)? {
+            Some(len) if len <= self.buffer.len() => {
+                frame.extend(self.buffer.drain(..len));
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Returns bytes of the next complete packet, [`None`] if more bytes are needed.
+    pub fn next_frame(&mut self) -> Result<Option<Bytes>, BotError> {
+        let mut frame = vec![];
+        match self.next_frame_into(&mut frame)? {
+            true => Ok(Some(frame)),
+            false => Ok(None),
+        }
+    }
+
+    /// Returns the next complete packet, [`None`] if more bytes are needed.
+    pub fn next_packet(&mut self) -> Result<Option<Packet>, BotError> {
+        match self.next_frame()? {
+            Some(frame) => match parse_packet(&frame) {
+                Ok(packet) => Ok(Some(packet)),
+                Err(e) => Err(BotError::PacketParse(e)),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+// Bytes to raw packet structs:
+
+impl ParsePacket for RawJoinRequest {
+    const TOKEN: u8 = b'J';
+
+    fn parse(value: &[u8], mode: ParseMode) -> Result<Self, PacketParseError> {
+        check_token(value, Self::TOKEN, mode)?;
         if value.len() != JOIN_REQ_SIZE {
             return Err(PacketParseError::SizeMismatch(JOIN_REQ_SIZE, value.len()));
         }
@@ -442,52 +1488,56 @@ impl TryFrom<Bytes> for RawJoinRequest {
     }
 }
 
-impl TryFrom<Bytes> for RawJoinResponse {
-    type Error = PacketParseError;
+impl ParsePacket for RawJoinResponse {
+    const TOKEN: u8 = b'j';
 
-    fn try_from(value: Bytes) -> Result<Self, Self::Error> {
+    fn parse(value: &[u8], mode: ParseMode) -> Result<Self, PacketParseError> {
+        check_token(value, Self::TOKEN, mode)?;
         if value.len() != JOIN_RSP_SIZE {
             return Err(PacketParseError::SizeMismatch(JOIN_RSP_SIZE, value.len()));
         }
         Ok(Self {
             token: value[0],
             max_clients: value[1],
-            id: u16::from_ne_bytes([value[2], value[3]]),
+            id: u16::from_le_bytes([value[2], value[3]]),
         })
     }
 }
 
-impl TryFrom<Bytes> for RawJoinBroadcast {
-    type Error = PacketParseError;
+impl ParsePacket for RawJoinBroadcast {
+    const TOKEN: u8 = b'j';
 
-    fn try_from(value: Bytes) -> Result<Self, Self::Error> {
+    fn parse(value: &[u8], mode: ParseMode) -> Result<Self, PacketParseError> {
+        check_token(value, Self::TOKEN, mode)?;
         if value.len() != JOIN_BRC_SIZE {
             return Err(PacketParseError::SizeMismatch(JOIN_BRC_SIZE, value.len()));
         }
         Ok(Self {
             token: value[0],
             player_pose: PlayerPose {
-                animation: value[1],
+                animation: value[1].into(),
                 frame: value[2],
-                action_or_mount: value[3],
+                action_or_mount: value[3].into(),
                 position: [
-                    f32::from_ne_bytes([value[4], value[5], value[6], value[7]]),
-                    f32::from_ne_bytes([value[8], value[9], value[10], value[11]]),
-                    f32::from_ne_bytes([value[12], value[13], value[14], value[15]]),
-                ],
-                direction: f32::from_ne_bytes([value[16], value[17], value[18], value[19]]),
-                sprite: u16::from_ne_bytes([value[22], value[23]]),
+                    f32::from_le_bytes([value[4], value[5], value[6], value[7]]),
+                    f32::from_le_bytes([value[8], value[9], value[10], value[11]]),
+                    f32::from_le_bytes([value[12], value[13], value[14], value[15]]),
+                ]
+                .into(),
+                direction: f32::from_le_bytes([value[16], value[17], value[18], value[19]]),
+                sprite: u16::from_le_bytes([value[22], value[23]]),
             },
-            id: u16::from_ne_bytes([value[20], value[21]]),
+            id: u16::from_le_bytes([value[20], value[21]]),
             name: value[24..24 + 32].try_into().unwrap(),
         })
     }
 }
 
-impl TryFrom<Bytes> for RawExitBroadcast {
-    type Error = PacketParseError;
+impl ParsePacket for RawExitBroadcast {
+    const TOKEN: u8 = b'e';
 
-    fn try_from(value: Bytes) -> Result<Self, Self::Error> {
+    fn parse(value: &[u8], mode: ParseMode) -> Result<Self, PacketParseError> {
+        check_token(value, Self::TOKEN, mode)?;
         if value.len() != EXIT_BRC_SIZE {
             return Err(PacketParseError::SizeMismatch(EXIT_BRC_SIZE, value.len()));
         }
@@ -499,68 +1549,69 @@ impl TryFrom<Bytes> for RawExitBroadcast {
     }
 }
 
-impl TryFrom<Bytes> for RawPoseRequest {
-    type Error = PacketParseError;
+impl ParsePacket for RawPoseRequest {
+    const TOKEN: u8 = b'P';
 
-    fn try_from(value: Bytes) -> Result<Self, Self::Error> {
+    fn parse(value: &[u8], mode: ParseMode) -> Result<Self, PacketParseError> {
+        check_token(value, Self::TOKEN, mode)?;
         if value.len() != POSE_REQ_SIZE {
             return Err(PacketParseError::SizeMismatch(POSE_REQ_SIZE, value.len()));
         }
         Ok(Self {
             token: value[0],
-            player_pose: PlayerPose::try_from(value[1..=21].to_vec())?,
+            player_pose: PlayerPose::try_from(&value[1..=21])?,
         })
     }
 }
 
-impl TryFrom<Bytes> for RawPoseBroadcast {
-    type Error = PacketParseError;
+impl ParsePacket for RawPoseBroadcast {
+    const TOKEN: u8 = b'p';
 
-    fn try_from(value: Bytes) -> Result<Self, Self::Error> {
+    fn parse(value: &[u8], mode: ParseMode) -> Result<Self, PacketParseError> {
+        check_token(value, Self::TOKEN, mode)?;
         if value.len() != POSE_BRC_SIZE {
             return Err(PacketParseError::SizeMismatch(POSE_BRC_SIZE, value.len()));
         }
         Ok(Self {
             token: value[0],
-            player_pose: PlayerPose::try_from(value[1..=21].to_vec())?,
-            id: u16::from_ne_bytes([value[22], value[23]]),
+            player_pose: PlayerPose::try_from(&value[1..=21])?,
+            id: u16::from_le_bytes([value[22], value[23]]),
         })
     }
 }
 
-impl TryFrom<Bytes> for RawTalkRequest {
-    type Error = PacketParseError;
+impl ParsePacket for RawTalkRequest {
+    const TOKEN: u8 = b'T';
 
-    fn try_from(mut value: Bytes) -> Result<Self, Self::Error> {
-        let len = match first_nul(&value[2..]) {
-            None => return Err(PacketParseError::NoNullByte(value[2..].to_vec())),
-            Some(l) => l,
-        };
+    fn parse(value: &[u8], mode: ParseMode) -> Result<Self, PacketParseError> {
+        check_token(value, Self::TOKEN, mode)?;
         Ok(Self {
+            str: talk_str(value, 2)?,
             token: value[0],
             len: value[1],
-            str: unsafe { CString::from_vec_unchecked(Into::<Vec<u8>>::into(&mut value[2..len])) },
         })
     }
 }
 
-impl TryFrom<Bytes> for RawTalkBroadcast {
-    type Error = PacketParseError;
+impl ParsePacket for RawTalkBroadcast {
+    const TOKEN: u8 = b't';
 
-    fn try_from(mut value: Bytes) -> Result<Self, Self::Error> {
+    fn parse(value: &[u8], mode: ParseMode) -> Result<Self, PacketParseError> {
+        check_token(value, Self::TOKEN, mode)?;
         Ok(Self {
+            str: talk_str(value, 4)?,
             token: value[0],
             len: value[1],
-            id: u16::from_ne_bytes([value[2], value[3]]),
-            str: unsafe { CString::from_vec_unchecked(Into::<Vec<u8>>::into(&mut value[4..])) },
+            id: u16::from_le_bytes([value[2], value[3]]),
         })
     }
 }
 
-impl TryFrom<Bytes> for RawLagRequest {
-    type Error = PacketParseError;
+impl ParsePacket for RawLagRequest {
+    const TOKEN: u8 = b'L';
 
-    fn try_from(value: Bytes) -> Result<Self, Self::Error> {
+    fn parse(value: &[u8], mode: ParseMode) -> Result<Self, PacketParseError> {
+        check_token(value, Self::TOKEN, mode)?;
         if value.len() != LAG_REQ_SIZE {
             return Err(PacketParseError::SizeMismatch(LAG_REQ_SIZE, value.len()));
         }
@@ -571,10 +1622,11 @@ impl TryFrom<Bytes> for RawLagRequest {
     }
 }
 
-impl TryFrom<Bytes> for RawLagResponse {
-    type Error = PacketParseError;
+impl ParsePacket for RawLagResponse {
+    const TOKEN: u8 = b'l';
 
-    fn try_from(value: Bytes) -> Result<Self, Self::Error> {
+    fn parse(value: &[u8], mode: ParseMode) -> Result<Self, PacketParseError> {
+        check_token(value, Self::TOKEN, mode)?;
         if value.len() != LAG_RSP_SIZE {
             return Err(PacketParseError::SizeMismatch(LAG_RSP_SIZE, value.len()));
         }
@@ -585,13 +1637,49 @@ impl TryFrom<Bytes> for RawLagResponse {
     }
 }
 
+impl_strict_parsing!(RawJoinRequest RawJoinResponse RawJoinBroadcast RawExitBroadcast RawPoseRequest RawPoseBroadcast RawTalkRequest RawTalkBroadcast RawLagRequest RawLagResponse);
+
+/// Checks the token byte of `value` unless `mode` is [`ParseMode::Lenient`].
+fn check_token(value: &[u8], expected: u8, mode: ParseMode) -> Result<(), PacketParseError> {
+    match value.first() {
+        None => Err(PacketParseError::Empty),
+        Some(&got) if mode == ParseMode::Strict && got != expected => {
+            Err(PacketParseError::InvalidToken { expected, got })
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+/// Parses the message of a talk packet, which follows `header` bytes,
+/// the length byte is always the second one.
+///
+/// The message is cut at the length byte, or at an earlier null byte.
+fn talk_str(value: &[u8], header: usize) -> Result<CString, PacketParseError> {
+    if value.len() < header {
+        return Err(PacketParseError::Truncated(header, value.len()));
+    }
+    // Message and its terminating null byte, padding is optional
+    let len = value[1] as usize;
+    let min = header + len + 1;
+    if value.len() < min {
+        return Err(PacketParseError::Truncated(min, value.len()));
+    }
+    let str = &value[header..header + len];
+    let end = first_nul(str).unwrap_or(len);
+    Ok(unsafe { CString::from_vec_unchecked(str[..end].to_vec()) })
+}
+
 // Raw to clean packet structs:
 
 impl From<RawJoinRequest> for JoinRequest {
     fn from(value: RawJoinRequest) -> Self {
         let cstr = value.name.to_vec();
         Self {
-            name: unsafe { CString::from_vec_unchecked(cstr[0..first_nul(&cstr).unwrap_or(32)].to_vec()) },
+            name: unsafe {
+                CString::from_vec_unchecked(
+                    cstr[0..first_nul(&cstr).unwrap_or(cstr.len())].to_vec(),
+                )
+            },
         }
     }
 }
@@ -610,7 +1698,11 @@ impl From<RawJoinBroadcast> for JoinBroadcast {
         Self {
             player_pose: value.player_pose,
             id: value.id,
-            name: unsafe { CString::from_vec_unchecked(value.name[0..first_nul(&value.name).unwrap_or(32)].to_vec()) },
+            name: unsafe {
+                CString::from_vec_unchecked(
+                    value.name[0..first_nul(&value.name).unwrap_or(value.name.len())].to_vec(),
+                )
+            },
         }
     }
 }
@@ -667,269 +1759,548 @@ impl From<RawLagResponse> for LagResponse {
 
 // Clean to raw packet structs:
 
-impl Into<RawJoinRequest> for JoinRequest {
-    fn into(self) -> RawJoinRequest {
-        let mut name = [b'\0'; 31];
-        let mut i = 0;
-        for elem in self.name.into_bytes() {
-            name[i] = elem;
-            i += 1;
+impl From<JoinRequest> for RawJoinRequest {
+    fn from(value: JoinRequest) -> Self {
+        RawJoinRequest {
+            token: b'J',
+            name: name_array(&value.name),
         }
-        RawJoinRequest { token: b'J', name }
     }
 }
 
-impl Into<RawJoinResponse> for JoinResponse {
-    fn into(self) -> RawJoinResponse {
+impl From<JoinResponse> for RawJoinResponse {
+    fn from(value: JoinResponse) -> Self {
         RawJoinResponse {
             token: b'j',
-            max_clients: self.max_clients,
-            id: self.id,
+            max_clients: value.max_clients,
+            id: value.id,
         }
     }
 }
 
-impl Into<RawJoinBroadcast> for JoinBroadcast {
-    fn into(self) -> RawJoinBroadcast {
-        let mut name = [b'\0'; 32];
-        let mut i = 0;
-        for elem in self.name.into_bytes() {
-            name[i] = elem;
-            i += 1;
-        }
+impl From<JoinBroadcast> for RawJoinBroadcast {
+    fn from(value: JoinBroadcast) -> Self {
         RawJoinBroadcast {
             token: b'j',
-            player_pose: self.player_pose,
-            id: self.id,
-            name,
+            player_pose: value.player_pose,
+            id: value.id,
+            name: name_array(&value.name),
         }
     }
 }
 
-impl Into<RawExitBroadcast> for ExitBroadcast {
-    fn into(self) -> RawExitBroadcast {
+impl From<ExitBroadcast> for RawExitBroadcast {
+    fn from(value: ExitBroadcast) -> Self {
         RawExitBroadcast {
             token: b'e',
             _padding: 0,
-            id: self.id,
+            id: value.id,
         }
     }
 }
 
-impl Into<RawPoseRequest> for PoseRequest {
-    fn into(self) -> RawPoseRequest {
+impl From<PoseRequest> for RawPoseRequest {
+    fn from(value: PoseRequest) -> Self {
         RawPoseRequest {
             token: b'P',
-            player_pose: self.player_pose,
+            player_pose: value.player_pose,
         }
     }
 }
 
-impl Into<RawPoseBroadcast> for PoseBroadcast {
-    fn into(self) -> RawPoseBroadcast {
+impl From<PoseBroadcast> for RawPoseBroadcast {
+    fn from(value: PoseBroadcast) -> Self {
         RawPoseBroadcast {
             token: b'p',
-            player_pose: self.player_pose,
-            id: self.id,
+            player_pose: value.player_pose,
+            id: value.id,
         }
     }
 }
 
-impl Into<RawTalkRequest> for TalkRequest {
-    fn into(self) -> RawTalkRequest {
-        let bytes = self.str.as_bytes();
+impl From<TalkRequest> for RawTalkRequest {
+    fn from(value: TalkRequest) -> Self {
+        let bytes = value.str.as_bytes();
         RawTalkRequest {
             token: b'T',
             len: bytes.len() as u8,
-            str: self.str,
+            str: value.str,
         }
     }
 }
 
-impl Into<RawTalkBroadcast> for TalkBroadcast {
-    fn into(self) -> RawTalkBroadcast {
-        let bytes = self.str.as_bytes();
+impl From<TalkBroadcast> for RawTalkBroadcast {
+    fn from(value: TalkBroadcast) -> Self {
+        let bytes = value.str.as_bytes();
         RawTalkBroadcast {
             token: b't',
             len: bytes.len() as u8,
-            id: self.id,
-            str: self.str,
+            id: value.id,
+            str: value.str,
         }
     }
 }
 
-impl Into<RawLagRequest> for LagRequest {
-    fn into(self) -> RawLagRequest {
+impl From<LagRequest> for RawLagRequest {
+    fn from(value: LagRequest) -> Self {
         RawLagRequest {
             token: b'L',
-            stamp: self.stamp,
+            stamp: value.stamp,
         }
     }
 }
 
-impl Into<RawLagResponse> for LagResponse {
-    fn into(self) -> RawLagResponse {
+impl From<LagResponse> for RawLagResponse {
+    fn from(value: LagResponse) -> Self {
         RawLagResponse {
             token: b'l',
-            stamp: self.stamp,
+            stamp: value.stamp,
         }
     }
 }
 
-impl Into<Bytes> for RawJoinRequest {
-    fn into(self) -> Bytes {
-        let mut b = Bytes::new();
-        b.push(self.token);
-        b.extend_from_slice(&self.name);
-        b
+/// Copies `name` into a null-padded array, like raw join packets store it.
+fn name_array<const N: usize>(name: &CStr) -> [u8; N] {
+    let mut array = [b'\0'; N];
+    let bytes = name.to_bytes();
+    array[..bytes.len()].copy_from_slice(bytes);
+    array
+}
+
+/// Copies `parts` one after another into an array, they have to add up to exactly `N` bytes.
+fn concat_array<const N: usize>(parts: &[&[u8]]) -> [u8; N] {
+    let mut array = [0; N];
+    let mut at = 0;
+    for part in parts {
+        array[at..at + part.len()].copy_from_slice(part);
+        at += part.len();
     }
+    debug_assert_eq!(at, N, "Parts don't fill the array");
+    array
 }
 
-impl Into<Bytes> for RawJoinResponse {
-    fn into(self) -> Bytes {
-        let mut b = Bytes::new();
-        b.push(self.token);
-        b.push(self.max_clients);
-        b.extend_from_slice(&self.id.to_ne_bytes());
-        b
+impl RawJoinRequest {
+    /// Encodes the packet onto the stack, without allocating.
+    pub fn to_array(&self) -> [u8; JOIN_REQ_SIZE] {
+        concat_array(&[&[self.token], &self.name])
     }
 }
 
-impl Into<Bytes> for RawJoinBroadcast {
-    fn into(self) -> Bytes {
-        let mut b = Bytes::new();
-        b.push(self.token);
-        b.push(self.player_pose.animation);
-        b.push(self.player_pose.frame);
-        b.push(self.player_pose.action_or_mount);
-        for coord in self.player_pose.position {
-            b.extend_from_slice(&coord.to_ne_bytes());
-        }
-        b.extend_from_slice(&self.player_pose.direction.to_ne_bytes());
-        b.extend_from_slice(&self.id.to_ne_bytes());
-        b.extend_from_slice(&self.player_pose.sprite.to_ne_bytes());
-        b.extend_from_slice(&self.name);
-        b
+impl EncodePacket for RawJoinRequest {
+    fn encode_into(&self, b: &mut Bytes) {
+        b.extend_from_slice(&self.to_array());
+    }
+
+    fn encoded_len(&self) -> usize {
+        JOIN_REQ_SIZE
+    }
+}
+
+impl RawJoinResponse {
+    /// Encodes the packet onto the stack, without allocating.
+    pub fn to_array(&self) -> [u8; JOIN_RSP_SIZE] {
+        concat_array(&[&[self.token, self.max_clients], &self.id.to_le_bytes()])
+    }
+}
+
+impl EncodePacket for RawJoinResponse {
+    fn encode_into(&self, b: &mut Bytes) {
+        b.extend_from_slice(&self.to_array());
+    }
+
+    fn encoded_len(&self) -> usize {
+        JOIN_RSP_SIZE
     }
 }
 
-impl Into<Bytes> for RawExitBroadcast {
-    fn into(self) -> Bytes {
-        let mut b = Bytes::new();
-        b.push(self.token);
-        b.push(0);
-        b.extend_from_slice(&self.id.to_ne_bytes());
-        b
+impl RawJoinBroadcast {
+    /// Encodes the packet onto the stack, without allocating.
+    pub fn to_array(&self) -> [u8; JOIN_BRC_SIZE] {
+        // Id goes between the direction and the sprite of the pose
+        let pose = self.player_pose.to_array();
+        let (before_sprite, sprite) = pose.split_at(PLAYER_POSE_SIZE - 2);
+        concat_array(&[
+            &[self.token],
+            before_sprite,
+            &self.id.to_le_bytes(),
+            sprite,
+            &self.name,
+        ])
+    }
+}
+
+impl EncodePacket for RawJoinBroadcast {
+    fn encode_into(&self, b: &mut Bytes) {
+        b.extend_from_slice(&self.to_array());
+    }
+
+    fn encoded_len(&self) -> usize {
+        JOIN_BRC_SIZE
+    }
+}
+
+impl RawExitBroadcast {
+    /// Encodes the packet onto the stack, without allocating.
+    pub fn to_array(&self) -> [u8; EXIT_BRC_SIZE] {
+        concat_array(&[&[self.token, 0], &self.id.to_le_bytes()])
+    }
+}
+
+impl EncodePacket for RawExitBroadcast {
+    fn encode_into(&self, b: &mut Bytes) {
+        b.extend_from_slice(&self.to_array());
+    }
+
+    fn encoded_len(&self) -> usize {
+        EXIT_BRC_SIZE
     }
 }
 
-impl Into<Bytes> for RawPoseRequest {
-    fn into(self) -> Bytes {
-        let mut b = Bytes::new();
-        b.push(self.token);
-        b.push(self.player_pose.animation);
-        b.push(self.player_pose.frame);
-        b.push(self.player_pose.action_or_mount);
-        for coord in self.player_pose.position {
-            b.extend_from_slice(&coord.to_ne_bytes());
+impl RawPoseRequest {
+    /// Encodes the packet onto the stack, without allocating.
+    pub fn to_array(&self) -> [u8; POSE_REQ_SIZE] {
+        concat_array(&[&[self.token], &self.player_pose.to_array()])
+    }
+}
+
+impl EncodePacket for RawPoseRequest {
+    fn encode_into(&self, b: &mut Bytes) {
+        b.extend_from_slice(&self.to_array());
+    }
+
+    fn encoded_len(&self) -> usize {
+        POSE_REQ_SIZE
+    }
+}
+
+impl RawPoseBroadcast {
+    /// Encodes the packet onto the stack, without allocating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::packets::*;
+    ///
+    /// let pose_brc = PoseBroadcast {
+    ///     player_pose: PlayerPose::default(),
+    ///     id: 7,
+    /// };
+    /// let array = RawPoseBroadcast::from(pose_brc.clone()).to_array();
+    /// assert_eq!(array.len(), POSE_BRC_SIZE);
+    /// assert_eq!(array[..], Bytes::from(pose_brc.clone())[..]);
+    /// assert_eq!(pose_brc.to_array(), array);
+    /// ```
+    pub fn to_array(&self) -> [u8; POSE_BRC_SIZE] {
+        concat_array(&[
+            &[self.token],
+            &self.player_pose.to_array(),
+            &self.id.to_le_bytes(),
+        ])
+    }
+}
+
+impl EncodePacket for RawPoseBroadcast {
+    fn encode_into(&self, b: &mut Bytes) {
+        b.extend_from_slice(&self.to_array());
+    }
+
+    fn encoded_len(&self) -> usize {
+        POSE_BRC_SIZE
+    }
+}
+
+/// Writes a talk packet: `head` (token, length and possibly id) followed by `str`.
+fn encode_talk(b: &mut Bytes, head: &[u8], str: &CStr) {
+    b.extend_from_slice(head);
+    b.extend_from_slice(str.to_bytes_with_nul());
+    b.push(b'\0'); // Additional null-byte for padding, not terminating
+}
+
+/// Length of what [`encode_talk`] writes.
+fn talk_len(head_len: usize, str: &CStr) -> usize {
+    head_len + str.to_bytes_with_nul().len() + 1
+}
+
+impl EncodePacket for RawTalkRequest {
+    fn encode_into(&self, b: &mut Bytes) {
+        encode_talk(b, &[self.token, self.len], &self.str);
+    }
+
+    fn encoded_len(&self) -> usize {
+        talk_len(2, &self.str)
+    }
+}
+
+impl EncodePacket for RawTalkBroadcast {
+    fn encode_into(&self, b: &mut Bytes) {
+        let [id_low, id_high] = self.id.to_le_bytes();
+        encode_talk(b, &[self.token, self.len, id_low, id_high], &self.str);
+    }
+
+    fn encoded_len(&self) -> usize {
+        talk_len(4, &self.str)
+    }
+}
+
+impl RawLagRequest {
+    /// Encodes the packet onto the stack, without allocating.
+    pub fn to_array(&self) -> [u8; LAG_REQ_SIZE] {
+        concat_array(&[&[self.token], &self.stamp])
+    }
+}
+
+impl EncodePacket for RawLagRequest {
+    fn encode_into(&self, b: &mut Bytes) {
+        b.extend_from_slice(&self.to_array());
+    }
+
+    fn encoded_len(&self) -> usize {
+        LAG_REQ_SIZE
+    }
+}
+
+impl RawLagResponse {
+    /// Encodes the packet onto the stack, without allocating.
+    pub fn to_array(&self) -> [u8; LAG_RSP_SIZE] {
+        concat_array(&[&[self.token], &self.stamp])
+    }
+}
+
+impl EncodePacket for RawLagResponse {
+    fn encode_into(&self, b: &mut Bytes) {
+        b.extend_from_slice(&self.to_array());
+    }
+
+    fn encoded_len(&self) -> usize {
+        LAG_RSP_SIZE
+    }
+}
+
+// Clean packets are encoded straight from their fields, without copying strings:
+
+impl JoinRequest {
+    /// Encodes the packet onto the stack, without allocating.
+    pub fn to_array(&self) -> [u8; JOIN_REQ_SIZE] {
+        RawJoinRequest {
+            token: b'J',
+            name: name_array(&self.name),
         }
-        b.extend_from_slice(&self.player_pose.direction.to_ne_bytes());
-        b.extend_from_slice(&self.player_pose.sprite.to_ne_bytes());
-        b
+        .to_array()
     }
 }
 
-impl Into<Bytes> for RawPoseBroadcast {
-    fn into(self) -> Bytes {
-        let mut b = Bytes::new();
-        b.push(self.token);
-        b.push(self.player_pose.animation);
-        b.push(self.player_pose.frame);
-        b.push(self.player_pose.action_or_mount);
-        for coord in self.player_pose.position {
-            b.extend_from_slice(&coord.to_ne_bytes());
+impl JoinBroadcast {
+    /// Encodes the packet onto the stack, without allocating.
+    pub fn to_array(&self) -> [u8; JOIN_BRC_SIZE] {
+        RawJoinBroadcast {
+            token: b'j',
+            player_pose: self.player_pose.clone(),
+            id: self.id,
+            name: name_array(&self.name),
         }
-        b.extend_from_slice(&self.player_pose.direction.to_ne_bytes());
-        b.extend_from_slice(&self.player_pose.sprite.to_ne_bytes());
-        b.extend_from_slice(&self.id.to_ne_bytes());
-        b
+        .to_array()
     }
 }
 
-impl Into<Bytes> for RawTalkRequest {
-    fn into(self) -> Bytes {
-        let mut b = Bytes::new();
-        let mut string = self.str.into_bytes_with_nul();
-        string.push(b'\0'); // Additional null-byte for padding, not terminating
-        b.push(self.token);
-        b.push(self.len);
-        b.extend(&string);
-        b
+impl EncodePacket for TalkRequest {
+    fn encode_into(&self, b: &mut Bytes) {
+        encode_talk(b, &[b'T', self.str.as_bytes().len() as u8], &self.str);
+    }
+
+    fn encoded_len(&self) -> usize {
+        talk_len(2, &self.str)
     }
 }
 
-impl Into<Bytes> for RawTalkBroadcast {
-    fn into(self) -> Bytes {
-        let mut b = Bytes::new();
-        let mut string = self.str.into_bytes_with_nul();
-        string.push(b'\0'); // Additional null-byte for padding, not terminating
-        b.push(self.token);
-        b.push(self.len);
-        b.extend_from_slice(&self.id.to_ne_bytes());
-        b.extend(&string);
-        b
+impl EncodePacket for TalkBroadcast {
+    fn encode_into(&self, b: &mut Bytes) {
+        let [id_low, id_high] = self.id.to_le_bytes();
+        let len = self.str.as_bytes().len() as u8;
+        encode_talk(b, &[b't', len, id_low, id_high], &self.str);
+    }
+
+    fn encoded_len(&self) -> usize {
+        talk_len(4, &self.str)
     }
 }
 
-impl Into<Bytes> for RawLagRequest {
-    fn into(self) -> Bytes {
-        let mut b = Bytes::new();
-        b.push(self.token);
-        b.extend_from_slice(&self.stamp);
-        b
+/// Fields of a pose, in the order [`PlayerPose`] encodes them.
+fn pose_fields(pose: &PlayerPose) -> Vec<PacketField> {
+    let position = pose.position;
+    vec![
+        PacketField::new("player_pose.animation", 1, format!("{:?}", pose.animation)),
+        PacketField::new("player_pose.frame", 1, pose.frame),
+        PacketField::new(
+            "player_pose.action_or_mount",
+            1,
+            format!("{:?}", pose.action_or_mount),
+        ),
+        PacketField::new("player_pose.position.x", 4, position.x),
+        PacketField::new("player_pose.position.y", 4, position.y),
+        PacketField::new("player_pose.position.z", 4, position.z),
+        PacketField::new("player_pose.direction", 4, pose.direction),
+        PacketField::new("player_pose.sprite", 2, pose.sprite),
+    ]
+}
+
+/// Token field, shown as a character.
+fn token_field(token: u8) -> PacketField {
+    PacketField::new("token", 1, format!("{:?}", token as char))
+}
+
+/// String field of `len` bytes, shown up to its first nul byte.
+fn text_field(name: &'static str, len: usize, bytes: &[u8]) -> PacketField {
+    let text = &bytes[..first_nul(bytes).unwrap_or(bytes.len())];
+    PacketField::new(name, len, format!("{:?}", String::from_utf8_lossy(text)))
+}
+
+impl PacketFields for RawJoinRequest {
+    fn fields(&self) -> Vec<PacketField> {
+        vec![
+            token_field(self.token),
+            text_field("name", self.name.len(), &self.name),
+        ]
     }
 }
 
-impl Into<Bytes> for RawLagResponse {
-    fn into(self) -> Bytes {
-        let mut b = Bytes::new();
-        b.push(self.token);
-        b.extend_from_slice(&self.stamp);
-        b
+impl PacketFields for RawJoinResponse {
+    fn fields(&self) -> Vec<PacketField> {
+        vec![
+            token_field(self.token),
+            PacketField::new("max_clients", 1, self.max_clients),
+            PacketField::new("id", 2, self.id),
+        ]
     }
 }
 
-#[doc(hidden)]
-macro_rules! impl_from_bytes_for_clean {
-    ($($name:ident)+) => {
-        $(
-            impl TryFrom<Bytes> for $name {
-                type Error = PacketParseError;
+impl PacketFields for RawJoinBroadcast {
+    fn fields(&self) -> Vec<PacketField> {
+        let mut fields = vec![token_field(self.token)];
+        fields.extend(pose_fields(&self.player_pose));
+        // Id comes before the sprite here, unlike in pose broadcasts
+        let sprite = fields.pop().unwrap();
+        fields.push(PacketField::new("id", 2, self.id));
+        fields.push(sprite);
+        fields.push(text_field("name", self.name.len(), &self.name));
+        fields
+    }
+}
 
-                fn try_from(value: Bytes) -> Result<Self, Self::Error> {
-                    match <concat_idents!(id = Raw, $name { id })>::try_from(value) {
-                        Err(e) => return Err(e),
-                        Ok(d) => return Ok($name::from(d)),
-                    }
-                }
-            }
-        )+
-    };
+impl PacketFields for RawExitBroadcast {
+    fn fields(&self) -> Vec<PacketField> {
+        vec![
+            token_field(self.token),
+            PacketField::new("_padding", 1, self._padding),
+            PacketField::new("id", 2, self.id),
+        ]
+    }
+}
+
+impl PacketFields for RawPoseRequest {
+    fn fields(&self) -> Vec<PacketField> {
+        let mut fields = vec![token_field(self.token)];
+        fields.extend(pose_fields(&self.player_pose));
+        fields
+    }
 }
 
-impl_from_bytes_for_clean!(JoinRequest JoinResponse JoinBroadcast ExitBroadcast PoseRequest PoseBroadcast TalkRequest TalkBroadcast LagRequest LagResponse);
+impl PacketFields for RawPoseBroadcast {
+    fn fields(&self) -> Vec<PacketField> {
+        let mut fields = vec![token_field(self.token)];
+        fields.extend(pose_fields(&self.player_pose));
+        fields.push(PacketField::new("id", 2, self.id));
+        fields
+    }
+}
 
-#[doc(hidden)]
-macro_rules! impl_into_bytes_for_clean {
-    ($($name:ident)+) => {
-        $(
-            impl Into<Bytes> for $name {
-                fn into(self) -> Bytes {
-                    Into::<concat_idents!(id = Raw, $name { id })>::into(self).into()
-                }
-            }
-        )+
-    };
+impl PacketFields for RawTalkRequest {
+    fn fields(&self) -> Vec<PacketField> {
+        let str = self.str.as_bytes_with_nul();
+        vec![
+            token_field(self.token),
+            PacketField::new("len", 1, self.len),
+            text_field("str", str.len(), str),
+            PacketField::new("_padding", 1, 0),
+        ]
+    }
+}
+
+impl PacketFields for RawTalkBroadcast {
+    fn fields(&self) -> Vec<PacketField> {
+        let str = self.str.as_bytes_with_nul();
+        vec![
+            token_field(self.token),
+            PacketField::new("len", 1, self.len),
+            PacketField::new("id", 2, self.id),
+            text_field("str", str.len(), str),
+            PacketField::new("_padding", 1, 0),
+        ]
+    }
+}
+
+impl PacketFields for RawLagRequest {
+    fn fields(&self) -> Vec<PacketField> {
+        vec![
+            token_field(self.token),
+            PacketField::new("stamp", 3, format!("{:?}", self.stamp)),
+        ]
+    }
 }
 
-impl_into_bytes_for_clean!(JoinRequest JoinResponse JoinBroadcast ExitBroadcast PoseRequest PoseBroadcast TalkRequest TalkBroadcast LagRequest LagResponse);
+impl PacketFields for RawLagResponse {
+    fn fields(&self) -> Vec<PacketField> {
+        vec![
+            token_field(self.token),
+            PacketField::new("stamp", 3, format!("{:?}", self.stamp)),
+        ]
+    }
+}
+
+impl_hex_display!(RawJoinRequest RawJoinResponse RawJoinBroadcast RawExitBroadcast RawPoseRequest RawPoseBroadcast RawTalkRequest RawTalkBroadcast RawLagRequest RawLagResponse);
+
+impl_owned_encoding!(RawJoinRequest RawJoinResponse RawJoinBroadcast RawExitBroadcast RawPoseRequest RawPoseBroadcast RawTalkRequest RawTalkBroadcast RawLagRequest RawLagResponse);
+
+impl_clean_packets!(JoinRequest JoinResponse JoinBroadcast ExitBroadcast PoseRequest PoseBroadcast TalkRequest TalkBroadcast LagRequest LagResponse);
+
+impl_copy_to_array!(JoinResponse JOIN_RSP_SIZE ExitBroadcast EXIT_BRC_SIZE PoseRequest POSE_REQ_SIZE PoseBroadcast POSE_BRC_SIZE LagRequest LAG_REQ_SIZE LagResponse LAG_RSP_SIZE);
+impl_array_encoding!(JoinRequest JOIN_REQ_SIZE JoinResponse JOIN_RSP_SIZE JoinBroadcast JOIN_BRC_SIZE ExitBroadcast EXIT_BRC_SIZE PoseRequest POSE_REQ_SIZE PoseBroadcast POSE_BRC_SIZE LagRequest LAG_REQ_SIZE LagResponse LAG_RSP_SIZE);
+
+// Version-agnostic traits:
+
+impl ChatEvent for TalkBroadcast {
+    fn author_id(&self) -> u16 {
+        self.id
+    }
+
+    fn text(&self) -> String {
+        self.str.to_string_lossy().replace('\u{0}', "")
+    }
+}
+
+impl PlayerStateEvent for JoinBroadcast {
+    fn player_id(&self) -> u16 {
+        self.id
+    }
+
+    fn position(&self) -> Position {
+        self.player_pose.position
+    }
+
+    fn direction(&self) -> f32 {
+        self.player_pose.direction
+    }
+}
+
+impl PlayerStateEvent for PoseBroadcast {
+    fn player_id(&self) -> u16 {
+        self.id
+    }
+
+    fn position(&self) -> Position {
+        self.player_pose.position
+    }
+
+    fn direction(&self) -> f32 {
+        self.player_pose.direction
+    }
+}
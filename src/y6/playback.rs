@@ -0,0 +1,318 @@
+use super::bot::{
+    intercept, patch_world_with, Bot, BotData, InterceptAction, Player, Receiver, Sender, Taps,
+    World, MESSAGE_CAPACITY,
+};
+use super::handle::{subscribe, Event, EventSender, EVENT_CAPACITY};
+use super::packets::{parse_packet, JoinResponse, Packet};
+use super::replay::{Direction, Replay, ReplayError};
+use super::utils::BotError;
+use crate::common::{Instant, Runtime};
+
+use std::path::Path;
+use std::sync::Arc;
+
+use futures_util::Stream;
+use tokio::sync::{broadcast, mpsc, watch};
+
+/// What the bot ended up with after a [`ReplayPlayer`] played the whole replay.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Playback {
+    /// The bot itself
+    pub player: Player,
+    /// The world as the bot saw it at the end
+    pub world: World,
+    /// Everything the callbacks said, in order
+    pub said: Vec<String>,
+}
+
+/// Plays a recorded [`Replay`] into the callbacks of a [`Bot`], without connecting anywhere.
+///
+/// Frames the server sent to one connection of the replay, see [`ReplayPlayer::peer`],
+/// go through the interceptors, the [`IgnoreList`](super::ignore::IgnoreList) and
+/// the callbacks of the bot like on a live connection. Join responses recreate the world
+/// and call the ready callback. Frames the client sent are skipped, callbacks of the bot
+/// produce their own, what they say ends up in [`Playback::said`].
+///
+/// By default frames are played at their original pace, see [`ReplayPlayer::speed`].
+///
+/// [`ReplayPlayer::reenact`] plays the other direction instead, sending what the recorded
+/// client sent to a live server.
+///
+/// # Examples
+///
+/// ## Regression test against captured traffic:
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+/// use std::{ffi::CString, sync::Arc, time::Duration};
+/// use tokio::sync::{Mutex, RwLock};
+///
+/// #[callback]
+/// async fn echo(
+///     talk_brc: TalkBroadcast,
+///     _: Arc<Mutex<Player>>,
+///     _: Arc<RwLock<World>>,
+///     sender: MessageSender,
+/// ) -> BotResult {
+///     sender.send(talk_brc.str.to_string_lossy().into_owned()).await.unwrap();
+///     Ok(())
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// // Usually loaded with `ReplayPlayer::load` from a file made by `Bot::record_session`
+/// let frame = |ms, frame| ReplayEntry {
+///     at: Duration::from_millis(ms),
+///     direction: Direction::ToClient,
+///     peer: 0,
+///     frame,
+/// };
+/// let replay = Replay {
+///     entries: vec![
+///         frame(0, JoinResponse { max_clients: 8, id: 1 }.into()),
+///         frame(10, JoinBroadcast {
+///             player_pose: Default::default(),
+///             id: 2,
+///             name: CString::new("player").unwrap(),
+///         }
+///         .into()),
+///         frame(20, TalkBroadcast { id: 2, str: CString::new("hello").unwrap() }.into()),
+///     ],
+/// };
+///
+/// let mut bot = BotBuilder::new("echo").build();
+/// bot.on_talk(echo);
+/// let playback = ReplayPlayer::new(bot, replay).speed(10.0).run().await.unwrap();
+/// assert_eq!(playback.player.id, 1);
+/// assert_eq!(playback.world.max_clients, 8);
+/// assert_eq!(playback.said, ["hello"]);
+/// assert!(playback.world.player_by_name("player").is_some());
+/// # }
+/// ```
+pub struct ReplayPlayer {
+    bot: Bot,
+    replay: Replay,
+    peer: Option<u16>,
+    speed: Option<f64>,
+    events: EventSender,
+}
+
+impl ReplayPlayer {
+    /// Creates a new [`ReplayPlayer`] playing `replay` into the callbacks of `bot`.
+    pub fn new(bot: Bot, replay: Replay) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        Self {
+            bot,
+            replay,
+            peer: None,
+            speed: Some(1.0),
+            events,
+        }
+    }
+
+    /// Loads the replay at `path`, see [`Replay::load`].
+    pub fn load<P: AsRef<Path>>(bot: Bot, path: P) -> Result<Self, ReplayError> {
+        Ok(Self::new(bot, Replay::load(path)?))
+    }
+
+    /// Sets the connection to play, defaults to the connection of the first entry.
+    ///
+    /// Replays of a [`Bot`] have only one, replays of a server or a proxy have one per client.
+    pub fn peer(mut self, peer: u16) -> Self {
+        self.peer = Some(peer);
+        self
+    }
+
+    /// Scales the pace of the replay, `2.0` plays twice as fast as it was recorded.
+    ///
+    /// Speeds that aren't positive and finite play every frame without waiting.
+    pub fn speed(mut self, speed: f64) -> Self {
+        self.speed = (speed.is_finite() && speed > 0.0).then_some(speed);
+        self
+    }
+
+    /// Subscribes to the events of the playback, like [`BotHandle::events`].
+    ///
+    /// Subscribe before [`ReplayPlayer::run`], the stream ends once it returns.
+    ///
+    /// [`BotHandle::events`]: super::handle::BotHandle::events
+    pub fn events(&self) -> impl Stream<Item = Event> + Send + Unpin {
+        subscribe(&self.events)
+    }
+
+    /// Re-enacts the replay against a live server: runs the bot and sends the pose and talk
+    /// requests the recorded client sent, at the pace of [`ReplayPlayer::speed`].
+    ///
+    /// Joining and lag requests are left to the bot itself, the recorded ones are skipped.
+    /// Callbacks run on what the live server sends, as usual. Timing starts once the bot
+    /// has joined and is relative to the recorded join request.
+    ///
+    /// Returns the running bot after the last request was sent, useful for reproducing bugs
+    /// and for ghost bots retracing the moves of a previous player.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use std::{ffi::CString, time::Duration};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let server = Server::new(8).bind("127.0.0.1:0").await.unwrap();
+    /// let address = format!("ws://{}", server.address);
+    /// let (_, observer) = BotBuilder::new("observer").address(&address).build().run().await.unwrap();
+    ///
+    /// let request = |ms, frame| ReplayEntry {
+    ///     at: Duration::from_millis(ms),
+    ///     direction: Direction::ToServer,
+    ///     peer: 0,
+    ///     frame,
+    /// };
+    /// let mut pose = PlayerPose::default();
+    /// pose.position = [5.0, 5.0, 0.0].into();
+    /// let replay = Replay {
+    ///     entries: vec![
+    ///         request(0, JoinRequest { name: CString::new("player").unwrap() }.into()),
+    ///         request(10, PoseRequest { player_pose: pose }.into()),
+    ///         request(20, TalkRequest { str: CString::new("I was here").unwrap() }.into()),
+    ///     ],
+    /// };
+    /// let ghost = BotBuilder::new("ghost").address(&address).build();
+    /// let (_, _ghost) = ReplayPlayer::new(ghost, replay).reenact().await.unwrap();
+    ///
+    /// let seen = async {
+    ///     loop {
+    ///         let world = observer.1.read().await;
+    ///         let ghost = world.player_by_name("ghost").map(|ghost| ghost.pose.position);
+    ///         if let (Some(message), Some(position)) = (world.messages.iter().next(), ghost) {
+    ///             if position == [5.0, 5.0, 0.0].into() {
+    ///                 return message.content.clone();
+    ///             }
+    ///         }
+    ///         drop(world);
+    ///         tokio::time::sleep(Duration::from_millis(5)).await;
+    ///     }
+    /// };
+    /// let said = tokio::time::timeout(Duration::from_secs(5), seen).await.unwrap();
+    /// assert_eq!(said, "I was here");
+    /// # }
+    /// ```
+    pub async fn reenact(self) -> Result<((Receiver, Sender), BotData), BotError> {
+        let peer = self
+            .peer
+            .or_else(|| self.replay.entries.first().map(|entry| entry.peer));
+        let requests: Vec<_> = self
+            .replay
+            .entries
+            .into_iter()
+            .filter(|entry| entry.direction == Direction::ToServer && Some(entry.peer) == peer)
+            .collect();
+        let base = requests.first().map(|entry| entry.at).unwrap_or_default();
+        let runtime = self.bot.executor()?;
+        let (threads, data) = self.bot.run().await?;
+        let start = Instant::now();
+        let reenacted = requests
+            .into_iter()
+            .filter(|entry| matches!(entry.frame.first(), Some(b'P' | b'T')));
+        for entry in reenacted {
+            if let Some(speed) = self.speed {
+                sleep_until(
+                    &*runtime,
+                    start + entry.at.saturating_sub(base).div_f64(speed),
+                )
+                .await;
+            }
+            match parse_packet(&entry.frame) {
+                Ok(Packet::PoseRequest(pose_req)) => {
+                    data.3.send_replace(pose_req.player_pose);
+                }
+                Ok(Packet::TalkRequest(talk_req)) => data
+                    .2
+                    .send(talk_req.str.to_string_lossy().into_owned())
+                    .await
+                    .map_err(|_| BotError::ChannelClosed("sender"))?,
+                // Malformed requests would only get the bot kicked
+                _ => {}
+            }
+        }
+        Ok((threads, data))
+    }
+
+    /// Plays the replay and returns the final state.
+    ///
+    /// Stops at the first callback returning an error, or at a frame that doesn't parse.
+    pub async fn run(self) -> Result<Playback, BotError> {
+        let runtime = self.bot.executor()?;
+        let (tx, mut rx) = mpsc::channel(MESSAGE_CAPACITY);
+        let tx = Arc::new(tx);
+        let callbacks = self.bot.callbacks();
+        let interceptors = self.bot.interceptors();
+        let ignore_list = self.bot.ignore_list();
+        let (mut bot, mut world) = self.bot.initial_data(&JoinResponse {
+            max_clients: 255,
+            id: 0,
+        });
+        let peer = self
+            .peer
+            .or_else(|| self.replay.entries.first().map(|entry| entry.peer));
+        let sprites = self.bot.sprite_catalog();
+        let start = Instant::now();
+        let mut said = vec![];
+        let frames = self
+            .replay
+            .entries
+            .into_iter()
+            .filter(|entry| entry.direction == Direction::ToClient && Some(entry.peer) == peer);
+        for entry in frames {
+            if let Some(speed) = self.speed {
+                sleep_until(&*runtime, start + entry.at.div_f64(speed)).await;
+            }
+            let mut frame = entry.frame;
+            if let Ok(Packet::JoinResponse(join_rsp)) = parse_packet(&frame) {
+                (bot, world) = self.bot.initial_data(&join_rsp);
+                let pose = bot.lock().await.pose.clone();
+                let data = (
+                    Arc::clone(&bot),
+                    Arc::clone(&world),
+                    Arc::clone(&tx),
+                    // Nothing is sent, poses set by the ready callback go nowhere
+                    Arc::new(watch::channel(pose).0),
+                );
+                self.bot.joined(0, &join_rsp, &data).await?;
+            } else if intercept(&interceptors, &mut frame) != InterceptAction::Drop {
+                patch_world_with(
+                    Arc::clone(&callbacks),
+                    &frame,
+                    Arc::clone(&world),
+                    Arc::clone(&bot),
+                    self.bot.replaces_invalid_utf8(),
+                    Arc::clone(&tx),
+                    Taps {
+                        events: Some(&self.events),
+                        sprites: sprites.as_deref(),
+                        ignore: Some(&ignore_list),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            }
+            while let Ok(message) = rx.try_recv() {
+                said.push(message);
+            }
+        }
+        let player = bot.lock().await.clone();
+        let world = world.read().await.clone();
+        Ok(Playback {
+            player,
+            world,
+            said,
+        })
+    }
+}
+
+/// Sleeps on `runtime` until `deadline`, completes right away if it already passed.
+async fn sleep_until(runtime: &dyn Runtime, deadline: Instant) {
+    let now = Instant::now();
+    if deadline > now {
+        runtime.sleep(deadline - now).await;
+    }
+}
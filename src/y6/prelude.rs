@@ -1,6 +1,50 @@
 #[cfg(feature = "bot")]
 pub use super::bot::*;
+#[cfg(feature = "bot")]
+pub use super::chatlog::*;
+#[cfg(all(feature = "packets", feature = "std"))]
+pub use super::client::*;
+#[cfg(feature = "codec")]
+pub use super::codec::*;
+#[cfg(feature = "bot")]
+pub use super::commands::*;
+#[cfg(feature = "bot")]
+pub use super::delivery::*;
+#[cfg(feature = "ffi")]
+pub use super::ffi::*;
+#[cfg(feature = "bot")]
+pub use super::handle::*;
+#[cfg(feature = "bot")]
+pub use super::ignore::*;
+#[cfg(feature = "bot")]
+pub use super::latency::*;
+#[cfg(feature = "bot")]
+pub use super::manager::*;
 #[cfg(feature = "packets")]
 pub use super::packets::*;
+#[cfg(feature = "bot")]
+pub use super::playback::*;
+#[cfg(feature = "packets")]
+pub use super::protocol::*;
+#[cfg(feature = "server")]
+pub use super::proxy::*;
+#[cfg(feature = "bot")]
+pub use super::recording::*;
+#[cfg(all(feature = "packets", feature = "std"))]
+pub use super::replay::*;
+#[cfg(feature = "server")]
+pub use super::server::*;
+#[cfg(feature = "bot")]
+pub use super::snapshot::*;
+#[cfg(feature = "packets")]
+pub use super::sprites::{Sprite, SpriteCatalog};
+#[cfg(feature = "bot")]
+pub use super::stats::*;
+#[cfg(feature = "bot")]
+pub use super::test_util::*;
 #[cfg(any(feature = "bot", feature = "packets"))]
 pub use super::utils::*;
+#[cfg(all(feature = "packets", feature = "std"))]
+pub use super::world::pathfinding::*;
+#[cfg(all(feature = "packets", feature = "std"))]
+pub use super::world::terrain::*;
@@ -0,0 +1,110 @@
+use super::packets::{
+    parse_packet, JoinBroadcast, JoinRequest, Packet, PlayerPose, PoseBroadcast, PoseRequest,
+    TalkBroadcast, TalkRequest,
+};
+use super::utils::BotError;
+use crate::common::{Bytes, Position, Version};
+use crate::traits::{Protocol, ProtocolEvent};
+
+use alloc::ffi::CString;
+use alloc::vec::Vec;
+
+/// [`Protocol`] of the Y6 version, for code generic over versions.
+///
+/// # Examples
+///
+/// ## Generic chat relay instantiated for Y6:
+/// ```
+/// use asciicker_rs::common::{Transport, WebSocketTransport};
+/// use asciicker_rs::traits::*;
+/// use asciicker_rs::y6::prelude::*;
+///
+/// /// Joins, then returns the first chat message of someone else.
+/// async fn first_message<P: Protocol>(address: &str, nickname: &str) -> Result<String, BotError> {
+///     let (mut sink, mut stream) = WebSocketTransport.connect(address).await?;
+///     sink.send_frame(P::join(nickname)?).await?;
+///     let mut id = None;
+///     while let Some(frame) = stream.next_frame().await {
+///         match P::decode(&frame?)? {
+///             ProtocolEvent::Joined { id: joined, .. } => {
+///                 id = Some(joined);
+///                 sink.send_frame(P::pose([1.0, 2.0, 0.0].into(), 90.0)).await?;
+///             }
+///             ProtocolEvent::Chat(chat) if Some(chat.author_id()) != id => return Ok(chat.text()),
+///             _ => {}
+///         }
+///     }
+///     Err(BotError::ChannelClosed("server"))
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let server = Server::new(8);
+/// let address = format!("ws://{}", server.bind("127.0.0.1:0").await.unwrap().address);
+/// let listener = tokio::spawn({
+///     let address = address.clone();
+///     async move { first_message::<Y6>(&address, "listener").await }
+/// });
+/// # while server.clients().await.is_empty() {
+/// #     tokio::task::yield_now().await;
+/// # }
+/// let mut talker = Client::connect("talker", &address).await.unwrap();
+/// talker.send_talk("hello").await.unwrap();
+/// assert_eq!(listener.await.unwrap().unwrap(), "hello");
+/// # }
+/// ```
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Y6;
+
+impl Protocol for Y6 {
+    const VERSION: Version = Version::Y6;
+    type Packet = Packet;
+    type Chat = TalkBroadcast;
+    type PlayerState = PoseBroadcast;
+
+    fn join(nickname: &str) -> Result<Bytes, BotError> {
+        Ok(JoinRequest {
+            name: CString::new(nickname)?,
+        }
+        .into())
+    }
+
+    fn pose(position: Position, direction: f32) -> Bytes {
+        PoseRequest {
+            player_pose: PlayerPose {
+                position,
+                direction,
+                ..PlayerPose::default()
+            },
+        }
+        .into()
+    }
+
+    fn talk(text: &str) -> Result<Vec<Bytes>, BotError> {
+        Ok(TalkRequest::split(text)?
+            .into_iter()
+            .map(Bytes::from)
+            .collect())
+    }
+
+    fn decode(frame: &[u8]) -> Result<ProtocolEvent<Self>, BotError> {
+        Ok(match parse_packet(frame).map_err(BotError::PacketParse)? {
+            Packet::JoinResponse(join_rsp) => ProtocolEvent::Joined {
+                id: join_rsp.id,
+                max_clients: join_rsp.max_clients,
+            },
+            Packet::JoinBroadcast(JoinBroadcast {
+                player_pose,
+                id,
+                name,
+            }) => ProtocolEvent::PlayerJoined {
+                nickname: name.to_string_lossy().into_owned(),
+                state: PoseBroadcast { player_pose, id },
+            },
+            Packet::ExitBroadcast(exit_brc) => ProtocolEvent::PlayerLeft(exit_brc.id),
+            Packet::PoseBroadcast(pose_brc) => ProtocolEvent::PlayerMoved(pose_brc),
+            Packet::TalkBroadcast(talk_brc) => ProtocolEvent::Chat(talk_brc),
+            packet => ProtocolEvent::Other(packet),
+        })
+    }
+}
@@ -0,0 +1,214 @@
+use super::packets::{parse_packet, Bytes, Packet};
+use super::replay::{Direction, SharedRecorder};
+use super::server::{accept_websocket, listen, RunningServer};
+use super::utils::BotError;
+use crate::common::transport::{FrameSink, FrameStream, Transport, WebSocketTransport};
+
+use std::sync::Arc;
+
+use tokio::net::{TcpListener, TcpStream};
+
+/// Type alias for a proxy hook, gets every packet passing through the [`Proxy`].
+///
+/// Returning the packet (possibly modified) forwards it, returning [`None`] drops it.
+pub type ProxyHook = Arc<dyn Fn(Direction, Packet) -> Option<Packet> + Send + Sync>;
+
+/// Man-in-the-middle proxy between real clients and a real server.
+///
+/// Every client connecting to the proxy gets its own connection to the upstream server,
+/// packets are forwarded both ways through the hooks added with [`Proxy::on_packet`].
+/// Hooks run in registration order until one of them drops the packet.
+///
+/// Frames that don't parse as packets, like packets of a newer version, are forwarded untouched.
+/// Without hooks frames aren't parsed at all.
+///
+/// # Examples
+///
+/// ## Logging and censoring chat:
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let server = Server::new(8).bind("127.0.0.1:0").await.unwrap();
+/// let mut proxy = Proxy::new(format!("ws://{}", server.address));
+/// proxy.on_packet(|direction, packet| match packet {
+///     Packet::TalkRequest(talk_req) => {
+///         println!("{:?}: {:?}", direction, talk_req.str);
+///         let text = talk_req.str.to_string_lossy().to_uppercase();
+///         Some(Packet::TalkRequest(TalkRequest {
+///             str: std::ffi::CString::new(text).unwrap(),
+///         }))
+///     }
+///     packet => Some(packet),
+/// });
+/// let proxy = proxy.bind("127.0.0.1:0").await.unwrap();
+///
+/// let (_, alice) = BotBuilder::new("alice")
+///     .address(format!("ws://{}", proxy.address))
+///     .build()
+///     .run()
+///     .await
+///     .unwrap();
+/// let (_, bob) = BotBuilder::new("bob")
+///     .address(format!("ws://{}", server.address))
+///     .build()
+///     .run()
+///     .await
+///     .unwrap();
+///
+/// alice.2.send("quiet please".to_string()).await.unwrap();
+/// let heard = async {
+///     loop {
+///         if let Some(message) = bob.1.read().await.messages.iter().next() {
+///             return message.content.clone();
+///         }
+///         tokio::time::sleep(Duration::from_millis(5)).await;
+///     }
+/// };
+/// let heard = tokio::time::timeout(Duration::from_secs(5), heard).await.unwrap();
+/// assert_eq!(heard, "QUIET PLEASE");
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Proxy {
+    upstream: String,
+    transport: Arc<dyn Transport>,
+    hooks: Arc<Vec<ProxyHook>>,
+    recorder: Option<SharedRecorder>,
+}
+
+impl Proxy {
+    /// Constructs a new [`Proxy`] to the server at `upstream`, connected over websockets.
+    pub fn new<S: Into<String>>(upstream: S) -> Self {
+        Self::with_transport(upstream, Arc::new(WebSocketTransport))
+    }
+
+    /// Constructs a new [`Proxy`] connecting to `upstream` with `transport`.
+    pub fn with_transport<S: Into<String>>(upstream: S, transport: Arc<dyn Transport>) -> Self {
+        Self {
+            upstream: upstream.into(),
+            transport,
+            hooks: Default::default(),
+            recorder: None,
+        }
+    }
+
+    /// Adds a [`ProxyHook`] to the end of the chain.
+    ///
+    /// Hooks are copied into the running proxy by [`Proxy::bind`] and [`Proxy::serve`],
+    /// add them before.
+    pub fn on_packet<F>(&mut self, hook: F)
+    where
+        F: Fn(Direction, Packet) -> Option<Packet> + Send + Sync + 'static,
+    {
+        Arc::make_mut(&mut self.hooks).push(Arc::new(hook));
+    }
+
+    /// Starts recording every frame passing through the proxy with timestamps into `recorder`.
+    ///
+    /// Frames are recorded as the client sees them, after the hooks on the way to it
+    /// and before the hooks on the way to the server. Clients are told apart by
+    /// [`ReplayEntry::peer`], numbered in the order they connect.
+    /// Copied into the running proxy like hooks, call it before [`Proxy::bind`].
+    ///
+    /// [`ReplayEntry::peer`]: super::replay::ReplayEntry::peer
+    pub fn record_session(&mut self, recorder: SharedRecorder) {
+        self.recorder = Some(recorder);
+    }
+
+    /// Address of the upstream server.
+    pub fn upstream(&self) -> &str {
+        &self.upstream
+    }
+
+    /// Listens on `address` (`host:port`) and accepts clients in the background.
+    pub async fn bind(&self, address: &str) -> Result<RunningServer, BotError> {
+        let (listener, address) = listen(address).await?;
+        let proxy = self.clone();
+        let thread = tokio::spawn(async move { proxy.serve(listener).await });
+        Ok(RunningServer { address, thread })
+    }
+
+    /// Accepts websocket clients from `listener`, every client is proxied by its own task.
+    ///
+    /// Returns only if accepting fails.
+    pub async fn serve(&self, listener: TcpListener) -> Result<(), BotError> {
+        loop {
+            let (stream, _) = listener
+                .accept()
+                .await
+                .map_err(|e| BotError::Transport(Arc::new(e)))?;
+            let proxy = self.clone();
+            tokio::spawn(async move { proxy.accept(stream).await });
+        }
+    }
+
+    /// Performs the websocket handshake on `stream` and proxies the client.
+    async fn accept(&self, stream: TcpStream) -> Result<(), BotError> {
+        let (sink, stream) = accept_websocket(stream).await?;
+        self.handle_connection(sink, stream).await
+    }
+
+    /// Connects to the upstream server and forwards packets of a single client
+    /// connected over any transport until either side closes the connection.
+    pub async fn handle_connection(
+        &self,
+        mut client_sink: Box<dyn FrameSink>,
+        mut client_stream: Box<dyn FrameStream>,
+    ) -> Result<(), BotError> {
+        if let Some(recorder) = &self.recorder {
+            (client_sink, client_stream) = recorder.attach(
+                (client_sink, client_stream),
+                Direction::ToServer,
+                recorder.next_peer(),
+            );
+        }
+        let (mut server_sink, mut server_stream) =
+            match self.transport.connect(&self.upstream).await {
+                Ok(halves) => halves,
+                Err(e) => {
+                    let _ = client_sink.close().await;
+                    return Err(e);
+                }
+            };
+        let result = tokio::select! {
+            result = self.pump(Direction::ToServer, &mut client_stream, &mut server_sink) => result,
+            result = self.pump(Direction::ToClient, &mut server_stream, &mut client_sink) => result,
+        };
+        // One side is gone, the other one has nobody to talk to
+        let _ = client_sink.close().await;
+        let _ = server_sink.close().await;
+        result
+    }
+
+    /// Forwards frames from `from` to `to` until `from` is closed.
+    async fn pump(
+        &self,
+        direction: Direction,
+        from: &mut Box<dyn FrameStream>,
+        to: &mut Box<dyn FrameSink>,
+    ) -> Result<(), BotError> {
+        while let Some(frame) = from.next_frame().await {
+            if let Some(frame) = self.intercept(direction, frame?) {
+                to.send_frame(frame).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs `frame` through the hooks, [`None`] if one of them dropped it.
+    fn intercept(&self, direction: Direction, frame: Bytes) -> Option<Bytes> {
+        if self.hooks.is_empty() {
+            return Some(frame);
+        }
+        let Ok(mut packet) = parse_packet(&frame) else {
+            return Some(frame);
+        };
+        for hook in self.hooks.iter() {
+            packet = hook(direction, packet)?;
+        }
+        Some(packet.into())
+    }
+}
@@ -0,0 +1,575 @@
+//! Python bindings of this version.
+//!
+//! [`python_module`] is the Python module `asciicker_rs`: [`Bot`](PyBot) runs a bot calling Python functions
+//! for its events, [`Packet`](PyPacket) parses and encodes packets without a bot.
+//!
+//! Build the extension with [maturin](https://www.maturin.rs) and the `python` feature,
+//! or with `cargo rustc --release --features python,pyo3/extension-module --crate-type cdylib`
+//! and rename the library to `asciicker_rs.so` (`asciicker_rs.pyd` on Windows).
+//!
+//! ```python
+//! import asciicker_rs
+//!
+//! bot = asciicker_rs.Bot("echo", "ws://localhost:8080")
+//!
+//! def echo(packet, context):
+//!     context.say(packet.text)
+//!
+//! bot.on_talk(echo)
+//! bot.run()
+//! ```
+//!
+//! Callbacks are called with the packet and a [`Context`](PyContext), in registration order
+//! until one of them returns `True`. An exception raised in a callback stops the bot,
+//! and `Bot.run` raises it as a `RuntimeError`.
+//!
+//! # Examples
+//!
+//! ## Encoding and parsing packets in Python:
+//! ```
+//! use asciicker_rs::y6::python::python_module;
+//! use pyo3::prelude::*;
+//!
+//! pyo3::append_to_inittab!(python_module);
+//! pyo3::prepare_freethreaded_python();
+//! Python::with_gil(|py| {
+//!     py.run(
+//!         cr#"
+//! from asciicker_rs import Packet, PlayerPose
+//!
+//! talk = Packet.talk_request("hello")
+//! assert Packet.parse(talk.to_bytes()) == talk
+//! assert talk.kind == "TalkRequest" and talk.text == "hello" and talk.id is None
+//!
+//! pose = Packet.parse(Packet.pose_request(PlayerPose(position=(1.0, 2.0, 3.0))).to_bytes())
+//! assert pose.pose.position == (1.0, 2.0, 3.0)
+//!
+//! try:
+//!     Packet.join_request("x" * 32)
+//! except ValueError:
+//!     pass
+//! else:
+//!     raise AssertionError("Name is too long")
+//! "#,
+//!         None,
+//!         None,
+//!     )
+//! })
+//! .unwrap();
+//! ```
+//!
+//! ## Stopping a bot from a callback:
+//! ```
+//! use asciicker_rs::y6::prelude::*;
+//! use asciicker_rs::y6::python::python_module;
+//! use pyo3::prelude::*;
+//! use pyo3::types::PyDict;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let server = Server::new(8);
+//! let running = server.bind("127.0.0.1:0").await.unwrap();
+//! let address = format!("ws://{}", running.address);
+//!
+//! pyo3::append_to_inittab!(python_module);
+//! pyo3::prepare_freethreaded_python();
+//! // The bot runs its own runtime, so Python can't be called from this one
+//! std::thread::spawn(move || {
+//!     Python::with_gil(|py| {
+//!         let globals = PyDict::new(py);
+//!         globals.set_item("address", address)?;
+//!         py.run(
+//!             cr#"
+//! from asciicker_rs import Bot
+//!
+//! bot = Bot("python", address)
+//! ready = []
+//!
+//! def on_ready(packet, context):
+//!     ready.append((packet.max_clients, context.nickname))
+//!     raise ValueError("Leaving")
+//!
+//! bot.on_ready(on_ready)
+//! try:
+//!     bot.run()
+//! except RuntimeError as e:
+//!     assert "Leaving" in str(e), e
+//! assert ready == [(8, "python")], ready
+//! "#,
+//!             Some(&globals),
+//!             None,
+//!         )
+//!     })
+//! })
+//! .join()
+//! .unwrap()
+//! .unwrap();
+//! # }
+//! ```
+
+use super::bot::{Bot, BotBuilder, ChatSender, FutureFlowResult, MessageSender, Player, World};
+use super::packets::{
+    parse_packet, Bytes, JoinRequest, LagRequest, Packet, PlayerPose, PoseRequest, TalkRequest,
+    MAX_NAME_LEN, MAX_TALK_LEN,
+};
+use super::utils::BotError;
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+
+/// How often [`PyBot::run`] lets Python handle signals, so Ctrl+C stops the bot.
+const SIGNAL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Python module `asciicker_rs`, see the [module](self) docs.
+#[pymodule]
+#[pyo3(name = "asciicker_rs")]
+pub fn python_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyBot>()?;
+    module.add_class::<PyContext>()?;
+    module.add_class::<PyPacket>()?;
+    module.add_class::<PyPlayerPose>()?;
+    Ok(())
+}
+
+/// Python class `PlayerPose`, a [`PlayerPose`] with plain numbers for its animation and action.
+#[pyclass(name = "PlayerPose")]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PyPlayerPose {
+    /// Animation id
+    #[pyo3(get, set)]
+    pub animation: u8,
+    /// Animation frame
+    #[pyo3(get, set)]
+    pub frame: u8,
+    /// Action id, or the mount while not acting
+    #[pyo3(get, set)]
+    pub action_or_mount: u8,
+    /// Position as `(x, y, z)`
+    #[pyo3(get, set)]
+    pub position: (f32, f32, f32),
+    /// Direction in degrees
+    #[pyo3(get, set)]
+    pub direction: f32,
+    /// Sprite id
+    #[pyo3(get, set)]
+    pub sprite: u16,
+}
+
+#[pymethods]
+impl PyPlayerPose {
+    #[new]
+    #[pyo3(signature = (animation = 0, frame = 0, action_or_mount = 0, position = (0.0, 0.0, 0.0), direction = 0.0, sprite = 0))]
+    fn new(
+        animation: u8,
+        frame: u8,
+        action_or_mount: u8,
+        position: (f32, f32, f32),
+        direction: f32,
+        sprite: u16,
+    ) -> Self {
+        Self {
+            animation,
+            frame,
+            action_or_mount,
+            position,
+            direction,
+            sprite,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl From<&PlayerPose> for PyPlayerPose {
+    fn from(pose: &PlayerPose) -> Self {
+        let [x, y, z]: [f32; 3] = pose.position.into();
+        Self {
+            animation: pose.animation.into(),
+            frame: pose.frame,
+            action_or_mount: pose.action_or_mount.into(),
+            position: (x, y, z),
+            direction: pose.direction,
+            sprite: pose.sprite,
+        }
+    }
+}
+
+impl From<&PyPlayerPose> for PlayerPose {
+    fn from(pose: &PyPlayerPose) -> Self {
+        let (x, y, z) = pose.position;
+        Self {
+            animation: pose.animation.into(),
+            frame: pose.frame,
+            action_or_mount: pose.action_or_mount.into(),
+            position: [x, y, z].into(),
+            direction: pose.direction,
+            sprite: pose.sprite,
+        }
+    }
+}
+
+/// Python class `Packet`, any [`Packet`].
+///
+/// `kind` is the name of the variant, the other properties are `None`
+/// unless the packet has the field.
+#[pyclass(name = "Packet")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PyPacket(pub Packet);
+
+#[pymethods]
+impl PyPacket {
+    /// Parses `bytes`, raises `ValueError` if they aren't a valid packet.
+    #[staticmethod]
+    fn parse(bytes: &[u8]) -> PyResult<Self> {
+        parse_packet(bytes)
+            .map(Self)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Join request with `name`, raises `ValueError` if it's too long or has a null byte.
+    #[staticmethod]
+    fn join_request(name: &str) -> PyResult<Self> {
+        let name = text(name, MAX_NAME_LEN)?;
+        Ok(Self(Packet::JoinRequest(JoinRequest { name })))
+    }
+
+    /// Pose request with `pose`.
+    #[staticmethod]
+    fn pose_request(pose: &PyPlayerPose) -> Self {
+        Self(Packet::PoseRequest(PoseRequest {
+            player_pose: pose.into(),
+        }))
+    }
+
+    /// Talk request with `text`, raises `ValueError` if it's too long or has a null byte.
+    #[staticmethod]
+    fn talk_request(text: &str) -> PyResult<Self> {
+        let str = self::text(text, MAX_TALK_LEN)?;
+        Ok(Self(Packet::TalkRequest(TalkRequest { str })))
+    }
+
+    /// Lag request with `stamp`.
+    #[staticmethod]
+    fn lag_request(stamp: [u8; 3]) -> Self {
+        Self(Packet::LagRequest(LagRequest { stamp }))
+    }
+
+    /// Encodes the packet.
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &Bytes::from(self.0.clone()))
+    }
+
+    #[getter]
+    fn kind(&self) -> &'static str {
+        match self.0 {
+            Packet::JoinRequest(_) => "JoinRequest",
+            Packet::JoinResponse(_) => "JoinResponse",
+            Packet::JoinBroadcast(_) => "JoinBroadcast",
+            Packet::ExitBroadcast(_) => "ExitBroadcast",
+            Packet::PoseRequest(_) => "PoseRequest",
+            Packet::PoseBroadcast(_) => "PoseBroadcast",
+            Packet::TalkRequest(_) => "TalkRequest",
+            Packet::TalkBroadcast(_) => "TalkBroadcast",
+            Packet::LagRequest(_) => "LagRequest",
+            Packet::LagResponse(_) => "LagResponse",
+        }
+    }
+
+    #[getter]
+    fn id(&self) -> Option<u16> {
+        match &self.0 {
+            Packet::JoinResponse(rsp) => Some(rsp.id),
+            Packet::JoinBroadcast(brc) => Some(brc.id),
+            Packet::ExitBroadcast(brc) => Some(brc.id),
+            Packet::PoseBroadcast(brc) => Some(brc.id),
+            Packet::TalkBroadcast(brc) => Some(brc.id),
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn max_clients(&self) -> Option<u8> {
+        match &self.0 {
+            Packet::JoinResponse(rsp) => Some(rsp.max_clients),
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn name(&self) -> Option<String> {
+        match &self.0 {
+            Packet::JoinRequest(req) => Some(req.name.to_string_lossy().into_owned()),
+            Packet::JoinBroadcast(brc) => Some(brc.name.to_string_lossy().into_owned()),
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn text(&self) -> Option<String> {
+        match &self.0 {
+            Packet::TalkRequest(req) => Some(req.str.to_string_lossy().into_owned()),
+            Packet::TalkBroadcast(brc) => Some(brc.str.to_string_lossy().into_owned()),
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn pose(&self) -> Option<PyPlayerPose> {
+        match &self.0 {
+            Packet::JoinBroadcast(brc) => Some((&brc.player_pose).into()),
+            Packet::PoseRequest(req) => Some((&req.player_pose).into()),
+            Packet::PoseBroadcast(brc) => Some((&brc.player_pose).into()),
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn stamp(&self) -> Option<[u8; 3]> {
+        match &self.0 {
+            Packet::LagRequest(req) => Some(req.stamp),
+            Packet::LagResponse(rsp) => Some(rsp.stamp),
+            _ => None,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+/// Converts `value` into a [`CString`] of at most `max_len` bytes.
+fn text(value: &str, max_len: usize) -> PyResult<CString> {
+    if value.len() > max_len {
+        return Err(PyValueError::new_err(format!(
+            "{} bytes long, at most {} fit",
+            value.len(),
+            max_len
+        )));
+    }
+    CString::new(value).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Python class `Context`, passed to every callback with the packet.
+///
+/// Holds what the bot knew when the callback was called.
+#[pyclass(name = "Context")]
+pub struct PyContext {
+    /// Id of the bot
+    #[pyo3(get)]
+    id: u16,
+    /// Nickname of the bot
+    #[pyo3(get)]
+    nickname: String,
+    /// Nicknames of the other clients by their id
+    #[pyo3(get)]
+    players: HashMap<u16, String>,
+    sender: MessageSender,
+}
+
+impl PyContext {
+    async fn new(player: &Mutex<Player>, world: &RwLock<World>, sender: MessageSender) -> Self {
+        let (id, nickname) = {
+            let player = player.lock().await;
+            (player.id, player.nickname.clone())
+        };
+        let players = world
+            .read()
+            .await
+            .clients
+            .iter()
+            .map(|(id, client)| (*id, client.nickname.clone()))
+            .collect();
+        Self {
+            id,
+            nickname,
+            players,
+            sender,
+        }
+    }
+}
+
+#[pymethods]
+impl PyContext {
+    /// Queues `message` to be said, long messages are split.
+    ///
+    /// Raises `ValueError` if it has a null byte, `RuntimeError` if the queue is full
+    /// or the bot disconnected.
+    fn say(&self, message: &str) -> PyResult<()> {
+        ChatSender::new(Arc::clone(&self.sender))
+            .validate(message)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        self.sender
+            .try_send(message.to_string())
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+    }
+}
+
+/// Python class `Bot`, a [`Bot`] calling Python functions for its events.
+///
+/// Callbacks are set with `on_join`, `on_exit`, `on_pose`, `on_talk`, `on_lag` and `on_ready`,
+/// then `run` connects and blocks until the bot disconnects.
+#[pyclass(name = "Bot")]
+pub struct PyBot {
+    bot: Option<Bot>,
+}
+
+#[pymethods]
+impl PyBot {
+    /// Raises `ValueError` if `nickname` is too long.
+    #[new]
+    #[pyo3(signature = (nickname, address = None))]
+    fn new(nickname: &str, address: Option<&str>) -> PyResult<Self> {
+        if nickname.len() > MAX_NAME_LEN {
+            return Err(PyValueError::new_err(format!(
+                "Nickname is {} bytes long, at most {} fit",
+                nickname.len(),
+                MAX_NAME_LEN
+            )));
+        }
+        let mut builder = BotBuilder::new(nickname).replace_invalid_utf8(true);
+        if let Some(address) = address {
+            builder = builder.address(address);
+        }
+        Ok(Self {
+            bot: Some(builder.build()),
+        })
+    }
+
+    /// Adds a callback called with every join broadcast to the end of the chain.
+    fn on_join(&mut self, callback: PyObject) -> PyResult<()> {
+        self.bot()?
+            .on_join(python_callback(callback, Packet::JoinBroadcast));
+        Ok(())
+    }
+
+    /// Adds a callback called with every exit broadcast to the end of the chain.
+    fn on_exit(&mut self, callback: PyObject) -> PyResult<()> {
+        self.bot()?
+            .on_exit(python_callback(callback, Packet::ExitBroadcast));
+        Ok(())
+    }
+
+    /// Adds a callback called with every pose broadcast to the end of the chain.
+    fn on_pose(&mut self, callback: PyObject) -> PyResult<()> {
+        self.bot()?
+            .on_pose(python_callback(callback, Packet::PoseBroadcast));
+        Ok(())
+    }
+
+    /// Adds a callback called with every talk broadcast to the end of the chain.
+    fn on_talk(&mut self, callback: PyObject) -> PyResult<()> {
+        self.bot()?
+            .on_talk(python_callback(callback, Packet::TalkBroadcast));
+        Ok(())
+    }
+
+    /// Adds a callback called with every lag response to the end of the chain.
+    fn on_lag(&mut self, callback: PyObject) -> PyResult<()> {
+        self.bot()?
+            .on_lag(python_callback(callback, Packet::LagResponse));
+        Ok(())
+    }
+
+    /// Replaces the callback called once the server accepted the bot,
+    /// its return value is ignored.
+    fn on_ready(&mut self, callback: PyObject) -> PyResult<()> {
+        let callback = python_callback(callback, Packet::JoinResponse);
+        self.bot()?.on_ready(move |rsp, player, world, sender| {
+            let flow = callback(rsp, player, world, sender);
+            Box::pin(async move { flow.await.map(|_| ()) })
+        });
+        Ok(())
+    }
+
+    /// Connects and blocks until the bot disconnects, a bot runs only once.
+    ///
+    /// Raises `RuntimeError` if connecting failed or the connection ended with an error,
+    /// and the exception of a signal handler, like `KeyboardInterrupt` on Ctrl+C.
+    fn run(&mut self, py: Python<'_>) -> PyResult<()> {
+        let bot = self
+            .bot
+            .take()
+            .ok_or_else(|| PyRuntimeError::new_err("Bot already ran"))?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        // If a signal handler raised, dropping the runtime stops the threads of the bot
+        py.allow_threads(|| {
+            runtime.block_on(async {
+                let ((receiver, _sender), _data) = bot.run().await.map_err(bot_error)?;
+                let mut thread = receiver.thread;
+                loop {
+                    tokio::select! {
+                        result = &mut thread => {
+                            return match result {
+                                Ok(result) => result.map_err(bot_error),
+                                Err(e) => Err(PyRuntimeError::new_err(e.to_string())),
+                            };
+                        }
+                        _ = tokio::time::sleep(SIGNAL_INTERVAL) => {
+                            Python::with_gil(|py| py.check_signals())?;
+                        }
+                    }
+                }
+            })
+        })
+    }
+}
+
+impl PyBot {
+    fn bot(&mut self) -> PyResult<&mut Bot> {
+        self.bot
+            .as_mut()
+            .ok_or_else(|| PyRuntimeError::new_err("Bot already ran"))
+    }
+}
+
+fn bot_error(e: BotError) -> PyErr {
+    PyRuntimeError::new_err(e.to_string())
+}
+
+/// Wraps a Python `callback` into a callback of the bot, `packet` tells the [`Packet`] variant of `T`.
+fn python_callback<T: Send + 'static>(
+    callback: PyObject,
+    packet: fn(T) -> Packet,
+) -> impl Fn(T, Arc<Mutex<Player>>, Arc<RwLock<World>>, MessageSender) -> FutureFlowResult
+       + Send
+       + Sync
+       + 'static {
+    let callback = Arc::new(callback);
+    move |value, player, world, sender| {
+        let callback = Arc::clone(&callback);
+        Box::pin(async move {
+            let context = PyContext::new(&player, &world, sender).await;
+            Python::with_gil(|py| {
+                let stop = callback
+                    .call1(py, (PyPacket(packet(value)), context))?
+                    .extract::<bool>(py)
+                    .unwrap_or(false);
+                Ok(match stop {
+                    true => ControlFlow::Break(()),
+                    false => ControlFlow::Continue(()),
+                })
+            })
+            .map_err(|e: PyErr| BotError::callback(e))
+        })
+    }
+}
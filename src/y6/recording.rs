@@ -0,0 +1,150 @@
+use super::packets::Bytes;
+
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+/// Type alias for a [`Recording`] shared between the user and the sender thread.
+pub type SharedRecording = Arc<Mutex<Recording>>;
+
+/// Sequence of binary frames, in the exact order they were written to the websocket.
+///
+/// Created by [`Bot::record_outbound`] and filled by the [`Sender`] thread
+/// (and the join handshake), so two runs of the same scripted bot can be compared
+/// byte-for-byte with [`Recording::compare`].
+///
+/// [`Bot::record_outbound`]: super::bot::Bot::record_outbound
+/// [`Sender`]: super::bot::Sender
+#[derive(Default, Debug, Clone, PartialOrd, PartialEq)]
+pub struct Recording {
+    /// Recorded frames
+    pub frames: Vec<Bytes>,
+}
+
+/// Single difference between two [`Recording`]s, returned by [`Recording::compare`].
+#[derive(Debug, Clone, PartialOrd, PartialEq)]
+pub enum FrameMismatch {
+    /// Both recordings have a frame at `index`, but the bytes are different.
+    ///
+    /// `offset` is the position of the first differing byte
+    /// (or the length of the shorter frame if one is a prefix of the other).
+    Differs {
+        /// Index of the frame
+        index: usize,
+        /// Offset of the first differing byte
+        offset: usize,
+        /// Frame from the expected recording
+        expected: Bytes,
+        /// Frame from the actual recording
+        got: Bytes,
+    },
+    /// Expected recording has a frame at `index`, but the actual one ended earlier.
+    Missing {
+        /// Index of the frame
+        index: usize,
+        /// Frame from the expected recording
+        expected: Bytes,
+    },
+    /// Actual recording has a frame at `index`, but the expected one ended earlier.
+    Extra {
+        /// Index of the frame
+        index: usize,
+        /// Frame from the actual recording
+        got: Bytes,
+    },
+}
+
+impl Recording {
+    /// Creates a new empty [`Recording`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a frame to the recording.
+    pub fn push(&mut self, frame: Bytes) {
+        self.frames.push(frame);
+    }
+
+    /// Returns a copy of the recording with only frames starting with `token`.
+    ///
+    /// Pose requests are sent on a timer, so their amount depends on how long the run took;
+    /// filtering them out (or keeping only them) makes recordings of the same script comparable.
+    pub fn with_token(&self, token: u8) -> Self {
+        Self {
+            frames: self
+                .frames
+                .iter()
+                .filter(|f| f.first() == Some(&token))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Returns a copy of the recording without frames starting with `token`.
+    pub fn without_token(&self, token: u8) -> Self {
+        Self {
+            frames: self
+                .frames
+                .iter()
+                .filter(|f| f.first() != Some(&token))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Compares `self` (the expected recording) with `other` frame by frame.
+    ///
+    /// Returns every [`FrameMismatch`] found, empty [`Vec`] means the recordings are byte-for-byte equal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    ///
+    /// let expected = Recording { frames: vec![vec![b'T', 2, b'h', b'i', 0, 0]] };
+    /// let got = Recording { frames: vec![vec![b'T', 2, b'h', b'o', 0, 0]] };
+    ///
+    /// assert!(expected.compare(&expected).is_empty());
+    /// assert_eq!(
+    ///     expected.compare(&got),
+    ///     vec![FrameMismatch::Differs {
+    ///         index: 0,
+    ///         offset: 3,
+    ///         expected: expected.frames[0].clone(),
+    ///         got: got.frames[0].clone(),
+    ///     }]
+    /// );
+    /// ```
+    pub fn compare(&self, other: &Recording) -> Vec<FrameMismatch> {
+        let mut mismatches = vec![];
+        for index in 0..self.frames.len().max(other.frames.len()) {
+            match (self.frames.get(index), other.frames.get(index)) {
+                (Some(expected), Some(got)) => {
+                    if expected != got {
+                        let offset = expected
+                            .iter()
+                            .zip(got.iter())
+                            .position(|(e, g)| e != g)
+                            .unwrap_or_else(|| expected.len().min(got.len()));
+                        mismatches.push(FrameMismatch::Differs {
+                            index,
+                            offset,
+                            expected: expected.clone(),
+                            got: got.clone(),
+                        });
+                    }
+                }
+                (Some(expected), None) => mismatches.push(FrameMismatch::Missing {
+                    index,
+                    expected: expected.clone(),
+                }),
+                (None, Some(got)) => mismatches.push(FrameMismatch::Extra {
+                    index,
+                    got: got.clone(),
+                }),
+                (None, None) => unreachable!(),
+            }
+        }
+        mismatches
+    }
+}
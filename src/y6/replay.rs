@@ -0,0 +1,569 @@
+use super::packets::Bytes;
+use super::utils::BotError;
+use crate::common::transport::{FrameHalves, FrameSink, FrameStream, Transport};
+
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::{
+    atomic::{AtomicU16, Ordering},
+    Arc, Mutex,
+};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+use futures_util::future::BoxFuture;
+
+/// Magic bytes every replay file starts with.
+pub const REPLAY_MAGIC: [u8; 4] = *b"ACKR";
+/// Version of the replay format written by [`Replay::write_to`].
+pub const REPLAY_VERSION: u8 = 1;
+/// Header flag: body is compressed with zstd.
+const FLAG_ZSTD: u8 = 1;
+
+/// Which way a packet went.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Sent by the client to the server
+    ToServer,
+    /// Sent by the server to the client
+    ToClient,
+}
+
+/// Error of reading or writing a [`Replay`].
+#[derive(Debug, Clone)]
+pub enum ReplayError {
+    /// Reading or writing failed
+    Io(Arc<io::Error>),
+    /// Data doesn't start with [`REPLAY_MAGIC`]
+    NotAReplay,
+    /// Replay was written by a newer version of the format, contains it
+    UnsupportedVersion(u8),
+    /// Replay is compressed, but the `zstd` feature is disabled
+    CompressionUnsupported,
+    /// Replay ends in the middle of an entry
+    Truncated,
+    /// Entry has a direction byte that isn't known, contains it
+    InvalidDirection(u8),
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "Replay IO failed: {}", e),
+            ReplayError::NotAReplay => write!(f, "Not a replay"),
+            ReplayError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported replay version: {}", version)
+            }
+            ReplayError::CompressionUnsupported => {
+                write!(f, "Replay is compressed, enable the zstd feature")
+            }
+            ReplayError::Truncated => write!(f, "Replay is truncated"),
+            ReplayError::InvalidDirection(direction) => {
+                write!(f, "Invalid direction in replay: {}", direction)
+            }
+        }
+    }
+}
+
+impl Error for ReplayError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReplayError::Io(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ReplayError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(Arc::new(e))
+    }
+}
+
+/// Single frame of a [`Replay`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayEntry {
+    /// Time since the recording started
+    pub at: Duration,
+    /// Which way the frame went
+    pub direction: Direction,
+    /// Connection the frame belongs to, always `0` for a bot, numbered from `0` by servers and proxies
+    pub peer: u16,
+    /// The frame
+    pub frame: Bytes,
+}
+
+/// Recorded session, every frame that went through the recorded connections with timestamps.
+///
+/// File format, all numbers little-endian:
+/// - header: [`REPLAY_MAGIC`], version byte ([`REPLAY_VERSION`]), flags byte
+///   (`1` if the body is zstd-compressed)
+/// - body: entries until the end, each is direction byte (`0` to server, `1` to client),
+///   `u16` peer, `u64` microseconds since the start, `u32` length and the frame itself
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+/// use std::time::Duration;
+///
+/// let replay = Replay {
+///     entries: vec![ReplayEntry {
+///         at: Duration::from_millis(5),
+///         direction: Direction::ToServer,
+///         peer: 0,
+///         frame: vec![b'L', 1, 2, 3],
+///     }],
+/// };
+/// let mut file = vec![];
+/// replay.write_to(&mut file, false).unwrap();
+/// assert_eq!(Replay::read_from(&file[..]).unwrap(), replay);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Replay {
+    /// Recorded frames in the order they were recorded
+    pub entries: Vec<ReplayEntry>,
+}
+
+impl Replay {
+    /// Writes the replay to `writer`, compressed if `compress` is `true`.
+    ///
+    /// Compression needs the `zstd` feature, [`ReplayError::CompressionUnsupported`] without it.
+    pub fn write_to<W: Write>(&self, mut writer: W, compress: bool) -> Result<(), ReplayError> {
+        let mut body = vec![];
+        for entry in &self.entries {
+            write_entry(
+                &mut body,
+                entry.at,
+                entry.direction,
+                entry.peer,
+                &entry.frame,
+            )?;
+        }
+        if compress {
+            body = compress_body(&body)?;
+        }
+        write_header(&mut writer, compress)?;
+        writer.write_all(&body)?;
+        Ok(())
+    }
+
+    /// Reads a replay written by [`Replay::write_to`].
+    pub fn read_from<R: Read>(mut reader: R) -> Result<Self, ReplayError> {
+        let mut header = [0u8; 6];
+        reader.read_exact(&mut header).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => ReplayError::NotAReplay,
+            _ => e.into(),
+        })?;
+        if header[..4] != REPLAY_MAGIC {
+            return Err(ReplayError::NotAReplay);
+        }
+        if header[4] > REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(header[4]));
+        }
+        let mut body = vec![];
+        reader.read_to_end(&mut body)?;
+        if header[5] & FLAG_ZSTD != 0 {
+            body = decompress_body(&body)?;
+        }
+        let mut entries = vec![];
+        let mut rest = &body[..];
+        while !rest.is_empty() {
+            const HEADER: usize = 1 + 2 + 8 + 4;
+            if rest.len() < HEADER {
+                return Err(ReplayError::Truncated);
+            }
+            let direction = match rest[0] {
+                0 => Direction::ToServer,
+                1 => Direction::ToClient,
+                direction => return Err(ReplayError::InvalidDirection(direction)),
+            };
+            let peer = u16::from_le_bytes([rest[1], rest[2]]);
+            let at = u64::from_le_bytes(rest[3..11].try_into().unwrap());
+            let len = u32::from_le_bytes(rest[11..15].try_into().unwrap()) as usize;
+            if rest.len() < HEADER + len {
+                return Err(ReplayError::Truncated);
+            }
+            entries.push(ReplayEntry {
+                at: Duration::from_micros(at),
+                direction,
+                peer,
+                frame: rest[HEADER..HEADER + len].to_vec(),
+            });
+            rest = &rest[HEADER + len..];
+        }
+        Ok(Self { entries })
+    }
+
+    /// Writes the replay to the file at `path`, see [`Replay::write_to`].
+    pub fn save<P: AsRef<Path>>(&self, path: P, compress: bool) -> Result<(), ReplayError> {
+        let file = std::fs::File::create(path)?;
+        self.write_to(io::BufWriter::new(file), compress)
+    }
+
+    /// Reads the replay from the file at `path`, see [`Replay::read_from`].
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ReplayError> {
+        let file = std::fs::File::open(path)?;
+        Self::read_from(io::BufReader::new(file))
+    }
+}
+
+fn write_header<W: Write>(writer: &mut W, compress: bool) -> io::Result<()> {
+    let flags = if compress { FLAG_ZSTD } else { 0 };
+    writer.write_all(&REPLAY_MAGIC)?;
+    writer.write_all(&[REPLAY_VERSION, flags])
+}
+
+fn write_entry<W: Write + ?Sized>(
+    writer: &mut W,
+    at: Duration,
+    direction: Direction,
+    peer: u16,
+    frame: &[u8],
+) -> io::Result<()> {
+    let direction = match direction {
+        Direction::ToServer => 0u8,
+        Direction::ToClient => 1,
+    };
+    writer.write_all(&[direction])?;
+    writer.write_all(&peer.to_le_bytes())?;
+    writer.write_all(&(at.as_micros() as u64).to_le_bytes())?;
+    writer.write_all(&(frame.len() as u32).to_le_bytes())?;
+    writer.write_all(frame)
+}
+
+#[cfg(feature = "zstd")]
+fn compress_body(body: &[u8]) -> Result<Vec<u8>, ReplayError> {
+    Ok(zstd::encode_all(body, 0)?)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn compress_body(_: &[u8]) -> Result<Vec<u8>, ReplayError> {
+    Err(ReplayError::CompressionUnsupported)
+}
+
+#[cfg(feature = "zstd")]
+fn decompress_body(body: &[u8]) -> Result<Vec<u8>, ReplayError> {
+    Ok(zstd::decode_all(body)?)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress_body(_: &[u8]) -> Result<Vec<u8>, ReplayError> {
+    Err(ReplayError::CompressionUnsupported)
+}
+
+/// Type alias for a [`SessionRecorder`] shared between its owner and the recorded connections.
+pub type SharedRecorder = Arc<SessionRecorder>;
+
+/// Records every frame of the connections it is attached to.
+///
+/// [`SessionRecorder::streaming`] and [`SessionRecorder::create`] write every frame through a
+/// buffer as it arrives, so the recording doesn't grow in memory however long the session is.
+/// The replay is complete after [`SessionRecorder::finish`], or once the last handle to the
+/// recorder is dropped. [`SessionRecorder::in_memory`] keeps the whole [`Replay`] in memory
+/// instead, which only suits short sessions and tests.
+///
+/// Attach it with [`Bot::record_session`], [`Server::record_session`] or
+/// [`Proxy::record_session`], or wrap any [`Transport`] with [`RecordingTransport`].
+/// Timestamps are relative to the creation of the recorder, with microsecond precision.
+///
+/// [`Bot::record_session`]: super::bot::Bot::record_session
+/// [`Server::record_session`]: super::server::Server::record_session
+/// [`Proxy::record_session`]: super::proxy::Proxy::record_session
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+///
+/// let path = std::env::temp_dir().join(format!("recorder-{}.ackr", std::process::id()));
+/// let recorder = SessionRecorder::create(&path, false).unwrap();
+/// recorder.record(Direction::ToServer, 0, &[b'J', 1, 2, 3]);
+/// recorder.record(Direction::ToClient, 0, &[b'j', 4]);
+/// recorder.finish().unwrap();
+///
+/// let replay = Replay::load(&path).unwrap();
+/// assert_eq!(replay.entries.len(), 2);
+/// assert_eq!(replay.entries[1].frame, [b'j', 4]);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct SessionRecorder {
+    start: Instant,
+    storage: Mutex<Storage>,
+    peers: AtomicU16,
+}
+
+enum Storage {
+    Memory(Vec<ReplayEntry>),
+    Stream(Output),
+    /// Output was finished, or failed with the error kept here
+    Done(Option<ReplayError>),
+}
+
+type BoxedWriter = io::BufWriter<Box<dyn Write + Send>>;
+
+enum Output {
+    Plain(BoxedWriter),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, BoxedWriter>),
+}
+
+impl Output {
+    fn new(mut writer: BoxedWriter, compress: bool) -> Result<Self, ReplayError> {
+        if compress {
+            return compressed_output(writer);
+        }
+        write_header(&mut writer, false)?;
+        Ok(Self::Plain(writer))
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Self::Plain(writer) => writer,
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder,
+        }
+    }
+
+    fn finish(self) -> Result<(), ReplayError> {
+        let mut writer = match self {
+            Self::Plain(writer) => writer,
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder.finish()?,
+        };
+        Ok(writer.flush()?)
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn compressed_output(mut writer: BoxedWriter) -> Result<Output, ReplayError> {
+    write_header(&mut writer, true)?;
+    Ok(Output::Zstd(zstd::stream::write::Encoder::new(writer, 0)?))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn compressed_output(_: BoxedWriter) -> Result<Output, ReplayError> {
+    Err(ReplayError::CompressionUnsupported)
+}
+
+impl SessionRecorder {
+    fn with_storage(storage: Storage) -> SharedRecorder {
+        Arc::new(Self {
+            start: Instant::now(),
+            storage: Mutex::new(storage),
+            peers: AtomicU16::new(0),
+        })
+    }
+
+    /// Creates a new [`SharedRecorder`] keeping everything in memory, see [`SessionRecorder::replay`].
+    ///
+    /// Memory grows with every frame, prefer [`SessionRecorder::streaming`] for long sessions.
+    pub fn in_memory() -> SharedRecorder {
+        Self::with_storage(Storage::Memory(vec![]))
+    }
+
+    /// Creates a new [`SharedRecorder`] writing the replay to `writer` as frames arrive,
+    /// compressed if `compress` is `true`.
+    ///
+    /// The header is written right away. Compression needs the `zstd` feature,
+    /// [`ReplayError::CompressionUnsupported`] without it.
+    pub fn streaming<W: Write + Send + 'static>(
+        writer: W,
+        compress: bool,
+    ) -> Result<SharedRecorder, ReplayError> {
+        let writer = io::BufWriter::new(Box::new(writer) as Box<dyn Write + Send>);
+        let output = Output::new(writer, compress)?;
+        Ok(Self::with_storage(Storage::Stream(output)))
+    }
+
+    /// Creates the file at `path` and streams the replay to it, see [`SessionRecorder::streaming`].
+    pub fn create<P: AsRef<Path>>(path: P, compress: bool) -> Result<SharedRecorder, ReplayError> {
+        Self::streaming(std::fs::File::create(path)?, compress)
+    }
+
+    /// Records `frame` of the connection `peer`.
+    ///
+    /// If writing fails, the recording stops and [`SessionRecorder::finish`] returns the error.
+    pub fn record(&self, direction: Direction, peer: u16, frame: &[u8]) {
+        // Replays store microseconds, keep what is recorded equal to what is saved
+        let at = Duration::from_micros(self.start.elapsed().as_micros() as u64);
+        let mut storage = self.storage.lock().unwrap();
+        match &mut *storage {
+            Storage::Memory(entries) => entries.push(ReplayEntry {
+                at,
+                direction,
+                peer,
+                frame: frame.to_vec(),
+            }),
+            Storage::Stream(output) => {
+                if let Err(e) = write_entry(output.writer(), at, direction, peer, frame) {
+                    *storage = Storage::Done(Some(e.into()));
+                }
+            }
+            Storage::Done(_) => {}
+        }
+    }
+
+    /// Returns a new peer number for a connection.
+    pub fn next_peer(&self) -> u16 {
+        self.peers.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Wraps both halves of a connection, so everything going through them is recorded.
+    ///
+    /// `incoming` is the direction of the frames read from the stream.
+    pub fn attach(
+        self: &Arc<Self>,
+        (sink, stream): FrameHalves,
+        incoming: Direction,
+        peer: u16,
+    ) -> FrameHalves {
+        let outgoing = match incoming {
+            Direction::ToServer => Direction::ToClient,
+            Direction::ToClient => Direction::ToServer,
+        };
+        (
+            Box::new(RecordingSink {
+                inner: sink,
+                recorder: Arc::clone(self),
+                direction: outgoing,
+                peer,
+            }),
+            Box::new(RecordingStream {
+                inner: stream,
+                recorder: Arc::clone(self),
+                direction: incoming,
+                peer,
+            }),
+        )
+    }
+
+    /// Returns a copy of everything recorded so far,
+    /// `None` unless the recorder was made by [`SessionRecorder::in_memory`].
+    pub fn replay(&self) -> Option<Replay> {
+        match &*self.storage.lock().unwrap() {
+            Storage::Memory(entries) => Some(Replay {
+                entries: entries.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Flushes a streaming recorder and ends its compressed body, frames recorded afterwards
+    /// are dropped. Returns the error that stopped the recording, if any.
+    ///
+    /// Dropping the last handle to the recorder finishes it too, ignoring errors.
+    /// Does nothing for an in-memory recorder.
+    pub fn finish(&self) -> Result<(), ReplayError> {
+        let mut storage = self.storage.lock().unwrap();
+        match std::mem::replace(&mut *storage, Storage::Done(None)) {
+            Storage::Memory(entries) => {
+                *storage = Storage::Memory(entries);
+                Ok(())
+            }
+            Storage::Stream(output) => output.finish(),
+            Storage::Done(error) => error.map_or(Ok(()), Err),
+        }
+    }
+}
+
+impl Debug for SessionRecorder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionRecorder")
+            .field("start", &self.start)
+            .field("peers", &self.peers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for SessionRecorder {
+    fn drop(&mut self) {
+        if let Ok(Storage::Stream(_)) = self.storage.get_mut() {
+            let _ = self.finish();
+        }
+    }
+}
+
+/// [`Transport`] recording every connection it makes as a client.
+pub struct RecordingTransport {
+    inner: Arc<dyn Transport>,
+    recorder: SharedRecorder,
+}
+
+impl RecordingTransport {
+    /// Wraps `inner`, connections are recorded into `recorder`.
+    pub fn new(inner: Arc<dyn Transport>, recorder: SharedRecorder) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn connect<'a>(&'a self, address: &'a str) -> BoxFuture<'a, Result<FrameHalves, BotError>> {
+        Box::pin(async move {
+            let halves = self.inner.connect(address).await?;
+            Ok(self.recorder.attach(halves, Direction::ToClient, 0))
+        })
+    }
+
+    fn connect_with_headers<'a>(
+        &'a self,
+        address: &'a str,
+        headers: &'a [(String, String)],
+    ) -> BoxFuture<'a, Result<FrameHalves, BotError>> {
+        Box::pin(async move {
+            let halves = self.inner.connect_with_headers(address, headers).await?;
+            Ok(self.recorder.attach(halves, Direction::ToClient, 0))
+        })
+    }
+}
+
+struct RecordingSink {
+    inner: Box<dyn FrameSink>,
+    recorder: SharedRecorder,
+    direction: Direction,
+    peer: u16,
+}
+
+impl FrameSink for RecordingSink {
+    fn send_frame(&mut self, frame: Bytes) -> BoxFuture<'_, Result<(), BotError>> {
+        self.recorder.record(self.direction, self.peer, &frame);
+        self.inner.send_frame(frame)
+    }
+
+    fn send_slice<'a>(&'a mut self, frame: &'a [u8]) -> BoxFuture<'a, Result<(), BotError>> {
+        self.recorder.record(self.direction, self.peer, frame);
+        self.inner.send_slice(frame)
+    }
+
+    fn close(&mut self) -> BoxFuture<'_, Result<(), BotError>> {
+        self.inner.close()
+    }
+}
+
+struct RecordingStream {
+    inner: Box<dyn FrameStream>,
+    recorder: SharedRecorder,
+    direction: Direction,
+    peer: u16,
+}
+
+impl FrameStream for RecordingStream {
+    fn next_frame(&mut self) -> BoxFuture<'_, Option<Result<Bytes, BotError>>> {
+        Box::pin(async move {
+            let frame = self.inner.next_frame().await;
+            if let Some(Ok(frame)) = &frame {
+                self.recorder.record(self.direction, self.peer, frame);
+            }
+            frame
+        })
+    }
+
+    fn recycle(&mut self, frame: Bytes) {
+        self.inner.recycle(frame)
+    }
+}
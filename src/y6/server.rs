@@ -0,0 +1,605 @@
+use super::packets::{
+    parse_packet, Bytes, ExitBroadcast, JoinBroadcast, JoinResponse, LagResponse, Packet,
+    PlayerPose, PoseBroadcast, TalkBroadcast, MAX_NAME_LEN,
+};
+use super::replay::{Direction, SharedRecorder};
+use super::utils::BotError;
+use crate::common::transport::{ws_halves, FrameHalves, FrameSink, FrameStream};
+
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use tokio::{
+    net::{TcpListener, TcpStream},
+    runtime::Handle,
+    sync::{mpsc, Mutex},
+    task::JoinHandle,
+};
+use tokio_tungstenite::MaybeTlsStream;
+
+/// Client connected to a [`Server`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerClient {
+    /// Id assigned by the server
+    pub id: u16,
+    /// Name from the join request
+    pub name: CString,
+    /// Last pose the client sent
+    pub pose: PlayerPose,
+}
+
+/// Box-pinned result of a [`ServerHook`], [`None`] vetoes the packet.
+pub type FutureHookResult<T> = Pin<Box<dyn Future<Output = Option<T>> + Send>>;
+/// Type alias for a server hook of broadcast `T`, called with the client that caused it.
+///
+/// Hooks of an event run in registration order, each gets the broadcast returned by the previous
+/// one and may rewrite it. Returning [`None`] vetoes it and skips the rest of the hooks.
+/// Ids in the returned broadcasts are always reset to the id of the client.
+pub type ServerHook<T> = Arc<dyn Fn(ServerClient, T) -> FutureHookResult<T> + Send + Sync>;
+
+/// Hooks of every event.
+#[derive(Default, Clone)]
+struct Hooks {
+    join: Vec<ServerHook<JoinBroadcast>>,
+    exit: Vec<ServerHook<ExitBroadcast>>,
+    pose: Vec<ServerHook<PoseBroadcast>>,
+    chat: Vec<ServerHook<TalkBroadcast>>,
+}
+
+/// Runs `event` through `hooks` in order until one of them vetoes it.
+async fn run_hooks<T>(hooks: &[ServerHook<T>], client: &ServerClient, event: T) -> Option<T> {
+    let mut event = event;
+    for hook in hooks {
+        event = hook(client.clone(), event).await?;
+    }
+    Some(event)
+}
+
+/// Frees the slot of a client once its connection is over, see [`Server::leave`].
+///
+/// Dropping it without [`LeaveGuard::leave`], like when a hook panics, leaves in a new task,
+/// so the slot never leaks.
+struct LeaveGuard {
+    server: Option<Server>,
+    id: u16,
+}
+
+impl LeaveGuard {
+    async fn leave(mut self) {
+        if let Some(server) = self.server.take() {
+            server.leave(self.id).await;
+        }
+    }
+}
+
+impl Drop for LeaveGuard {
+    fn drop(&mut self) {
+        let (Some(server), Ok(runtime)) = (self.server.take(), Handle::try_current()) else {
+            return;
+        };
+        let id = self.id;
+        runtime.spawn(async move { server.leave(id).await });
+    }
+}
+
+/// Connected client and the queue of frames to send to it.
+struct Slot {
+    client: ServerClient,
+    outbound: mpsc::UnboundedSender<Bytes>,
+    /// `false` while the join hooks run, nobody knows about the client yet
+    joined: bool,
+}
+
+/// Y6 server, relays what its clients do to each other.
+///
+/// Clients connect over websockets and join with a join request. The server refuses the join
+/// by closing the connection once it has `max_clients` clients, otherwise it assigns the
+/// lowest free id, answers with a join response followed by join broadcasts of everyone
+/// already there, and announces the new client to the others.
+///
+/// After that pose and talk requests are relayed to every other client as broadcasts,
+/// lag requests are answered to the sender only and leaving clients are announced
+/// with exit broadcasts.
+///
+/// Hooks registered with [`Server::on_client_join`] and similar methods can veto or rewrite
+/// the broadcasts before anyone receives them.
+///
+/// [`Server`] is a cheap handle, clones share the same clients. Hooks are copied into
+/// the running server by [`Server::bind`] and [`Server::serve`], register them before.
+///
+/// # Examples
+///
+/// ## Two bots meeting on a local server:
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let server = Server::new(8);
+/// let running = server.bind("127.0.0.1:0").await.unwrap();
+/// let address = format!("ws://{}", running.address);
+///
+/// let (_, alice) = BotBuilder::new("alice").address(&address).build().run().await.unwrap();
+/// let (_, bob) = BotBuilder::new("bob").address(&address).build().run().await.unwrap();
+///
+/// alice.2.send("hi bob".to_string()).await.unwrap();
+/// let heard = async {
+///     loop {
+///         if let Some(message) = bob.1.read().await.messages.iter().next() {
+///             return message.content.clone();
+///         }
+///         tokio::time::sleep(Duration::from_millis(5)).await;
+///     }
+/// };
+/// let heard = tokio::time::timeout(Duration::from_secs(5), heard).await.unwrap();
+/// assert_eq!(heard, "hi bob");
+/// assert_eq!(server.clients().await.len(), 2);
+/// # }
+/// ```
+///
+/// ## Chat filter and spawn point:
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut server = Server::new(8);
+/// server.on_client_join(|_, mut join_brc| {
+///     join_brc.player_pose.position = [10.0, 20.0, 0.0].into();
+///     Box::pin(async move { Some(join_brc) })
+/// });
+/// server.on_client_chat(|_, talk_brc| {
+///     let text = talk_brc.str.to_string_lossy().replace("darn", "****");
+///     Box::pin(async move {
+///         (!text.contains("spam")).then(|| TalkBroadcast {
+///             str: std::ffi::CString::new(text).unwrap(),
+///             ..talk_brc
+///         })
+///     })
+/// });
+/// let running = server.bind("127.0.0.1:0").await.unwrap();
+/// let address = format!("ws://{}", running.address);
+///
+/// // Alice doesn't send poses of her own, so she stays at the spawn point
+/// let (_, alice) = BotBuilder::new("alice")
+///     .address(&address)
+///     .pose_interval(None)
+///     .build()
+///     .run()
+///     .await
+///     .unwrap();
+/// let (_, bob) = BotBuilder::new("bob").address(&address).build().run().await.unwrap();
+///
+/// alice.2.send("spam".to_string()).await.unwrap();
+/// alice.2.send("darn it".to_string()).await.unwrap();
+/// let heard = async {
+///     loop {
+///         if let Some(message) = bob.1.read().await.messages.iter().next() {
+///             return message.content.clone();
+///         }
+///         tokio::time::sleep(Duration::from_millis(5)).await;
+///     }
+/// };
+/// let heard = tokio::time::timeout(Duration::from_secs(5), heard).await.unwrap();
+/// assert_eq!(heard, "**** it");
+/// let alice = bob.1.read().await.player_by_name("alice").unwrap().clone();
+/// assert_eq!(alice.pose.position, [10.0, 20.0, 0.0].into());
+/// # }
+/// ```
+///
+/// ## Freeing the slot of a client whose hook panicked:
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut server = Server::new(1);
+/// server.on_client_chat(|_, talk_brc| {
+///     assert_ne!(talk_brc.str.as_bytes(), b"boom");
+///     Box::pin(async move { Some(talk_brc) })
+/// });
+/// let running = server.bind("127.0.0.1:0").await.unwrap();
+/// let address = format!("ws://{}", running.address);
+///
+/// let (_, unlucky) = BotBuilder::new("unlucky").address(&address).build().run().await.unwrap();
+/// unlucky.2.send("boom".to_string()).await.unwrap();
+/// let freed = async {
+///     while !server.clients().await.is_empty() {
+///         tokio::time::sleep(Duration::from_millis(5)).await;
+///     }
+/// };
+/// tokio::time::timeout(Duration::from_secs(5), freed).await.unwrap();
+/// // The only slot is free again
+/// BotBuilder::new("next").address(&address).build().run().await.unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Server {
+    max_clients: u8,
+    slots: Arc<Mutex<HashMap<u16, Slot>>>,
+    hooks: Arc<Hooks>,
+    recorder: Option<SharedRecorder>,
+}
+
+/// [`Server`] accepting connections in the background, returned by [`Server::bind`].
+pub struct RunningServer {
+    /// Address the server listens on, useful when bound to port `0`
+    pub address: SocketAddr,
+    /// Accepting thread, stops only on error or when aborted
+    pub thread: JoinHandle<Result<(), BotError>>,
+}
+
+impl Server {
+    /// Constructs a new [`Server`] without clients.
+    pub fn new(max_clients: u8) -> Self {
+        Self {
+            max_clients,
+            slots: Default::default(),
+            hooks: Default::default(),
+            recorder: None,
+        }
+    }
+
+    /// Adds a hook called when a client joins, before anyone is told about them.
+    ///
+    /// Rewriting the [`JoinBroadcast`] changes the name and the spawn pose of the client,
+    /// vetoing it refuses the join like a full server does. So does a name longer than
+    /// [`MAX_NAME_LEN`] bytes, it wouldn't fit the join broadcasts.
+    ///
+    /// # Examples
+    ///
+    /// ## Refusing a name that grew too long:
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use std::ffi::CString;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut server = Server::new(8);
+    /// server.on_client_join(|client, join_brc| {
+    ///     let name = format!("{}-the-magnificent-and-very-verbose", client.name.to_str().unwrap());
+    ///     Box::pin(async move {
+    ///         Some(JoinBroadcast { name: CString::new(name).unwrap(), ..join_brc })
+    ///     })
+    /// });
+    /// let running = server.bind("127.0.0.1:0").await.unwrap();
+    /// let bot = BotBuilder::new("bot").address(format!("ws://{}", running.address)).build();
+    /// assert!(bot.run().await.is_err());
+    /// assert!(server.clients().await.is_empty());
+    /// # }
+    /// ```
+    pub fn on_client_join<F>(&mut self, hook: F)
+    where
+        F: Fn(ServerClient, JoinBroadcast) -> FutureHookResult<JoinBroadcast>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Arc::make_mut(&mut self.hooks).join.push(Arc::new(hook));
+    }
+
+    /// Adds a hook called when a client says something, before it is relayed.
+    pub fn on_client_chat<F>(&mut self, hook: F)
+    where
+        F: Fn(ServerClient, TalkBroadcast) -> FutureHookResult<TalkBroadcast>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Arc::make_mut(&mut self.hooks).chat.push(Arc::new(hook));
+    }
+
+    /// Adds a hook called when a client sends a pose, before it is stored and relayed.
+    pub fn on_client_pose<F>(&mut self, hook: F)
+    where
+        F: Fn(ServerClient, PoseBroadcast) -> FutureHookResult<PoseBroadcast>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Arc::make_mut(&mut self.hooks).pose.push(Arc::new(hook));
+    }
+
+    /// Adds a hook called when a client leaves, vetoing it keeps the others from being told.
+    ///
+    /// The client is already removed from [`Server::clients`] when it runs.
+    pub fn on_client_exit<F>(&mut self, hook: F)
+    where
+        F: Fn(ServerClient, ExitBroadcast) -> FutureHookResult<ExitBroadcast>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Arc::make_mut(&mut self.hooks).exit.push(Arc::new(hook));
+    }
+
+    /// Starts recording every frame of every client with timestamps into `recorder`.
+    ///
+    /// Clients are told apart by [`ReplayEntry::peer`], numbered in the order they connect.
+    /// Like hooks, the recorder is copied into the running server by [`Server::bind`]
+    /// and [`Server::serve`], call it before.
+    ///
+    /// [`ReplayEntry::peer`]: super::replay::ReplayEntry::peer
+    pub fn record_session(&mut self, recorder: SharedRecorder) {
+        self.recorder = Some(recorder);
+    }
+
+    /// Max amount of clients.
+    pub fn max_clients(&self) -> u8 {
+        self.max_clients
+    }
+
+    /// Returns the clients connected at the moment, ordered by id.
+    pub async fn clients(&self) -> Vec<ServerClient> {
+        let mut clients: Vec<ServerClient> = self
+            .slots
+            .lock()
+            .await
+            .values()
+            .filter(|slot| slot.joined)
+            .map(|slot| slot.client.clone())
+            .collect();
+        clients.sort_by_key(|client| client.id);
+        clients
+    }
+
+    /// Listens on `address` (`host:port`) and accepts clients in the background.
+    pub async fn bind(&self, address: &str) -> Result<RunningServer, BotError> {
+        let (listener, address) = listen(address).await?;
+        let server = self.clone();
+        let thread = tokio::spawn(async move { server.serve(listener).await });
+        Ok(RunningServer { address, thread })
+    }
+
+    /// Accepts websocket clients from `listener`, every client is served by its own task.
+    ///
+    /// Returns only if accepting fails.
+    pub async fn serve(&self, listener: TcpListener) -> Result<(), BotError> {
+        loop {
+            let (stream, _) = listener
+                .accept()
+                .await
+                .map_err(|e| BotError::Transport(Arc::new(e)))?;
+            let server = self.clone();
+            tokio::spawn(async move { server.accept(stream).await });
+        }
+    }
+
+    /// Performs the websocket handshake on `stream` and serves the client.
+    async fn accept(&self, stream: TcpStream) -> Result<(), BotError> {
+        let (sink, stream) = accept_websocket(stream).await?;
+        self.handle_connection(sink, stream).await
+    }
+
+    /// Serves a single client connected over any transport until it leaves.
+    ///
+    /// Returns [`BotError::HandshakeFailed`] if the first packet isn't a join request
+    /// and [`BotError::PacketParse`] if the client sends something that isn't a packet.
+    pub async fn handle_connection(
+        &self,
+        mut sink: Box<dyn FrameSink>,
+        mut stream: Box<dyn FrameStream>,
+    ) -> Result<(), BotError> {
+        if let Some(recorder) = &self.recorder {
+            (sink, stream) =
+                recorder.attach((sink, stream), Direction::ToServer, recorder.next_peer());
+        }
+        let name = match stream.next_frame().await {
+            Some(frame) => match parse_packet(&frame?) {
+                Ok(Packet::JoinRequest(join_req)) => join_req.name,
+                _ => {
+                    let _ = sink.close().await;
+                    return Err(BotError::HandshakeFailed(
+                        "First packet is not a join request".to_string(),
+                    ));
+                }
+            },
+            None => return Ok(()),
+        };
+        let (outbound, mut queue) = mpsc::unbounded_channel();
+        let guard = match self.join(name, outbound).await {
+            Some(guard) => guard,
+            None => {
+                // Full, the client sees the connection closed instead of a join response
+                let _ = sink.close().await;
+                return Ok(());
+            }
+        };
+        let writer = tokio::spawn(async move {
+            while let Some(frame) = queue.recv().await {
+                sink.send_frame(frame).await?;
+            }
+            sink.close().await
+        });
+        let result = self.relay(guard.id, &mut stream).await;
+        guard.leave().await;
+        // Writer finishes once the queue is dropped with the slot, a send error means the client is gone
+        let _ = writer.await;
+        result
+    }
+
+    /// Assigns an id to the client and introduces them to everyone, [`None`] if the server
+    /// is full, a hook vetoed the join or renamed the client to more than [`MAX_NAME_LEN`] bytes.
+    async fn join(
+        &self,
+        name: CString,
+        outbound: mpsc::UnboundedSender<Bytes>,
+    ) -> Option<LeaveGuard> {
+        let client = {
+            let mut slots = self.slots.lock().await;
+            if slots.len() >= self.max_clients as usize {
+                return None;
+            }
+            let id = (0..=u16::MAX).find(|id| !slots.contains_key(id))?;
+            let client = ServerClient {
+                id,
+                name,
+                pose: PlayerPose::default(),
+            };
+            // Reserved, so the id isn't given to anyone else while the hooks run
+            let slot = Slot {
+                client: client.clone(),
+                outbound: outbound.clone(),
+                joined: false,
+            };
+            slots.insert(id, slot);
+            client
+        };
+        let id = client.id;
+        let guard = LeaveGuard {
+            server: Some(self.clone()),
+            id,
+        };
+        let join_brc = JoinBroadcast {
+            player_pose: client.pose.clone(),
+            id,
+            name: client.name.clone(),
+        };
+        let join_brc = run_hooks(&self.hooks.join, &client, join_brc)
+            .await
+            .filter(|join_brc| join_brc.name.as_bytes().len() <= MAX_NAME_LEN);
+        let Some(join_brc) = join_brc else {
+            guard.leave().await;
+            return None;
+        };
+        let join_brc = JoinBroadcast { id, ..join_brc };
+        let mut slots = self.slots.lock().await;
+        let _ = outbound.send(
+            JoinResponse {
+                max_clients: self.max_clients,
+                id,
+            }
+            .into(),
+        );
+        for slot in slots.values().filter(|slot| slot.joined) {
+            let _ = outbound.send(
+                JoinBroadcast {
+                    player_pose: slot.client.pose.clone(),
+                    id: slot.client.id,
+                    name: slot.client.name.clone(),
+                }
+                .into(),
+            );
+        }
+        let client = ServerClient {
+            id,
+            name: join_brc.name.clone(),
+            pose: join_brc.player_pose.clone(),
+        };
+        broadcast(&slots, id, join_brc.into());
+        slots.insert(
+            id,
+            Slot {
+                client,
+                outbound,
+                joined: true,
+            },
+        );
+        Some(guard)
+    }
+
+    /// Relays requests of the client with `id` until they disconnect.
+    async fn relay(&self, id: u16, stream: &mut Box<dyn FrameStream>) -> Result<(), BotError> {
+        while let Some(frame) = stream.next_frame().await {
+            let frame = frame?;
+            let packet = parse_packet(&frame)?;
+            stream.recycle(frame);
+            let client = match self.slots.lock().await.get(&id) {
+                Some(slot) => slot.client.clone(),
+                None => return Ok(()),
+            };
+            match packet {
+                Packet::PoseRequest(pose_req) => {
+                    let pose_brc = PoseBroadcast {
+                        player_pose: pose_req.player_pose,
+                        id,
+                    };
+                    if let Some(pose_brc) = run_hooks(&self.hooks.pose, &client, pose_brc).await {
+                        let mut slots = self.slots.lock().await;
+                        if let Some(slot) = slots.get_mut(&id) {
+                            slot.client.pose = pose_brc.player_pose.clone();
+                        }
+                        broadcast(&slots, id, PoseBroadcast { id, ..pose_brc }.into());
+                    }
+                }
+                Packet::TalkRequest(talk_req) => {
+                    let talk_brc = TalkBroadcast {
+                        id,
+                        str: talk_req.str,
+                    };
+                    if let Some(talk_brc) = run_hooks(&self.hooks.chat, &client, talk_brc).await {
+                        let slots = self.slots.lock().await;
+                        broadcast(&slots, id, TalkBroadcast { id, ..talk_brc }.into());
+                    }
+                }
+                Packet::LagRequest(lag_req) => {
+                    if let Some(slot) = self.slots.lock().await.get(&id) {
+                        let _ = slot.outbound.send(
+                            LagResponse {
+                                stamp: lag_req.stamp,
+                            }
+                            .into(),
+                        );
+                    }
+                }
+                _ => {} // Only requests are relayed
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the client with `id` and tells everyone they left, if anyone was told they joined.
+    async fn leave(&self, id: u16) {
+        let Some(slot) = self.slots.lock().await.remove(&id) else {
+            return;
+        };
+        if !slot.joined {
+            return;
+        }
+        let exit_brc = ExitBroadcast { id };
+        if run_hooks(&self.hooks.exit, &slot.client, exit_brc)
+            .await
+            .is_some()
+        {
+            let slots = self.slots.lock().await;
+            broadcast(&slots, id, ExitBroadcast { id }.into());
+        }
+    }
+}
+
+/// Binds a listener to `address`, returns it with the actual address.
+pub(crate) async fn listen(address: &str) -> Result<(TcpListener, SocketAddr), BotError> {
+    let listener = TcpListener::bind(address)
+        .await
+        .map_err(|e| BotError::ConnectionFailed(Arc::new(e)))?;
+    let address = listener
+        .local_addr()
+        .map_err(|e| BotError::ConnectionFailed(Arc::new(e)))?;
+    Ok((listener, address))
+}
+
+/// Performs the server side of the websocket handshake on `stream`.
+pub(crate) async fn accept_websocket(stream: TcpStream) -> Result<FrameHalves, BotError> {
+    let ws = tokio_tungstenite::accept_async(MaybeTlsStream::Plain(stream))
+        .await
+        .map_err(|e| BotError::HandshakeFailed(e.to_string()))?;
+    Ok(ws_halves(ws))
+}
+
+/// Queues `frame` for every client except `except`.
+fn broadcast(slots: &HashMap<u16, Slot>, except: u16, frame: Bytes) {
+    for slot in slots
+        .values()
+        .filter(|slot| slot.joined && slot.client.id != except)
+    {
+        // Client is leaving, its task removes the slot soon
+        let _ = slot.outbound.send(frame.clone());
+    }
+}
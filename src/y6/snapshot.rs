@@ -0,0 +1,320 @@
+use super::bot::{Message, Player, ServerId, World};
+use super::packets::{Bytes, EncodePacket, PlayerPose, PLAYER_POSE_SIZE};
+use crate::common::{Instant, SystemTime};
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Magic bytes every world snapshot file starts with.
+pub const SNAPSHOT_MAGIC: [u8; 4] = *b"ACKW";
+/// Version of the snapshot format written by [`WorldSnapshot::write_to`].
+pub const SNAPSHOT_VERSION: u8 = 1;
+
+/// Error of reading or writing a [`WorldSnapshot`].
+#[derive(Debug, Clone)]
+pub enum SnapshotError {
+    /// Reading or writing failed
+    Io(Arc<io::Error>),
+    /// Data doesn't start with [`SNAPSHOT_MAGIC`]
+    NotASnapshot,
+    /// Snapshot was written by a newer version of the format, contains it
+    UnsupportedVersion(u8),
+    /// Snapshot ends in the middle of a value
+    Truncated,
+    /// Nickname, server id or message isn't valid UTF-8
+    InvalidString,
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "Snapshot IO failed: {}", e),
+            SnapshotError::NotASnapshot => write!(f, "Not a world snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported snapshot version: {}", version)
+            }
+            SnapshotError::Truncated => write!(f, "Snapshot is truncated"),
+            SnapshotError::InvalidString => write!(f, "Snapshot contains invalid UTF-8"),
+        }
+    }
+}
+
+impl Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SnapshotError::Io(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(Arc::new(e))
+    }
+}
+
+/// Client of a [`WorldSnapshot`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerSnapshot {
+    /// Id of the client
+    pub id: u16,
+    /// Nickname of the client
+    pub nickname: String,
+    /// Latest pose of the client
+    pub pose: PlayerPose,
+}
+
+/// Chat message of a [`WorldSnapshot`], see [`Message`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageSnapshot {
+    /// Id of the message
+    pub id: u64,
+    /// Contents of the message
+    pub content: String,
+    /// Author id
+    pub author: u16,
+    /// Nickname of the author when the message was received
+    pub author_name: Option<String>,
+    /// When the message was sent
+    pub sent_at: SystemTime,
+}
+
+/// Plain copy of the state of a [`World`], created by [`World::snapshot`].
+///
+/// Unlike the world it holds no [`Instant`]s, so it can be written to a file with
+/// [`WorldSnapshot::write_to`] and compared with snapshots taken later. Clients are ordered by id.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct WorldSnapshot {
+    /// Max amount of clients the server supports
+    pub max_clients: u8,
+    /// Id of the server the world belongs to
+    pub server_id: Option<ServerId>,
+    /// Clients, ordered by id
+    pub clients: Vec<PlayerSnapshot>,
+    /// Chat messages, oldest first
+    pub messages: Vec<MessageSnapshot>,
+    /// Id the next chat message gets
+    pub next_message_id: u64,
+}
+
+impl WorldSnapshot {
+    /// Writes the snapshot to `writer`.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<(), SnapshotError> {
+        let mut body = vec![self.max_clients];
+        put_optional(&mut body, self.server_id.as_deref());
+        body.extend_from_slice(&(self.clients.len() as u32).to_le_bytes());
+        for client in &self.clients {
+            body.extend_from_slice(&client.id.to_le_bytes());
+            put_string(&mut body, &client.nickname);
+            client.pose.encode_into(&mut body);
+        }
+        body.extend_from_slice(&self.next_message_id.to_le_bytes());
+        body.extend_from_slice(&(self.messages.len() as u32).to_le_bytes());
+        for message in &self.messages {
+            let sent_at = message
+                .sent_at
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default();
+            body.extend_from_slice(&message.id.to_le_bytes());
+            body.extend_from_slice(&message.author.to_le_bytes());
+            body.extend_from_slice(&(sent_at.as_nanos() as u64).to_le_bytes());
+            put_optional(&mut body, message.author_name.as_deref());
+            put_string(&mut body, &message.content);
+        }
+        writer.write_all(&SNAPSHOT_MAGIC)?;
+        writer.write_all(&[SNAPSHOT_VERSION])?;
+        writer.write_all(&body)?;
+        Ok(())
+    }
+
+    /// Reads a snapshot written by [`WorldSnapshot::write_to`].
+    pub fn read_from<R: Read>(mut reader: R) -> Result<Self, SnapshotError> {
+        let mut header = [0u8; 5];
+        reader.read_exact(&mut header).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => SnapshotError::NotASnapshot,
+            _ => e.into(),
+        })?;
+        if header[..4] != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::NotASnapshot);
+        }
+        if header[4] > SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(header[4]));
+        }
+        let mut body = vec![];
+        reader.read_to_end(&mut body)?;
+        let mut rest = &body[..];
+        let max_clients = take::<1>(&mut rest)?[0];
+        let server_id = take_optional(&mut rest)?;
+        let mut clients = vec![];
+        for _ in 0..u32::from_le_bytes(take(&mut rest)?) {
+            let id = u16::from_le_bytes(take(&mut rest)?);
+            let nickname = take_string(&mut rest)?;
+            let pose = PlayerPose::try_from(take_slice(&mut rest, PLAYER_POSE_SIZE)?)
+                .map_err(|_| SnapshotError::Truncated)?;
+            clients.push(PlayerSnapshot { id, nickname, pose });
+        }
+        let next_message_id = u64::from_le_bytes(take(&mut rest)?);
+        let mut messages = vec![];
+        for _ in 0..u32::from_le_bytes(take(&mut rest)?) {
+            let id = u64::from_le_bytes(take(&mut rest)?);
+            let author = u16::from_le_bytes(take(&mut rest)?);
+            let sent_at = Duration::from_nanos(u64::from_le_bytes(take(&mut rest)?));
+            let author_name = take_optional(&mut rest)?;
+            let content = take_string(&mut rest)?;
+            messages.push(MessageSnapshot {
+                id,
+                content,
+                author,
+                author_name,
+                sent_at: SystemTime::UNIX_EPOCH + sent_at,
+            });
+        }
+        Ok(Self {
+            max_clients,
+            server_id,
+            clients,
+            messages,
+            next_message_id,
+        })
+    }
+}
+
+impl World {
+    /// Copies the state of the world into a [`WorldSnapshot`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asciicker_rs::y6::prelude::*;
+    /// use tokio::time::Instant;
+    ///
+    /// let mut world = World::default();
+    /// world.max_clients = 8;
+    /// world.clients.insert(2, Player::new("alice", PlayerPose::default(), 2));
+    /// let mut message = Message::new("hello", 2, Instant::now());
+    /// message.author_name = Some("alice".to_string());
+    /// world.messages.push(message);
+    ///
+    /// let mut file = vec![];
+    /// world.snapshot().write_to(&mut file).unwrap();
+    ///
+    /// let mut restored = World::default();
+    /// restored.restore(WorldSnapshot::read_from(&file[..]).unwrap());
+    /// assert_eq!(restored.max_clients, 8);
+    /// assert_eq!(restored.client(2).unwrap().nickname, "alice");
+    /// let messages: Vec<_> = restored.messages.iter().map(|m| m.content.as_str()).collect();
+    /// assert_eq!(messages, ["hello"]);
+    /// assert_eq!(restored.snapshot(), world.snapshot());
+    /// ```
+    pub fn snapshot(&self) -> WorldSnapshot {
+        let mut clients: Vec<_> = self
+            .clients
+            .values()
+            .map(|player| PlayerSnapshot {
+                id: player.id,
+                nickname: player.nickname.clone(),
+                pose: player.pose.clone(),
+            })
+            .collect();
+        clients.sort_by_key(|client| client.id);
+        WorldSnapshot {
+            max_clients: self.max_clients,
+            server_id: self.server_id.clone(),
+            clients,
+            messages: self
+                .messages
+                .iter()
+                .map(|message| MessageSnapshot {
+                    id: message.id,
+                    content: message.content.clone(),
+                    author: message.author,
+                    author_name: message.author_name.clone(),
+                    sent_at: message.sent_at,
+                })
+                .collect(),
+            next_message_id: self.messages.next_id(),
+        }
+    }
+
+    /// Replaces the state of the world with `snapshot`.
+    ///
+    /// [`World::pose_history`] and the capacity of [`World::messages`] are kept, pose histories
+    /// and velocities of the clients start over. Receive times of the messages are derived from
+    /// [`MessageSnapshot::sent_at`].
+    pub fn restore(&mut self, snapshot: WorldSnapshot) {
+        self.max_clients = snapshot.max_clients;
+        self.server_id = snapshot.server_id;
+        self.clients = snapshot
+            .clients
+            .into_iter()
+            .map(|client| {
+                let mut player = Player::new(client.nickname, client.pose, client.id);
+                player.keep_history(self.pose_history);
+                (client.id, player)
+            })
+            .collect();
+        let (now, wall_now) = (Instant::now(), SystemTime::now());
+        let messages = snapshot.messages.into_iter().map(|message| {
+            let age = wall_now.duration_since(message.sent_at).unwrap_or_default();
+            Message {
+                id: message.id,
+                content: message.content,
+                author: message.author,
+                author_name: message.author_name,
+                when: now.checked_sub(age).unwrap_or(now),
+                sent_at: message.sent_at,
+            }
+        });
+        self.messages.restore(messages, snapshot.next_message_id);
+    }
+}
+
+/// Appends `text` prefixed with its length.
+fn put_string(body: &mut Bytes, text: &str) {
+    body.extend_from_slice(&(text.len() as u32).to_le_bytes());
+    body.extend_from_slice(text.as_bytes());
+}
+
+/// Appends a presence byte and `text` if there is one.
+fn put_optional(body: &mut Bytes, text: Option<&str>) {
+    match text {
+        Some(text) => {
+            body.push(1);
+            put_string(body, text);
+        }
+        None => body.push(0),
+    }
+}
+
+/// Takes `len` bytes from the start of `rest`.
+fn take_slice<'a>(rest: &mut &'a [u8], len: usize) -> Result<&'a [u8], SnapshotError> {
+    if rest.len() < len {
+        return Err(SnapshotError::Truncated);
+    }
+    let (taken, remaining) = rest.split_at(len);
+    *rest = remaining;
+    Ok(taken)
+}
+
+/// Takes `N` bytes from the start of `rest`.
+fn take<const N: usize>(rest: &mut &[u8]) -> Result<[u8; N], SnapshotError> {
+    Ok(take_slice(rest, N)?.try_into().unwrap())
+}
+
+/// Takes a string written by [`put_string`].
+fn take_string(rest: &mut &[u8]) -> Result<String, SnapshotError> {
+    let len = u32::from_le_bytes(take(rest)?) as usize;
+    String::from_utf8(take_slice(rest, len)?.to_vec()).map_err(|_| SnapshotError::InvalidString)
+}
+
+/// Takes a string written by [`put_optional`].
+fn take_optional(rest: &mut &[u8]) -> Result<Option<String>, SnapshotError> {
+    match take::<1>(rest)?[0] {
+        0 => Ok(None),
+        _ => take_string(rest).map(Some),
+    }
+}
@@ -0,0 +1,89 @@
+use alloc::collections::BTreeSet;
+
+/// Sprite ids a bot expects in poses, see [`BotBuilder::validate_sprites`].
+///
+/// Poses carry sprites as plain ids defined by the assets of the client, not by the
+/// [protocol](<https://github.com/msokalski/asciicker/blob/80708c9ca5f0ea8539653bb632082ce38b103903/network.h>),
+/// so the catalog starts empty: fill it with the ids of the game version the bot plays.
+///
+/// [`BotBuilder::validate_sprites`]: super::bot::BotBuilder::validate_sprites
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::sprites::*;
+///
+/// let catalog: SpriteCatalog = (0..16).collect();
+/// assert_eq!(catalog.sprite(3), Sprite::Known(3));
+/// assert!(!catalog.sprite(0x7fff).is_known());
+/// assert_eq!(u16::from(catalog.sprite(0x7fff)), 0x7fff);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SpriteCatalog {
+    ids: BTreeSet<u16>,
+}
+
+impl SpriteCatalog {
+    /// Constructs a new empty [`SpriteCatalog`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `id`, returns `false` if it was known already.
+    pub fn insert(&mut self, id: u16) -> bool {
+        self.ids.insert(id)
+    }
+
+    /// Returns `true` if `id` is in the catalog.
+    pub fn contains(&self, id: u16) -> bool {
+        self.ids.contains(&id)
+    }
+
+    /// Looks `id` up in the catalog.
+    pub fn sprite(&self, id: u16) -> Sprite {
+        match self.contains(id) {
+            true => Sprite::Known(id),
+            false => Sprite::Unknown(id),
+        }
+    }
+}
+
+impl FromIterator<u16> for SpriteCatalog {
+    fn from_iter<I: IntoIterator<Item = u16>>(iter: I) -> Self {
+        Self {
+            ids: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<u16> for SpriteCatalog {
+    fn extend<I: IntoIterator<Item = u16>>(&mut self, iter: I) {
+        self.ids.extend(iter)
+    }
+}
+
+/// Sprite of a [`PlayerPose`](super::packets::PlayerPose), looked up in a [`SpriteCatalog`].
+///
+/// Both variants keep the id, so it survives a round trip.
+#[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Eq, Hash)]
+pub enum Sprite {
+    /// Sprite in the catalog
+    Known(u16),
+    /// Sprite missing from the catalog
+    Unknown(u16),
+}
+
+impl Sprite {
+    /// Returns `false` for [`Sprite::Unknown`].
+    pub fn is_known(&self) -> bool {
+        matches!(self, Self::Known(_))
+    }
+}
+
+impl From<Sprite> for u16 {
+    fn from(value: Sprite) -> Self {
+        match value {
+            Sprite::Known(id) | Sprite::Unknown(id) => id,
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use super::packets::Packet;
+use super::utils::PacketParseError;
+use crate::common::Instant;
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::sync::{Arc, Mutex};
+
+/// Shared handle to the [`PacketStats`] of a bot, updated by the running bot.
+pub type SharedPacketStats = Arc<Mutex<PacketStats>>;
+
+/// Traffic of one token byte, see [`PacketStats::tokens`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TokenStats {
+    /// Packets received
+    pub packets: u64,
+    /// Bytes received
+    pub bytes: u64,
+}
+
+/// Counters of the packets the receiver of a bot got from the server.
+///
+/// Frames dropped by an interceptor are not counted. Get them from a running bot with
+/// [`BotHandle::stats`](super::handle::BotHandle::stats) or [`Bot::stats`](super::bot::Bot::stats).
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let bot = BotBuilder::new("counter").replace_invalid_utf8(true).build();
+/// let stats = bot.stats();
+/// Scenario::new(&bot)
+///     .join(2, "alice")
+///     .talk(2, "hi")
+///     .talk(2, "there")
+///     .run()
+///     .await
+///     .unwrap();
+///
+/// let stats = stats.lock().unwrap().clone();
+/// assert_eq!(stats.token(b't').packets, 2);
+/// assert_eq!(stats.token(b'j').bytes, JOIN_BRC_SIZE as u64);
+/// assert_eq!(stats.packets(), 3);
+/// assert_eq!(stats.parse_errors, 0);
+/// assert!(stats.last_received.is_some());
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PacketStats {
+    /// Traffic by token byte, tokens this version doesn't know included
+    pub tokens: BTreeMap<u8, TokenStats>,
+    /// Packets with a token this version doesn't know
+    pub unknown: u64,
+    /// Packets that failed to parse, not counting unknown tokens
+    pub parse_errors: u64,
+    /// When the last packet was received, [`None`] before the first one
+    pub last_received: Option<Instant>,
+}
+
+impl PacketStats {
+    /// Packets received in total.
+    pub fn packets(&self) -> u64 {
+        self.tokens.values().map(|token| token.packets).sum()
+    }
+
+    /// Bytes received in total.
+    pub fn bytes(&self) -> u64 {
+        self.tokens.values().map(|token| token.bytes).sum()
+    }
+
+    /// Traffic of `token`, zero if it was never received.
+    pub fn token(&self, token: u8) -> TokenStats {
+        self.tokens.get(&token).copied().unwrap_or_default()
+    }
+
+    /// Counts `frame`, received at `now`, and the outcome of parsing it.
+    pub(crate) fn record(
+        &mut self,
+        frame: &[u8],
+        parsed: &Result<Packet, PacketParseError>,
+        now: Instant,
+    ) {
+        self.last_received = Some(now);
+        if let Some(&token) = frame.first() {
+            let stats = self.tokens.entry(token).or_default();
+            stats.packets += 1;
+            stats.bytes += frame.len() as u64;
+        }
+        match parsed {
+            Ok(_) => {}
+            Err(PacketParseError::UnknownToken(_)) => self.unknown += 1,
+            Err(_) => self.parse_errors += 1,
+        }
+    }
+}
+
+impl Display for PacketStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} packets, {} bytes, {} unknown, {} invalid",
+            self.packets(),
+            self.bytes(),
+            self.unknown,
+            self.parse_errors
+        )?;
+        for (token, stats) in &self.tokens {
+            write!(
+                f,
+                "; {:?}: {} ({} bytes)",
+                *token as char, stats.packets, stats.bytes
+            )?;
+        }
+        Ok(())
+    }
+}
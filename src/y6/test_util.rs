@@ -0,0 +1,287 @@
+use super::bot::{
+    intercept, patch_world_with, Bot, Callbacks, InterceptAction, Interceptor, Player, Taps, World,
+    MESSAGE_CAPACITY,
+};
+use super::ignore::SharedIgnoreList;
+use super::packets::{
+    Bytes, ExitBroadcast, JoinBroadcast, LagResponse, LagStamp, PlayerPose, PoseBroadcast,
+    TalkBroadcast,
+};
+use super::sprites::SpriteCatalog;
+use super::stats::SharedPacketStats;
+use super::utils::BotError;
+use crate::common::{Instant, Runtime};
+
+use std::{ffi::CString, sync::Arc, time::Duration};
+
+use tokio::sync::{mpsc, Mutex, RwLock};
+
+/// # Mock module
+/// Mock module provides [`MockServer`], a websocket server scripted by the test
+/// for testing bots end to end without hitting asciicker.com.
+#[cfg(feature = "server")]
+mod mock;
+#[cfg(feature = "server")]
+pub use mock::*;
+/// # Simulation module
+/// Simulation module provides [`Simulation`], which runs a bot against scripted events
+/// in virtual time, so timing-dependent behavior can be tested deterministically.
+#[cfg(feature = "simulation")]
+mod simulation;
+#[cfg(feature = "simulation")]
+pub use simulation::*;
+
+/// Single step of a [`Scenario`].
+pub enum ScenarioStep {
+    /// Feed a packet to the bot as if the server sent it
+    Receive(Bytes),
+    /// Wait until the bot says something matching the predicate, fail after the timeout
+    ExpectTalk(Box<dyn Fn(&str) -> bool + Send + Sync>, Duration),
+    /// Fail if the bot says anything during the duration
+    ExpectSilence(Duration),
+    /// Just wait
+    Wait(Duration),
+}
+
+/// Readable acceptance test for bot callbacks.
+///
+/// Scenario runs the callbacks of a [`Bot`] in-process, without connecting anywhere:
+/// packets are fed straight into [`patch_world`](super::bot::patch_world) and everything the callbacks send
+/// through the [`MessageSender`](super::bot::MessageSender) is checked by expectations.
+///
+/// Steps are executed in the order they were added.
+///
+/// # Examples
+///
+/// ## Testing a greeter bot:
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+/// use std::{sync::Arc, time::Duration};
+/// use tokio::sync::{Mutex, RwLock};
+///
+/// #[callback]
+/// async fn greet(
+///     join_brc: JoinBroadcast,
+///     _: Arc<Mutex<Player>>,
+///     _: Arc<RwLock<World>>,
+///     sender: MessageSender,
+/// ) -> BotResult {
+///     sender
+///         .send(format!("Hi, {}!", join_brc.name.to_string_lossy()))
+///         .await
+///         .unwrap();
+///     Ok(())
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut bot = BotBuilder::new("greeter").replace_invalid_utf8(true).build();
+/// bot.on_join(greet);
+///
+/// let world = Scenario::new(&bot)
+///     .join(2, "player")
+///     .expect_talk(|m| m == "Hi, player!", Duration::from_secs(1))
+///     .talk(2, "bye")
+///     .exit(2)
+///     .expect_silence(Duration::from_millis(10))
+///     .run()
+///     .await
+///     .unwrap();
+/// assert!(world.clients.is_empty());
+/// let bye = world.messages.iter().next().unwrap();
+/// assert_eq!(bye.author_name.as_deref(), Some("player"));
+/// # }
+/// ```
+pub struct Scenario {
+    callbacks: Arc<Callbacks>,
+    interceptors: Vec<Interceptor>,
+    ignore_list: SharedIgnoreList,
+    stats: SharedPacketStats,
+    sprites: Option<Arc<SpriteCatalog>>,
+    replace_invalid_utf8: bool,
+    runtime: Result<Arc<dyn Runtime>, BotError>,
+    bot: Player,
+    max_clients: u8,
+    pose_history: usize,
+    steps: Vec<ScenarioStep>,
+}
+
+impl Scenario {
+    /// Creates a new empty [`Scenario`] for the callbacks of `bot`.
+    ///
+    /// Bot gets id `1` and the world has `max_clients` of `255` unless changed.
+    pub fn new(bot: &Bot) -> Self {
+        Self {
+            callbacks: bot.callbacks(),
+            interceptors: bot.interceptors(),
+            ignore_list: bot.ignore_list(),
+            stats: bot.stats(),
+            sprites: bot.sprite_catalog(),
+            replace_invalid_utf8: bot.replaces_invalid_utf8(),
+            runtime: bot.executor(),
+            bot: Player::new(bot.nickname(), Default::default(), 1),
+            max_clients: 255,
+            pose_history: 0,
+            steps: vec![],
+        }
+    }
+
+    /// Sets the id of the bot.
+    pub fn bot_id(mut self, id: u16) -> Self {
+        self.bot.id = id;
+        self
+    }
+
+    /// Sets `max_clients` of the world.
+    pub fn max_clients(mut self, max_clients: u8) -> Self {
+        self.max_clients = max_clients;
+        self
+    }
+
+    /// Sets how many poses each client keeps, see [`Player::history`].
+    pub fn pose_history(mut self, capacity: usize) -> Self {
+        self.pose_history = capacity;
+        self
+    }
+
+    /// Adds a custom step.
+    pub fn step(mut self, step: ScenarioStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Feeds raw bytes to the bot.
+    pub fn receive<B: Into<Bytes>>(self, packet: B) -> Self {
+        self.step(ScenarioStep::Receive(packet.into()))
+    }
+
+    /// Feeds a [`JoinBroadcast`] with default pose to the bot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` contains a null byte.
+    pub fn join(self, id: u16, name: &str) -> Self {
+        self.receive(JoinBroadcast {
+            player_pose: Default::default(),
+            id,
+            name: CString::new(name).expect("Player name cannot contain null bytes"),
+        })
+    }
+
+    /// Feeds an [`ExitBroadcast`] to the bot.
+    pub fn exit(self, id: u16) -> Self {
+        self.receive(ExitBroadcast { id })
+    }
+
+    /// Feeds a [`PoseBroadcast`] to the bot.
+    pub fn pose(self, id: u16, player_pose: PlayerPose) -> Self {
+        self.receive(PoseBroadcast { player_pose, id })
+    }
+
+    /// Feeds a [`TalkBroadcast`] to the bot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `text` contains a null byte.
+    pub fn talk(self, id: u16, text: &str) -> Self {
+        self.receive(TalkBroadcast {
+            id,
+            str: CString::new(text).expect("Message cannot contain null bytes"),
+        })
+    }
+
+    /// Feeds a [`LagResponse`] to the bot.
+    pub fn lag(self, stamp: LagStamp) -> Self {
+        self.receive(LagResponse { stamp })
+    }
+
+    /// Expects the bot to say something matching `predicate` within `timeout`.
+    ///
+    /// Messages that don't match are skipped.
+    pub fn expect_talk<F: Fn(&str) -> bool + Send + Sync + 'static>(
+        self,
+        predicate: F,
+        timeout: Duration,
+    ) -> Self {
+        self.step(ScenarioStep::ExpectTalk(Box::new(predicate), timeout))
+    }
+
+    /// Expects the bot to say nothing during `duration`.
+    pub fn expect_silence(self, duration: Duration) -> Self {
+        self.step(ScenarioStep::ExpectSilence(duration))
+    }
+
+    /// Waits for `duration` before the next step.
+    pub fn wait(self, duration: Duration) -> Self {
+        self.step(ScenarioStep::Wait(duration))
+    }
+
+    /// Runs the scenario and returns the resulting [`World`] if every expectation was met.
+    pub async fn run(self) -> Result<World, BotError> {
+        let runtime = self.runtime?;
+        let (tx, mut rx) = mpsc::channel(MESSAGE_CAPACITY);
+        let tx = Arc::new(tx);
+        let bot = Arc::new(Mutex::new(self.bot));
+        #[allow(deprecated)]
+        let world = Arc::new(RwLock::new(World {
+            max_clients: self.max_clients,
+            pose_history: self.pose_history,
+            ..Default::default()
+        }));
+        for (index, step) in self.steps.into_iter().enumerate() {
+            match step {
+                ScenarioStep::Receive(mut data) => {
+                    if intercept(&self.interceptors, &mut data) == InterceptAction::Drop {
+                        continue;
+                    }
+                    patch_world_with(
+                        Arc::clone(&self.callbacks),
+                        &data,
+                        Arc::clone(&world),
+                        Arc::clone(&bot),
+                        self.replace_invalid_utf8,
+                        Arc::clone(&tx),
+                        Taps {
+                            ignore: Some(&self.ignore_list),
+                            stats: Some(&self.stats),
+                            sprites: self.sprites.as_deref(),
+                            ..Default::default()
+                        },
+                    )
+                    .await?
+                }
+                ScenarioStep::ExpectTalk(predicate, timeout) => {
+                    let deadline = Instant::now() + timeout;
+                    let mut said = vec![];
+                    'expect: loop {
+                        while let Ok(message) = rx.try_recv() {
+                            if predicate(&message) {
+                                break 'expect;
+                            }
+                            said.push(message);
+                        }
+                        if Instant::now() >= deadline {
+                            return Err(BotError::Other(format!(
+                                "Step {}: no matching message within {:?}, bot said: {:?}",
+                                index, timeout, said
+                            )));
+                        }
+                        runtime.sleep(Duration::from_millis(1)).await;
+                    }
+                }
+                ScenarioStep::ExpectSilence(duration) => {
+                    runtime.sleep(duration).await;
+                    if let Ok(message) = rx.try_recv() {
+                        return Err(BotError::Other(format!(
+                            "Step {}: expected silence, bot said: {:?}",
+                            index, message
+                        )));
+                    }
+                }
+                ScenarioStep::Wait(duration) => runtime.sleep(duration).await,
+            }
+        }
+        let world = world.read().await.clone();
+        Ok(world)
+    }
+}
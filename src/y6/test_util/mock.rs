@@ -0,0 +1,384 @@
+use crate::common::transport::{FrameSink, FrameStream};
+use crate::y6::packets::{
+    parse_packet, Bytes, ExitBroadcast, JoinBroadcast, JoinResponse, LagResponse, Packet,
+    PlayerPose, PoseBroadcast, TalkBroadcast,
+};
+use crate::y6::server::{accept_websocket, listen, ServerClient};
+use crate::y6::utils::BotError;
+
+use std::collections::BTreeMap;
+use std::ffi::CString;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex as SyncMutex};
+use std::time::Duration;
+
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, Mutex},
+    task::JoinHandle,
+    time::{self, Instant},
+};
+
+/// `max_clients` a [`MockServer`] announces in its join responses.
+pub const MOCK_MAX_CLIENTS: u8 = 255;
+
+/// Packet received by a [`MockServer`] and the id of the client that sent it.
+pub type ReceivedPacket = (u16, Packet);
+
+/// Y6 server driven by the test instead of by its clients.
+///
+/// Unlike [`Server`](crate::y6::server::Server) it relays nothing: every client that sends a join
+/// request is accepted with the lowest free id from `1` up and a join response, lag requests are
+/// answered, and everything else the clients send is only recorded for [`MockServer::expect`]
+/// and [`MockServer::received`]. Broadcasts are emitted by the test with [`MockServer::join`]
+/// and similar methods, they go to every connected client.
+///
+/// Dropping the server stops accepting clients and closes every connection.
+///
+/// # Examples
+///
+/// ## Testing an echo bot end to end:
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+/// use std::{sync::Arc, time::Duration};
+/// use tokio::sync::{Mutex, RwLock};
+///
+/// #[callback]
+/// async fn echo(
+///     talk_brc: TalkBroadcast,
+///     bot: Arc<Mutex<Player>>,
+///     _: Arc<RwLock<World>>,
+///     sender: MessageSender,
+/// ) -> BotResult {
+///     if talk_brc.id != bot.lock().await.id {
+///         sender.send(talk_brc.str.to_string_lossy().into_owned()).await.unwrap();
+///     }
+///     Ok(())
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let server = MockServer::bind("127.0.0.1:0").await.unwrap();
+/// let mut bot = BotBuilder::new("echo").address(server.url()).build();
+/// bot.on_talk(echo);
+/// let (_threads, _data) = bot.run().await.unwrap();
+///
+/// let client = server.accept(Duration::from_secs(5)).await.unwrap();
+/// assert_eq!((client.id, client.name.to_str().unwrap()), (1, "echo"));
+///
+/// server.join(2, "alice").talk(2, "hello");
+/// let echoed = server.expect_talk(|text| text == "hello", Duration::from_secs(5)).await;
+/// assert_eq!(echoed.unwrap(), "hello");
+///
+/// server.exit(2);
+/// server.expect_silence(Duration::from_millis(50)).await.unwrap();
+/// assert!(matches!(server.received()[0], (1, Packet::JoinRequest(_))));
+/// # }
+/// ```
+pub struct MockServer {
+    /// Address the server listens on, useful when bound to port `0`
+    pub address: SocketAddr,
+    state: Arc<State>,
+    joins: Mutex<mpsc::UnboundedReceiver<ServerClient>>,
+    inbox: Mutex<mpsc::UnboundedReceiver<ReceivedPacket>>,
+    thread: JoinHandle<Result<(), BotError>>,
+}
+
+/// Connected client and the queue of frames to send to it.
+struct Connection {
+    client: ServerClient,
+    outbound: mpsc::UnboundedSender<Bytes>,
+}
+
+/// State shared by the [`MockServer`] and the tasks serving its clients.
+struct State {
+    clients: SyncMutex<BTreeMap<u16, Connection>>,
+    log: SyncMutex<Vec<ReceivedPacket>>,
+    joins: mpsc::UnboundedSender<ServerClient>,
+    inbox: mpsc::UnboundedSender<ReceivedPacket>,
+}
+
+impl MockServer {
+    /// Listens on `address` (`host:port`) and accepts clients in the background.
+    pub async fn bind(address: &str) -> Result<Self, BotError> {
+        let (listener, address) = listen(address).await?;
+        let (joins, joins_rx) = mpsc::unbounded_channel();
+        let (inbox, inbox_rx) = mpsc::unbounded_channel();
+        let state = Arc::new(State {
+            clients: Default::default(),
+            log: Default::default(),
+            joins,
+            inbox,
+        });
+        let thread = tokio::spawn(serve(listener, Arc::clone(&state)));
+        Ok(Self {
+            address,
+            state,
+            joins: Mutex::new(joins_rx),
+            inbox: Mutex::new(inbox_rx),
+            thread,
+        })
+    }
+
+    /// Websocket url of the server, for [`BotBuilder::address`](crate::y6::bot::BotBuilder::address).
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.address)
+    }
+
+    /// Waits for the next client to join, fails after `timeout`.
+    pub async fn accept(&self, timeout: Duration) -> Result<ServerClient, BotError> {
+        let mut joins = self.joins.lock().await;
+        match time::timeout(timeout, joins.recv()).await {
+            Ok(Some(client)) => Ok(client),
+            Ok(None) => Err(BotError::ChannelClosed("mock server")),
+            Err(_) => Err(BotError::Other(format!(
+                "No client joined within {:?}",
+                timeout
+            ))),
+        }
+    }
+
+    /// Returns the clients connected at the moment, ordered by id, with the last pose they sent.
+    pub fn clients(&self) -> Vec<ServerClient> {
+        let clients = self.state.clients.lock().unwrap();
+        clients.values().map(|c| c.client.clone()).collect()
+    }
+
+    /// Returns every packet received so far, oldest first, expected ones included.
+    pub fn received(&self) -> Vec<ReceivedPacket> {
+        self.state.log.lock().unwrap().clone()
+    }
+
+    /// Sends raw bytes to every connected client.
+    pub fn send<B: Into<Bytes>>(&self, packet: B) -> &Self {
+        let frame = packet.into();
+        for connection in self.state.clients.lock().unwrap().values() {
+            // Client is leaving, its task removes it soon
+            let _ = connection.outbound.send(frame.clone());
+        }
+        self
+    }
+
+    /// Sends raw bytes to the client with `id` only, returns `false` if it isn't connected.
+    pub fn send_to<B: Into<Bytes>>(&self, id: u16, packet: B) -> bool {
+        match self.state.clients.lock().unwrap().get(&id) {
+            Some(connection) => connection.outbound.send(packet.into()).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Sends a [`JoinBroadcast`] with default pose to every client.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` contains a null byte.
+    pub fn join(&self, id: u16, name: &str) -> &Self {
+        self.send(JoinBroadcast {
+            player_pose: Default::default(),
+            id,
+            name: CString::new(name).expect("Player name cannot contain null bytes"),
+        })
+    }
+
+    /// Sends an [`ExitBroadcast`] to every client.
+    pub fn exit(&self, id: u16) -> &Self {
+        self.send(ExitBroadcast { id })
+    }
+
+    /// Sends a [`PoseBroadcast`] to every client.
+    pub fn pose(&self, id: u16, player_pose: PlayerPose) -> &Self {
+        self.send(PoseBroadcast { player_pose, id })
+    }
+
+    /// Sends a [`TalkBroadcast`] to every client.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `text` contains a null byte.
+    pub fn talk(&self, id: u16, text: &str) -> &Self {
+        self.send(TalkBroadcast {
+            id,
+            str: CString::new(text).expect("Message cannot contain null bytes"),
+        })
+    }
+
+    /// Expects a client to send a packet matching `predicate` within `timeout`.
+    ///
+    /// Packets that don't match are skipped, every packet is expected at most once.
+    pub async fn expect<F: Fn(&Packet) -> bool>(
+        &self,
+        predicate: F,
+        timeout: Duration,
+    ) -> Result<ReceivedPacket, BotError> {
+        let deadline = Instant::now() + timeout;
+        let mut inbox = self.inbox.lock().await;
+        let mut skipped = vec![];
+        loop {
+            match time::timeout_at(deadline, inbox.recv()).await {
+                Ok(Some((id, packet))) if predicate(&packet) => return Ok((id, packet)),
+                Ok(Some((_, packet))) => skipped.push(packet),
+                Ok(None) => return Err(BotError::ChannelClosed("mock server")),
+                Err(_) => {
+                    return Err(BotError::Other(format!(
+                        "No matching packet within {:?}, received: {:?}",
+                        timeout, skipped
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Expects a client to say something matching `predicate` within `timeout`, returns the text.
+    pub async fn expect_talk<F: Fn(&str) -> bool>(
+        &self,
+        predicate: F,
+        timeout: Duration,
+    ) -> Result<String, BotError> {
+        let matches = |packet: &Packet| match packet {
+            Packet::TalkRequest(talk_req) => predicate(&talk_req.str.to_string_lossy()),
+            _ => false,
+        };
+        match self.expect(matches, timeout).await? {
+            (_, Packet::TalkRequest(talk_req)) => Ok(talk_req.str.to_string_lossy().into_owned()),
+            _ => unreachable!("Only talk requests match"),
+        }
+    }
+
+    /// Expects no client to say anything during `duration`.
+    ///
+    /// Other packets, like the poses and lag requests bots send on their own, are skipped.
+    pub async fn expect_silence(&self, duration: Duration) -> Result<(), BotError> {
+        let deadline = Instant::now() + duration;
+        let mut inbox = self.inbox.lock().await;
+        loop {
+            match time::timeout_at(deadline, inbox.recv()).await {
+                Ok(Some((id, packet @ Packet::TalkRequest(_)))) => {
+                    return Err(BotError::Other(format!(
+                        "Expected silence, client {} sent: {:?}",
+                        id, packet
+                    )))
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => return Err(BotError::ChannelClosed("mock server")),
+                Err(_) => return Ok(()),
+            }
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.thread.abort();
+        // Writers close the connections once their queues are gone
+        self.state.clients.lock().unwrap().clear();
+    }
+}
+
+/// Accepts clients from `listener`, every client is served by its own task.
+async fn serve(listener: TcpListener, state: Arc<State>) -> Result<(), BotError> {
+    loop {
+        let (stream, _) = listener
+            .accept()
+            .await
+            .map_err(|e| BotError::Transport(Arc::new(e)))?;
+        tokio::spawn(connection(stream, Arc::clone(&state)));
+    }
+}
+
+/// Serves a single client until it leaves.
+async fn connection(stream: TcpStream, state: Arc<State>) -> Result<(), BotError> {
+    let (mut sink, mut stream) = accept_websocket(stream).await?;
+    let join_req = match stream.next_frame().await {
+        Some(frame) => match parse_packet(&frame?) {
+            Ok(Packet::JoinRequest(join_req)) => join_req,
+            _ => {
+                let _ = sink.close().await;
+                return Err(BotError::HandshakeFailed(
+                    "First packet is not a join request".to_string(),
+                ));
+            }
+        },
+        None => return Ok(()),
+    };
+    let (outbound, queue) = mpsc::unbounded_channel();
+    let client = {
+        let mut clients = state.clients.lock().unwrap();
+        let id = (1..=u16::MAX)
+            .find(|id| !clients.contains_key(id))
+            .ok_or_else(|| BotError::Other("Mock server is full".to_string()))?;
+        let client = ServerClient {
+            id,
+            name: join_req.name.clone(),
+            pose: PlayerPose::default(),
+        };
+        let _ = outbound.send(
+            JoinResponse {
+                max_clients: MOCK_MAX_CLIENTS,
+                id,
+            }
+            .into(),
+        );
+        let connection = Connection {
+            client: client.clone(),
+            outbound,
+        };
+        clients.insert(id, connection);
+        client
+    };
+    let id = client.id;
+    let writer = tokio::spawn(write(sink, queue));
+    state.record(id, Packet::JoinRequest(join_req));
+    let _ = state.joins.send(client);
+    let result = read(&state, id, &mut stream).await;
+    state.clients.lock().unwrap().remove(&id);
+    // Writer finishes once the queue is dropped with the connection
+    let _ = writer.await;
+    result
+}
+
+/// Sends the frames queued for a client until the queue is dropped.
+async fn write(
+    mut sink: Box<dyn FrameSink>,
+    mut queue: mpsc::UnboundedReceiver<Bytes>,
+) -> Result<(), BotError> {
+    while let Some(frame) = queue.recv().await {
+        sink.send_frame(frame).await?;
+    }
+    sink.close().await
+}
+
+/// Records the packets of the client with `id` until it disconnects.
+async fn read(state: &State, id: u16, stream: &mut Box<dyn FrameStream>) -> Result<(), BotError> {
+    while let Some(frame) = stream.next_frame().await {
+        let frame = frame?;
+        let packet = parse_packet(&frame)?;
+        stream.recycle(frame);
+        match &packet {
+            Packet::LagRequest(lag_req) => {
+                let lag_rsp = LagResponse {
+                    stamp: lag_req.stamp,
+                };
+                if let Some(connection) = state.clients.lock().unwrap().get(&id) {
+                    let _ = connection.outbound.send(lag_rsp.into());
+                }
+            }
+            Packet::PoseRequest(pose_req) => {
+                if let Some(connection) = state.clients.lock().unwrap().get_mut(&id) {
+                    connection.client.pose = pose_req.player_pose.clone();
+                }
+            }
+            _ => {}
+        }
+        state.record(id, packet);
+    }
+    Ok(())
+}
+
+impl State {
+    /// Logs `packet` of the client with `id` and hands it to the expectations.
+    fn record(&self, id: u16, packet: Packet) {
+        self.log.lock().unwrap().push((id, packet.clone()));
+        // Server is being dropped, nobody expects anything anymore
+        let _ = self.inbox.send((id, packet));
+    }
+}
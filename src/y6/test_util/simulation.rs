@@ -0,0 +1,446 @@
+use super::ScenarioStep;
+use crate::common::transport::{FrameHalves, FrameSink, FrameStream, Transport};
+use crate::common::Instant;
+use crate::y6::bot::Bot;
+use crate::y6::handle::{BotHandle, Snapshot};
+use crate::y6::packets::{
+    parse_packet, Bytes, ExitBroadcast, JoinBroadcast, JoinResponse, LagResponse, Packet,
+    PlayerPose, PoseBroadcast, TalkBroadcast,
+};
+use crate::y6::utils::BotError;
+
+use std::ffi::CString;
+use std::future::Future;
+use std::sync::{Arc, Mutex as SyncMutex};
+use std::time::Duration;
+
+use futures_util::future::{self, BoxFuture};
+use tokio::{runtime, sync::mpsc, time};
+
+/// What happened during a [`Simulation`], times are virtual and relative to its start.
+#[derive(Debug, Clone)]
+pub struct SimulationReport {
+    /// Every packet the bot sent, join requests of every connection included
+    pub sent: Vec<(Duration, Packet)>,
+    /// Every connection attempt, failed ones included
+    pub connects: Vec<Duration>,
+    /// Bot and its world once the last step finished
+    pub snapshot: Arc<Snapshot>,
+    /// Virtual time the simulation took
+    pub elapsed: Duration,
+}
+
+impl SimulationReport {
+    /// Returns what the bot said and when.
+    pub fn talk(&self) -> Vec<(Duration, String)> {
+        self.sent
+            .iter()
+            .filter_map(|(at, packet)| match packet {
+                Packet::TalkRequest(talk_req) => {
+                    Some((*at, talk_req.str.to_string_lossy().into_owned()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Runs a [`Bot`] against scripted events in virtual time.
+///
+/// The bot connects through an in-memory transport to a fake server, which answers join
+/// and lag requests and records everything the bot sends. Steps are executed in the order
+/// they were added, like in a [`Scenario`](super::Scenario), but with the real connection
+/// threads of the bot running: pose and lag intervals, reconnect delays, the join queue and
+/// [`BotHandle`] movement all take part.
+///
+/// Everything runs on a single-threaded tokio runtime with the clock paused. The clock only
+/// moves once every task is idle and then jumps straight to the next timer, so hours of
+/// waiting take no real time and every run produces the same [`SimulationReport`].
+/// [`Simulation::run`] builds that runtime itself, so it can't be called from async code.
+///
+/// # Examples
+///
+/// ## Reconnect backoff and lag requests:
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+/// use std::time::Duration;
+///
+/// let bot = BotBuilder::new("patient")
+///     .pose_interval(None)
+///     .reconnect(ReconnectPolicy::Backoff {
+///         initial: Duration::from_secs(1),
+///         max: Duration::from_secs(60),
+///         attempts: None,
+///     })
+///     .build();
+/// let report = Simulation::new(bot)
+///     .fail_connects(3)
+///     .wait(Duration::from_secs(60))
+///     .run()
+///     .unwrap();
+///
+/// let connects: Vec<u64> = report.connects.iter().map(|at| at.as_secs()).collect();
+/// assert_eq!(connects, [0, 1, 3, 7]);
+/// let lags: Vec<Duration> = report
+///     .sent
+///     .iter()
+///     .filter(|(_, packet)| matches!(packet, Packet::LagRequest(_)))
+///     .map(|&(at, _)| at)
+///     .collect();
+/// assert_eq!(lags[0], Duration::from_secs(9));
+/// assert!(lags.windows(2).all(|pair| pair[1] - pair[0] == Duration::from_secs(2)));
+/// ```
+///
+/// ## Walking a patrol route:
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+/// use asciicker_rs::y6::bot::movement::*;
+/// use std::time::Duration;
+///
+/// let bot = BotBuilder::new("guard")
+///     .pose_interval(Some(Duration::from_millis(100)))
+///     .build();
+/// let path = Path::new(PathMode::Once).waypoint([10.0, 0.0, 0.0]);
+/// let report = Simulation::new(bot)
+///     .join(2, "alice")
+///     .run_with(|handle| async move { handle.patrol(&path).await.unwrap() })
+///     .unwrap();
+///
+/// // 10 units at WALK_SPEED take a second
+/// assert_eq!(report.elapsed.as_secs(), 1);
+/// assert_eq!(report.snapshot.player.pose.position, [10.0, 0.0, 0.0].into());
+/// assert!(report.snapshot.world.player_by_name("alice").is_some());
+/// ```
+pub struct Simulation {
+    bot: Bot,
+    bot_id: u16,
+    max_clients: u8,
+    fail_connects: u32,
+    refuse_joins: u32,
+    steps: Vec<ScenarioStep>,
+}
+
+impl Simulation {
+    /// Creates a new empty [`Simulation`] of `bot`, replacing its [`Transport`].
+    ///
+    /// Bot gets id `1` and the world has `max_clients` of `255` unless changed.
+    pub fn new(bot: Bot) -> Self {
+        Self {
+            bot,
+            bot_id: 1,
+            max_clients: 255,
+            fail_connects: 0,
+            refuse_joins: 0,
+            steps: vec![],
+        }
+    }
+
+    /// Sets the id of the bot.
+    pub fn bot_id(mut self, id: u16) -> Self {
+        self.bot_id = id;
+        self
+    }
+
+    /// Sets `max_clients` of the world.
+    pub fn max_clients(mut self, max_clients: u8) -> Self {
+        self.max_clients = max_clients;
+        self
+    }
+
+    /// Makes the first `attempts` connection attempts fail, see [`ReconnectPolicy`].
+    ///
+    /// [`ReconnectPolicy`]: crate::y6::bot::ReconnectPolicy
+    pub fn fail_connects(mut self, attempts: u32) -> Self {
+        self.fail_connects = attempts;
+        self
+    }
+
+    /// Makes the server refuse the first `joins` join requests, see [`Bot::join_queue`].
+    pub fn refuse_joins(mut self, joins: u32) -> Self {
+        self.refuse_joins = joins;
+        self
+    }
+
+    /// Adds a custom step.
+    pub fn step(mut self, step: ScenarioStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Feeds raw bytes to the bot.
+    pub fn receive<B: Into<Bytes>>(self, packet: B) -> Self {
+        self.step(ScenarioStep::Receive(packet.into()))
+    }
+
+    /// Feeds a [`JoinBroadcast`] with default pose to the bot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` contains a null byte.
+    pub fn join(self, id: u16, name: &str) -> Self {
+        self.receive(JoinBroadcast {
+            player_pose: Default::default(),
+            id,
+            name: CString::new(name).expect("Player name cannot contain null bytes"),
+        })
+    }
+
+    /// Feeds an [`ExitBroadcast`] to the bot.
+    pub fn exit(self, id: u16) -> Self {
+        self.receive(ExitBroadcast { id })
+    }
+
+    /// Feeds a [`PoseBroadcast`] to the bot.
+    pub fn pose(self, id: u16, player_pose: PlayerPose) -> Self {
+        self.receive(PoseBroadcast { player_pose, id })
+    }
+
+    /// Feeds a [`TalkBroadcast`] to the bot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `text` contains a null byte.
+    pub fn talk(self, id: u16, text: &str) -> Self {
+        self.receive(TalkBroadcast {
+            id,
+            str: CString::new(text).expect("Message cannot contain null bytes"),
+        })
+    }
+
+    /// Expects the bot to say something matching `predicate` within `timeout` of virtual time.
+    ///
+    /// Messages that don't match are skipped.
+    pub fn expect_talk<F: Fn(&str) -> bool + Send + Sync + 'static>(
+        self,
+        predicate: F,
+        timeout: Duration,
+    ) -> Self {
+        self.step(ScenarioStep::ExpectTalk(Box::new(predicate), timeout))
+    }
+
+    /// Expects the bot to say nothing during `duration` of virtual time.
+    pub fn expect_silence(self, duration: Duration) -> Self {
+        self.step(ScenarioStep::ExpectSilence(duration))
+    }
+
+    /// Lets `duration` of virtual time pass before the next step.
+    pub fn wait(self, duration: Duration) -> Self {
+        self.step(ScenarioStep::Wait(duration))
+    }
+
+    /// Runs the simulation until the last step finished.
+    ///
+    /// Returns [`BotError`] if the bot failed to join or an expectation wasn't met.
+    pub fn run(self) -> Result<SimulationReport, BotError> {
+        self.run_with(|_| future::ready(()))
+    }
+
+    /// Runs the simulation like [`Simulation::run`], with `driver` running next to the steps
+    /// once the bot joined, until both are done.
+    pub fn run_with<F, Fut>(self, driver: F) -> Result<SimulationReport, BotError>
+    where
+        F: FnOnce(BotHandle) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let runtime = runtime::Builder::new_current_thread()
+            .enable_time()
+            .start_paused(true)
+            .build()
+            .map_err(|e| BotError::Other(format!("Failed to build the runtime: {}", e)))?;
+        runtime.block_on(self.simulate(driver))
+    }
+
+    async fn simulate<F, Fut>(self, driver: F) -> Result<SimulationReport, BotError>
+    where
+        F: FnOnce(BotHandle) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let (talks, mut said) = mpsc::unbounded_channel();
+        let server = Arc::new(FakeServer {
+            start: Instant::now(),
+            bot_id: self.bot_id,
+            max_clients: self.max_clients,
+            state: SyncMutex::new(ServerState {
+                fail_connects: self.fail_connects,
+                refuse_joins: self.refuse_joins,
+                ..Default::default()
+            }),
+            talks,
+        });
+        let mut bot = self.bot;
+        bot.transport(Arc::new(SimulatedTransport(Arc::clone(&server))));
+        let (_threads, handle) = bot.spawn().await?;
+        let steps = async {
+            for (index, step) in self.steps.into_iter().enumerate() {
+                match step {
+                    ScenarioStep::Receive(data) => {
+                        let client = server.state.lock().unwrap().client.clone();
+                        match client {
+                            Some(client) if client.send(data).is_ok() => {}
+                            _ => {
+                                return Err(BotError::Other(format!(
+                                    "Step {}: bot is not connected",
+                                    index
+                                )))
+                            }
+                        }
+                    }
+                    ScenarioStep::ExpectTalk(predicate, timeout) => {
+                        let deadline = Instant::now() + timeout;
+                        let mut skipped = vec![];
+                        loop {
+                            match time::timeout_at(deadline, said.recv()).await {
+                                Ok(Some(message)) if predicate(&message) => break,
+                                Ok(Some(message)) => skipped.push(message),
+                                Ok(None) | Err(_) => {
+                                    return Err(BotError::Other(format!(
+                                        "Step {}: no matching message within {:?}, bot said: {:?}",
+                                        index, timeout, skipped
+                                    )))
+                                }
+                            }
+                        }
+                    }
+                    ScenarioStep::ExpectSilence(duration) => {
+                        time::sleep(duration).await;
+                        if let Ok(message) = said.try_recv() {
+                            return Err(BotError::Other(format!(
+                                "Step {}: expected silence, bot said: {:?}",
+                                index, message
+                            )));
+                        }
+                    }
+                    ScenarioStep::Wait(duration) => time::sleep(duration).await,
+                }
+            }
+            Ok(())
+        };
+        let (result, ()) = future::join(steps, driver(handle.clone())).await;
+        result?;
+        let elapsed = server.start.elapsed();
+        // Clock moves only once every task is idle, so the snapshot has caught up after this
+        time::sleep(Duration::from_millis(1)).await;
+        let state = server.state.lock().unwrap();
+        Ok(SimulationReport {
+            sent: state.sent.clone(),
+            connects: state.connects.clone(),
+            snapshot: handle.snapshot(),
+            elapsed,
+        })
+    }
+}
+
+/// Server side of a [`Simulation`], shared with every connection.
+struct FakeServer {
+    start: Instant,
+    bot_id: u16,
+    max_clients: u8,
+    state: SyncMutex<ServerState>,
+    talks: mpsc::UnboundedSender<String>,
+}
+
+#[derive(Default)]
+struct ServerState {
+    fail_connects: u32,
+    refuse_joins: u32,
+    connects: Vec<Duration>,
+    sent: Vec<(Duration, Packet)>,
+    /// Queue of frames to the bot over the latest joined connection
+    client: Option<mpsc::UnboundedSender<Bytes>>,
+}
+
+/// [`Transport`] connecting to the [`FakeServer`] in memory.
+struct SimulatedTransport(Arc<FakeServer>);
+
+impl Transport for SimulatedTransport {
+    fn connect<'a>(&'a self, _: &'a str) -> BoxFuture<'a, Result<FrameHalves, BotError>> {
+        Box::pin(async move {
+            let server = &self.0;
+            let mut state = server.state.lock().unwrap();
+            state.connects.push(server.start.elapsed());
+            if state.fail_connects > 0 {
+                state.fail_connects -= 1;
+                return Err(BotError::ConnectionFailed(Arc::from(Box::<
+                    dyn std::error::Error + Send + Sync,
+                >::from(
+                    "Simulated connection failure",
+                ))));
+            }
+            let (client, inbound) = mpsc::unbounded_channel();
+            let sink = SimulatedSink {
+                server: Arc::clone(server),
+                client: Some(client),
+            };
+            Ok((
+                Box::new(sink) as Box<dyn FrameSink>,
+                Box::new(SimulatedStream(inbound)) as Box<dyn FrameStream>,
+            ))
+        })
+    }
+}
+
+/// Sending half of a simulated connection, the [`FakeServer`] handles frames as they are sent.
+struct SimulatedSink {
+    server: Arc<FakeServer>,
+    /// Dropped to close the connection
+    client: Option<mpsc::UnboundedSender<Bytes>>,
+}
+
+impl FrameSink for SimulatedSink {
+    fn send_frame(&mut self, frame: Bytes) -> BoxFuture<'_, Result<(), BotError>> {
+        Box::pin(async move {
+            let packet = parse_packet(&frame)?;
+            let server = &self.server;
+            let mut state = server.state.lock().unwrap();
+            state.sent.push((server.start.elapsed(), packet.clone()));
+            let Some(client) = &self.client else {
+                return Err(BotError::ChannelClosed("simulated connection"));
+            };
+            match packet {
+                Packet::JoinRequest(_) if state.refuse_joins > 0 => {
+                    // Closing instead of responding is how a full server refuses
+                    state.refuse_joins -= 1;
+                    self.client = None;
+                }
+                Packet::JoinRequest(_) => {
+                    let join_rsp = JoinResponse {
+                        max_clients: server.max_clients,
+                        id: server.bot_id,
+                    };
+                    let _ = client.send(join_rsp.into());
+                    state.client = Some(client.clone());
+                }
+                Packet::LagRequest(lag_req) => {
+                    let _ = client.send(
+                        LagResponse {
+                            stamp: lag_req.stamp,
+                        }
+                        .into(),
+                    );
+                }
+                Packet::TalkRequest(talk_req) => {
+                    // Nobody expects anything once the steps are done
+                    let _ = server
+                        .talks
+                        .send(talk_req.str.to_string_lossy().into_owned());
+                }
+                _ => {}
+            }
+            Ok(())
+        })
+    }
+
+    fn close(&mut self) -> BoxFuture<'_, Result<(), BotError>> {
+        self.client = None;
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Receiving half of a simulated connection.
+struct SimulatedStream(mpsc::UnboundedReceiver<Bytes>);
+
+impl FrameStream for SimulatedStream {
+    fn next_frame(&mut self) -> BoxFuture<'_, Option<Result<Bytes, BotError>>> {
+        Box::pin(async move { self.0.recv().await.map(Ok) })
+    }
+}
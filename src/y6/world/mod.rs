@@ -0,0 +1,10 @@
+//! Static data of asciicker worlds, loaded from the files the game ships with.
+
+/// # Pathfinding module
+/// Pathfinding module provides [`Pathfinder`](pathfinding::Pathfinder), which plans routes over
+/// a terrain around cliffs and water for [`BotHandle::patrol`](crate::y6::handle::BotHandle::patrol).
+pub mod pathfinding;
+/// # Terrain module
+/// Terrain module provides [`Terrain`](terrain::Terrain), the patches of a world's heightmap
+/// with height queries, so bots can stand on the ground instead of a hardcoded height.
+pub mod terrain;
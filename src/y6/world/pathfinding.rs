@@ -0,0 +1,277 @@
+use super::terrain::{Terrain, HEIGHT_CELLS, PATCH_SIZE};
+use crate::common::Position;
+#[cfg(feature = "bot")]
+use crate::y6::bot::movement::{Path, PathMode};
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Default steepest walkable slope of a [`Pathfinder`], height per unit of horizontal distance.
+pub const DEFAULT_MAX_SLOPE: f32 = 1.0;
+/// Default limit of grid points a [`Pathfinder`] explores before giving up.
+pub const DEFAULT_MAX_NODES: usize = 100_000;
+/// Distance between two grid points of a [`Pathfinder`], one height cell.
+pub const GRID_STEP: f32 = PATCH_SIZE / HEIGHT_CELLS as f32;
+
+/// Neighbours of a grid point: straight moves first, then diagonal ones.
+const NEIGHBOURS: [(i32, i32); 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+/// Plans walkable routes over a [`Terrain`] with A*.
+///
+/// Routes go over the corners of the height cells, [`GRID_STEP`] units apart, in eight
+/// directions. A step is walkable if both ends are on the terrain, above water unless
+/// [`Pathfinder::allow_water`] is set, and not steeper than [`Pathfinder::max_slope`].
+/// Diagonal steps also need both straight steps around them to be walkable, so routes
+/// don't cut corners of cliffs.
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+///
+/// // Two rows of flat patches with a cliff in the middle of the first row
+/// let mut terrain = Terrain::default();
+/// for x in 0..3 {
+///     for y in 0..2 {
+///         let height = if (x, y) == (1, 0) { 1000 } else { 100 };
+///         terrain.patches.insert((x, y), Patch::flat(x, y, height));
+///     }
+/// }
+///
+/// let pathfinder = Pathfinder::new(&terrain);
+/// let route = pathfinder.find([1.0, 1.0, 0.0], [21.0, 1.0, 0.0]).unwrap();
+/// assert!(route.iter().all(|point| point.z == 100.0));
+/// assert!(route.iter().any(|point| point.y >= PATCH_SIZE));
+/// assert_eq!(*route.last().unwrap(), Position::new(21.0, 1.0, 100.0));
+///
+/// // Ready for BotHandle::patrol
+/// let path = pathfinder.path([1.0, 1.0, 0.0], [21.0, 1.0, 0.0]).unwrap();
+/// assert_eq!(path.waypoints().len(), route.len());
+///
+/// // Without the second row there is no way around
+/// terrain.patches.retain(|&(_, y), _| y == 0);
+/// assert!(Pathfinder::new(&terrain).find([1.0, 1.0, 0.0], [21.0, 1.0, 0.0]).is_none());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Pathfinder<'a> {
+    terrain: &'a Terrain,
+    max_slope: f32,
+    allow_water: bool,
+    max_nodes: usize,
+}
+
+impl<'a> Pathfinder<'a> {
+    /// Creates a new [`Pathfinder`] over `terrain`, avoiding water and slopes steeper than
+    /// [`DEFAULT_MAX_SLOPE`].
+    pub fn new(terrain: &'a Terrain) -> Self {
+        Self {
+            terrain,
+            max_slope: DEFAULT_MAX_SLOPE,
+            allow_water: false,
+            max_nodes: DEFAULT_MAX_NODES,
+        }
+    }
+
+    /// Sets the steepest walkable slope, height per unit of horizontal distance.
+    pub fn max_slope(mut self, max_slope: f32) -> Self {
+        self.max_slope = max_slope;
+        self
+    }
+
+    /// Allows walking below [`Terrain::water_level`].
+    pub fn allow_water(mut self, allow_water: bool) -> Self {
+        self.allow_water = allow_water;
+        self
+    }
+
+    /// Sets how many grid points are explored before giving up.
+    pub fn max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    /// Finds a route from `from` to `to`, heights are taken from the terrain.
+    ///
+    /// Returns the points to walk through, ending at `to`. Points on a straight line are
+    /// merged, so the route only has the turns. [`None`] if there is no walkable route or
+    /// the search explored [`Pathfinder::max_nodes`] points without finding one.
+    pub fn find<P: Into<Position>>(&self, from: P, to: P) -> Option<Vec<Position>> {
+        let (from, to) = (from.into(), to.into());
+        let start = grid_point(from);
+        let goal = grid_point(to);
+        let start_height = self.walkable_height(start)?;
+        self.walkable_height(goal)?;
+        let mut open = BinaryHeap::from([Candidate {
+            estimate: 0.0,
+            point: start,
+        }]);
+        let mut costs = HashMap::from([(start, 0.0f32)]);
+        let mut came_from = HashMap::new();
+        let mut heights = HashMap::from([(start, start_height)]);
+        while let Some(Candidate { point, estimate }) = open.pop() {
+            if point == goal {
+                return Some(self.route(&came_from, &heights, goal, to));
+            }
+            let cost = costs[&point];
+            if estimate > cost + distance(point, goal) {
+                // Outdated entry, the point was reached more cheaply since
+                continue;
+            }
+            if costs.len() > self.max_nodes {
+                return None;
+            }
+            let height = heights[&point];
+            for (dx, dy) in NEIGHBOURS {
+                let next = (point.0 + dx, point.1 + dy);
+                let next_height = match self.step_height(point, height, next) {
+                    Some(next_height) => next_height,
+                    None => continue,
+                };
+                if dx != 0 && dy != 0 {
+                    let straight = [(point.0 + dx, point.1), (point.0, point.1 + dy)];
+                    if straight
+                        .into_iter()
+                        .any(|side| self.step_height(point, height, side).is_none())
+                    {
+                        continue;
+                    }
+                }
+                let climb = next_height - height;
+                let step = distance(point, next);
+                let next_cost = cost + (step * step + climb * climb).sqrt();
+                if costs.get(&next).is_none_or(|&known| next_cost < known) {
+                    costs.insert(next, next_cost);
+                    heights.insert(next, next_height);
+                    came_from.insert(next, point);
+                    open.push(Candidate {
+                        estimate: next_cost + distance(next, goal),
+                        point: next,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// Finds a route like [`Pathfinder::find`] and turns it into a [`Path`] for
+    /// [`BotHandle::patrol`](crate::y6::handle::BotHandle::patrol).
+    #[cfg(feature = "bot")]
+    pub fn path<P: Into<Position>>(&self, from: P, to: P) -> Option<Path> {
+        let route = self.find(from, to)?;
+        Some(
+            route
+                .into_iter()
+                .fold(Path::new(PathMode::Once), |path, point| {
+                    path.waypoint(point)
+                }),
+        )
+    }
+
+    /// Height at `point` if the bot may stand there.
+    fn walkable_height(&self, point: (i32, i32)) -> Option<f32> {
+        let (x, y) = world_xy(point);
+        let height = self.terrain.height_at(x, y)?;
+        (self.allow_water || height >= self.terrain.water_level).then_some(height)
+    }
+
+    /// Height at `next` if the step from `point` at `height` to it is walkable.
+    fn step_height(&self, point: (i32, i32), height: f32, next: (i32, i32)) -> Option<f32> {
+        let next_height = self.walkable_height(next)?;
+        let slope = (next_height - height).abs() / distance(point, next);
+        (slope <= self.max_slope).then_some(next_height)
+    }
+
+    /// Walks `came_from` back from `goal`, merging points on a straight line.
+    fn route(
+        &self,
+        came_from: &HashMap<(i32, i32), (i32, i32)>,
+        heights: &HashMap<(i32, i32), f32>,
+        goal: (i32, i32),
+        to: Position,
+    ) -> Vec<Position> {
+        let mut points = vec![goal];
+        while let Some(&previous) = came_from.get(points.last().unwrap()) {
+            points.push(previous);
+        }
+        points.reverse();
+        let mut route: Vec<Position> = points
+            .iter()
+            .enumerate()
+            .filter(|&(index, point)| {
+                let (Some(before), Some(after)) = (
+                    index.checked_sub(1).and_then(|i| points.get(i)),
+                    points.get(index + 1),
+                ) else {
+                    // Start is where the bot already is, the goal is replaced by `to` below
+                    return false;
+                };
+                (point.0 - before.0, point.1 - before.1) != (after.0 - point.0, after.1 - point.1)
+            })
+            .map(|(_, &point)| {
+                let (x, y) = world_xy(point);
+                Position::new(x, y, heights[&point])
+            })
+            .collect();
+        let z = self.terrain.height_at(to.x, to.y).unwrap_or(heights[&goal]);
+        route.push(Position { z, ..to });
+        route
+    }
+}
+
+/// Point of the open set, ordered so the lowest estimate is popped first.
+#[derive(Debug, Copy, Clone)]
+struct Candidate {
+    estimate: f32,
+    point: (i32, i32),
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .total_cmp(&self.estimate)
+            .then_with(|| self.point.cmp(&other.point))
+    }
+}
+
+/// Closest grid point to `position`.
+fn grid_point(position: Position) -> (i32, i32) {
+    (
+        (position.x / GRID_STEP).round() as i32,
+        (position.y / GRID_STEP).round() as i32,
+    )
+}
+
+/// World coordinates of a grid point.
+fn world_xy(point: (i32, i32)) -> (f32, f32) {
+    (point.0 as f32 * GRID_STEP, point.1 as f32 * GRID_STEP)
+}
+
+/// Horizontal distance between two grid points in world units.
+fn distance(a: (i32, i32), b: (i32, i32)) -> f32 {
+    let (dx, dy) = ((a.0 - b.0) as f32, (a.1 - b.1) as f32);
+    (dx * dx + dy * dy).sqrt() * GRID_STEP
+}
@@ -0,0 +1,241 @@
+//! Terrain section of asciicker's `.a3d` world files.
+//!
+//! The terrain is a sparse grid of square patches, each with a small heightmap and the
+//! material of every visual cell. Every patch is stored as (all little-endian):
+//!
+//! | Field    | Type                                           |
+//! |----------|------------------------------------------------|
+//! | `x`, `y` | `i32` each, patch coordinates                  |
+//! | `diag`   | `u16`, one bit per height cell, see [`Patch::diag`] |
+//! | `height` | `u16` × ([`HEIGHT_CELLS`] + 1)², rows of `x`   |
+//! | `visual` | `u16` × [`VISUAL_CELLS`]², rows of `x`         |
+//!
+//! after an `i32` with the amount of patches. Whatever follows the terrain section
+//! in a file is left unread.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::common::Position;
+
+/// Height cells along one side of a [`Patch`].
+pub const HEIGHT_CELLS: usize = 4;
+/// Visual cells along one side of a [`Patch`].
+pub const VISUAL_CELLS: usize = 8;
+/// Length of one side of a [`Patch`] in world units, one unit per visual cell.
+pub const PATCH_SIZE: f32 = VISUAL_CELLS as f32;
+/// Size of one stored [`Patch`] in bytes.
+pub const PATCH_RECORD_SIZE: usize =
+    4 + 4 + 2 + (HEIGHT_CELLS + 1).pow(2) * 2 + VISUAL_CELLS.pow(2) * 2;
+
+/// Error of reading a [`Terrain`].
+#[derive(Debug, Clone)]
+pub enum TerrainError {
+    /// Reading failed
+    Io(Arc<io::Error>),
+    /// Data ends in the middle of a patch
+    Truncated,
+    /// Amount of patches is negative, contains it
+    InvalidPatchCount(i32),
+}
+
+impl Display for TerrainError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TerrainError::Io(e) => write!(f, "Terrain IO failed: {}", e),
+            TerrainError::Truncated => write!(f, "Terrain is truncated"),
+            TerrainError::InvalidPatchCount(count) => {
+                write!(f, "Invalid amount of terrain patches: {}", count)
+            }
+        }
+    }
+}
+
+impl Error for TerrainError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TerrainError::Io(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TerrainError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => Self::Truncated,
+            _ => Self::Io(Arc::new(e)),
+        }
+    }
+}
+
+/// Square piece of the [`Terrain`], [`PATCH_SIZE`] units wide.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Patch {
+    /// X coordinate of the patch, it starts at `x * PATCH_SIZE` world units
+    pub x: i32,
+    /// Y coordinate of the patch, it starts at `y * PATCH_SIZE` world units
+    pub y: i32,
+    /// Bit `y * HEIGHT_CELLS + x` tells how height cell `x`, `y` is split into two triangles:
+    /// if set along the diagonal from corner `x`, `y` to `x + 1`, `y + 1`, otherwise along the other one
+    pub diag: u16,
+    /// Heights of the corners of the height cells, indexed `[y][x]`
+    pub height: [[u16; HEIGHT_CELLS + 1]; HEIGHT_CELLS + 1],
+    /// Materials of the visual cells, indexed `[y][x]`
+    pub visual: [[u16; VISUAL_CELLS]; VISUAL_CELLS],
+}
+
+impl Patch {
+    /// Creates a flat patch at `x`, `y` with every corner at `height`.
+    pub fn flat(x: i32, y: i32, height: u16) -> Self {
+        Self {
+            x,
+            y,
+            diag: 0,
+            height: [[height; HEIGHT_CELLS + 1]; HEIGHT_CELLS + 1],
+            visual: [[0; VISUAL_CELLS]; VISUAL_CELLS],
+        }
+    }
+
+    /// Height at `u`, `v` in `0.0..=1.0` across the patch, interpolated over the triangles.
+    pub fn height_at(&self, u: f32, v: f32) -> f32 {
+        let scale = HEIGHT_CELLS as f32;
+        let (cx, cy) = ((u * scale) as usize, (v * scale) as usize);
+        let (cx, cy) = (cx.min(HEIGHT_CELLS - 1), cy.min(HEIGHT_CELLS - 1));
+        let (u, v) = (u * scale - cx as f32, v * scale - cy as f32);
+        let corner = |dx: usize, dy: usize| f32::from(self.height[cy + dy][cx + dx]);
+        let (h00, h10, h01, h11) = (corner(0, 0), corner(1, 0), corner(0, 1), corner(1, 1));
+        match self.diag & (1 << (cy * HEIGHT_CELLS + cx)) != 0 {
+            true if u >= v => h00 + u * (h10 - h00) + v * (h11 - h10),
+            true => h00 + v * (h01 - h00) + u * (h11 - h01),
+            false if u + v <= 1.0 => h00 + u * (h10 - h00) + v * (h01 - h00),
+            false => h11 + (1.0 - u) * (h01 - h11) + (1.0 - v) * (h10 - h11),
+        }
+    }
+
+    /// Reads one patch record.
+    fn read_from<R: Read>(reader: &mut R) -> Result<Self, TerrainError> {
+        let mut record = [0u8; PATCH_RECORD_SIZE];
+        reader.read_exact(&mut record)?;
+        let mut words = record[10..]
+            .chunks_exact(2)
+            .map(|word| u16::from_le_bytes([word[0], word[1]]));
+        let mut patch = Self::flat(
+            i32::from_le_bytes(record[0..4].try_into().unwrap()),
+            i32::from_le_bytes(record[4..8].try_into().unwrap()),
+            0,
+        );
+        patch.diag = u16::from_le_bytes([record[8], record[9]]);
+        for row in patch.height.iter_mut() {
+            row.fill_with(|| words.next().unwrap());
+        }
+        for row in patch.visual.iter_mut() {
+            row.fill_with(|| words.next().unwrap());
+        }
+        Ok(patch)
+    }
+}
+
+/// Heightmap of a world, made of [`Patch`]es.
+///
+/// Heights are in the same units as [`Position::z`]. Places without a patch have no ground,
+/// queries return [`None`] there.
+///
+/// # Examples
+///
+/// ```
+/// use asciicker_rs::y6::prelude::*;
+///
+/// // One patch at the origin, sloping up along x
+/// let mut data = vec![];
+/// data.extend_from_slice(&1i32.to_le_bytes());
+/// data.extend_from_slice(&0i32.to_le_bytes());
+/// data.extend_from_slice(&0i32.to_le_bytes());
+/// data.extend_from_slice(&0u16.to_le_bytes());
+/// for _ in 0..=HEIGHT_CELLS {
+///     for x in 0..=HEIGHT_CELLS {
+///         data.extend_from_slice(&(100 + 10 * x as u16).to_le_bytes());
+///     }
+/// }
+/// data.extend_from_slice(&[0; VISUAL_CELLS * VISUAL_CELLS * 2]);
+///
+/// let mut terrain = Terrain::read_from(&data[..]).unwrap();
+/// terrain.water_level = 105.0;
+/// assert_eq!(terrain.height_at(0.0, 3.0), Some(100.0));
+/// assert_eq!(terrain.height_at(PATCH_SIZE / 2.0, 3.0), Some(120.0));
+/// assert_eq!(terrain.height_at(-1.0, 3.0), None);
+/// assert!(terrain.is_underwater(0.5, 0.5));
+/// assert!(!terrain.is_underwater(2.0, 0.5));
+///
+/// let standing = terrain.place_on_ground([2.0, 5.0, 900.0].into());
+/// assert_eq!(standing, Position::new(2.0, 5.0, 110.0));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Terrain {
+    /// Patches by their coordinates
+    pub patches: HashMap<(i32, i32), Patch>,
+    /// Height of the water surface, not part of the terrain section so it has to be set
+    pub water_level: f32,
+}
+
+impl Terrain {
+    /// Reads the terrain section, the reader is left right after it.
+    pub fn read_from<R: Read>(mut reader: R) -> Result<Self, TerrainError> {
+        let mut count = [0u8; 4];
+        reader.read_exact(&mut count)?;
+        let count = i32::from_le_bytes(count);
+        if count < 0 {
+            return Err(TerrainError::InvalidPatchCount(count));
+        }
+        let mut patches = HashMap::new();
+        for _ in 0..count {
+            let patch = Patch::read_from(&mut reader)?;
+            patches.insert((patch.x, patch.y), patch);
+        }
+        Ok(Self {
+            patches,
+            water_level: 0.0,
+        })
+    }
+
+    /// Reads the terrain from the start of the file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TerrainError> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Returns the patch at world coordinates `x`, `y`.
+    pub fn patch_at(&self, x: f32, y: f32) -> Option<&Patch> {
+        let key = (
+            (x / PATCH_SIZE).floor() as i32,
+            (y / PATCH_SIZE).floor() as i32,
+        );
+        self.patches.get(&key)
+    }
+
+    /// Height of the ground at world coordinates `x`, `y`.
+    pub fn height_at(&self, x: f32, y: f32) -> Option<f32> {
+        let patch = self.patch_at(x, y)?;
+        let u = x / PATCH_SIZE - patch.x as f32;
+        let v = y / PATCH_SIZE - patch.y as f32;
+        Some(patch.height_at(u, v))
+    }
+
+    /// Returns `true` if the ground at `x`, `y` is below [`Terrain::water_level`].
+    pub fn is_underwater(&self, x: f32, y: f32) -> bool {
+        self.height_at(x, y)
+            .is_some_and(|height| height < self.water_level)
+    }
+
+    /// Moves `position` onto the ground, or leaves it as is where there is no terrain.
+    pub fn place_on_ground(&self, position: Position) -> Position {
+        match self.height_at(position.x, position.y) {
+            Some(z) => Position { z, ..position },
+            None => position,
+        }
+    }
+}